@@ -0,0 +1,121 @@
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=Cargo.lock");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+    println!("cargo:rerun-if-changed=CHANGELOG.md");
+
+    println!("cargo:rustc-env=GIT_HASH={}", git_hash().unwrap_or_else(|| "unknown".to_owned()));
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let licenses = third_party_licenses().unwrap_or_else(|| "License information was not available at build time.\n".to_owned());
+    fs::write(out_dir.join("third_party_licenses.txt"), licenses).unwrap();
+    let changelog = fs::read_to_string("CHANGELOG.md").unwrap_or_default();
+    fs::write(out_dir.join("changelog.rs"), changelog_source(&changelog)).unwrap();
+}
+
+// The releases in CHANGELOG.md as a Rust expression for src/changelog.rs: headings
+// "## [version]" start a release, "### Category" a group, and "- " lines are its entries
+fn changelog_source(text: &str) -> String {
+    let mut releases: Vec<(String, Vec<(String, String)>)> = Vec::new();
+    let mut category = None;
+    for line in text.lines().map(str::trim) {
+        if let Some(version) = line.strip_prefix("## ") {
+            let version = version.split(']').next().unwrap_or_default().trim_start_matches('[').trim();
+            releases.push((version.to_owned(), Vec::new()));
+            category = None;
+        } else if let Some(name) = line.strip_prefix("### ") {
+            match name.trim() {
+                name @ ("Added" | "Changed" | "Fixed" | "Removed") => category = Some(name.to_owned()),
+                other => panic!("CHANGELOG.md: unknown category \"{}\", expected Added, Changed, Fixed or Removed", other),
+            }
+        } else if let (Some(entry), Some(category), Some((_, entries))) = (line.strip_prefix("- "), &category, releases.last_mut()) {
+            entries.push((category.clone(), entry.trim().to_owned()));
+        }
+    }
+    let mut source = String::from("&[\n");
+    for (version, entries) in releases {
+        source.push_str(&format!("    Release {{ version: {:?}, entries: &[\n", version));
+        for (category, entry) in entries {
+            source.push_str(&format!("        (Category::{}, {:?}),\n", category, entry));
+        }
+        source.push_str("    ] },\n");
+    }
+    source.push(']');
+    source
+}
+
+fn git_hash() -> Option<String> {
+    let output = Command::new("git").args(["rev-parse", "--short", "HEAD"]).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8(output.stdout).ok()?.trim().to_owned())
+}
+
+// Groups every crate compiled into the binary by the text of its license file(s),
+// the same shape cargo-about produces, so identical MIT texts are only shown once
+fn third_party_licenses() -> Option<String> {
+    let cargo = env::var("CARGO").ok()?;
+    let target = env::var("TARGET").ok()?;
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").ok()?;
+    let output = Command::new(cargo)
+        .args(["metadata", "--format-version", "1", "--offline", "--filter-platform", &target])
+        .current_dir(&manifest_dir)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let metadata: Value = serde_json::from_slice(&output.stdout).ok()?;
+    let root = metadata["resolve"]["root"].as_str().unwrap_or_default();
+
+    let mut crates = Vec::new();
+    let mut texts: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for package in metadata["packages"].as_array()? {
+        if package["id"].as_str() == Some(root) {
+            continue;
+        }
+        let name = format!("{} {}", package["name"].as_str()?, package["version"].as_str()?);
+        let license = package["license"].as_str().unwrap_or("see license file");
+        crates.push(format!("{} — {}", name, license));
+
+        let dir = Path::new(package["manifest_path"].as_str()?).parent()?;
+        for text in license_texts(dir) {
+            texts.entry(text).or_default().push(name.clone());
+        }
+    }
+    crates.sort();
+
+    let mut report = String::from("Crates\n======\n\n");
+    report.push_str(&crates.join("\n"));
+    for (text, users) in texts {
+        report.push_str("\n\n\n");
+        report.push_str(&format!("Used by: {}\n\n", users.join(", ")));
+        report.push_str(text.trim());
+    }
+    report.push('\n');
+    Some(report)
+}
+
+fn license_texts(dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_ascii_uppercase();
+            path.is_file() && (name.starts_with("LICENSE") || name.starts_with("LICENCE") || name.starts_with("COPYING"))
+        })
+        .collect();
+    files.sort();
+    files.into_iter().filter_map(|path| fs::read_to_string(path).ok()).collect()
+}
@@ -0,0 +1,65 @@
+use eframe::egui;
+
+pub const VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), " (", env!("GIT_HASH"), ")");
+const REPOSITORY: &str = "https://github.com/DudeTux42/FerrisFocus";
+const ISSUES: &str = "https://github.com/DudeTux42/FerrisFocus/issues";
+const THIRD_PARTY_LICENSES: &str = include_str!(concat!(env!("OUT_DIR"), "/third_party_licenses.txt"));
+
+/// Assets shipped inside the binary: (asset, author, license).
+const BUNDLED_ASSETS: &[(&str, &str, &str)] = &[
+    ("Ubuntu Light font", "Canonical Ltd.", "Ubuntu Font License 1.0"),
+    ("Hack font (timer digits)", "Source Foundry", "MIT / Bitstream Vera License"),
+    ("Alarm chime, app icon and mascot", "FerrisFocus contributors", "GNU GPL v3"),
+    ("Noto Emoji font", "Google Inc.", "SIL Open Font License 1.1"),
+    ("emoji-icon-font", "Jens Eliasson", "MIT / SIL Open Font License 1.1"),
+];
+
+pub fn window(ctx: &egui::Context, open: &mut bool) {
+    egui::Window::new("About FerrisFocus")
+        .open(open)
+        .collapsible(false)
+        .default_width(360.0)
+        .show(ctx, |ui| {
+            crate::compact_text(ui.style_mut());
+            ui.heading("FerrisFocus");
+            ui.label(format!("Version {}", VERSION));
+            ui.label("A Pomodoro timer written in Rust, licensed under the GNU GPL v3.");
+            ui.horizontal(|ui| {
+                if ui.link("Repository").clicked() {
+                    open_link(REPOSITORY);
+                }
+                if ui.link("Report an issue").clicked() {
+                    open_link(ISSUES);
+                }
+            });
+
+            ui.separator();
+            ui.strong("Bundled assets");
+            egui::Grid::new("bundled_assets").striped(true).show(ui, |ui| {
+                for (asset, author, license) in BUNDLED_ASSETS {
+                    ui.label(*asset);
+                    ui.label(*author);
+                    ui.label(*license);
+                    ui.end_row();
+                }
+            });
+
+            ui.separator();
+            egui::CollapsingHeader::new("Third-party licenses").show(ui, |ui| {
+                // The text is several hundred kilobytes, so only lay out the visible lines
+                let lines: Vec<&str> = THIRD_PARTY_LICENSES.lines().collect();
+                let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+                egui::ScrollArea::both().max_height(240.0).show_rows(ui, row_height, lines.len(), |ui, rows| {
+                    for line in &lines[rows] {
+                        ui.monospace(*line);
+                    }
+                });
+            });
+        });
+}
+
+fn open_link(url: &str) {
+    if let Err(err) = opener::open(url) {
+        log::warn!("Failed to open {}: {}", url, err);
+    }
+}
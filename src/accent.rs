@@ -0,0 +1,189 @@
+//! The desktop's accent color, for users who want the timer to match the rest of their system.
+
+use eframe::egui::{self, Color32};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// Changing the accent is rare; a minute's delay is fine and asking costs a process spawn
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+// WCAG's minimum for graphics and large text
+const MIN_CONTRAST: f32 = 3.0;
+
+/// Somewhere the system accent can be read from.
+trait Reader {
+    fn read(&self) -> Option<Color32>;
+}
+
+/// KDE's `kdeglobals`, which has the accent as `AccentColor=r,g,b`.
+#[cfg(target_os = "linux")]
+struct Kde;
+
+#[cfg(target_os = "linux")]
+impl Reader for Kde {
+    fn read(&self) -> Option<Color32> {
+        let dir = std::env::var_os("XDG_CONFIG_HOME")
+            .map(std::path::PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| std::path::PathBuf::from(home).join(".config")))?;
+        let text = std::fs::read_to_string(dir.join("kdeglobals")).ok()?;
+        let value = text.lines().find_map(|line| line.trim().strip_prefix("AccentColor="))?;
+        let rgb: Vec<u8> = value.split(',').filter_map(|part| part.trim().parse().ok()).collect();
+        match rgb[..] {
+            [r, g, b] => Some(Color32::from_rgb(r, g, b)),
+            _ => None,
+        }
+    }
+}
+
+/// GNOME 47 and later, which offers a fixed set of named accents.
+#[cfg(target_os = "linux")]
+struct Gnome;
+
+#[cfg(target_os = "linux")]
+impl Reader for Gnome {
+    fn read(&self) -> Option<Color32> {
+        let name = output("gsettings", &["get", "org.gnome.desktop.interface", "accent-color"])?;
+        let (r, g, b) = match name.trim().trim_matches('\'') {
+            "blue" => (53, 132, 228),
+            "teal" => (33, 144, 164),
+            "green" => (58, 148, 74),
+            "yellow" => (200, 136, 0),
+            "orange" => (237, 91, 0),
+            "red" => (230, 45, 66),
+            "pink" => (213, 97, 153),
+            "purple" => (145, 65, 172),
+            "slate" => (111, 131, 150),
+            _ => return None,
+        };
+        Some(Color32::from_rgb(r, g, b))
+    }
+}
+
+/// The accent from the DWM settings in the registry, stored as `0xAABBGGRR`.
+#[cfg(windows)]
+struct Windows;
+
+#[cfg(windows)]
+impl Reader for Windows {
+    fn read(&self) -> Option<Color32> {
+        let text = output("reg", &["query", r"HKCU\Software\Microsoft\Windows\DWM", "/v", "AccentColor"])?;
+        let hex = text.split_whitespace().find_map(|word| word.strip_prefix("0x"))?;
+        let [r, g, b, _] = u32::from_str_radix(hex, 16).ok()?.to_le_bytes();
+        Some(Color32::from_rgb(r, g, b))
+    }
+}
+
+/// The global `AppleAccentColor` default, a number for one of the fixed accents.
+#[cfg(target_os = "macos")]
+struct MacOs;
+
+#[cfg(target_os = "macos")]
+impl Reader for MacOs {
+    fn read(&self) -> Option<Color32> {
+        // Unset means the default blue
+        let number = output("defaults", &["read", "-g", "AppleAccentColor"]).unwrap_or_else(|| "4".to_owned());
+        let (r, g, b) = match number.trim() {
+            "-1" => (152, 152, 152),
+            "0" => (255, 82, 89),
+            "1" => (247, 130, 27),
+            "2" => (255, 199, 38),
+            "3" => (98, 186, 70),
+            "4" => (0, 122, 255),
+            "5" => (165, 80, 167),
+            "6" => (247, 79, 158),
+            _ => return None,
+        };
+        Some(Color32::from_rgb(r, g, b))
+    }
+}
+
+/// The readers for this platform, most specific first.
+fn readers() -> Vec<Box<dyn Reader + Send>> {
+    vec![
+        #[cfg(target_os = "linux")]
+        Box::new(Kde),
+        #[cfg(target_os = "linux")]
+        Box::new(Gnome),
+        #[cfg(windows)]
+        Box::new(Windows),
+        #[cfg(target_os = "macos")]
+        Box::new(MacOs),
+    ]
+}
+
+#[cfg(any(target_os = "linux", windows, target_os = "macos"))]
+fn output(program: &str, args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new(program).args(args).output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The system accent as last read on a background thread, which stops once this is dropped.
+pub struct Monitor {
+    accent: Arc<Mutex<Option<Color32>>>,
+}
+
+impl Monitor {
+    pub fn start(ctx: &egui::Context) -> Self {
+        let accent = Arc::new(Mutex::new(None));
+        let (shared, ctx) = (Arc::downgrade(&accent), ctx.clone());
+        let readers = readers();
+        let spawned = std::thread::Builder::new().name("accent".to_owned()).spawn(move || loop {
+            let color = readers.iter().find_map(|reader| reader.read());
+            let Some(shared) = shared.upgrade() else {
+                return;
+            };
+            if let Ok(mut last) = shared.lock() {
+                if *last != color {
+                    *last = color;
+                    ctx.request_repaint();
+                }
+            }
+            drop(shared);
+            std::thread::sleep(POLL_INTERVAL);
+        });
+        if let Err(err) = spawned {
+            log::warn!("Cannot start accent color thread: {}", err);
+        }
+        Self { accent }
+    }
+
+    /// `None` until read, and where the system has no accent to offer.
+    pub fn accent(&self) -> Option<Color32> {
+        self.accent.lock().ok().and_then(|accent| *accent)
+    }
+}
+
+/// `accent`, lightened or darkened as little as needed to stand out against `background`.
+pub fn readable(accent: Color32, background: Color32) -> Color32 {
+    let toward = if contrast(Color32::WHITE, background) > contrast(Color32::BLACK, background) {
+        Color32::WHITE
+    } else {
+        Color32::BLACK
+    };
+    (0..=10)
+        .map(|step| mix(accent, toward, step as f32 / 10.0))
+        .find(|color| contrast(*color, background) >= MIN_CONTRAST)
+        .unwrap_or(toward)
+}
+
+fn mix(from: Color32, to: Color32, amount: f32) -> Color32 {
+    let channel = |a: u8, b: u8| (f32::from(a) + (f32::from(b) - f32::from(a)) * amount).round() as u8;
+    Color32::from_rgb(channel(from.r(), to.r()), channel(from.g(), to.g()), channel(from.b(), to.b()))
+}
+
+// Relative luminance and contrast ratio as defined by WCAG 2
+fn luminance(color: Color32) -> f32 {
+    let linear = |channel: u8| {
+        let c = f32::from(channel) / 255.0;
+        if c <= 0.039_28 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    0.2126 * linear(color.r()) + 0.7152 * linear(color.g()) + 0.0722 * linear(color.b())
+}
+
+fn contrast(a: Color32, b: Color32) -> f32 {
+    let (a, b) = (luminance(a), luminance(b));
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
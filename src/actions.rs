@@ -0,0 +1,137 @@
+use eframe::egui::{Key, KeyboardShortcut, Modifiers};
+
+/// Everything the user can trigger from the menu bar or a keyboard shortcut.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    StartPause,
+    Skip,
+    Reset,
+    Extend,
+    ToggleMute,
+    ToggleWorkOffline,
+    ToggleMenuBar,
+    ToggleDarkMode,
+    ShowStats,
+    CopyStandup,
+    ShowSettings,
+    TogglePrivacy,
+    ShowShortcuts,
+    OpenConfigFolder,
+    OpenDataFolder,
+    CopyDiagnostics,
+    SaveSnapshot,
+    WhatsNew,
+    About,
+}
+
+/// Which top-level menu an action is listed in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Menu {
+    Timer,
+    View,
+    Help,
+}
+
+impl Menu {
+    pub const ALL: [Menu; 3] = [Menu::Timer, Menu::View, Menu::Help];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Menu::Timer => "Timer",
+            Menu::View => "View",
+            Menu::Help => "Help",
+        }
+    }
+}
+
+impl Action {
+    pub const ALL: [Action; 19] = [
+        Action::StartPause,
+        Action::Skip,
+        Action::Reset,
+        Action::Extend,
+        Action::ToggleMute,
+        Action::ToggleWorkOffline,
+        Action::ToggleMenuBar,
+        Action::ToggleDarkMode,
+        Action::ShowStats,
+        Action::CopyStandup,
+        Action::ShowSettings,
+        Action::TogglePrivacy,
+        Action::ShowShortcuts,
+        Action::OpenConfigFolder,
+        Action::OpenDataFolder,
+        Action::CopyDiagnostics,
+        Action::SaveSnapshot,
+        Action::WhatsNew,
+        Action::About,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::StartPause => "Start / Pause",
+            Action::Skip => "Skip interval",
+            Action::Reset => "Reset",
+            Action::Extend => "Extend by 5 minutes",
+            Action::ToggleMute => "Mute sounds",
+            Action::ToggleWorkOffline => "Work offline",
+            Action::ToggleMenuBar => "Show menu bar",
+            Action::ToggleDarkMode => "Dark mode",
+            Action::ShowStats => "Statistics",
+            Action::CopyStandup => "Copy for standup",
+            Action::ShowSettings => "Settings",
+            Action::TogglePrivacy => "Privacy mode",
+            Action::ShowShortcuts => "Keyboard shortcuts",
+            Action::OpenConfigFolder => "Open config folder",
+            Action::OpenDataFolder => "Open data folder",
+            Action::CopyDiagnostics => "Copy diagnostics",
+            Action::SaveSnapshot => "Save state snapshot",
+            Action::WhatsNew => "What's new",
+            Action::About => "About FerrisFocus",
+        }
+    }
+
+    pub fn menu(self) -> Menu {
+        match self {
+            Action::StartPause | Action::Skip | Action::Reset | Action::Extend | Action::ToggleMute | Action::ToggleWorkOffline => Menu::Timer,
+            Action::ToggleMenuBar
+            | Action::ToggleDarkMode
+            | Action::ShowStats
+            | Action::CopyStandup
+            | Action::ShowSettings
+            | Action::TogglePrivacy => Menu::View,
+            Action::ShowShortcuts
+            | Action::OpenConfigFolder
+            | Action::OpenDataFolder
+            | Action::CopyDiagnostics
+            | Action::SaveSnapshot
+            | Action::WhatsNew
+            | Action::About => Menu::Help,
+        }
+    }
+
+    pub fn shortcut(self) -> Option<KeyboardShortcut> {
+        match self {
+            Action::StartPause => Some(KeyboardShortcut::new(Modifiers::NONE, Key::Space)),
+            Action::Skip => Some(KeyboardShortcut::new(Modifiers::COMMAND, Key::N)),
+            Action::Reset => Some(KeyboardShortcut::new(Modifiers::COMMAND, Key::R)),
+            Action::Extend => Some(KeyboardShortcut::new(Modifiers::COMMAND, Key::E)),
+            Action::ToggleMute => Some(KeyboardShortcut::new(Modifiers::COMMAND.plus(Modifiers::SHIFT), Key::M)),
+            // Must stay reachable while the bar itself is hidden
+            Action::ToggleMenuBar => Some(KeyboardShortcut::new(Modifiers::COMMAND, Key::M)),
+            Action::ToggleDarkMode => Some(KeyboardShortcut::new(Modifiers::COMMAND.plus(Modifiers::SHIFT), Key::D)),
+            Action::ShowStats => Some(KeyboardShortcut::new(Modifiers::COMMAND, Key::T)),
+            Action::ShowSettings => Some(KeyboardShortcut::new(Modifiers::COMMAND, Key::Comma)),
+            Action::TogglePrivacy => Some(KeyboardShortcut::new(Modifiers::COMMAND.plus(Modifiers::SHIFT), Key::P)),
+            Action::ShowShortcuts => Some(KeyboardShortcut::new(Modifiers::NONE, Key::F1)),
+            Action::ToggleWorkOffline
+            | Action::CopyStandup
+            | Action::OpenConfigFolder
+            | Action::OpenDataFolder
+            | Action::CopyDiagnostics
+            | Action::SaveSnapshot
+            | Action::WhatsNew
+            | Action::About => None,
+        }
+    }
+}
@@ -0,0 +1,152 @@
+//! The alarm queued on the audio output shortly before an interval ends, behind as much
+//! silence as is left of it, so it starts right on time however late the next frame is drawn.
+//! Taken back if the end moves or the timer stops before then.
+
+use std::time::{Duration, Instant};
+
+/// How long before the end the alarm is queued.
+pub const AHEAD: Duration = Duration::from_secs(2);
+// Leeway for the end worked out again on a later frame
+const SLACK: Duration = Duration::from_millis(5);
+
+pub enum Step {
+    /// Queue the alarm behind this much silence, then call [`AlarmQueue::queued`].
+    Queue(Duration),
+    /// Stop the queued alarm.
+    Cancel,
+    Nothing,
+}
+
+/// What was queued, and for which end.
+pub struct AlarmQueue<T> {
+    queued: Option<(Instant, T)>,
+}
+
+impl<T> Default for AlarmQueue<T> {
+    fn default() -> Self {
+        Self { queued: None }
+    }
+}
+
+impl<T> AlarmQueue<T> {
+    /// What to do at `now` with `remaining` left of the running interval; None while nothing
+    /// runs.
+    pub fn update(&mut self, now: Instant, remaining: Option<Duration>) -> Step {
+        let end = remaining.map(|remaining| now + remaining);
+        match (&self.queued, remaining) {
+            (Some((queued_end, _)), _) if end.is_none_or(|end| end.max(*queued_end) - end.min(*queued_end) > SLACK) => {
+                self.queued = None;
+                Step::Cancel
+            }
+            (None, Some(remaining)) if !remaining.is_zero() && remaining <= AHEAD => Step::Queue(remaining),
+            _ => Step::Nothing,
+        }
+    }
+
+    /// Notes that `what` was queued to start at `end`.
+    pub fn queued(&mut self, end: Instant, what: T) {
+        self.queued = Some((end, what));
+    }
+
+    /// What was queued, once the interval has ended.
+    pub fn take(&mut self) -> Option<T> {
+        self.queued.take().map(|(_, what)| what)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pomodoro_timer::timer::{Durations, PomodoroTimer};
+
+    use crate::alerts::Sound;
+    use crate::audio::{AudioBackend, Call, RecordingBackend, Role, Shape};
+    use crate::clock::{Clock, ManualClock};
+
+    const LENGTH: Duration = Duration::from_secs(10);
+    // Frames drawn at an awkward rate, so the end of an interval is noticed late
+    const UNEVEN_FRAME: Duration = Duration::from_millis(700);
+
+    // The app's frame: queue, or take back, with the audio recording how far ahead; when it queued
+    fn frame(queue: &mut AlarmQueue<()>, timer: &PomodoroTimer, audio: &mut RecordingBackend, now: Instant) -> Option<Instant> {
+        audio.set_now(now);
+        match queue.update(now, timer.is_running().then(|| timer.remaining(now))) {
+            Step::Queue(delay) if audio.play(Role::Alarm, Sound::Chime, Shape { ramp: None, delay: Some(delay) }) => {
+                queue.queued(now + delay, ());
+                Some(now)
+            }
+            Step::Cancel => {
+                audio.stop();
+                None
+            }
+            Step::Queue(_) | Step::Nothing => None,
+        }
+    }
+
+    fn silences(audio: &RecordingBackend) -> usize {
+        audio.calls().iter().filter(|(_, call)| *call == Call::Silence(Role::Alarm)).count()
+    }
+
+    #[test]
+    fn the_alarm_starts_right_at_the_end_noticed_late() {
+        let clock = ManualClock::starting_now();
+        let (mut audio, mut queue) = (RecordingBackend::default(), AlarmQueue::default());
+        let mut timer = PomodoroTimer::new(Durations { work: LENGTH, short_break: LENGTH });
+        let end = clock.instant() + LENGTH;
+        timer.start(clock.instant());
+        let mut queued_at = None;
+        let noticed = loop {
+            clock.advance(UNEVEN_FRAME);
+            let now = clock.instant();
+            if timer.tick(now).is_some() {
+                break now;
+            }
+            queued_at = frame(&mut queue, &timer, &mut audio, now).or(queued_at);
+        };
+        let delays: Vec<Duration> = audio
+            .calls()
+            .iter()
+            .filter_map(|(_, call)| match call {
+                Call::Play { starts_in, .. } => Some(*starts_in),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(delays.len(), 1, "queued once");
+        assert!(delays[0] <= AHEAD);
+        assert_eq!(queued_at.expect("queued ahead of the end") + delays[0], end);
+        assert!(noticed > end, "the frames are uneven enough to notice the end late");
+    }
+
+    #[test]
+    fn pausing_or_extending_takes_the_alarm_back() {
+        let clock = ManualClock::starting_now();
+        let mut audio = RecordingBackend::default();
+        for extend in [false, true] {
+            let mut queue = AlarmQueue::default();
+            let mut timer = PomodoroTimer::new(Durations { work: LENGTH, short_break: LENGTH });
+            timer.start(clock.instant());
+            while frame(&mut queue, &timer, &mut audio, clock.instant()).is_none() {
+                clock.advance(UNEVEN_FRAME);
+            }
+            let before = silences(&audio);
+            if extend {
+                timer.set_duration(LENGTH * 2);
+            } else {
+                timer.pause(clock.instant());
+            }
+            frame(&mut queue, &timer, &mut audio, clock.instant());
+            assert_eq!(silences(&audio) - before, 1, "taken back after {}", if extend { "extending" } else { "pausing" });
+            assert!(queue.take().is_none());
+        }
+    }
+
+    #[test]
+    fn nothing_is_queued_while_idle_or_far_from_the_end() {
+        let mut queue = AlarmQueue::<()>::default();
+        let now = Instant::now();
+        assert!(matches!(queue.update(now, None), Step::Nothing));
+        assert!(matches!(queue.update(now, Some(AHEAD * 2)), Step::Nothing));
+        assert!(matches!(queue.update(now, Some(Duration::ZERO)), Step::Nothing));
+        assert!(matches!(queue.update(now, Some(AHEAD)), Step::Queue(delay) if delay == AHEAD));
+    }
+}
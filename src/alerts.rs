@@ -0,0 +1,255 @@
+//! How loudly each kind of moment interrupts: which sound plays and whether the window asks
+//! for attention. Every alert goes through [`Alerts::profile`], so sound and window agree.
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+// Shortest and longest ramp offered
+const RAMP_SECS: [u8; 2] = [3, 10];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Alert {
+    WorkEnd,
+    /// The last focus session of a cycle ends, and a long break begins.
+    LongBreakStarts,
+    BreakEnd,
+    LongBreakEnds,
+    /// A break ends, and a focus session follows after a moment to get ready.
+    GetReady,
+    /// An easy start ends, asking whether to keep going.
+    EasyStartEnds,
+    GoalReached,
+    /// A quick timer set from the command line ends.
+    QuickTimer,
+}
+
+impl Alert {
+    pub const ALL: [Alert; 8] = [
+        Alert::WorkEnd,
+        Alert::LongBreakStarts,
+        Alert::BreakEnd,
+        Alert::LongBreakEnds,
+        Alert::GetReady,
+        Alert::EasyStartEnds,
+        Alert::GoalReached,
+        Alert::QuickTimer,
+    ];
+
+    /// The alert for the end of a focus session (`work`) or break, where `long_break` is the
+    /// break starting or ending.
+    pub fn period_end(work: bool, long_break: bool) -> Alert {
+        match (work, long_break) {
+            (true, false) => Alert::WorkEnd,
+            (true, true) => Alert::LongBreakStarts,
+            (false, false) => Alert::BreakEnd,
+            (false, true) => Alert::LongBreakEnds,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Alert::WorkEnd => "Focus session ends",
+            Alert::LongBreakStarts => "Last session of a cycle ends",
+            Alert::BreakEnd => "Break ends",
+            Alert::LongBreakEnds => "Long break ends",
+            Alert::GetReady => "Get ready to focus",
+            Alert::EasyStartEnds => "Easy start ends",
+            Alert::GoalReached => "Daily goal reached",
+            Alert::QuickTimer => "Quick timer ends",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Sound {
+    /// The alarm, repeating until acknowledged.
+    Ring,
+    /// The alarm, once.
+    Chime,
+    /// A short, quick version of the alarm.
+    Blip,
+    /// Rising notes, then the alarm.
+    Fanfare,
+    Silent,
+}
+
+impl Sound {
+    const ALL: [Sound; 5] = [Sound::Ring, Sound::Chime, Sound::Blip, Sound::Fanfare, Sound::Silent];
+
+    fn label(self) -> &'static str {
+        match self {
+            Sound::Ring => "Ring until stopped",
+            Sound::Chime => "Chime once",
+            Sound::Blip => "Short blip",
+            Sound::Fanfare => "Fanfare",
+            Sound::Silent => "Silent",
+        }
+    }
+}
+
+/// How much the window asks for attention, e.g. by flashing in the taskbar.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Urgency {
+    Quiet,
+    Informational,
+    Critical,
+}
+
+impl Urgency {
+    const ALL: [Urgency; 3] = [Urgency::Quiet, Urgency::Informational, Urgency::Critical];
+
+    fn label(self) -> &'static str {
+        match self {
+            Urgency::Quiet => "Don't ask",
+            Urgency::Informational => "Ask for attention",
+            Urgency::Critical => "Insist",
+        }
+    }
+
+    /// The window command to send, if any.
+    pub fn attention(self) -> Option<egui::UserAttentionType> {
+        match self {
+            Urgency::Quiet => None,
+            Urgency::Informational => Some(egui::UserAttentionType::Informational),
+            Urgency::Critical => Some(egui::UserAttentionType::Critical),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    pub sound: Sound,
+    pub urgency: Urgency,
+    /// Seconds over which the sound rises from 20% to the alarm volume; at once when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ramp_secs: Option<u8>,
+}
+
+impl Profile {
+    pub fn ramp(&self) -> Option<Duration> {
+        self.ramp_secs.map(|secs| Duration::from_secs(u64::from(secs.clamp(RAMP_SECS[0], RAMP_SECS[1]))))
+    }
+}
+
+/// A profile per alert. By default the end of focus insists, since ignoring it costs a
+/// break, while the end of a break and the daily goal only chime. The long break gets its own
+/// fanfare, and the way back from it rings so it isn't missed. Getting ready only blips, since
+/// focus starts by itself anyway; the end of an easy start chimes, as a question is waiting, and
+/// so does a quick timer.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Alerts {
+    pub work_end: Profile,
+    pub long_break_starts: Profile,
+    pub break_end: Profile,
+    pub long_break_ends: Profile,
+    pub get_ready: Profile,
+    pub easy_start_ends: Profile,
+    pub goal_reached: Profile,
+    pub quick_timer: Profile,
+}
+
+impl Default for Alerts {
+    fn default() -> Self {
+        Self {
+            work_end: Profile { sound: Sound::Ring, urgency: Urgency::Critical, ramp_secs: None },
+            long_break_starts: Profile { sound: Sound::Fanfare, urgency: Urgency::Critical, ramp_secs: None },
+            break_end: Profile { sound: Sound::Chime, urgency: Urgency::Informational, ramp_secs: None },
+            long_break_ends: Profile { sound: Sound::Ring, urgency: Urgency::Informational, ramp_secs: None },
+            get_ready: Profile { sound: Sound::Blip, urgency: Urgency::Quiet, ramp_secs: None },
+            easy_start_ends: Profile { sound: Sound::Chime, urgency: Urgency::Informational, ramp_secs: None },
+            goal_reached: Profile { sound: Sound::Chime, urgency: Urgency::Informational, ramp_secs: None },
+            quick_timer: Profile { sound: Sound::Chime, urgency: Urgency::Informational, ramp_secs: None },
+        }
+    }
+}
+
+impl Alerts {
+    pub fn profile(&self, alert: Alert) -> Profile {
+        match alert {
+            Alert::WorkEnd => self.work_end,
+            Alert::LongBreakStarts => self.long_break_starts,
+            Alert::BreakEnd => self.break_end,
+            Alert::LongBreakEnds => self.long_break_ends,
+            Alert::GetReady => self.get_ready,
+            Alert::EasyStartEnds => self.easy_start_ends,
+            Alert::GoalReached => self.goal_reached,
+            Alert::QuickTimer => self.quick_timer,
+        }
+    }
+
+    fn profile_mut(&mut self, alert: Alert) -> &mut Profile {
+        match alert {
+            Alert::WorkEnd => &mut self.work_end,
+            Alert::LongBreakStarts => &mut self.long_break_starts,
+            Alert::BreakEnd => &mut self.break_end,
+            Alert::LongBreakEnds => &mut self.long_break_ends,
+            Alert::GetReady => &mut self.get_ready,
+            Alert::EasyStartEnds => &mut self.easy_start_ends,
+            Alert::GoalReached => &mut self.goal_reached,
+            Alert::QuickTimer => &mut self.quick_timer,
+        }
+    }
+}
+
+/// What was done in the [`matrix`].
+#[derive(Default)]
+pub struct Edit {
+    pub changed: bool,
+    /// An alert whose sound is to be played as it is set up.
+    pub preview: Option<Alert>,
+}
+
+/// One row per alert, one column per choice.
+pub fn matrix(ui: &mut egui::Ui, alerts: &mut Alerts) -> Edit {
+    let mut edit = Edit::default();
+    let changed = &mut edit.changed;
+    egui::Grid::new("alerts").num_columns(5).show(ui, |ui| {
+        ui.strong("When");
+        ui.strong("Sound");
+        ui.strong("Window");
+        ui.strong("Gentle start");
+        ui.end_row();
+        for alert in Alert::ALL {
+            let profile = alerts.profile_mut(alert);
+            ui.label(alert.label());
+            egui::ComboBox::from_id_source(("alert sound", alert.label())).selected_text(profile.sound.label()).show_ui(
+                ui,
+                |ui| {
+                    for sound in Sound::ALL {
+                        *changed |= ui.selectable_value(&mut profile.sound, sound, sound.label()).changed();
+                    }
+                },
+            );
+            egui::ComboBox::from_id_source(("alert urgency", alert.label()))
+                .selected_text(profile.urgency.label())
+                .show_ui(ui, |ui| {
+                    for urgency in Urgency::ALL {
+                        *changed |= ui.selectable_value(&mut profile.urgency, urgency, urgency.label()).changed();
+                    }
+                });
+            let silent = profile.sound == Sound::Silent;
+            ui.add_enabled_ui(!silent, |ui| {
+                ui.horizontal(|ui| {
+                    let mut ramped = profile.ramp_secs.is_some();
+                    if ui.checkbox(&mut ramped, "").on_hover_text("Start at 20% of the volume and rise to it").changed() {
+                        profile.ramp_secs = ramped.then_some(5);
+                        *changed = true;
+                    }
+                    if let Some(secs) = &mut profile.ramp_secs {
+                        let response = ui.add(egui::DragValue::new(secs).range(RAMP_SECS[0]..=RAMP_SECS[1]).suffix(" s"));
+                        *changed |= response.drag_stopped() || response.lost_focus();
+                    }
+                });
+            });
+            if ui.add_enabled(!silent, egui::Button::new("▶")).on_hover_text("Play it as it is set up").clicked() {
+                edit.preview = Some(alert);
+            }
+            ui.end_row();
+        }
+    });
+    edit
+}
@@ -0,0 +1,81 @@
+//! Task labels put away once their project is over: hidden from the task suggestions and the
+//! reports unless asked for, while their sessions stay in the history as they were.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use time::Date;
+
+use crate::history::Session;
+use crate::paths;
+
+const FILE_NAME: &str = "archive.json";
+/// Labels and tasks unused for this long are offered for archiving together.
+pub const STALE_DAYS: i64 = 90;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Archive {
+    labels: BTreeSet<String>,
+}
+
+impl Archive {
+    pub fn load() -> Self {
+        path()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        let Some(path) = path() else {
+            return;
+        };
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let result = serde_json::to_string(self)
+            .map_err(|e| e.to_string())
+            .and_then(|json| std::fs::write(&path, json).map_err(|e| e.to_string()));
+        if let Err(err) = result {
+            log::warn!("Cannot save {}: {}", path.display(), err);
+        }
+    }
+
+    pub fn contains(&self, label: &str) -> bool {
+        self.labels.contains(label)
+    }
+
+    pub fn set(&mut self, label: &str, archived: bool) {
+        if archived {
+            self.labels.insert(label.to_owned());
+        } else {
+            self.labels.remove(label);
+        }
+    }
+
+    /// Archives the labels last used before `today` minus [`STALE_DAYS`]; returns how many.
+    pub fn archive_stale(&mut self, sessions: &[Session], today: Date) -> usize {
+        let cutoff = today - time::Duration::days(STALE_DAYS);
+        let mut last_used = BTreeMap::<&str, Date>::new();
+        for session in sessions {
+            if let Some(label) = &session.label {
+                let last = last_used.entry(label).or_insert(session.date());
+                *last = (*last).max(session.date());
+            }
+        }
+        let stale: Vec<&str> = last_used.into_iter().filter(|(label, last)| *last < cutoff && !self.contains(label)).map(|(label, _)| label).collect();
+        for label in &stale {
+            self.set(label, true);
+        }
+        stale.len()
+    }
+
+    /// The first `count` of `labels`, newest first, that aren't archived, for suggestions.
+    pub fn recent_labels(&self, labels: &[String], count: usize) -> Vec<String> {
+        labels.iter().filter(|label| !self.contains(label)).take(count).cloned().collect()
+    }
+}
+
+fn path() -> Option<PathBuf> {
+    paths::data_dir().map(|dir| dir.join(FILE_NAME))
+}
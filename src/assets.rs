@@ -0,0 +1,78 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::paths;
+
+/// Files compiled into the binary, each replaceable by a file of the same name
+/// in the config dir's `assets/` folder.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Asset {
+    AlarmSound,
+    Icon,
+    TimerFont,
+    /// Two frames per mascot state, one state per row.
+    MascotSprites,
+}
+
+impl Asset {
+    pub const ALL: [Asset; 4] = [Asset::AlarmSound, Asset::Icon, Asset::TimerFont, Asset::MascotSprites];
+
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Asset::AlarmSound => "alarm.wav",
+            Asset::Icon => "icon.png",
+            Asset::TimerFont => "timer_font.ttf",
+            Asset::MascotSprites => "ferris_sprites.png",
+        }
+    }
+
+    fn embedded(self) -> &'static [u8] {
+        match self {
+            Asset::AlarmSound => include_bytes!("../assets/alarm.wav"),
+            Asset::Icon => include_bytes!("../assets/icon.png"),
+            Asset::TimerFont => include_bytes!("../assets/timer_font.ttf"),
+            Asset::MascotSprites => include_bytes!("../assets/ferris_sprites.png"),
+        }
+    }
+}
+
+static CACHE: OnceLock<HashMap<Asset, Cow<'static, [u8]>>> = OnceLock::new();
+
+// Overrides are read once, the first time any asset is requested
+fn cache() -> &'static HashMap<Asset, Cow<'static, [u8]>> {
+    CACHE.get_or_init(|| {
+        let dir = paths::config_dir().map(|dir| dir.join("assets"));
+        Asset::ALL
+            .into_iter()
+            .map(|asset| {
+                let path = dir.as_ref().map(|dir| dir.join(asset.file_name()));
+                let bytes = match path.map(std::fs::read) {
+                    Some(Ok(bytes)) => {
+                        log::info!("Using override for {}", asset.file_name());
+                        Cow::Owned(bytes)
+                    }
+                    Some(Err(err)) if err.kind() != std::io::ErrorKind::NotFound => {
+                        log::warn!("Ignoring unreadable override for {}: {}", asset.file_name(), err);
+                        Cow::Borrowed(asset.embedded())
+                    }
+                    _ => Cow::Borrowed(asset.embedded()),
+                };
+                (asset, bytes)
+            })
+            .collect()
+    })
+}
+
+pub fn load(asset: Asset) -> Cow<'static, [u8]> {
+    Cow::Borrowed(&cache()[&asset])
+}
+
+/// Assets currently replaced by a user-supplied file.
+pub fn overrides() -> Vec<&'static str> {
+    Asset::ALL
+        .into_iter()
+        .filter(|asset| matches!(cache()[asset], Cow::Owned(_)))
+        .map(Asset::file_name)
+        .collect()
+}
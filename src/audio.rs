@@ -0,0 +1,692 @@
+use rodio::buffer::SamplesBuffer;
+use rodio::cpal::traits::HostTrait;
+use rodio::{Decoder, DeviceTrait, OutputStream, OutputStreamHandle, Sample, Sink, Source};
+use std::io::{Cursor, Read, Seek};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::alerts::Sound;
+use crate::assets::{self, Asset};
+
+// About two minutes of 48 kHz stereo; anything longer is cut off rather than held in memory
+const MAX_SAMPLES: usize = 48_000 * 2 * 120;
+// Where a ramped sound starts, as a share of the volume
+const RAMP_START: f32 = 0.2;
+
+/// A sound decoded once up front, so playing it can neither stall nor fail on disk access.
+struct DecodedSound {
+    channels: u16,
+    sample_rate: u32,
+    samples: Vec<i16>,
+    // Where it was decoded from and when that file was last modified, for invalidation
+    file: Option<(PathBuf, Option<SystemTime>)>,
+}
+
+impl DecodedSound {
+    fn decode<R: Read + Seek + Send + Sync + 'static>(reader: R) -> Result<Self, String> {
+        let decoder = Decoder::new(reader).map_err(|e| e.to_string())?;
+        let channels = decoder.channels();
+        let sample_rate = decoder.sample_rate();
+        let samples: Vec<i16> = decoder.take(MAX_SAMPLES + 1).collect();
+        if samples.is_empty() {
+            return Err("the file contains no audio".to_owned());
+        }
+        let mut sound = Self { channels, sample_rate, samples, file: None };
+        if sound.samples.len() > MAX_SAMPLES {
+            log::info!("Alarm sound is longer than two minutes, cutting it short");
+            sound.samples.truncate(MAX_SAMPLES);
+        }
+        Ok(sound)
+    }
+
+    fn from_file(path: &Path) -> Result<Self, String> {
+        let file = std::fs::File::open(path).map_err(|e| e.to_string())?;
+        let mut sound = Self::decode(std::io::BufReader::new(file))?;
+        sound.file = Some((path.to_path_buf(), modified(path)));
+        Ok(sound)
+    }
+
+    fn bundled() -> Self {
+        Self::decode(Cursor::new(assets::load(Asset::AlarmSound))).unwrap_or_else(|err| {
+            log::warn!("Cannot decode alarm sound: {}", err);
+            Self { channels: 1, sample_rate: 44_100, samples: Vec::new(), file: None }
+        })
+    }
+
+    fn source(&self) -> SamplesBuffer<i16> {
+        SamplesBuffer::new(self.channels, self.sample_rate, self.samples.clone())
+    }
+
+    // In the sound's own format, so the samples that follow start exactly `length` later
+    fn silence(&self, length: Duration) -> SamplesBuffer<i16> {
+        let frames = (length.as_secs_f64() * f64::from(self.sample_rate)).round() as usize;
+        SamplesBuffer::new(self.channels, self.sample_rate, vec![0; frames * usize::from(self.channels)])
+    }
+
+    // A file that was edited in place is decoded again; one that was moved away keeps playing from memory
+    fn refresh(&mut self) {
+        let Some((path, decoded_at)) = &self.file else {
+            return;
+        };
+        let current = modified(path);
+        if current.is_none() || current == *decoded_at {
+            return;
+        }
+        match Self::from_file(path) {
+            Ok(sound) => {
+                log::info!("Reloaded changed alarm sound {}", path.display());
+                *self = sound;
+            }
+            Err(err) => log::warn!("Keeping previous alarm sound, cannot decode {}: {}", path.display(), err),
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+/// Endless brown noise, generated rather than bundled so it never loops audibly.
+struct BrownNoise {
+    level: f32,
+    seed: u32,
+}
+
+impl Default for BrownNoise {
+    fn default() -> Self {
+        Self { level: 0.0, seed: 0x9e37_79b9 }
+    }
+}
+
+impl Iterator for BrownNoise {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        // xorshift32 white noise, integrated and leaked back towards zero
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        let white = self.seed as f32 / u32::MAX as f32 * 2.0 - 1.0;
+        self.level = (self.level * 0.998 + white * 0.02).clamp(-1.0, 1.0);
+        Some(self.level * 2.0)
+    }
+}
+
+impl Source for BrownNoise {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        44_100
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// Starts `inner` quietly and brings it up to full volume, `offset` into a ramp lasting `ramp`,
+/// for a sound made of several parts; plays it unchanged without a ramp.
+struct Ramp<S> {
+    inner: S,
+    // Samples played so far, counted from the start of the ramp
+    played: u64,
+    ramp: Option<Duration>,
+}
+
+impl<S: Source> Ramp<S>
+where
+    S::Item: Sample,
+{
+    fn new(inner: S, ramp: Option<Duration>, offset: Duration) -> Self {
+        let played = (offset.as_secs_f64() * f64::from(inner.sample_rate()) * f64::from(inner.channels())) as u64;
+        Self { inner, played, ramp }
+    }
+}
+
+impl<S: Source> Iterator for Ramp<S>
+where
+    S::Item: Sample,
+{
+    type Item = S::Item;
+
+    fn next(&mut self) -> Option<S::Item> {
+        let sample = self.inner.next()?;
+        let Some(ramp) = self.ramp else {
+            return Some(sample);
+        };
+        let rate = f64::from(self.inner.sample_rate()) * f64::from(self.inner.channels());
+        let elapsed = Duration::from_secs_f64(self.played as f64 / rate);
+        self.played += 1;
+        let gain = RAMP_START + (1.0 - RAMP_START) * pomodoro_timer::timer::fraction(elapsed, ramp);
+        Some(sample.amplify(gain))
+    }
+}
+
+impl<S: Source> Source for Ramp<S>
+where
+    S::Item: Sample,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+}
+
+/// An open output stream and the sinks playing into it, one per role.
+struct Output {
+    _stream: OutputStream, // Keep the stream alive
+    handle: OutputStreamHandle,
+    alarm: Sink,
+    cue: Sink,
+    ambient: Option<Sink>, // Created when ambient sound first plays
+    device_name: String,
+}
+
+impl Output {
+    fn sink(&self, role: Role) -> &Sink {
+        match role {
+            Role::Alarm => &self.alarm,
+            Role::Cue => &self.cue,
+        }
+    }
+}
+
+/// What a sound is played for. A rodio sink plays what is appended to it one after another, so
+/// each role has a sink of its own and a sound never waits behind one of another role. Within
+/// a role, [`AudioBackend::play`] decides:
+///
+/// - An alarm starts right away and stops any cue. One that rings until stopped replaces the
+///   alarm before it; any other is dropped while an alarm is still queued or playing.
+/// - A cue is dropped while an alarm or another cue is queued or playing.
+/// - Ambient sound has a sink of its own too and plays alongside everything, see
+///   [`AudioBackend::set_ambient`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    /// The end of an interval, or anything else the user has to hear.
+    Alarm,
+    /// A small reminder, such as the posture switch or the next stretch.
+    Cue,
+}
+
+/// How a sound starts.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Shape {
+    /// Brought up from 20% to the volume over this long; only its first repetition, if it
+    /// repeats.
+    pub ramp: Option<Duration>,
+    /// After this much silence, so it can be queued ahead of when it is due; stopping takes
+    /// it back.
+    pub delay: Option<Duration>,
+}
+
+impl Shape {
+    pub fn ramped(ramp: Option<Duration>) -> Self {
+        Self { ramp, delay: None }
+    }
+}
+
+/// Everything the timer needs from sound output. Transition code only talks to this trait,
+/// so it can run against [`RecordingBackend`] where no audio device exists.
+pub trait AudioBackend {
+    /// Whether a sound of `role` is queued or playing.
+    fn busy(&self, role: Role) -> bool;
+    /// Appends `sound` to the sink of `role`. Only [`AudioBackend::play`] calls this, once the
+    /// sink is free.
+    fn queue(&mut self, role: Role, sound: Sound, shape: Shape);
+    /// Stops what `role` is playing and drops what it has queued.
+    fn silence(&mut self, role: Role);
+    fn set_volume(&mut self, volume: f32);
+    /// Plays ambient noise at `volume` alongside everything else, or stops it with `None`.
+    /// Cheap enough to call every frame.
+    fn set_ambient(&mut self, volume: Option<f32>);
+    /// Decodes the alarm up front so a broken file is reported now rather than when the timer ends.
+    /// On error the previous sound stays in place.
+    fn set_alarm_file(&mut self, path: Option<&Path>) -> Result<(), String>;
+    /// One line for the diagnostics report.
+    fn describe(&self) -> String;
+    /// A message to show the user the first time the output had to be reopened.
+    fn take_recovery_notice(&mut self) -> Option<String> {
+        None
+    }
+
+    /// Plays `sound` for `role` as the policy described at [`Role`] allows. Returns whether it
+    /// is played.
+    fn play(&mut self, role: Role, sound: Sound, shape: Shape) -> bool {
+        if sound == Sound::Silent {
+            return false;
+        }
+        let dropped = match (role, sound) {
+            (Role::Alarm, Sound::Ring) => false,
+            (Role::Alarm, _) => self.busy(Role::Alarm),
+            (Role::Cue, _) => self.busy(Role::Alarm) || self.busy(Role::Cue),
+        };
+        if dropped {
+            log::debug!("Dropped {:?} as {:?}: the sink is busy", sound, role);
+            return false;
+        }
+        if role == Role::Alarm {
+            for other in [Role::Alarm, Role::Cue] {
+                if self.busy(other) {
+                    self.silence(other);
+                }
+            }
+        }
+        self.queue(role, sound, shape);
+        true
+    }
+
+    /// Stops every alarm and cue; ambient sound plays on.
+    fn stop(&mut self) {
+        self.silence(Role::Alarm);
+        self.silence(Role::Cue);
+    }
+}
+
+const DUCK_FINAL: Duration = Duration::new(60, 0);
+const DUCK_FADE: Duration = Duration::new(5, 0);
+
+/// How loud ambient sound plays with `remaining` left in a focus session: full volume until the
+/// final minute, then a short fade down to 30% so the end of the session doesn't startle.
+pub fn ambient_duck(remaining: Duration) -> f32 {
+    const LEVEL: f32 = 0.3;
+    let into_final = DUCK_FINAL.saturating_sub(remaining);
+    let faded = pomodoro_timer::timer::fraction(into_final, DUCK_FADE);
+    1.0 - (1.0 - LEVEL) * faded
+}
+
+/// Whether [`ambient_duck`] is on its way down with `remaining` left.
+pub fn ambient_fading(remaining: Duration) -> bool {
+    remaining <= DUCK_FINAL && remaining > DUCK_FINAL - DUCK_FADE
+}
+
+/// Picks the silent recorder when `FERRISFOCUS_AUDIO=silent` is set (CI, headless runs),
+/// otherwise the real output.
+pub fn backend(alarm_file: Option<&Path>, volume: f32) -> Box<dyn AudioBackend> {
+    let mut backend: Box<dyn AudioBackend> = match std::env::var("FERRISFOCUS_AUDIO").as_deref() {
+        Ok("silent") => Box::<RecordingBackend>::default(),
+        _ => Box::new(RodioBackend::new(None)),
+    };
+    backend.set_volume(volume);
+    if let Err(err) = backend.set_alarm_file(alarm_file) {
+        log::warn!("Using the bundled alarm sound: {}", err);
+    }
+    backend
+}
+
+/// Plays through rodio, reopening the output whenever the device it was opened on goes away
+/// (docking, unplugging headphones) so sounds don't silently vanish.
+pub struct RodioBackend {
+    output: Option<Output>,
+    alarm: DecodedSound,
+    volume: f32,
+    preferred_device: Option<String>,
+    recovered: bool,
+    recovery_notice: Option<String>,
+}
+
+impl AudioBackend for RodioBackend {
+    fn busy(&self, role: Role) -> bool {
+        self.output.as_ref().is_some_and(|output| !output.sink(role).empty())
+    }
+
+    fn queue(&mut self, role: Role, sound: Sound, shape: Shape) {
+        const NOTE: Duration = Duration::from_millis(300);
+        self.reopen_if_device_changed();
+        let Some(output) = &self.output else {
+            log::warn!("No audio output, cannot play sound.");
+            return;
+        };
+        self.alarm.refresh();
+        let sink = output.sink(role);
+        if let Some(delay) = shape.delay {
+            sink.append(self.alarm.silence(delay));
+        }
+        match (sound, shape.ramp) {
+            // Ramped once, then at full volume for as long as it rings
+            (Sound::Ring, Some(ramp)) => {
+                sink.append(Ramp::new(self.alarm.source(), Some(ramp), Duration::ZERO));
+                sink.append(self.alarm.source().repeat_infinite());
+            }
+            (Sound::Ring, None) => sink.append(self.alarm.source().repeat_infinite()),
+            (Sound::Chime, ramp) => sink.append(Ramp::new(self.alarm.source(), ramp, Duration::ZERO)),
+            (Sound::Blip, ramp) => {
+                let blip = self.alarm.source().speed(1.5).take_duration(Duration::from_millis(700));
+                sink.append(Ramp::new(blip, ramp, Duration::ZERO));
+            }
+            // One ramp across the notes and the alarm after them
+            (Sound::Fanfare, ramp) => {
+                for (note, speed) in [1.0, 1.26, 1.5].into_iter().enumerate() {
+                    sink.append(Ramp::new(self.alarm.source().speed(speed).take_duration(NOTE), ramp, NOTE * note as u32));
+                }
+                sink.append(Ramp::new(self.alarm.source(), ramp, NOTE * 3));
+            }
+            (Sound::Silent, _) => {}
+        }
+        sink.play();
+        log::debug!("Playing {:?} as {:?}", sound, role);
+    }
+
+    fn silence(&mut self, role: Role) {
+        if let Some(output) = &self.output {
+            output.sink(role).stop();
+        }
+    }
+
+    fn set_volume(&mut self, volume: f32) {
+        self.volume = volume;
+        if let Some(output) = &self.output {
+            output.alarm.set_volume(volume);
+            output.cue.set_volume(volume);
+        }
+    }
+
+    fn set_ambient(&mut self, volume: Option<f32>) {
+        let Some(volume) = volume else {
+            if let Some(sink) = self.output.as_mut().and_then(|output| output.ambient.take()) {
+                sink.stop();
+            }
+            return;
+        };
+        if self.output.as_ref().is_some_and(|output| output.ambient.is_none()) {
+            self.reopen_if_device_changed();
+        }
+        let Some(output) = &mut self.output else {
+            return;
+        };
+        if output.ambient.is_none() {
+            match Sink::try_new(&output.handle) {
+                Ok(sink) => {
+                    sink.append(BrownNoise::default());
+                    output.ambient = Some(sink);
+                }
+                Err(err) => {
+                    log::warn!("Cannot play ambient sound: {}", err);
+                    return;
+                }
+            }
+        }
+        if let Some(sink) = &output.ambient {
+            sink.set_volume(volume);
+        }
+    }
+
+    fn set_alarm_file(&mut self, path: Option<&Path>) -> Result<(), String> {
+        self.alarm = match path {
+            Some(path) => DecodedSound::from_file(path).map_err(|err| format!("{}: {}", path.display(), err))?,
+            None => DecodedSound::bundled(),
+        };
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        self.device_name().unwrap_or("unavailable").to_owned()
+    }
+
+    fn take_recovery_notice(&mut self) -> Option<String> {
+        self.recovery_notice.take()
+    }
+}
+
+impl RodioBackend {
+    pub fn new(preferred_device: Option<String>) -> Self {
+        let mut backend = Self {
+            output: None,
+            alarm: DecodedSound::bundled(),
+            volume: 1.0,
+            preferred_device,
+            recovered: false,
+            recovery_notice: None,
+        };
+        backend.output = backend.open();
+        backend
+    }
+
+    fn device_name(&self) -> Option<&str> {
+        self.output.as_ref().map(|output| output.device_name.as_str())
+    }
+
+    // Checked right before playing rather than polled, since enumerating devices is slow
+    fn reopen_if_device_changed(&mut self) {
+        let target = target_device(self.preferred_device.as_deref());
+        let target_name = target.as_ref().and_then(|device| device.name().ok());
+        let current_name = self.device_name().map(str::to_owned);
+        if target_name.is_some() && target_name == current_name {
+            return;
+        }
+
+        let previous = current_name.unwrap_or_else(|| "none".to_owned());
+        self.output = None;
+        self.output = self.open();
+        let Some(now) = self.device_name().map(str::to_owned) else {
+            log::warn!("Audio device {} went away and no replacement could be opened", previous);
+            return;
+        };
+        log::info!("Audio output switched from {} to {}", previous, now);
+        if !self.recovered {
+            self.recovered = true;
+            self.recovery_notice = Some(format!("Audio output changed, now playing on {}", now));
+        }
+    }
+
+    fn open(&self) -> Option<Output> {
+        let device = target_device(self.preferred_device.as_deref())?;
+        let device_name = device.name().unwrap_or_else(|_| "unknown device".to_owned());
+        let result = OutputStream::try_from_device(&device).map_err(|e| e.to_string()).and_then(|(stream, handle)| {
+            let alarm = Sink::try_new(&handle).map_err(|e| e.to_string())?;
+            let cue = Sink::try_new(&handle).map_err(|e| e.to_string())?;
+            Ok((stream, handle, alarm, cue))
+        });
+        match result {
+            Ok((stream, handle, alarm, cue)) => {
+                alarm.set_volume(self.volume);
+                cue.set_volume(self.volume);
+                Some(Output { _stream: stream, handle, alarm, cue, ambient: None, device_name })
+            }
+            Err(err) => {
+                log::warn!("Cannot open audio device {}: {}", device_name, err);
+                None
+            }
+        }
+    }
+}
+
+// The preferred device if it's still connected, otherwise whatever the system default is now
+fn target_device(preferred: Option<&str>) -> Option<rodio::Device> {
+    let host = rodio::cpal::default_host();
+    if let Some(preferred) = preferred {
+        let found = host
+            .output_devices()
+            .ok()
+            .and_then(|mut devices| devices.find(|device| device.name().is_ok_and(|name| name == preferred)));
+        if found.is_some() {
+            return found;
+        }
+    }
+    host.default_output_device()
+}
+
+// How long the recorder takes each sound to play; the real length depends on the alarm file
+fn nominal_length(sound: Sound) -> Duration {
+    match sound {
+        // Until stopped; a day stands in for that
+        Sound::Ring => Duration::from_secs(24 * 60 * 60),
+        Sound::Chime => Duration::from_secs(2),
+        Sound::Blip => Duration::from_millis(700),
+        Sound::Fanfare => Duration::from_millis(2900),
+        Sound::Silent => Duration::ZERO,
+    }
+}
+
+/// One call made on a [`RecordingBackend`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Call {
+    /// Appended to the sink of `role`, to start `starts_in` from then: after the delay asked
+    /// for, and after whatever that sink still had queued.
+    Play { role: Role, sound: Sound, shape: Shape, starts_in: Duration },
+    Silence(Role),
+    SetVolume(f32),
+    /// Ambient sound turned on or off; volume changes while on aren't recorded.
+    SetAmbient(bool),
+    SetAlarmFile(Option<PathBuf>),
+}
+
+/// Plays nothing and remembers every call with the moment it was made, keeping track of how
+/// long each sink would still be busy as a real one would.
+#[derive(Default)]
+pub struct RecordingBackend {
+    calls: Vec<(Instant, Call)>,
+    ambient: bool,
+    /// When each role's sink runs out of what was appended to it; alarm first.
+    busy_until: [Option<Instant>; 2],
+    /// The time on a simulated clock; the real time when unset.
+    now: Option<Instant>,
+}
+
+impl RecordingBackend {
+    pub fn calls(&self) -> &[(Instant, Call)] {
+        &self.calls
+    }
+
+    /// Takes calls to be made at `now`, for running on a simulated clock.
+    pub fn set_now(&mut self, now: Instant) {
+        self.now = Some(now);
+    }
+
+    fn now(&self) -> Instant {
+        self.now.unwrap_or_else(Instant::now)
+    }
+
+    fn record(&mut self, call: Call) {
+        self.calls.push((self.now(), call));
+    }
+
+    fn busy_until(&mut self, role: Role) -> &mut Option<Instant> {
+        &mut self.busy_until[role as usize]
+    }
+}
+
+impl AudioBackend for RecordingBackend {
+    fn busy(&self, role: Role) -> bool {
+        self.busy_until[role as usize].is_some_and(|until| until > self.now())
+    }
+
+    fn queue(&mut self, role: Role, sound: Sound, shape: Shape) {
+        let now = self.now();
+        let free = self.busy_until(role).filter(|until| *until > now).unwrap_or(now);
+        let starts = free + shape.delay.unwrap_or_default();
+        *self.busy_until(role) = Some(starts + nominal_length(sound));
+        self.record(Call::Play { role, sound, shape, starts_in: starts - now });
+    }
+
+    fn silence(&mut self, role: Role) {
+        *self.busy_until(role) = None;
+        self.record(Call::Silence(role));
+    }
+
+    fn set_volume(&mut self, volume: f32) {
+        self.record(Call::SetVolume(volume));
+    }
+
+    fn set_ambient(&mut self, volume: Option<f32>) {
+        if self.ambient != volume.is_some() {
+            self.ambient = volume.is_some();
+            self.record(Call::SetAmbient(self.ambient));
+        }
+    }
+
+    fn set_alarm_file(&mut self, path: Option<&Path>) -> Result<(), String> {
+        self.record(Call::SetAlarmFile(path.map(Path::to_path_buf)));
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        let alarms = self.calls().iter().filter(|(_, call)| matches!(call, Call::Play { role: Role::Alarm, .. })).count();
+        format!("silent ({} alarms suppressed)", alarms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ONCE: Shape = Shape { ramp: None, delay: None };
+
+    // Plays `sound` `ms` into a simulated clock started at `start`
+    fn at(audio: &mut RecordingBackend, start: Instant, ms: u64, role: Role, sound: Sound, shape: Shape) -> bool {
+        audio.set_now(start + Duration::from_millis(ms));
+        audio.play(role, sound, shape)
+    }
+
+    fn silenced(audio: &RecordingBackend, role: Role) -> usize {
+        audio.calls().iter().filter(|(_, call)| *call == Call::Silence(role)).count()
+    }
+
+    #[test]
+    fn alarms_start_on_time_over_cues_and_ambient_sound() {
+        let start = Instant::now();
+        let mut audio = RecordingBackend::default();
+        let ahead = Shape { ramp: None, delay: Some(Duration::from_secs(2)) };
+        audio.set_ambient(Some(0.5));
+        at(&mut audio, start, 0, Role::Cue, Sound::Blip, ONCE);
+        assert!(at(&mut audio, start, 100, Role::Alarm, Sound::Chime, ONCE));
+        audio.stop();
+        at(&mut audio, start, 7_000, Role::Cue, Sound::Blip, ONCE);
+        assert!(at(&mut audio, start, 7_100, Role::Alarm, Sound::Fanfare, ahead));
+        assert!(at(&mut audio, start, 8_000, Role::Alarm, Sound::Ring, ONCE));
+        let mut alarms = 0;
+        for (_, call) in audio.calls() {
+            if let Call::Play { role: Role::Alarm, shape, starts_in, .. } = call {
+                alarms += 1;
+                assert_eq!(*starts_in, shape.delay.unwrap_or_default(), "an alarm started late, behind other sound");
+            }
+        }
+        assert_eq!(alarms, 3);
+    }
+
+    #[test]
+    fn cues_give_way_to_alarms() {
+        let start = Instant::now();
+        let mut audio = RecordingBackend::default();
+        assert!(at(&mut audio, start, 0, Role::Cue, Sound::Blip, ONCE));
+        assert!(at(&mut audio, start, 100, Role::Alarm, Sound::Chime, ONCE));
+        assert_eq!(silenced(&audio, Role::Cue), 1, "the alarm played over the cue instead of stopping it");
+        assert!(!at(&mut audio, start, 200, Role::Cue, Sound::Blip, ONCE), "a cue played over a queued alarm");
+    }
+
+    #[test]
+    fn an_alarm_asked_for_every_frame_plays_once() {
+        let start = Instant::now();
+        let mut audio = RecordingBackend::default();
+        let played = (0..60).filter(|frame| at(&mut audio, start, frame * 16, Role::Alarm, Sound::Chime, ONCE)).count();
+        assert_eq!(played, 1);
+        assert_eq!(audio.calls().len(), 1, "{:?}", audio.calls());
+    }
+
+    #[test]
+    fn a_ringing_alarm_is_not_cut_short_by_a_chime() {
+        let start = Instant::now();
+        let mut audio = RecordingBackend::default();
+        assert!(at(&mut audio, start, 0, Role::Alarm, Sound::Ring, ONCE));
+        assert!(!at(&mut audio, start, 1_000, Role::Alarm, Sound::Chime, ONCE));
+        assert_eq!(silenced(&audio, Role::Alarm), 0);
+        // Another ring replaces it
+        assert!(at(&mut audio, start, 2_000, Role::Alarm, Sound::Ring, ONCE));
+    }
+}
@@ -0,0 +1,190 @@
+//! Noticing the user come back to the computer, for a word about an interval that ended while
+//! they were away. The system's idle time, how long since the last key press or mouse move
+//! anywhere, is polled on a background thread, only while something is waiting to be said:
+//! from GNOME's idle monitor over D-Bus, or from `xprintidle` on X11. Elsewhere nobody is
+//! noticed coming back. A return only counts once input keeps coming for a few seconds, so a
+//! key brushed in passing doesn't.
+
+use eframe::egui;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Idle this long counts as away.
+pub const AWAY: Duration = Duration::from_secs(5 * 60);
+/// Input has to keep coming this long to count as being back.
+pub const SETTLE: Duration = Duration::from_secs(10);
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Follows idle-time readings and tells when the user is back.
+#[derive(Default)]
+pub struct Presence {
+    /// The last input before going away.
+    away_since: Option<Instant>,
+    /// The first input seen since, while it isn't yet clear they are back.
+    first_input: Option<Instant>,
+}
+
+impl Presence {
+    /// Takes the idle time read at `now`; when the user went away, if they are back now.
+    pub fn observe(&mut self, now: Instant, idle: Duration) -> Option<Instant> {
+        let last_input = now.checked_sub(idle).unwrap_or(now);
+        let Some(away_since) = self.away_since else {
+            if idle >= AWAY {
+                self.away_since = Some(last_input);
+            }
+            return None;
+        };
+        // Input stopped again: a touch in passing, or they never came back
+        if idle >= SETTLE {
+            self.first_input = None;
+            return None;
+        }
+        let first_input = *self.first_input.get_or_insert(last_input);
+        if last_input.saturating_duration_since(first_input) < SETTLE {
+            return None;
+        }
+        *self = Self::default();
+        Some(away_since)
+    }
+}
+
+/// Polls the idle time while enabled, and says when the user came back.
+pub struct Monitor {
+    returns: Option<Receiver<Instant>>,
+    enabled: Arc<AtomicBool>,
+    thread: Option<std::thread::Thread>,
+}
+
+impl Default for Monitor {
+    fn default() -> Self {
+        Self { returns: None, enabled: Arc::new(AtomicBool::new(false)), thread: None }
+    }
+}
+
+impl Monitor {
+    /// Repaints `ctx` when the user comes back.
+    pub fn start(ctx: &egui::Context) -> Self {
+        let mut monitor = Self::default();
+        let (sender, returns) = mpsc::channel();
+        let (enabled, ctx) = (Arc::clone(&monitor.enabled), ctx.clone());
+        let spawned = std::thread::Builder::new().name("away".to_owned()).spawn(move || {
+            let Some(mut idle) = idle_time() else {
+                log::info!("Noticing the return from being away is not supported here");
+                return;
+            };
+            let mut presence = Presence::default();
+            loop {
+                if !enabled.load(Ordering::Relaxed) {
+                    presence = Presence::default();
+                    std::thread::park();
+                    continue;
+                }
+                if let Some(since) = idle().and_then(|idle| presence.observe(Instant::now(), idle)) {
+                    if sender.send(since).is_err() {
+                        return;
+                    }
+                    ctx.request_repaint();
+                }
+                std::thread::sleep(POLL_INTERVAL);
+            }
+        });
+        match spawned {
+            Ok(handle) => {
+                monitor.thread = Some(handle.thread().clone());
+                monitor.returns = Some(returns);
+            }
+            Err(err) => log::warn!("Cannot start idle detection thread: {}", err),
+        }
+        monitor
+    }
+
+    /// Polls only while enabled; there is no point while nothing waits to be said.
+    pub fn set_enabled(&self, enabled: bool) {
+        if self.enabled.swap(enabled, Ordering::Relaxed) != enabled && enabled {
+            if let Some(thread) = &self.thread {
+                thread.unpark();
+            }
+        }
+    }
+
+    /// When the user went away, if they came back since the last call.
+    pub fn returned(&self) -> Option<Instant> {
+        self.returns.as_ref()?.try_iter().last()
+    }
+}
+
+/// A way to read the idle time that works here, tried once.
+#[cfg(target_os = "linux")]
+fn idle_time() -> Option<Box<dyn FnMut() -> Option<Duration> + Send>> {
+    const MUTTER: &str = "org.gnome.Mutter.IdleMonitor";
+    let connection = zbus::blocking::Connection::session().ok();
+    let mutter = move || {
+        let reply = connection.as_ref()?.call_method(Some(MUTTER), "/org/gnome/Mutter/IdleMonitor/Core", Some(MUTTER), "GetIdletime", &()).ok()?;
+        reply.body::<u64>().ok().map(Duration::from_millis)
+    };
+    let xprintidle = || {
+        let output = std::process::Command::new("xprintidle").output().ok().filter(|output| output.status.success())?;
+        String::from_utf8_lossy(&output.stdout).trim().parse().ok().map(Duration::from_millis)
+    };
+    if mutter().is_some() {
+        Some(Box::new(mutter))
+    } else if xprintidle().is_some() {
+        Some(Box::new(xprintidle))
+    } else {
+        None
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn idle_time() -> Option<Box<dyn FnMut() -> Option<Duration> + Send>> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Reads idle time every poll through `until` s, given the seconds at which a key was
+    // pressed; when each return was noticed, and since when the user was away
+    fn returns(inputs: &[u64], until: u64) -> Vec<(u64, u64)> {
+        let start = Instant::now();
+        let mut presence = Presence::default();
+        let mut returns = Vec::new();
+        for second in (0..until).step_by(POLL_INTERVAL.as_secs() as usize) {
+            let last = inputs.iter().copied().filter(|input| *input <= second).max().unwrap_or(0);
+            if let Some(since) = presence.observe(start + Duration::from_secs(second), Duration::from_secs(second - last)) {
+                returns.push((second, since.duration_since(start).as_secs()));
+            }
+        }
+        returns
+    }
+
+    #[test]
+    fn typing_on_return_counts_once_it_settles() {
+        let back = 2400;
+        let inputs: Vec<u64> = (back..back + 30).step_by(3).collect();
+        let [(second, since)] = returns(&inputs, back + 40)[..] else {
+            panic!("not one return noticed");
+        };
+        assert_eq!(since, 0);
+        assert!(second >= back + SETTLE.as_secs(), "back after {} s of typing, before it settled", second - back);
+    }
+
+    #[test]
+    fn a_touch_in_passing_is_not_a_return() {
+        let back = 2400;
+        let mut inputs = vec![0, 1200];
+        inputs.extend((back..back + 30).step_by(3));
+        // Away from the start all the same, not from the touch
+        assert_eq!(returns(&inputs, back + 40).iter().map(|(_, since)| *since).collect::<Vec<_>>(), [0]);
+        assert!(returns(&[0, 1200], 2400).is_empty());
+    }
+
+    #[test]
+    fn a_short_break_is_not_being_away() {
+        let inputs: Vec<u64> = (0..30).chain((AWAY.as_secs() - 10..AWAY.as_secs() + 30).step_by(3)).collect();
+        assert!(returns(&inputs, AWAY.as_secs() + 40).is_empty());
+    }
+}
@@ -0,0 +1,58 @@
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+/// A value produced on a worker thread, so slow loading never holds up the first frame.
+pub enum Background<T> {
+    Pending(Receiver<T>),
+    Ready(T),
+}
+
+impl<T: Send + 'static> Background<T> {
+    pub fn spawn(name: &str, load: impl FnOnce() -> T + Send + 'static) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let spawned = thread::Builder::new().name(name.to_owned()).spawn(move || {
+            let _ = sender.send(load());
+        });
+        if let Err(err) = spawned {
+            log::warn!("Cannot start {} thread: {}", name, err);
+        }
+        Background::Pending(receiver)
+    }
+
+    /// The value if it has finished loading, without waiting.
+    pub fn get(&mut self) -> Option<&mut T> {
+        if let Background::Pending(receiver) = self {
+            match receiver.try_recv() {
+                Ok(value) => *self = Background::Ready(value),
+                Err(mpsc::TryRecvError::Empty) => return None,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    log::error!("Background loading failed");
+                    return None;
+                }
+            }
+        }
+        match self {
+            Background::Ready(value) => Some(value),
+            Background::Pending(_) => None,
+        }
+    }
+
+    /// The value if `get` or `wait` has already received it, for callers that only have `&self`.
+    pub fn peek(&self) -> Option<&T> {
+        match self {
+            Background::Ready(value) => Some(value),
+            Background::Pending(_) => None,
+        }
+    }
+
+    /// The value, blocking until it has loaded. For writes that must not be dropped.
+    pub fn wait(&mut self) -> Option<&mut T> {
+        if let Background::Pending(receiver) = self {
+            match receiver.recv() {
+                Ok(value) => *self = Background::Ready(value),
+                Err(_) => return None,
+            }
+        }
+        self.get()
+    }
+}
@@ -0,0 +1,295 @@
+//! Daily copies of the history and settings in `backups/` under the data dir, taken before the
+//! first write of each day, so a bad import or a damaged file costs at most a day's work.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+use time::macros::format_description;
+use time::Date;
+
+use crate::{clock, history, paths, settings};
+
+const DIR_NAME: &str = "backups";
+// Copies of what a restore replaced; never rotated out, since the user didn't ask for them to go
+const SAFETY_PREFIX: &str = "before-restore-";
+
+static KEEP: AtomicU32 = AtomicU32::new(7);
+static CHECKED: Mutex<Option<Date>> = Mutex::new(None);
+
+/// How many daily backups to keep; none are taken when 0.
+pub fn set_keep(days: u32) {
+    KEEP.store(days, Ordering::Relaxed);
+}
+
+/// A day's backup.
+pub struct Backup {
+    pub date: Date,
+    pub path: PathBuf,
+    /// Sessions in its history file; `None` without one.
+    pub sessions: Option<usize>,
+}
+
+/// Takes today's backup unless there already is one. Called before anything is written to the
+/// history or settings, and cheap after the first call of the day.
+pub fn before_write() {
+    let today = clock::now().date();
+    match CHECKED.lock() {
+        Ok(mut checked) if *checked != Some(today) => *checked = Some(today),
+        _ => return,
+    }
+    let keep = KEEP.load(Ordering::Relaxed) as usize;
+    let Some(root) = root() else {
+        return;
+    };
+    if keep == 0 {
+        return;
+    }
+    if let Err(err) = take(&root, today, &files()) {
+        log::warn!("Cannot back up history and settings: {}", err);
+    }
+    rotate(&root, keep);
+}
+
+/// Available backups, newest first. Folders that aren't a day's backup are ignored.
+pub fn list() -> Vec<Backup> {
+    let Some(root) = root() else {
+        return Vec::new();
+    };
+    let mut backups: Vec<Backup> = days(&root)
+        .into_iter()
+        .map(|(date, path)| {
+            let sessions = fs::read_to_string(path.join(history::SESSIONS_FILE))
+                .ok()
+                .map(|text| text.lines().filter(|line| !line.trim().is_empty()).count());
+            Backup { date, path, sessions }
+        })
+        .collect();
+    backups.reverse();
+    backups
+}
+
+/// Puts `backup` in place of the current history and settings, after copying those to a
+/// safety folder whose path is returned. Files the backup doesn't have are removed, so the
+/// result is exactly what was there that day.
+pub fn restore(backup: &Backup) -> Result<PathBuf, String> {
+    let root = root().ok_or("no data directory")?;
+    let stamp = clock::now()
+        .format(format_description!("[year]-[month]-[day]-[hour][minute][second]"))
+        .map_err(|e| e.to_string())?;
+    put_back(backup, &root.join(format!("{}{}", SAFETY_PREFIX, stamp)), &files())
+}
+
+fn put_back(backup: &Backup, safety: &Path, files: &[(&'static str, PathBuf)]) -> Result<PathBuf, String> {
+    copy_files(safety, files)?;
+    for (name, current) in files {
+        let saved = backup.path.join(name);
+        if saved.exists() {
+            fs::copy(&saved, current).map_err(|e| format!("{}: {}", current.display(), e))?;
+        } else if current.exists() {
+            fs::remove_file(current).map_err(|e| format!("{}: {}", current.display(), e))?;
+        }
+    }
+    log::info!("Restored the backup of {}, previous files are in {}", backup.date, safety.display());
+    Ok(safety.to_path_buf())
+}
+
+fn root() -> Option<PathBuf> {
+    paths::data_dir().map(|dir| dir.join(DIR_NAME))
+}
+
+/// Everything backed up, by file name in the backup and current path.
+fn files() -> Vec<(&'static str, PathBuf)> {
+    files_in(paths::data_dir(), paths::config_dir())
+}
+
+fn files_in(data: Option<PathBuf>, config: Option<PathBuf>) -> Vec<(&'static str, PathBuf)> {
+    let mut files = Vec::new();
+    if let Some(dir) = data {
+        files.extend(history::FILES.iter().map(|name| (*name, dir.join(name))));
+    }
+    if let Some(dir) = config {
+        files.push((settings::FILE_NAME, dir.join(settings::FILE_NAME)));
+    }
+    files
+}
+
+// Copied into a temporary folder and renamed, so a half-written backup never shows up as a day
+fn take(root: &Path, today: Date, files: &[(&'static str, PathBuf)]) -> Result<(), String> {
+    let target = root.join(today.to_string());
+    if target.exists() {
+        return Ok(());
+    }
+    let partial = root.join(format!("{}.partial", today));
+    let _ = fs::remove_dir_all(&partial);
+    copy_files(&partial, files)?;
+    fs::rename(&partial, &target).map_err(|e| e.to_string())
+}
+
+fn copy_files(target: &Path, files: &[(&'static str, PathBuf)]) -> Result<(), String> {
+    fs::create_dir_all(target).map_err(|e| format!("{}: {}", target.display(), e))?;
+    for (name, current) in files {
+        if current.exists() {
+            fs::copy(current, target.join(name)).map_err(|e| format!("{}: {}", current.display(), e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Removes all but the newest `keep` daily backups, and any left half-written.
+fn rotate(root: &Path, keep: usize) {
+    let backups = days(root);
+    let old = backups.len().saturating_sub(keep);
+    for (_, path) in &backups[..old] {
+        if let Err(err) = fs::remove_dir_all(path) {
+            log::warn!("Cannot remove old backup {}: {}", path.display(), err);
+        }
+    }
+    let Ok(entries) = fs::read_dir(root) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        if entry.file_name().to_string_lossy().ends_with(".partial") {
+            let _ = fs::remove_dir_all(entry.path());
+        }
+    }
+}
+
+/// Daily backup folders, oldest first. A missing `backups/` folder just means none.
+fn days(root: &Path) -> Vec<(Date, PathBuf)> {
+    let Ok(entries) = fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let mut days: Vec<(Date, PathBuf)> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let date = Date::parse(&name.to_string_lossy(), format_description!("[year]-[month]-[day]")).ok()?;
+            Some((date, entry.path()))
+        })
+        .collect();
+    days.sort_by_key(|(date, _)| *date);
+    days
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::date;
+
+    // A data and a config dir of their own, removed afterwards
+    struct Scratch(PathBuf);
+
+    impl Scratch {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("ferrisfocus-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(dir.join("data")).unwrap();
+            fs::create_dir_all(dir.join("config")).unwrap();
+            Self(dir)
+        }
+
+        fn root(&self) -> PathBuf {
+            self.0.join("data").join(DIR_NAME)
+        }
+
+        fn files(&self) -> Vec<(&'static str, PathBuf)> {
+            files_in(Some(self.0.join("data")), Some(self.0.join("config")))
+        }
+
+        fn write(&self, name: &str, text: &str) {
+            let (_, path) = self.files().into_iter().find(|(file, _)| *file == name).unwrap();
+            fs::write(path, text).unwrap();
+        }
+
+        fn read(&self, name: &str) -> Option<String> {
+            let (_, path) = self.files().into_iter().find(|(file, _)| *file == name).unwrap();
+            fs::read_to_string(path).ok()
+        }
+    }
+
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    // Backed up like the rest, but private to the history
+    const NAPS: &str = "naps.jsonl";
+
+    fn names(root: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(root).unwrap().flatten().map(|entry| entry.file_name().to_string_lossy().into_owned()).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn the_first_backup_of_the_day_is_the_one_kept() {
+        let scratch = Scratch::new("backup-first");
+        scratch.write(history::SESSIONS_FILE, "morning\n");
+        scratch.write(settings::FILE_NAME, "work_minutes = 25\n");
+        take(&scratch.root(), date!(2024 - 03 - 04), &scratch.files()).unwrap();
+        scratch.write(history::SESSIONS_FILE, "morning\nafternoon\n");
+        take(&scratch.root(), date!(2024 - 03 - 04), &scratch.files()).unwrap();
+
+        let day = scratch.root().join("2024-03-04");
+        assert_eq!(fs::read_to_string(day.join(history::SESSIONS_FILE)).unwrap(), "morning\n");
+        assert_eq!(fs::read_to_string(day.join(settings::FILE_NAME)).unwrap(), "work_minutes = 25\n");
+        // Files that didn't exist aren't made up
+        assert!(!day.join(NAPS).exists());
+        assert_eq!(names(&scratch.root()), ["2024-03-04"]);
+    }
+
+    #[test]
+    fn rotation_keeps_the_newest_days_and_what_isnt_a_day() {
+        let scratch = Scratch::new("backup-rotate");
+        let root = scratch.root();
+        scratch.write(history::SESSIONS_FILE, "a session\n");
+        // Taken out of order, so the folder order can't stand in for the dates
+        for day in [date!(2024 - 03 - 05), date!(2024 - 02 - 28), date!(2024 - 03 - 07), date!(2024 - 03 - 01)] {
+            take(&root, day, &scratch.files()).unwrap();
+        }
+        fs::create_dir_all(root.join("2024-03-08.partial")).unwrap();
+        fs::create_dir_all(root.join(format!("{}2024-03-01-120000", SAFETY_PREFIX))).unwrap();
+        fs::create_dir_all(root.join("notes")).unwrap();
+
+        let dates: Vec<Date> = days(&root).into_iter().map(|(date, _)| date).collect();
+        assert_eq!(dates, [date!(2024 - 02 - 28), date!(2024 - 03 - 01), date!(2024 - 03 - 05), date!(2024 - 03 - 07)]);
+
+        rotate(&root, 2);
+        assert_eq!(names(&root), ["2024-03-05", "2024-03-07", "before-restore-2024-03-01-120000", "notes"]);
+        // Nothing more to do a second time
+        rotate(&root, 2);
+        assert_eq!(days(&root).len(), 2);
+    }
+
+    #[test]
+    fn a_restore_puts_back_exactly_that_day_and_keeps_what_it_replaced() {
+        let scratch = Scratch::new("backup-restore");
+        let root = scratch.root();
+        scratch.write(history::SESSIONS_FILE, "monday\n");
+        scratch.write(settings::FILE_NAME, "work_minutes = 25\n");
+        take(&root, date!(2024 - 03 - 04), &scratch.files()).unwrap();
+        scratch.write(history::SESSIONS_FILE, "monday\nbroken");
+        scratch.write(settings::FILE_NAME, "work_minutes = 50\n");
+        scratch.write(NAPS, "a nap\n");
+
+        let (date, path) = days(&root).remove(0);
+        let backup = Backup { date, path, sessions: Some(1) };
+        let safety = put_back(&backup, &root.join(format!("{}test", SAFETY_PREFIX)), &scratch.files()).unwrap();
+
+        assert_eq!(scratch.read(history::SESSIONS_FILE).as_deref(), Some("monday\n"));
+        assert_eq!(scratch.read(settings::FILE_NAME).as_deref(), Some("work_minutes = 25\n"));
+        // Not in the backup, so not there that day
+        assert_eq!(scratch.read(NAPS), None);
+
+        assert_eq!(fs::read_to_string(safety.join(history::SESSIONS_FILE)).unwrap(), "monday\nbroken");
+        assert_eq!(fs::read_to_string(safety.join(settings::FILE_NAME)).unwrap(), "work_minutes = 50\n");
+        assert_eq!(fs::read_to_string(safety.join(NAPS)).unwrap(), "a nap\n");
+        // The safety copy is no day's backup, and outlives rotation
+        rotate(&root, 1);
+        assert_eq!(days(&root).len(), 1);
+        assert!(safety.exists());
+    }
+}
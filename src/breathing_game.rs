@@ -0,0 +1,145 @@
+//! A minute's game for breaks: a circle grows for four seconds as you breathe in and shrinks for
+//! four as you breathe out, and Space is tapped each time it turns. The score is how closely the
+//! taps kept to the turns, shown at the end and only kept, by day, for those who ask. Compiled
+//! in with the `games` feature, on by default, for those who'd rather have no games in a timer.
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use time::Date;
+
+use crate::paths;
+
+pub const LENGTH: Duration = Duration::from_secs(60);
+const HALF_BREATH: Duration = Duration::from_secs(4);
+// Taps further than this from a turn count for nothing
+const TOLERANCE: Duration = Duration::from_millis(1_000);
+const FILE_NAME: &str = "breathing_scores.json";
+
+pub struct Game {
+    started: Instant,
+    /// When Space was tapped, since the start.
+    taps: Vec<Duration>,
+    /// Set once the minute is up.
+    score: Option<u32>,
+}
+
+pub enum Outcome {
+    /// The minute is up, with the accuracy in percent.
+    Finished(u32),
+    Closed,
+}
+
+impl Game {
+    pub fn new(now: Instant) -> Self {
+        Self { started: now, taps: Vec::new(), score: None }
+    }
+
+    pub fn running(&self) -> bool {
+        self.score.is_none()
+    }
+}
+
+/// How closely `taps` kept to the turns up to `until`, from 0 to 1. Each turn counts by its
+/// closest tap, and a tap near no turn counts as a missed one, so tapping all the time doesn't pay.
+fn accuracy(taps: &[Duration], until: Duration) -> f32 {
+    let turns: Vec<Duration> = (1..).map(|n| HALF_BREATH * n).take_while(|turn| *turn <= until).collect();
+    let closeness = |tap: Duration, turn: Duration| 1.0 - (tap.abs_diff(turn).as_secs_f32() / TOLERANCE.as_secs_f32()).min(1.0);
+    let hit: f32 = turns.iter().map(|turn| taps.iter().map(|tap| closeness(*tap, *turn)).fold(0.0, f32::max)).sum();
+    let stray = taps.iter().filter(|tap| turns.iter().all(|turn| tap.abs_diff(*turn) >= TOLERANCE)).count();
+    match turns.len() + stray {
+        0 => 0.0,
+        counted => hit / counted as f32,
+    }
+}
+
+/// The game, then its score. Space taps and Escape closes. `still` draws a bar instead of the
+/// pulsing circle.
+pub fn show(ui: &mut egui::Ui, game: &mut Game, now: Instant, still: bool) -> Option<Outcome> {
+    let elapsed = now.saturating_duration_since(game.started).min(LENGTH);
+    if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+        return Some(Outcome::Closed);
+    }
+    if let Some(score) = game.score {
+        ui.label(format!("{} % in time with your breath", score));
+        let mut closed = false;
+        ui.horizontal(|ui| {
+            if ui.button("Again").clicked() {
+                *game = Game::new(now);
+            }
+            closed = ui.button("Close").clicked();
+        });
+        return closed.then_some(Outcome::Closed);
+    }
+    if ui.input(|i| i.key_pressed(egui::Key::Space)) {
+        game.taps.push(elapsed);
+    }
+    if elapsed >= LENGTH {
+        let score = (accuracy(&game.taps, LENGTH) * 100.0).round() as u32;
+        game.score = Some(score);
+        return Some(Outcome::Finished(score));
+    }
+    let into = elapsed.as_secs_f32() % (HALF_BREATH.as_secs_f32() * 2.0) / HALF_BREATH.as_secs_f32();
+    let (word, size) = if into < 1.0 { ("Breathe in", into) } else { ("Breathe out", 2.0 - into) };
+    ui.label(word);
+    if still {
+        ui.add(egui::ProgressBar::new(size).desired_width(120.0));
+    } else {
+        let (rect, _) = ui.allocate_exact_size(egui::vec2(120.0, 120.0), egui::Sense::hover());
+        // Eased, so the turns feel like the top and bottom of a breath
+        let eased = (1.0 - (size * std::f32::consts::PI).cos()) / 2.0;
+        let color = ui.visuals().selection.bg_fill;
+        ui.painter().circle_filled(rect.center(), 20.0 + 38.0 * eased, color.gamma_multiply(0.6));
+        ui.painter().circle_stroke(rect.center(), 60.0, egui::Stroke::new(1.0, color));
+    }
+    let left = (LENGTH - elapsed).as_secs();
+    ui.weak(format!("Tap Space as it turns · {} s left · Esc stops", left));
+    None
+}
+
+/// Scores by day, kept only when the setting asks for it.
+#[derive(Default, Serialize, Deserialize)]
+pub struct Scores {
+    days: BTreeMap<Date, Vec<u32>>,
+}
+
+impl Scores {
+    pub fn load() -> Self {
+        path()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn add(&mut self, day: Date, score: u32) {
+        self.days.entry(day).or_default().push(score);
+        self.save();
+    }
+
+    /// Rounds played on `day` and the best of them.
+    pub fn day(&self, day: Date) -> Option<(usize, u32)> {
+        let scores = self.days.get(&day)?;
+        Some((scores.len(), scores.iter().copied().max()?))
+    }
+
+    fn save(&self) {
+        let Some(path) = path() else {
+            return;
+        };
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let result = serde_json::to_string(self)
+            .map_err(|e| e.to_string())
+            .and_then(|json| std::fs::write(&path, json).map_err(|e| e.to_string()));
+        if let Err(err) = result {
+            log::warn!("Cannot save {}: {}", path.display(), err);
+        }
+    }
+}
+
+fn path() -> Option<PathBuf> {
+    paths::data_dir().map(|dir| dir.join(FILE_NAME))
+}
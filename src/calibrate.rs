@@ -0,0 +1,179 @@
+//! The calibration week in progress: when it started, the preset to go back to, and a trial
+//! for each focus session, kept in a file of its own until the result is applied or the week
+//! abandoned. The schedule and the scoring are in [`pomodoro_timer::calibration`].
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use time::Date;
+
+use pomodoro_timer::calibration::{self, Trial};
+
+use crate::paths;
+
+const FILE_NAME: &str = "calibration.json";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Calibration {
+    pub started: Date,
+    /// The preset in use before, put back when the calibration is abandoned.
+    pub previous_preset: Option<String>,
+    pub trials: Vec<Trial>,
+}
+
+pub enum Choice {
+    /// Make a preset with focus sessions this many minutes long.
+    Apply(u32),
+    Abandon,
+}
+
+impl Calibration {
+    pub fn start(today: Date, previous_preset: Option<String>) -> Self {
+        let calibration = Self { started: today, previous_preset, trials: Vec::new() };
+        calibration.save();
+        calibration
+    }
+
+    pub fn load() -> Option<Self> {
+        let text = std::fs::read_to_string(path()?).ok()?;
+        serde_json::from_str(&text).map_err(|err| log::warn!("Cannot read the calibration: {}", err)).ok()
+    }
+
+    fn save(&self) {
+        let Some(path) = path() else {
+            return;
+        };
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let result = serde_json::to_string(self)
+            .map_err(|e| e.to_string())
+            .and_then(|json| std::fs::write(&path, json).map_err(|e| e.to_string()));
+        if let Err(err) = result {
+            log::warn!("Cannot save {}: {}", path.display(), err);
+        }
+    }
+
+    /// Deletes the file once the calibration was applied or abandoned.
+    pub fn remove() {
+        if let Some(path) = path().filter(|path| path.exists()) {
+            if let Err(err) = std::fs::remove_file(&path) {
+                log::warn!("Cannot remove {}: {}", path.display(), err);
+            }
+        }
+    }
+
+    // Counted from 0
+    fn day(&self, today: Date) -> u32 {
+        u32::try_from((today - self.started).whole_days()).unwrap_or(0)
+    }
+
+    pub fn over(&self, today: Date) -> bool {
+        self.day(today) >= calibration::DAYS
+    }
+
+    /// How long the next focus session is, in minutes; None once the week is over.
+    pub fn next_minutes(&self, today: Date) -> Option<u32> {
+        (!self.over(today)).then(|| calibration::scheduled(self.day(today), self.trials.len() as u32))
+    }
+
+    pub fn record(&mut self, trial: Trial) {
+        self.trials.push(trial);
+        self.save();
+    }
+
+    /// Rates the session recorded last.
+    pub fn rate(&mut self, focus: u8) {
+        if let Some(trial) = self.trials.last_mut() {
+            trial.focus = Some(focus);
+            self.save();
+        }
+    }
+
+    /// How far along it is with the numbers so far, and the recommendation once the week is
+    /// over. Returns what was chosen.
+    pub fn show(&self, ui: &mut egui::Ui, today: Date) -> Option<Choice> {
+        let recommended = calibration::recommend(&self.trials);
+        match self.next_minutes(today) {
+            Some(minutes) => {
+                ui.label(format!("Day {} of {}: the next focus session is {} minutes", self.day(today) + 1, calibration::DAYS, minutes));
+                ui.weak("Rate each session as it ends. Breaks stay as they are.");
+            }
+            None => {
+                ui.label("The calibration week is over");
+            }
+        }
+        egui::Grid::new("calibration").striped(true).show(ui, |ui| {
+            for heading in ["Length", "Sessions", "Finished", "Focus", "Score"] {
+                ui.strong(heading);
+            }
+            ui.end_row();
+            for score in calibration::scores(&self.trials) {
+                let length = format!("{} min", score.minutes);
+                if Some(score.minutes) == recommended && self.over(today) {
+                    ui.strong(length);
+                } else {
+                    ui.label(length);
+                }
+                ui.label(score.trials.to_string());
+                ui.label(score.completed.to_string());
+                ui.label(score.focus.map_or("–".to_owned(), |focus| format!("{:.1} / 5", focus)));
+                ui.label(if score.trials == 0 { "–".to_owned() } else { format!("{:.0} %", score.score * 100.0) });
+                ui.end_row();
+            }
+        });
+        let mut choice = None;
+        if self.over(today) {
+            match recommended {
+                Some(minutes) => {
+                    ui.label(format!("{} minute sessions suit you best: finished most often, with the best focus.", minutes));
+                    if ui.button("Apply as my preset").clicked() {
+                        choice = Some(Choice::Apply(minutes));
+                    }
+                }
+                None => {
+                    ui.label(format!("Too few sessions to tell; each length needs {}.", calibration::MIN_TRIALS));
+                }
+            }
+        }
+        let abandon = if self.over(today) { "Discard" } else { "Abandon calibration" };
+        if ui.button(abandon).on_hover_text("Goes back to the preset used before").clicked() {
+            choice = Some(Choice::Abandon);
+        }
+        choice
+    }
+}
+
+/// "How focused were you?" at the end of a calibration session: Some rating, or Some(None)
+/// when skipped. Escape skips.
+pub fn ask_rating(ctx: &egui::Context, minutes: u32) -> Option<Option<u8>> {
+    let mut rating = None;
+    egui::Window::new("Rate your focus")
+        .resizable(false)
+        .collapsible(false)
+        .title_bar(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, |ui| {
+            crate::compact_text(ui.style_mut());
+            ui.label(format!("How focused were you for these {} minutes?", minutes));
+            ui.horizontal(|ui| {
+                for focus in 1..=5 {
+                    if ui.button(focus.to_string()).clicked() {
+                        rating = Some(Some(focus));
+                    }
+                }
+                if ui.small_button("Skip").clicked() {
+                    rating = Some(None);
+                }
+            });
+            ui.weak("1 is distracted throughout, 5 is deep focus");
+        });
+    if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+        return Some(None);
+    }
+    rating
+}
+
+fn path() -> Option<PathBuf> {
+    paths::data_dir().map(|dir| dir.join(FILE_NAME))
+}
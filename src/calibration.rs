@@ -0,0 +1,117 @@
+//! Finding the focus length that suits someone. For a week, sessions take turns at a few
+//! lengths, and each is rated for focus as it ends; the length most often finished with good
+//! focus is recommended. Pure, so the schedule and the scoring can be checked against made-up
+//! weeks.
+//!
+//! Every length comes up as often as the others, and each day starts on a different one:
+//!
+//! ```
+//! use pomodoro_timer::calibration::{scheduled, LENGTHS};
+//!
+//! let monday: Vec<u32> = (0..4).map(|session| scheduled(0, session)).collect();
+//! assert_eq!(monday, [15, 20, 25, 35]);
+//! assert_eq!(scheduled(1, 0), 20);
+//!
+//! let mut uses = [0; 4];
+//! for day in 0..7 {
+//!     for session in 0..6 {
+//!         let minutes = scheduled(day, session);
+//!         uses[LENGTHS.iter().position(|length| *length == minutes).unwrap()] += 1;
+//!     }
+//! }
+//! assert!(uses.iter().all(|count| (10..=11).contains(count)));
+//! ```
+//!
+//! Sessions given up on count against their length, and a length needs a few trials before it
+//! can be recommended:
+//!
+//! ```
+//! use pomodoro_timer::calibration::{recommend, scores, Trial};
+//!
+//! let trial = |minutes, completed, focus| Trial { minutes, completed, focus };
+//! let week = [
+//!     trial(15, true, Some(3)),
+//!     trial(15, true, Some(3)),
+//!     trial(25, true, Some(5)),
+//!     trial(25, true, Some(4)),
+//!     trial(35, true, Some(4)),
+//!     trial(35, false, None),
+//!     trial(20, true, Some(5)),
+//! ];
+//! let scores = scores(&week);
+//! assert_eq!(scores[2].completed, 2);
+//! assert_eq!(scores[3].focus, Some(4.0));
+//! assert_eq!(recommend(&week), Some(25));
+//!
+//! // 20 minutes went well, but only once
+//! assert_eq!(scores[1].trials, 1);
+//! assert_eq!(recommend(&week[..2]), Some(15));
+//! assert_eq!(recommend(&[]), None);
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+/// The focus lengths tried, in minutes.
+pub const LENGTHS: [u32; 4] = [15, 20, 25, 35];
+/// How many days a calibration runs.
+pub const DAYS: u32 = 7;
+/// Trials a length needs before it can be recommended.
+pub const MIN_TRIALS: u32 = 2;
+// Taken for finished sessions that weren't rated
+const NEUTRAL_FOCUS: f32 = 3.0;
+
+/// The length of the `session`th calibration session on day `day` of the calibration, both
+/// counted from 0. The lengths take turns, one further along each day.
+pub fn scheduled(day: u32, session: u32) -> u32 {
+    LENGTHS[((day + session) % LENGTHS.len() as u32) as usize]
+}
+
+/// One calibration session.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Trial {
+    pub minutes: u32,
+    /// Ran to its end rather than being reset or skipped.
+    pub completed: bool,
+    /// How well it went, from 1 to 5; None when not rated.
+    pub focus: Option<u8>,
+}
+
+/// How one length did.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Score {
+    pub minutes: u32,
+    pub trials: u32,
+    pub completed: u32,
+    /// The average rating of the finished sessions that were rated.
+    pub focus: Option<f32>,
+    /// The share finished times the average focus out of 5, from 0 to 1.
+    pub score: f32,
+}
+
+/// The score of each of [`LENGTHS`], in that order.
+pub fn scores(trials: &[Trial]) -> Vec<Score> {
+    LENGTHS
+        .iter()
+        .map(|&minutes| {
+            let tried: Vec<&Trial> = trials.iter().filter(|trial| trial.minutes == minutes).collect();
+            let completed = tried.iter().filter(|trial| trial.completed).count() as u32;
+            let ratings: Vec<f32> = tried.iter().filter(|trial| trial.completed).filter_map(|trial| trial.focus).map(f32::from).collect();
+            let focus = (!ratings.is_empty()).then(|| ratings.iter().sum::<f32>() / ratings.len() as f32);
+            let score = match tried.len() {
+                0 => 0.0,
+                trials => completed as f32 / trials as f32 * focus.unwrap_or(NEUTRAL_FOCUS) / 5.0,
+            };
+            Score { minutes, trials: tried.len() as u32, completed, focus, score }
+        })
+        .collect()
+}
+
+/// The best scoring length among those tried at least [`MIN_TRIALS`] times, the longer one on
+/// a tie; None until one has been.
+pub fn recommend(trials: &[Trial]) -> Option<u32> {
+    scores(trials)
+        .into_iter()
+        .filter(|score| score.trials >= MIN_TRIALS)
+        .max_by(|a, b| a.score.total_cmp(&b.score).then(a.minutes.cmp(&b.minutes)))
+        .map(|score| score.minutes)
+}
@@ -0,0 +1,162 @@
+//! Self-tests for features that rely on the operating system or desktop, which otherwise fail
+//! quietly: each one tries the real thing once and, if it doesn't work, says what to do here.
+
+use eframe::egui;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Capability {
+    /// Noticing calls, for pausing during them.
+    Microphone,
+    /// The battery level, for saving power.
+    Battery,
+    /// Keeping the screen on in kiosk mode.
+    ScreenSaver,
+    /// Tinting window borders during focus.
+    WindowBorders,
+    /// Screenshots at the end of focus sessions.
+    Screenshots,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 5] =
+        [Capability::Microphone, Capability::Battery, Capability::ScreenSaver, Capability::WindowBorders, Capability::Screenshots];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Capability::Microphone => "Call detection",
+            Capability::Battery => "Battery level",
+            Capability::ScreenSaver => "Keeping the screen on",
+            Capability::WindowBorders => "Window border tint",
+            Capability::Screenshots => "Screenshots",
+        }
+    }
+
+    /// Runs the self-test; takes a moment, as it may start a program or call over D-Bus.
+    pub fn check(self) -> Check {
+        let result = match self {
+            Capability::Microphone => microphone(),
+            Capability::Battery => battery(),
+            Capability::ScreenSaver => screen_saver(),
+            Capability::WindowBorders => window_borders(),
+            Capability::Screenshots => crate::screenshots::self_test(),
+        };
+        Check { capability: self, result }
+    }
+
+    // What to try when the self-test fails, for this platform
+    fn remedy(self) -> &'static str {
+        match self {
+            #[cfg(target_os = "linux")]
+            Capability::Microphone => {
+                "Install pactl: pulseaudio-utils on Debian, Ubuntu and Fedora, libpulse on Arch. It works with PipeWire too, through pipewire-pulse."
+            }
+            #[cfg(not(target_os = "linux"))]
+            Capability::Microphone => "Call detection is only available on Linux for now.",
+            #[cfg(target_os = "linux")]
+            Capability::Battery => "Desktops have no battery, so they never need to save power. On a laptop, check that the battery is listed under /sys/class/power_supply.",
+            #[cfg(not(target_os = "linux"))]
+            Capability::Battery => "The battery level is only read on Linux for now.",
+            #[cfg(target_os = "linux")]
+            Capability::ScreenSaver => {
+                "The screen saver is asked over D-Bus (org.freedesktop.ScreenSaver), which GNOME, KDE and Xfce provide. On a plain window manager, turn blanking off yourself, e.g. with xset s off -dpms."
+            }
+            #[cfg(target_os = "macos")]
+            Capability::ScreenSaver => "caffeinate ships with macOS; check that /usr/bin is in the PATH the app was started with.",
+            #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+            Capability::ScreenSaver => "Keeping the screen on isn't supported here; set the screen's power settings to never turn it off.",
+            #[cfg(target_os = "linux")]
+            Capability::WindowBorders => "Border tint works on Hyprland and Sway. Start the app from within the session so it sees HYPRLAND_INSTANCE_SIGNATURE or SWAYSOCK.",
+            #[cfg(not(target_os = "linux"))]
+            Capability::WindowBorders => "Border tint is only available on Linux with Hyprland or Sway.",
+            #[cfg(target_os = "linux")]
+            Capability::Screenshots => {
+                "Install grim on Wayland, or ImageMagick (for import) and xdotool on X11. Picking a window by its title on Wayland needs Hyprland or Sway."
+            }
+            #[cfg(target_os = "macos")]
+            Capability::Screenshots => "screencapture ships with macOS; allow the app to record the screen under System Settings → Privacy & Security → Screen Recording.",
+            #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+            Capability::Screenshots => "Screenshots are only available on Linux and macOS for now.",
+        }
+    }
+}
+
+/// The outcome of one self-test: what was found, or what went wrong.
+#[derive(Clone, Debug)]
+pub struct Check {
+    pub capability: Capability,
+    pub result: Result<String, String>,
+}
+
+impl Check {
+    /// The result in a line, with the remedy below it when the test failed.
+    pub fn show(&self, ui: &mut egui::Ui) {
+        match &self.result {
+            Ok(found) => {
+                ui.weak(format!("✔ {}", found));
+            }
+            Err(problem) => {
+                ui.colored_label(egui::Color32::RED, format!("⚠ {}", problem));
+                ui.add(egui::Label::new(egui::RichText::new(self.capability.remedy()).weak()).wrap());
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn microphone() -> Result<String, String> {
+    let output = std::process::Command::new("pactl").arg("info").output().map_err(|err| match err.kind() {
+        std::io::ErrorKind::NotFound => "pactl is not installed".to_owned(),
+        _ => format!("cannot run pactl: {}", err),
+    })?;
+    if !output.status.success() {
+        return Err("no PulseAudio or PipeWire server answered".to_owned());
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    let server = text.lines().find_map(|line| line.strip_prefix("Server Name:")).unwrap_or("sound server").trim();
+    Ok(format!("Asking {} which programs record", server))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn microphone() -> Result<String, String> {
+    Err("not supported on this platform".to_owned())
+}
+
+fn battery() -> Result<String, String> {
+    let battery = crate::power::read().ok_or("no battery found")?;
+    Ok(format!("{} %, {}", battery.percent, if battery.discharging { "on battery" } else { "plugged in" }))
+}
+
+#[cfg(target_os = "linux")]
+fn screen_saver() -> Result<String, String> {
+    let connection = zbus::blocking::Connection::session().map_err(|err| format!("no session bus: {}", err))?;
+    let bus = zbus::blocking::fdo::DBusProxy::new(&connection).map_err(|err| err.to_string())?;
+    let name = zbus::names::BusName::try_from("org.freedesktop.ScreenSaver").map_err(|err| err.to_string())?;
+    match bus.name_has_owner(name) {
+        Ok(true) => Ok("The desktop's screen saver can be held off".to_owned()),
+        Ok(false) => Err("no screen saver service on the session bus".to_owned()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn screen_saver() -> Result<String, String> {
+    match std::process::Command::new("caffeinate").args(["-t", "0"]).status() {
+        Ok(_) => Ok("caffeinate is available".to_owned()),
+        Err(err) => Err(format!("cannot run caffeinate: {}", err)),
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn screen_saver() -> Result<String, String> {
+    Err("not supported on this platform".to_owned())
+}
+
+#[cfg(target_os = "linux")]
+fn window_borders() -> Result<String, String> {
+    crate::compositor::BorderTint::self_test().map(|name| format!("{} answers", name))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn window_borders() -> Result<String, String> {
+    Err("not supported on this platform".to_owned())
+}
@@ -0,0 +1,103 @@
+//! What changed in each version, from CHANGELOG.md as parsed by the build script, shown once
+//! after an update and from the Help menu.
+
+use eframe::egui;
+use std::path::PathBuf;
+
+use crate::paths;
+
+const FILE_NAME: &str = "last_version";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Category {
+    Added,
+    Changed,
+    Fixed,
+    Removed,
+}
+
+impl Category {
+    const ALL: [Category; 4] = [Category::Added, Category::Changed, Category::Fixed, Category::Removed];
+
+    fn title(self) -> &'static str {
+        match self {
+            Category::Added => "✨ New",
+            Category::Changed => "🔧 Changed",
+            Category::Fixed => "🐛 Fixed",
+            Category::Removed => "🗑 Removed",
+        }
+    }
+}
+
+pub struct Release {
+    pub version: &'static str,
+    pub entries: &'static [(Category, &'static str)],
+}
+
+/// Newest first, as in the file.
+pub const RELEASES: &[Release] = include!(concat!(env!("OUT_DIR"), "/changelog.rs"));
+
+fn parse(version: &str) -> Vec<u64> {
+    version.split('.').map(|part| part.trim().parse().unwrap_or(0)).collect()
+}
+
+/// Records this run's version, and returns the releases since the one that ran before if that
+/// was older. Nothing is shown on the very first run, which has nothing to compare with.
+pub fn after_update() -> Vec<&'static Release> {
+    let current = env!("CARGO_PKG_VERSION");
+    let Some(path) = path() else {
+        return Vec::new();
+    };
+    let seen = std::fs::read_to_string(&path).ok();
+    if seen.as_deref().map(str::trim) != Some(current) {
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        if let Err(err) = std::fs::write(&path, current) {
+            log::warn!("Cannot save {}: {}", path.display(), err);
+        }
+    }
+    let Some(seen) = seen.map(|seen| parse(&seen)) else {
+        return Vec::new();
+    };
+    let current = parse(current);
+    RELEASES.iter().filter(|release| parse(release.version) > seen && parse(release.version) <= current).collect()
+}
+
+fn path() -> Option<PathBuf> {
+    paths::data_dir().map(|dir| dir.join(FILE_NAME))
+}
+
+/// The entries of `releases` grouped by category under each version.
+pub fn window(ctx: &egui::Context, open: &mut bool, releases: &[&Release]) {
+    let title = match releases {
+        [release] => format!("What's new in {}", release.version),
+        _ => "What's new".to_owned(),
+    };
+    egui::Window::new(title)
+        .id(egui::Id::new("changelog"))
+        .open(open)
+        .collapsible(false)
+        .default_width(340.0)
+        .show(ctx, |ui| {
+            crate::compact_text(ui.style_mut());
+            egui::ScrollArea::vertical().max_height(320.0).show(ui, |ui| {
+                for release in releases {
+                    if releases.len() > 1 {
+                        ui.heading(release.version);
+                    }
+                    for category in Category::ALL {
+                        let mut entries = release.entries.iter().filter(|(c, _)| *c == category).peekable();
+                        if entries.peek().is_none() {
+                            continue;
+                        }
+                        ui.strong(category.title());
+                        for (_, entry) in entries {
+                            ui.label(format!("• {}", entry));
+                        }
+                        ui.add_space(4.0);
+                    }
+                }
+            });
+        });
+}
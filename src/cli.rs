@@ -0,0 +1,129 @@
+use clap::{Parser, Subcommand};
+use pomodoro_timer::duration_text::{self, Unit};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::quick;
+
+/// A Pomodoro timer written in Rust.
+#[derive(Parser, Debug)]
+#[command(name = "ferrisfocus", version = crate::about::VERSION)]
+pub struct Cli {
+    /// Run with accelerated time and a throwaway history, for demos and for testing hooks
+    // Deliberately only available as a flag, so it can't be left on by accident
+    #[arg(long)]
+    pub demo: bool,
+
+    /// How many times faster than real time the demo runs
+    #[arg(long, default_value_t = 60.0, requires = "demo", value_parser = parse_speed)]
+    pub speed: f64,
+
+    /// Fill the screen with the timer, for a wall display; no buttons, and only Ctrl+Shift+Q quits
+    #[arg(long)]
+    pub kiosk: bool,
+
+    /// Name of the timer preset to use in kiosk mode
+    #[arg(long, requires = "kiosk")]
+    pub preset: Option<String>,
+
+    /// Show the developer panel in the Help menu, with self-tests and benchmarks for bug reports
+    #[arg(long)]
+    pub dev: bool,
+
+    /// How log lines are written to stderr; overrides the setting
+    #[arg(long, value_enum)]
+    pub log_format: Option<crate::logging::Format>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Feed an exported history file through the statistics as if it were recorded live
+    #[command(hide = true)]
+    Replay {
+        /// Session file in the history.jsonl format
+        file: PathBuf,
+
+        /// How many times faster than real time the recorded sessions are played back
+        #[arg(long, default_value_t = 100.0, value_parser = parse_speed)]
+        speed: f64,
+    },
+
+    /// Open the app in the state saved by Help → Save state snapshot, with throwaway settings and history
+    #[command(hide = true)]
+    Restore {
+        /// Snapshot file from the snapshots folder in the data directory
+        file: PathBuf,
+    },
+
+    /// Print the running timer for a tmux status line, or nothing if the app isn't running
+    TmuxStatus {
+        /// Leave out tmux color codes
+        #[arg(long)]
+        no_color: bool,
+
+        /// What to print, with placeholders such as {icon}, {remaining} and {paused}
+        #[arg(long, default_value = "{icon} {remaining} {paused}")]
+        format: String,
+    },
+
+    /// Print the remaining time for a shell prompt, e.g. "🍅12m", or nothing while idle
+    Prompt {
+        /// Only print when less than this is left, in minutes or e.g. 1h30m or 90s
+        #[arg(long, value_parser = parse_minutes)]
+        threshold: Option<Duration>,
+    },
+
+    /// Print the timer as JSON for a Waybar custom module
+    Waybar {
+        /// Keep printing a line whenever the timer changes, until the app exits
+        #[arg(long)]
+        follow: bool,
+    },
+
+    /// Write the task list, with estimates, spent pomodoros and session ids, as JSON
+    ExportTasks {
+        /// Where to write it; printed when left out
+        file: Option<PathBuf>,
+    },
+
+    /// Merge a task list exported on another machine into this one, through the app if it is running
+    ImportTasks {
+        /// File written by export-tasks
+        file: PathBuf,
+    },
+
+    /// Remind once after this long, apart from the pomodoro cycle and its history; in the app if it is running, else here
+    Quick {
+        /// In minutes, or e.g. 12m, 1h30m or 90s
+        #[arg(value_parser = parse_quick_length)]
+        length: Duration,
+
+        /// What to remind of
+        #[arg(long)]
+        message: Option<String>,
+    },
+
+    /// Print a debug recording in readable form
+    Inspect {
+        /// Recording file, usually recording.bin in the data folder
+        file: PathBuf,
+    },
+}
+
+fn parse_minutes(value: &str) -> Result<Duration, String> {
+    duration_text::parse(value, Unit::Minutes).map_err(|err| err.to_string())
+}
+
+fn parse_quick_length(value: &str) -> Result<Duration, String> {
+    parse_minutes(value).and_then(quick::check)
+}
+
+fn parse_speed(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(speed) if speed.is_finite() && speed > 0.0 && speed <= 10_000.0 => Ok(speed),
+        _ => Err("expected a number between 0 and 10000, e.g. 60".to_owned()),
+    }
+}
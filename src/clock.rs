@@ -0,0 +1,164 @@
+use std::cell::Cell;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use time::{OffsetDateTime, UtcOffset};
+
+static LOCAL_OFFSET: OnceLock<UtcOffset> = OnceLock::new();
+
+#[cfg(test)]
+thread_local! {
+    /// The computer's time zone as far as tests on this thread are concerned.
+    pub static TEST_OFFSET: Cell<Option<UtcOffset>> = const { Cell::new(None) };
+}
+
+/// Reads the local UTC offset. On Unix this is only possible while the process is still
+/// single-threaded, so it has to run first thing in `main`.
+pub fn init() {
+    let offset = UtcOffset::current_local_offset().unwrap_or_else(|err| {
+        log::warn!("Cannot determine the local time zone, using UTC: {}", err);
+        UtcOffset::UTC
+    });
+    let _ = LOCAL_OFFSET.set(offset);
+}
+
+pub fn local_offset() -> UtcOffset {
+    #[cfg(test)]
+    if let Some(offset) = TEST_OFFSET.get() {
+        return offset;
+    }
+    LOCAL_OFFSET.get().copied().unwrap_or(UtcOffset::UTC)
+}
+
+/// The current wall-clock time in the local time zone.
+pub fn now() -> OffsetDateTime {
+    OffsetDateTime::now_utc().to_offset(local_offset())
+}
+
+/// Where the timer gets the time from, so it can be sped up for demos.
+pub trait Clock {
+    /// Monotonic time for measuring intervals.
+    fn instant(&self) -> Instant;
+    /// Wall-clock time for timestamps, in the local time zone.
+    fn wall(&self) -> OffsetDateTime;
+    /// Time since boot, suspend included, where it can be read; what the wall clock is checked
+    /// against for jumps.
+    fn boot(&self) -> Option<Duration> {
+        None
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for std::rc::Rc<C> {
+    fn instant(&self) -> Instant {
+        (**self).instant()
+    }
+
+    fn wall(&self) -> OffsetDateTime {
+        (**self).wall()
+    }
+
+    fn boot(&self) -> Option<Duration> {
+        (**self).boot()
+    }
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn instant(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn wall(&self) -> OffsetDateTime {
+        now()
+    }
+
+    #[cfg(target_os = "linux")]
+    fn boot(&self) -> Option<Duration> {
+        let uptime = std::fs::read_to_string("/proc/uptime").ok()?;
+        let secs: f64 = uptime.split_whitespace().next()?.parse().ok()?;
+        Some(Duration::from_secs_f64(secs))
+    }
+}
+
+/// Runs `factor` times faster than real time, starting from the moment it was created.
+pub struct ScaledClock {
+    origin: Instant,
+    origin_wall: OffsetDateTime,
+    factor: f64,
+}
+
+impl ScaledClock {
+    pub fn new(factor: f64) -> Self {
+        Self::starting_at(now(), factor)
+    }
+
+    /// Pretends it's `wall` right now, for replaying recorded days.
+    pub fn starting_at(wall: OffsetDateTime, factor: f64) -> Self {
+        Self { origin: Instant::now(), origin_wall: wall, factor }
+    }
+
+    fn scaled_elapsed(&self) -> Duration {
+        self.origin.elapsed().mul_f64(self.factor)
+    }
+}
+
+// Without a boot clock, since real time since boot says nothing about time sped up
+impl Clock for ScaledClock {
+    fn instant(&self) -> Instant {
+        self.origin + self.scaled_elapsed()
+    }
+
+    fn wall(&self) -> OffsetDateTime {
+        self.origin_wall + self.scaled_elapsed()
+    }
+}
+
+/// Stands still until moved on, so hours of timer can be run through in an instant.
+pub struct ManualClock {
+    instant: Cell<Instant>,
+    wall: Cell<OffsetDateTime>,
+    boot: Cell<Duration>,
+}
+
+impl ManualClock {
+    pub fn starting_now() -> Self {
+        Self::starting_at(now())
+    }
+
+    pub fn starting_at(wall: OffsetDateTime) -> Self {
+        Self { instant: Cell::new(Instant::now()), wall: Cell::new(wall), boot: Cell::new(Duration::ZERO) }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.instant.set(self.instant.get() + by);
+        self.wall.set(self.wall.get() + by);
+        self.boot.set(self.boot.get() + by);
+    }
+
+    /// Passes `by` suspended: the monotonic clock stops, the others go on.
+    #[cfg(test)]
+    pub fn sleep(&self, by: Duration) {
+        self.wall.set(self.wall.get() + by);
+        self.boot.set(self.boot.get() + by);
+    }
+
+    /// Sets the wall clock, as a user or a flat battery would, leaving real time alone.
+    #[cfg(test)]
+    pub fn set_wall(&self, wall: OffsetDateTime) {
+        self.wall.set(wall);
+    }
+}
+
+impl Clock for ManualClock {
+    fn instant(&self) -> Instant {
+        self.instant.get()
+    }
+
+    fn wall(&self) -> OffsetDateTime {
+        self.wall.get()
+    }
+
+    fn boot(&self) -> Option<Duration> {
+        Some(self.boot.get())
+    }
+}
@@ -0,0 +1,182 @@
+//! Whether the wall clock can be trusted with what happens at a time of day: the automatic theme
+//! and night sound, the late-night prompt and the appointment check. Each frame the wall clock's
+//! advance is compared with real time, and one that jumps (set by hand, a flat clock battery, a
+//! virtual machine resumed from a snapshot) holds them off until the clock has kept steady for a
+//! while, or until the user says it is right. Real time is the boot clock on Linux,
+//! which keeps counting while suspended, so waking up isn't taken for a jump there; elsewhere
+//! a jump forward looks just like sleep, so only jumps back are noticed.
+
+use std::time::{Duration, Instant};
+use time::OffsetDateTime;
+
+use crate::clock::Clock;
+
+/// Smaller differences are drift being corrected, or a slow frame.
+pub const JUMP: Duration = Duration::from_secs(120);
+/// How long the clock has to keep steady before time-of-day automations resume.
+pub const STEADY: Duration = Duration::from_secs(10 * 60);
+
+/// The clocks read at one moment.
+#[derive(Clone, Copy, Debug)]
+pub struct Reading {
+    pub instant: Instant,
+    /// Time since boot, suspend included; None where it can't be read.
+    pub boot: Option<Duration>,
+    pub wall: OffsetDateTime,
+}
+
+impl Reading {
+    pub fn of(clock: &dyn Clock) -> Self {
+        Self { instant: clock.instant(), boot: clock.boot(), wall: clock.wall() }
+    }
+}
+
+#[derive(Default)]
+pub struct ClockCheck {
+    last: Option<Reading>,
+    /// When the latest jump was noticed and by how many seconds, forward positive.
+    jump: Option<(Instant, i64)>,
+    /// The user vouched for the clock since.
+    trusted: bool,
+}
+
+impl ClockCheck {
+    /// Compares `reading` with the one before; the seconds the wall clock jumped by if it did.
+    pub fn observe(&mut self, reading: Reading) -> Option<i64> {
+        let last = self.last.replace(reading)?;
+        let (real, through_sleep) = match (last.boot, reading.boot) {
+            (Some(before), Some(after)) => (after.saturating_sub(before), true),
+            _ => (reading.instant.saturating_duration_since(last.instant), false),
+        };
+        let jumped = ((reading.wall - last.wall) - real).whole_seconds();
+        let limit = JUMP.as_secs() as i64;
+        if jumped > -limit && (jumped < limit || !through_sleep) {
+            return None;
+        }
+        self.jump = Some((reading.instant, jumped));
+        self.trusted = false;
+        Some(jumped)
+    }
+
+    /// Whether time-of-day automations hold off at `now`.
+    pub fn suspended(&self, now: Instant) -> bool {
+        !self.trusted && self.jump.is_some_and(|(at, _)| now.saturating_duration_since(at) < STEADY)
+    }
+
+    /// The user says the clock is right; automations resume until it jumps again.
+    pub fn trust(&mut self) {
+        self.trusted = true;
+    }
+}
+
+/// "1 h 5 min back", say.
+pub fn describe(jumped: i64) -> String {
+    let minutes = jumped.unsigned_abs().div_ceil(60);
+    let amount = match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("{} min", minutes),
+        (hours, 0) => format!("{} h", hours),
+        (hours, minutes) => format!("{} h {} min", hours, minutes),
+    };
+    format!("{} {}", amount, if jumped < 0 { "back" } else { "forward" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    const STEP: Duration = Duration::from_secs(1);
+
+    // A clock check read every second, as the app does
+    struct Sim {
+        check: ClockCheck,
+        at: Reading,
+    }
+
+    impl Sim {
+        fn new() -> Self {
+            let mut sim = Self { check: ClockCheck::default(), at: Reading { instant: Instant::now(), boot: Some(Duration::ZERO), wall: datetime!(2024-03-04 21:59 UTC) } };
+            sim.check.observe(sim.at);
+            sim
+        }
+
+        // Real time passes, and the wall clock with it, then the wall clock is set `set` s on
+        fn pass(&mut self, real: Duration, set: i64) -> Vec<i64> {
+            let mut jumps = Vec::new();
+            for _ in 0..real.as_secs().max(1) {
+                self.at.instant += STEP;
+                self.at.boot = self.at.boot.map(|boot| boot + STEP);
+                self.at.wall += STEP;
+                jumps.extend(self.check.observe(self.at));
+            }
+            self.at.wall += time::Duration::seconds(set);
+            jumps.extend(self.check.observe(self.at));
+            jumps
+        }
+
+        fn suspended(&self) -> bool {
+            self.check.suspended(self.at.instant)
+        }
+    }
+
+    #[test]
+    fn a_steady_clock_is_trusted() {
+        let mut sim = Sim::new();
+        assert!(sim.pass(Duration::from_secs(120), 0).is_empty());
+        // Drift being corrected isn't a jump
+        assert!(sim.pass(STEP, 30).is_empty());
+        assert!(!sim.suspended());
+    }
+
+    #[test]
+    fn jumps_either_way_hold_off_until_the_clock_keeps_steady() {
+        let mut sim = Sim::new();
+        let back = sim.pass(STEP, -3600);
+        assert_eq!(back.len(), 1);
+        assert!(back[0] <= -3500, "{:?}", back);
+        assert!(sim.suspended());
+        let forward = sim.pass(STEP, 7200);
+        assert_eq!(forward.len(), 1);
+        assert!(forward[0] >= 7100, "{:?}", forward);
+        sim.pass(STEADY - STEP, 0);
+        assert!(sim.suspended(), "resumed before the clock had kept steady");
+        sim.pass(STEP, 0);
+        assert!(!sim.suspended());
+    }
+
+    #[test]
+    fn a_clock_vouched_for_is_trusted_until_it_jumps_again() {
+        let mut sim = Sim::new();
+        sim.pass(STEP, 1800);
+        sim.check.trust();
+        assert!(!sim.suspended());
+        sim.pass(STEP, -1800);
+        assert!(sim.suspended());
+    }
+
+    #[test]
+    fn sleep_is_not_taken_for_a_jump() {
+        let mut sim = Sim::new();
+        // An hour asleep: the boot clock counts it, the monotonic clock doesn't
+        let asleep = Duration::from_secs(3600);
+        let at = sim.at;
+        sim.at = Reading { instant: at.instant + STEP, boot: at.boot.map(|boot| boot + STEP + asleep), wall: at.wall + STEP + asleep };
+        assert_eq!(sim.check.observe(sim.at), None);
+        assert!(!sim.suspended());
+    }
+
+    #[test]
+    fn without_a_boot_clock_only_jumps_back_are_noticed() {
+        let mut sim = Sim::new();
+        sim.at.boot = None;
+        assert!(sim.pass(STEP, 3600).is_empty());
+        assert_eq!(sim.pass(STEP, -3600).len(), 1);
+    }
+
+    #[test]
+    fn jumps_are_described_in_whole_minutes() {
+        assert_eq!(describe(-3600), "1 h back");
+        assert_eq!(describe(3900), "1 h 5 min forward");
+        assert_eq!(describe(-121), "3 min back");
+    }
+}
@@ -0,0 +1,119 @@
+//! A calm status display on a second monitor during focus sessions: the countdown, the task and
+//! the cycle's dots, fullscreen on a dark background in a window of its own. egui can neither
+//! list monitors nor put a window on one, so both go through the compositor.
+
+use eframe::egui;
+use std::time::{Duration, Instant};
+
+use crate::background::Background;
+use crate::compositor::{self, Monitor};
+
+/// The companion window's title, which the compositor finds it by.
+pub const TITLE: &str = "FerrisFocus focus companion";
+// How often the monitor is looked for while the companion is open
+const RECHECK: Duration = Duration::from_secs(5);
+// How long the window is tried to be moved for before it is left where it opened
+const PLACE_FOR: Duration = Duration::from_secs(3);
+const BACKGROUND: egui::Color32 = egui::Color32::from_rgb(12, 12, 14);
+
+/// The monitors to choose from, listed on a thread of their own.
+pub fn list_monitors() -> Background<Result<Vec<Monitor>, String>> {
+    Background::spawn("monitors", compositor::monitors)
+}
+
+/// What the companion shows.
+pub struct Status {
+    pub remaining: Duration,
+    /// None in privacy mode too.
+    pub task: Option<String>,
+    /// Sessions done in the cycle and its length; None without long breaks.
+    pub cycle: Option<(u32, u32)>,
+}
+
+/// Open for one focus session on one monitor.
+pub struct Companion {
+    monitor: String,
+    opened: Instant,
+    placed: bool,
+    /// Closed by hand or because the monitor went away; stays closed until the session ends.
+    closed: bool,
+    monitors: Background<Result<Vec<Monitor>, String>>,
+    checked: Instant,
+}
+
+impl Companion {
+    pub fn open(monitor: String, now: Instant) -> Self {
+        Self { monitor, opened: now, placed: false, closed: false, monitors: list_monitors(), checked: now }
+    }
+
+    pub fn monitor(&self) -> &str {
+        &self.monitor
+    }
+
+    /// True once, when a check finds the monitor no longer connected; the companion closes.
+    pub fn disconnected(&mut self, now: Instant) -> bool {
+        if self.closed {
+            return false;
+        }
+        let Some(found) = self.monitors.get() else {
+            return false;
+        };
+        // A compositor that didn't answer is asked again rather than taken as the monitor gone
+        let gone = found.as_ref().is_ok_and(|monitors| monitors.iter().all(|monitor| monitor.name != self.monitor));
+        if gone {
+            log::info!("Monitor {} went away, closing the focus companion", self.monitor);
+            self.closed = true;
+        } else if now - self.checked >= RECHECK {
+            self.monitors = list_monitors();
+            self.checked = now;
+        }
+        gone
+    }
+
+    /// Draws the companion, unless it was closed.
+    pub fn show(&mut self, ctx: &egui::Context, now: Instant, status: &Status) {
+        if self.closed {
+            return;
+        }
+        let id = egui::ViewportId::from_hash_of("focus companion");
+        // Opened windowed where the compositor likes, then moved and made fullscreen there
+        if !self.placed && now - self.opened < PLACE_FOR {
+            match compositor::move_to_monitor(TITLE, &self.monitor) {
+                Ok(()) => {
+                    self.placed = true;
+                    ctx.send_viewport_cmd_to(id, egui::ViewportCommand::Fullscreen(true));
+                }
+                // Usually because the window isn't mapped yet
+                Err(err) => log::debug!("Cannot move the focus companion yet: {}", err),
+            }
+        }
+        let builder = egui::ViewportBuilder::default().with_title(TITLE).with_inner_size([640.0, 400.0]).with_decorations(false);
+        let mut closed = false;
+        ctx.show_viewport_immediate(id, builder, |ctx, _| {
+            closed = ctx.input(|i| i.viewport().close_requested());
+            egui::CentralPanel::default().frame(egui::Frame::none().fill(BACKGROUND)).show(ctx, |ui| draw(ui, status));
+        });
+        if closed {
+            self.closed = true;
+        }
+    }
+}
+
+fn draw(ui: &mut egui::Ui, status: &Status) {
+    let height = ui.available_height();
+    let soft = egui::Color32::from_gray(150);
+    ui.vertical_centered(|ui| {
+        ui.add_space(height * 0.3);
+        let (minutes, seconds) = (status.remaining.as_secs() / 60, status.remaining.as_secs() % 60);
+        let digits = egui::RichText::new(format!("{:02}:{:02}", minutes, seconds)).size(height * 0.2).color(egui::Color32::from_gray(220));
+        ui.label(digits);
+        if let Some(task) = &status.task {
+            ui.label(egui::RichText::new(task).size(height * 0.04).color(soft));
+        }
+        if let Some((done, length)) = status.cycle {
+            let dots: String = (0..length).map(|n| if n < done { '●' } else { '○' }).collect();
+            ui.add_space(height * 0.03);
+            ui.label(egui::RichText::new(dots).size(height * 0.04).color(soft));
+        }
+    });
+}
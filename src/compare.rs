@@ -0,0 +1,167 @@
+//! Focus time of a few task labels or tags side by side, week by week, to answer questions
+//! like "am I spending enough on X compared to Y this month?"
+
+use eframe::egui;
+use time::Date;
+
+use crate::archive::Archive;
+use crate::history::Session;
+use crate::stats::{self, Grouping, StatsIndex};
+
+const MAX_LABELS: usize = 4;
+const PERIODS: [usize; 3] = [4, 8, 12];
+const COLORS: [egui::Color32; MAX_LABELS] = [
+    egui::Color32::from_rgb(38, 139, 210),
+    egui::Color32::from_rgb(211, 54, 130),
+    egui::Color32::from_rgb(133, 153, 0),
+    egui::Color32::from_rgb(203, 75, 22),
+];
+
+/// What is being compared; kept while the statistics window is closed.
+pub struct Comparison {
+    pub grouping: Grouping,
+    /// The labels or tags picked, as grouped.
+    pub labels: Vec<String>,
+    pub weeks: usize,
+    // What was last worked out from the sessions, for the revision and picks it was for
+    names: Option<((u64, Grouping), Vec<String>)>,
+    totals: Option<(Picks, Vec<Vec<u64>>)>,
+}
+
+// The revision of the sessions, the grouping, the labels picked, the first day and the weeks
+type Picks = (u64, Grouping, Vec<String>, Date, usize);
+
+impl Default for Comparison {
+    fn default() -> Self {
+        Self { grouping: Grouping::Label, labels: Vec::new(), weeks: PERIODS[0], names: None, totals: None }
+    }
+}
+
+// The value kept in `slot` if it was worked out for `key`, else `work`'s, kept from now on
+fn cached<K: PartialEq, V>(slot: &mut Option<(K, V)>, key: K, work: impl FnOnce() -> V) -> &V {
+    if !matches!(slot, Some((kept, _)) if *kept == key) {
+        *slot = None;
+    }
+    &slot.get_or_insert_with(|| (key, work())).1
+}
+
+/// The label or tag picker, one group of bars per week, and totals with each one's share,
+/// counted like the days of `index`. Archived labels are left out unless `include_archived`; returns whether one was archived or
+/// restored.
+/// `revision` changes with `sessions`, as [`History::revision`](crate::history::History::revision) does.
+#[allow(clippy::too_many_arguments)]
+pub fn show(
+    ui: &mut egui::Ui,
+    comparison: &mut Comparison,
+    sessions: &[Session],
+    revision: u64,
+    today: Date,
+    index: &StatsIndex,
+    archive: &mut Archive,
+    include_archived: bool,
+) -> bool {
+    ui.horizontal(|ui| {
+        ui.selectable_value(&mut comparison.grouping, Grouping::Label, "By label");
+        ui.selectable_value(&mut comparison.grouping, Grouping::Tag, "By tag");
+    });
+    let by_label = comparison.grouping == Grouping::Label;
+    let grouping = comparison.grouping;
+    let mut known = cached(&mut comparison.names, (revision, grouping), || grouping.names(sessions)).clone();
+    known.retain(|label| include_archived || !by_label || !archive.contains(label));
+    let mut archived = false;
+    if known.len() < 2 {
+        ui.weak(if by_label { "Give sessions a task label to compare them here." } else { "Add #tags to task labels to compare them here." });
+        return false;
+    }
+    comparison.labels.retain(|label| known.contains(label));
+    ui.horizontal_wrapped(|ui| {
+        for label in &known {
+            let mut picked = comparison.labels.contains(label);
+            let full = comparison.labels.len() >= MAX_LABELS && !picked;
+            let checkbox = ui.add_enabled(!full, egui::Checkbox::new(&mut picked, label));
+            if checkbox.changed() {
+                if picked {
+                    comparison.labels.push(label.clone());
+                } else {
+                    comparison.labels.retain(|l| l != label);
+                }
+            }
+            if !by_label {
+                continue;
+            }
+            checkbox.context_menu(|ui| {
+                crate::compact_text(ui.style_mut());
+                let is_archived = archive.contains(label);
+                if ui.button(if is_archived { "Unarchive label" } else { "Archive label" }).clicked() {
+                    archive.set(label, !is_archived);
+                    archived = true;
+                    ui.close_menu();
+                }
+            });
+        }
+    });
+    ui.horizontal(|ui| {
+        for weeks in PERIODS {
+            ui.selectable_value(&mut comparison.weeks, weeks, format!("{} weeks", weeks));
+        }
+    });
+    if comparison.labels.len() < 2 {
+        ui.weak(format!("Pick 2 to {} {}.", MAX_LABELS, if by_label { "labels" } else { "tags" }));
+        return archived;
+    }
+
+    // Weeks start on Monday, the last one being this week
+    let this_week = today - time::Duration::days(i64::from(today.weekday().number_days_from_monday()));
+    let first_day = this_week - time::Duration::weeks(comparison.weeks as i64 - 1);
+    let (labels, weeks) = (comparison.labels.clone(), comparison.weeks);
+    let totals = cached(&mut comparison.totals, (revision, grouping, labels, first_day, weeks), || {
+        stats::weekly_by(sessions, grouping, &comparison.labels, first_day, weeks, index.zone(), index.counting())
+    });
+    bars(ui, totals, first_day);
+
+    let sums: Vec<u64> = totals.iter().map(|weeks| weeks.iter().sum()).collect();
+    let all: u64 = sums.iter().sum();
+    egui::Grid::new("comparison_totals").show(ui, |ui| {
+        for ((label, sum), color) in comparison.labels.iter().zip(&sums).zip(COLORS) {
+            ui.colored_label(color, "■");
+            ui.label(label);
+            ui.label(format!("{} min", sum / 60));
+            ui.label(match all {
+                0 => "–".to_owned(),
+                all => format!("{:.0} %", *sum as f64 * 100.0 / all as f64),
+            });
+            ui.end_row();
+        }
+    });
+    archived
+}
+
+// Grouped bars: one group per week, one bar per label. A label without focus time in a week
+// still gets a hairline, so it reads as zero rather than missing.
+fn bars(ui: &mut egui::Ui, totals: &[Vec<u64>], first_day: Date) {
+    let weeks = totals.first().map_or(0, Vec::len);
+    let highest = totals.iter().flatten().copied().max().unwrap_or(0).max(1);
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(ui.available_width().max(240.0), 120.0), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let chart = egui::Rect::from_min_max(rect.min, rect.max - egui::vec2(0.0, 14.0));
+    let group = chart.width() / weeks.max(1) as f32;
+    let bar = group * 0.8 / totals.len().max(1) as f32;
+    let text = ui.visuals().weak_text_color();
+    for week in 0..weeks {
+        let left = chart.left() + group * week as f32 + group * 0.1;
+        for (index, (row, color)) in totals.iter().zip(COLORS).enumerate() {
+            let height = (row[week] as f32 / highest as f32 * chart.height()).max(1.0);
+            let x = left + bar * index as f32;
+            let shape = egui::Rect::from_min_max(egui::pos2(x, chart.bottom() - height), egui::pos2(x + bar - 1.0, chart.bottom()));
+            painter.rect_filled(shape, 0.0, color);
+        }
+        let monday = first_day + time::Duration::weeks(week as i64);
+        painter.text(
+            egui::pos2(left + group * 0.4, rect.bottom()),
+            egui::Align2::CENTER_BOTTOM,
+            format!("{}.{}.", monday.day(), u8::from(monday.month())),
+            egui::FontId::proportional(10.0),
+            text,
+        );
+    }
+}
@@ -0,0 +1,253 @@
+//! Tints the focused window's border through the compositor during focus sessions,
+//! on Hyprland (IPC socket) and Sway (swaymsg), finds windows for screenshots, and lists
+//! monitors and moves windows onto them for the focus companion.
+
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const HYPRLAND_OPTION: &str = "general:col.active_border";
+
+enum Compositor {
+    Hyprland { socket: PathBuf },
+    Sway,
+}
+
+impl Compositor {
+    // Both compositors export their instance in the environment of every client
+    fn detect() -> Option<Self> {
+        if let Ok(signature) = std::env::var("HYPRLAND_INSTANCE_SIGNATURE") {
+            let runtime = std::env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from);
+            let candidates = [runtime.map(|dir| dir.join("hypr")), Some(PathBuf::from("/tmp/hypr"))];
+            let socket = candidates
+                .into_iter()
+                .flatten()
+                .map(|dir| dir.join(&signature).join(".socket.sock"))
+                .find(|path| path.exists())?;
+            return Some(Compositor::Hyprland { socket });
+        }
+        std::env::var_os("SWAYSOCK").map(|_| Compositor::Sway)
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Compositor::Hyprland { .. } => "Hyprland",
+            Compositor::Sway => "Sway",
+        }
+    }
+}
+
+/// Owns the border color while tinted and puts the previous one back when untinted or dropped.
+pub struct BorderTint {
+    compositor: Compositor,
+    current: Option<[u8; 3]>,
+    // Hyprland's own value from before the first tint
+    saved: Option<String>,
+}
+
+impl BorderTint {
+    /// `None` when not running under a supported compositor.
+    pub fn detect() -> Option<Self> {
+        let compositor = Compositor::detect()?;
+        log::info!("Window border tinting available through {}", compositor.name());
+        Some(Self { compositor, current: None, saved: None })
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.compositor.name()
+    }
+
+    /// Asks the compositor for its version, to show that it answers before anything is tinted.
+    pub fn self_test() -> Result<&'static str, String> {
+        let compositor = Compositor::detect().ok_or("neither Hyprland nor Sway is running")?;
+        match &compositor {
+            Compositor::Hyprland { socket } => hyprland(socket, "version").map(|_| ()),
+            Compositor::Sway => swaymsg(&["-t", "get_version"]),
+        }
+        .map_err(|err| format!("{} did not answer: {}", compositor.name(), err))?;
+        Ok(compositor.name())
+    }
+
+    /// Tints borders with `color`, or restores them with `None`. Only talks to the compositor on change.
+    pub fn apply(&mut self, color: Option<[u8; 3]>) {
+        if color == self.current {
+            return;
+        }
+        let result = match color {
+            Some(color) => self.tint(color),
+            None => self.restore(),
+        };
+        match result {
+            Ok(()) => self.current = color,
+            Err(err) => {
+                log::warn!("Cannot change window borders through {}: {}", self.compositor.name(), err);
+                // Don't retry every frame
+                self.current = color;
+            }
+        }
+    }
+
+    fn tint(&mut self, [r, g, b]: [u8; 3]) -> Result<(), String> {
+        match &self.compositor {
+            Compositor::Hyprland { socket } => {
+                if self.saved.is_none() {
+                    self.saved = Some(hyprland_border(socket)?);
+                }
+                let reply = hyprland(socket, &format!("keyword {} rgb({:02x}{:02x}{:02x})", HYPRLAND_OPTION, r, g, b))?;
+                expect_ok(&reply)
+            }
+            Compositor::Sway => {
+                let color = format!("#{:02x}{:02x}{:02x}", r, g, b);
+                swaymsg(&["client.focused", &color, &color, "#ffffff", &color, &color])
+            }
+        }
+    }
+
+    fn restore(&mut self) -> Result<(), String> {
+        match &self.compositor {
+            Compositor::Hyprland { socket } => {
+                let Some(saved) = self.saved.take() else {
+                    return Ok(());
+                };
+                expect_ok(&hyprland(socket, &format!("keyword {} {}", HYPRLAND_OPTION, saved))?)
+            }
+            // Sway can't report client colors, so its configuration is the previous value
+            Compositor::Sway => swaymsg(&["reload"]),
+        }
+    }
+}
+
+/// Where the first window with `title` in its title is, as `x,y widthxheight` for grim.
+pub fn window_geometry(title: &str) -> Result<String, String> {
+    let compositor = Compositor::detect().ok_or("picking a window needs Hyprland or Sway")?;
+    let rect = match &compositor {
+        Compositor::Hyprland { socket } => {
+            let reply = hyprland(socket, "j/clients")?;
+            let clients: Vec<serde_json::Value> = serde_json::from_str(&reply).map_err(|e| format!("unexpected reply: {}", e))?;
+            clients.iter().find(|client| client["title"].as_str().is_some_and(|t| t.contains(title))).and_then(|client| {
+                let number = |value: &serde_json::Value| value.as_i64();
+                Some([number(&client["at"][0])?, number(&client["at"][1])?, number(&client["size"][0])?, number(&client["size"][1])?])
+            })
+        }
+        Compositor::Sway => {
+            let output = Command::new("swaymsg").args(["-t", "get_tree"]).output().map_err(|e| e.to_string())?;
+            let tree: serde_json::Value = serde_json::from_slice(&output.stdout).map_err(|e| format!("unexpected reply: {}", e))?;
+            sway_window(&tree, title)
+        }
+    };
+    let [x, y, width, height] = rect.ok_or_else(|| format!("no window titled \"{}\"", title))?;
+    Ok(format!("{},{} {}x{}", x, y, width, height))
+}
+
+// Depth first through workspaces and containers; only windows have a process
+fn sway_window(node: &serde_json::Value, title: &str) -> Option<[i64; 4]> {
+    let rect = &node["rect"];
+    if node["pid"].is_i64() && node["name"].as_str().is_some_and(|name| name.contains(title)) {
+        return Some([rect["x"].as_i64()?, rect["y"].as_i64()?, rect["width"].as_i64()?, rect["height"].as_i64()?]);
+    }
+    ["nodes", "floating_nodes"]
+        .iter()
+        .filter_map(|key| node[*key].as_array())
+        .flatten()
+        .find_map(|child| sway_window(child, title))
+}
+
+/// A monitor as the compositor names it, with its size in pixels.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Monitor {
+    pub name: String,
+    pub width: i64,
+    pub height: i64,
+}
+
+/// The monitors connected and in use.
+pub fn monitors() -> Result<Vec<Monitor>, String> {
+    let compositor = Compositor::detect().ok_or("listing monitors needs Hyprland or Sway")?;
+    let monitors = match &compositor {
+        Compositor::Hyprland { socket } => hyprland_monitors(socket)?
+            .iter()
+            .filter_map(|monitor| {
+                Some(Monitor { name: monitor["name"].as_str()?.to_owned(), width: monitor["width"].as_i64()?, height: monitor["height"].as_i64()? })
+            })
+            .collect(),
+        Compositor::Sway => {
+            let output = Command::new("swaymsg").args(["-t", "get_outputs"]).output().map_err(|e| e.to_string())?;
+            let outputs: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).map_err(|e| format!("unexpected reply: {}", e))?;
+            // Outputs switched off are listed too
+            outputs
+                .iter()
+                .filter(|output| output["active"].as_bool() == Some(true))
+                .filter_map(|output| {
+                    let rect = &output["rect"];
+                    Some(Monitor { name: output["name"].as_str()?.to_owned(), width: rect["width"].as_i64()?, height: rect["height"].as_i64()? })
+                })
+                .collect()
+        }
+    };
+    Ok(monitors)
+}
+
+/// Moves the window titled exactly `title` onto `monitor`, to the workspace showing there.
+pub fn move_to_monitor(title: &str, monitor: &str) -> Result<(), String> {
+    let compositor = Compositor::detect().ok_or("moving windows needs Hyprland or Sway")?;
+    match &compositor {
+        Compositor::Hyprland { socket } => {
+            let monitors = hyprland_monitors(socket)?;
+            let workspace = monitors
+                .iter()
+                .find(|found| found["name"].as_str() == Some(monitor))
+                .and_then(|found| found["activeWorkspace"]["id"].as_i64())
+                .ok_or_else(|| format!("no monitor named {}", monitor))?;
+            expect_ok(&hyprland(socket, &format!("dispatch movetoworkspacesilent {},title:^({})$", workspace, title))?)
+        }
+        Compositor::Sway => swaymsg(&[&format!("[title=\"^{}$\"]", title), "move", "container", "to", "output", monitor]),
+    }
+}
+
+fn hyprland_monitors(socket: &Path) -> Result<Vec<serde_json::Value>, String> {
+    let reply = hyprland(socket, "j/monitors")?;
+    serde_json::from_str(&reply).map_err(|e| format!("unexpected reply: {}", e))
+}
+impl Drop for BorderTint {
+    fn drop(&mut self) {
+        self.apply(None);
+    }
+}
+
+fn hyprland(socket: &Path, request: &str) -> Result<String, String> {
+    let mut stream = UnixStream::connect(socket).map_err(|e| e.to_string())?;
+    stream.write_all(request.as_bytes()).map_err(|e| e.to_string())?;
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply).map_err(|e| e.to_string())?;
+    Ok(reply)
+}
+
+fn expect_ok(reply: &str) -> Result<(), String> {
+    match reply.trim() {
+        "ok" => Ok(()),
+        other => Err(other.to_owned()),
+    }
+}
+
+// The gradient as Hyprland reports it, e.g. "ee33ccff ee00ff99 45deg", turned back into
+// keyword syntax ("0xee33ccff 0xee00ff99 45deg")
+fn hyprland_border(socket: &Path) -> Result<String, String> {
+    let reply = hyprland(socket, &format!("j/getoption {}", HYPRLAND_OPTION))?;
+    let option: serde_json::Value = serde_json::from_str(&reply).map_err(|e| format!("unexpected reply: {}", e))?;
+    let custom = option.get("custom").and_then(|v| v.as_str()).ok_or("no current border color reported")?;
+    let tokens: Vec<String> = custom
+        .split_whitespace()
+        .map(|token| if token.ends_with("deg") { token.to_owned() } else { format!("0x{}", token) })
+        .collect();
+    Ok(tokens.join(" "))
+}
+
+fn swaymsg(args: &[&str]) -> Result<(), String> {
+    let output = Command::new("swaymsg").args(args).output().map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+    }
+}
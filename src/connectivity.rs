@@ -0,0 +1,176 @@
+//! Whether the network is there, for the integrations that need it, so that without it they
+//! hold what they would send instead of failing over and over. A cheap probe, a TCP
+//! connection to the hosts they talk to, runs now and then and right after a request gets no
+//! answer. It takes two probes in a row to change its mind, so one lost packet doesn't flip
+//! the app offline and back. Working offline by choice stops the probes along with the rest.
+
+use eframe::egui;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::Duration;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+const ONLINE_INTERVAL: Duration = Duration::from_secs(60);
+const OFFLINE_INTERVAL: Duration = Duration::from_secs(15);
+// While the last probe disagreed with the state, to settle it quickly
+const RECHECK_INTERVAL: Duration = Duration::from_secs(3);
+const FLIP_AFTER: u32 = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Online,
+    Offline,
+    /// Turned off in the settings.
+    WorkingOffline,
+}
+
+struct State {
+    /// `host:port` of everything the integrations talk to; nothing to probe while empty.
+    hosts: Vec<String>,
+    work_offline: bool,
+    online: bool,
+    /// Probes in a row that disagreed with `online`.
+    streak: u32,
+    check_now: bool,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self { hosts: Vec::new(), work_offline: false, online: true, streak: 0, check_now: false }
+    }
+}
+
+impl State {
+    /// Counts a probe; true when it changed the state.
+    fn observe(&mut self, reachable: bool) -> bool {
+        if reachable == self.online {
+            self.streak = 0;
+            return false;
+        }
+        self.streak += 1;
+        if self.streak < FLIP_AFTER {
+            return false;
+        }
+        self.online = reachable;
+        self.streak = 0;
+        true
+    }
+}
+
+#[derive(Default)]
+struct Shared {
+    state: Mutex<State>,
+    wake: Condvar,
+}
+
+/// Cheap to clone; clones share the state and the probing thread.
+#[derive(Clone, Default)]
+pub struct Connectivity {
+    shared: Arc<Shared>,
+}
+
+impl Connectivity {
+    /// Probes from a thread of its own, repainting `ctx` when the status changes.
+    pub fn start(&self, ctx: &egui::Context) {
+        let (shared, ctx) = (Arc::clone(&self.shared), ctx.clone());
+        let spawned = std::thread::Builder::new().name("connectivity".to_owned()).spawn(move || probe_loop(&shared, &ctx));
+        if let Err(err) = spawned {
+            log::warn!("Cannot start checking the network: {}", err);
+        }
+    }
+
+    pub fn configure(&self, hosts: Vec<String>, work_offline: bool) {
+        let mut state = self.lock();
+        state.check_now |= state.hosts != hosts;
+        state.hosts = hosts;
+        state.work_offline = work_offline;
+        // Nothing to reach means nothing is held back
+        if state.hosts.is_empty() || work_offline {
+            state.online = true;
+            state.streak = 0;
+        }
+        self.shared.wake.notify_all();
+    }
+
+    pub fn status(&self) -> Status {
+        let state = self.lock();
+        match (state.work_offline, state.online) {
+            (true, _) => Status::WorkingOffline,
+            (false, true) => Status::Online,
+            (false, false) => Status::Offline,
+        }
+    }
+
+    /// Whether requests should go out now.
+    pub fn reachable(&self) -> bool {
+        self.status() == Status::Online
+    }
+
+    /// A request got no answer at all, so the network is looked at right away.
+    pub fn failed(&self) {
+        self.lock().check_now = true;
+        self.shared.wake.notify_all();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.shared.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn probe_loop(shared: &Shared, ctx: &egui::Context) {
+    let mut state = shared.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    loop {
+        let interval = match (state.streak, state.online) {
+            (1.., _) => RECHECK_INTERVAL,
+            (_, true) => ONLINE_INTERVAL,
+            (_, false) => OFFLINE_INTERVAL,
+        };
+        if !state.check_now {
+            state = shared.wake.wait_timeout(state, interval).unwrap_or_else(|poisoned| poisoned.into_inner()).0;
+        }
+        state.check_now = false;
+        if state.hosts.is_empty() || state.work_offline {
+            continue;
+        }
+        let hosts = state.hosts.clone();
+        drop(state);
+        let reachable = hosts.iter().any(|host| probe(host));
+        state = shared.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        // Settings changed during the probe, which may have been for hosts no longer used
+        if state.hosts != hosts || state.work_offline {
+            continue;
+        }
+        if state.observe(reachable) {
+            log::info!("The network is {}", if state.online { "back" } else { "unreachable, holding deliveries" });
+            ctx.request_repaint();
+        }
+    }
+}
+
+// Resolving the name and opening a connection is enough; nothing is sent
+fn probe(host: &str) -> bool {
+    let Ok(mut addrs) = host.to_socket_addrs() else {
+        return false;
+    };
+    addrs.any(|addr| TcpStream::connect_timeout(&addr, PROBE_TIMEOUT).is_ok())
+}
+
+/// The `host:port` a URL's requests go to, with the scheme's port when none is given.
+pub fn host(url: &str) -> Option<String> {
+    let (scheme, rest) = url.trim().split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let authority = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+    if authority.is_empty() {
+        return None;
+    }
+    let has_port = authority.rsplit_once(':').is_some_and(|(_, port)| !port.contains(']') && port.parse::<u16>().is_ok());
+    if has_port {
+        return Some(authority.to_owned());
+    }
+    let port = match scheme.to_ascii_lowercase().as_str() {
+        "https" => 443,
+        "http" => 80,
+        _ => return None,
+    };
+    Some(format!("{}:{}", authority, port))
+}
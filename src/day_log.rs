@@ -0,0 +1,232 @@
+//! A day on one printed page, for those who keep a paper log: the hours the sessions ran as a
+//! timeline, a table of the sessions with their tasks and intents, the totals, and the day's
+//! note and mood. Written as a single HTML file with its styles inline and nothing loaded from
+//! elsewhere, laid out for A4, and opened in the browser to print.
+
+use std::fmt::Write;
+use std::path::PathBuf;
+use time::{Date, OffsetDateTime};
+
+use crate::day_note;
+use crate::history::{Counting, DayNote, DayTotals, DayZone, History, IntentOutcome, Met, Session};
+
+const STYLE: &str = "
+@page { size: A4; margin: 15mm; }
+body { font: 10.5pt/1.4 sans-serif; color: #000; margin: 0 auto; max-width: 180mm; }
+h1 { font-size: 16pt; margin: 0 0 4mm; }
+h2 { font-size: 11pt; margin: 6mm 0 2mm; border-bottom: 0.3mm solid #000; }
+.timeline { position: relative; height: 8mm; border: 0.3mm solid #000; }
+.timeline div { position: absolute; top: 0; bottom: 0; background: #999; border-right: 0.3mm solid #fff; }
+.hours { position: relative; height: 5mm; font-size: 8pt; }
+.hours span { position: absolute; transform: translateX(-50%); }
+table { width: 100%; border-collapse: collapse; }
+th, td { text-align: left; padding: 1mm 2mm; border-bottom: 0.2mm solid #999; vertical-align: top; }
+th { border-bottom: 0.3mm solid #000; }
+td.number { text-align: right; }
+.note { white-space: pre-wrap; }
+@media print { body { max-width: none; } .timeline div { print-color-adjust: exact; -webkit-print-color-adjust: exact; } }
+";
+
+/// What the page shows of one day.
+pub struct DayLog<'a> {
+    pub date: Date,
+    /// The sessions that started on the day, as the statistics count them.
+    pub sessions: Vec<&'a Session>,
+    pub totals: DayTotals,
+    pub note: Option<&'a DayNote>,
+    /// Whether intents were met, of any sessions.
+    pub outcomes: &'a [IntentOutcome],
+    /// The time zone the day is seen in.
+    pub zone: DayZone,
+}
+
+impl<'a> DayLog<'a> {
+    /// `date` as the day detail shows it.
+    pub fn of(history: &'a History, date: Date) -> Self {
+        Self {
+            date,
+            sessions: history.stats.sessions_on(&history.sessions, date),
+            totals: history.stats.day(date),
+            note: history.notes.get(&date),
+            outcomes: &history.outcomes,
+            zone: history.stats.zone(),
+        }
+    }
+
+    /// The page as HTML.
+    pub fn render(&self) -> String {
+        let mut html = String::new();
+        let title = format!("{} {}", self.date.weekday(), self.date);
+        let _ = write!(html, "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>FerrisFocus — {}</title>\n<style>{}</style>\n</head>\n<body>\n<h1>{}</h1>\n", title, STYLE, title);
+        self.timeline(&mut html);
+        self.table(&mut html);
+        self.summary(&mut html);
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+
+    // The sessions along the hours they ran in, from the first hour to the last
+    fn timeline(&self, html: &mut String) {
+        let spans: Vec<(OffsetDateTime, OffsetDateTime)> = self.sessions.iter().map(|session| span(session, self.zone)).collect();
+        let (Some(first), Some(last)) = (spans.iter().map(|(from, _)| *from).min(), spans.iter().map(|(_, to)| *to).max()) else {
+            return;
+        };
+        let from = first.replace_time(time::Time::from_hms(first.hour(), 0, 0).unwrap_or(time::Time::MIDNIGHT));
+        let hours = ((last - from).whole_minutes() + 59) / 60;
+        let hours = hours.max(1);
+        let percent = |at: OffsetDateTime| (at - from).whole_seconds() as f64 / (hours * 3600) as f64 * 100.0;
+        html.push_str("<h2>Timeline</h2>\n<div class=\"timeline\">");
+        for (start, end) in &spans {
+            let _ = write!(html, "<div style=\"left: {:.2}%; width: {:.2}%\"></div>", percent(*start), percent(*end) - percent(*start));
+        }
+        html.push_str("</div>\n<div class=\"hours\">");
+        for hour in 0..=hours {
+            let _ = write!(html, "<span style=\"left: {:.2}%\">{:02}</span>", hour as f64 / hours as f64 * 100.0, (i64::from(from.hour()) + hour) % 24);
+        }
+        html.push_str("</div>\n");
+    }
+
+    fn table(&self, html: &mut String) {
+        html.push_str("<h2>Sessions</h2>\n");
+        if self.sessions.is_empty() {
+            html.push_str("<p>No sessions.</p>\n");
+            return;
+        }
+        html.push_str("<table>\n<tr><th>Time</th><th>Minutes</th><th>Paused</th><th>Task</th><th>Intent</th></tr>\n");
+        for session in &self.sessions {
+            let (from, to) = span(session, self.zone);
+            let mut task = escape(session.label.as_deref().unwrap_or("–"));
+            if session.easy_start {
+                task.push_str(" (easy start)");
+            }
+            let intent = match &session.intent {
+                Some(intent) => {
+                    let met = self.outcomes.iter().find(|outcome| outcome.session_start == session.start).map(|outcome| match outcome.met {
+                        Met::Yes => " ✓",
+                        Met::Partly => " (partly)",
+                        Met::No => " ✗",
+                    });
+                    format!("{}{}", escape(intent), met.unwrap_or_default())
+                }
+                None => String::new(),
+            };
+            let paused = match session.paused_secs / 60 {
+                0 => String::new(),
+                minutes => minutes.to_string(),
+            };
+            let _ = writeln!(
+                html,
+                "<tr><td>{}–{}</td><td class=\"number\">{}</td><td class=\"number\">{}</td><td>{}</td><td>{}</td></tr>",
+                clock(from),
+                clock(to),
+                session.duration_secs / 60,
+                paused,
+                task,
+                intent
+            );
+        }
+        html.push_str("</table>\n");
+    }
+
+    fn summary(&self, html: &mut String) {
+        let totals = &self.totals;
+        let _ = write!(html, "<h2>Totals</h2>\n<p>{}, {} min focused", count(totals.sessions, "session"), totals.focus_secs / 60);
+        if totals.paused_secs >= 60 {
+            let _ = write!(html, ", {} min paused", totals.paused_secs / 60);
+        }
+        if totals.easy_starts > 0 {
+            let _ = write!(html, ", {}", count(totals.easy_starts, "easy start"));
+        }
+        if totals.late_sessions > 0 {
+            let _ = write!(html, ", {} late", totals.late_sessions);
+        }
+        html.push_str("</p>\n");
+        let Some(note) = self.note else {
+            return;
+        };
+        html.push_str("<h2>Note</h2>\n");
+        if let Some(mood) = note.mood {
+            let _ = writeln!(html, "<p>Mood {} {} of 5</p>", day_note::face(mood), mood);
+        }
+        if !note.note.is_empty() {
+            let _ = writeln!(html, "<p class=\"note\">{}</p>", escape(&note.note));
+        }
+    }
+}
+
+/// Writes `html` for `date` to the temporary folder and opens it in the browser.
+pub fn open(date: Date, html: &str) -> Result<PathBuf, String> {
+    let path = std::env::temp_dir().join(format!("ferrisfocus-{}.html", date));
+    std::fs::write(&path, html).map_err(|err| format!("Cannot write {}: {}", path.display(), err))?;
+    opener::open(&path).map_err(|err| format!("Cannot open {}: {}", path.display(), err))?;
+    Ok(path)
+}
+
+// From the start on the clock, pauses included, to the end
+fn span(session: &Session, zone: DayZone) -> (OffsetDateTime, OffsetDateTime) {
+    let end = session.end().to_offset(session.local_start(zone).offset());
+    (end - std::time::Duration::from_secs(session.counted_secs(Counting::WallClock)), end)
+}
+
+fn count(n: u32, thing: &str) -> String {
+    match n {
+        1 => format!("1 {}", thing),
+        n => format!("{} {}s", n, thing),
+    }
+}
+
+fn clock(at: OffsetDateTime) -> String {
+    format!("{:02}:{:02}", at.hour(), at.minute())
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::Compacted;
+    use crate::stats::{Fingerprint, StatsIndex};
+    use std::collections::BTreeMap;
+    use time::macros::{date, datetime};
+
+    // The day after, and the day before in UTC, stay off the page
+    const SESSIONS: &str = r#"{"start":"2026-03-13T23:00:00+00:00","duration_secs":1500,"label":"Not this day"}
+{"start":"2026-03-14T09:05:00+01:00","duration_secs":1500,"label":"Write <report> & slides","intent":"Draft the outline","pauses":1,"paused_secs":300}
+{"start":"2026-03-14T09:40:00+01:00","duration_secs":1500,"label":"Write <report> & slides"}
+{"start":"2026-03-14T11:15:00+01:00","duration_secs":120,"easy_start":true}
+{"start":"2026-03-14T14:00:00+01:00","duration_secs":3000,"label":"Review"}
+{"start":"2026-03-15T08:00:00+01:00","duration_secs":1500,"label":"Not this day either"}"#;
+
+    /// Any change to the printed page shows up in `assets/fixtures/day_log.html` first.
+    #[test]
+    fn the_page_matches_the_golden_file() {
+        let sessions: Vec<Session> = SESSIONS.lines().map(serde_json::from_str).collect::<Result<_, _>>().unwrap();
+        let date = date!(2026 - 03 - 14);
+        let history = History {
+            stats: StatsIndex::build(&sessions, &Compacted::default(), Fingerprint::default(), DayZone::Recorded, Counting::Focused),
+            notes: BTreeMap::from([(date, DayNote { date, note: "Slept badly & had <three> meetings".to_owned(), mood: Some(4) })]),
+            outcomes: vec![IntentOutcome { session_start: datetime!(2026-03-14 09:05 +01:00), met: Met::Partly }],
+            sessions,
+            ..History::default()
+        };
+        let page = DayLog::of(&history, date).render();
+        let expected = include_str!("../assets/fixtures/day_log.html");
+        for (number, (line, wanted)) in page.lines().zip(expected.lines()).enumerate() {
+            assert_eq!(line, wanted, "line {} differs", number + 1);
+        }
+        assert_eq!(page.lines().count(), expected.lines().count());
+    }
+}
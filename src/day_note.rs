@@ -0,0 +1,69 @@
+//! A one-line note and a mood for a whole day, such as "Slept badly, lots of meetings", kept
+//! next to the day's focus time in the statistics.
+
+use eframe::egui;
+
+use crate::history::{DayNote, DayTotals};
+
+const FACES: [&str; 5] = ["😫", "😕", "😐", "🙂", "😄"];
+
+/// The face shown for a mood from 1 to 5.
+pub fn face(mood: u8) -> &'static str {
+    FACES[usize::from(mood.clamp(1, 5)) - 1]
+}
+
+pub enum Detail {
+    Save,
+    Close,
+    /// Open the day as a page to print, as last saved.
+    Print,
+}
+
+/// A day's totals with its note and mood to edit, and thumbnails of the day's screenshots by
+/// name; any day can be opened, not just today.
+pub fn detail(ctx: &egui::Context, draft: &mut DayNote, totals: DayTotals, thumbnails: &[(String, egui::TextureHandle)]) -> Option<Detail> {
+    let mut open = true;
+    let mut detail = None;
+    egui::Window::new(format!("{} {}", draft.date.weekday(), draft.date))
+        .id(egui::Id::new("day detail"))
+        .open(&mut open)
+        .resizable(false)
+        .collapsible(false)
+        .show(ctx, |ui| {
+            crate::compact_text(ui.style_mut());
+            ui.label(format!("{} sessions, {} min", totals.sessions, totals.focus_secs / 60));
+            ui.add(egui::TextEdit::singleline(&mut draft.note).hint_text("How did the day go?").desired_width(260.0));
+            ui.horizontal(|ui| {
+                ui.label("Mood");
+                ui.selectable_value(&mut draft.mood, None, "–").on_hover_text("Not rated");
+                for mood in 1..=5 {
+                    ui.selectable_value(&mut draft.mood, Some(mood), face(mood))
+                        .on_hover_text(format!("{} of 5", mood));
+                }
+            });
+            if !thumbnails.is_empty() {
+                ui.horizontal_wrapped(|ui| {
+                    for (name, texture) in thumbnails {
+                        if ui.add(egui::ImageButton::new(texture)).on_hover_text(name).clicked() {
+                            crate::screenshots::open(name);
+                        }
+                    }
+                });
+            }
+            ui.horizontal(|ui| {
+                if ui.button("Save").clicked() {
+                    detail = Some(Detail::Save);
+                }
+                if ui.button("Cancel").clicked() {
+                    detail = Some(Detail::Close);
+                }
+                if ui.button("🖨 Print").on_hover_text("Open the day as a page to print, in the browser").clicked() {
+                    detail = Some(Detail::Print);
+                }
+            });
+        });
+    if !open {
+        return Some(Detail::Close);
+    }
+    detail
+}
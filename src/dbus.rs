@@ -0,0 +1,158 @@
+//! `org.ferrisfocus.Timer` on the session bus, for desktop widgets such as Plasma applets and
+//! GNOME extensions: a one-line `StatusText`, `Toggle()` and `OpenWindow()`.
+
+use std::collections::HashSet;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+use eframe::egui;
+use zbus::blocking::{fdo::DBusProxy, Connection, ConnectionBuilder};
+use zbus::{dbus_interface, MessageHeader};
+
+use crate::integration::StartError;
+use crate::ipc;
+
+const NAME: &str = "org.ferrisfocus.Timer";
+const PATH: &str = "/org/ferrisfocus/Timer";
+
+/// Commands from the bus, carried out by the UI thread.
+pub enum Request {
+    Toggle,
+    OpenWindow,
+}
+
+/// Example invocations for widget authors, shown in the diagnostics report.
+pub fn examples() -> [String; 3] {
+    [
+        format!("busctl --user call {NAME} {PATH} {NAME} Toggle"),
+        format!("busctl --user get-property {NAME} {PATH} {NAME} StatusText"),
+        format!("gdbus call --session --dest {NAME} --object-path {PATH} --method {NAME}.OpenWindow"),
+    ]
+}
+
+struct Timer {
+    status: Arc<Mutex<ipc::Status>>,
+    requests: Sender<Request>,
+    ctx: egui::Context,
+    /// Unique bus names of the peers that asked for change signals.
+    watchers: Arc<Mutex<HashSet<String>>>,
+}
+
+impl Timer {
+    fn send(&self, request: Request) {
+        let _ = self.requests.send(request);
+        self.ctx.request_repaint();
+    }
+}
+
+#[dbus_interface(name = "org.ferrisfocus.Timer")]
+impl Timer {
+    /// Starts or pauses the timer, like the Start / Pause action.
+    fn toggle(&self) {
+        self.send(Request::Toggle);
+    }
+
+    /// Brings the window to the front.
+    fn open_window(&self) {
+        self.send(Request::OpenWindow);
+    }
+
+    /// Asks for PropertiesChanged on `StatusText` until the caller leaves the bus. Nothing is
+    /// emitted while no one has asked, so an idle desktop isn't woken every second.
+    fn watch(&self, #[zbus(header)] header: MessageHeader<'_>) {
+        if let (Ok(Some(sender)), Ok(mut watchers)) = (header.sender(), self.watchers.lock()) {
+            watchers.insert(sender.to_string());
+        }
+    }
+
+    #[dbus_interface(property)]
+    fn status_text(&self) -> String {
+        self.status.lock().map(|status| status_text(&status)).unwrap_or_default()
+    }
+}
+
+/// Icon and remaining time, with a pause sign while not running, e.g. `🍅 12:34 ⏸`.
+fn status_text(status: &ipc::Status) -> String {
+    ["icon", "remaining", "paused"].iter().filter_map(|field| status.field(field)).collect::<Vec<_>>().join(" ")
+}
+
+/// Owns the bus name until dropped.
+pub struct Service {
+    _connection: Connection,
+    requests: Receiver<Request>,
+    /// Told by the output tick when the status changed; dropping it stops the signals.
+    changes: Sender<()>,
+}
+
+impl Service {
+    /// Fails with a conflict if another instance already owns the name.
+    pub fn start(status: Arc<Mutex<ipc::Status>>, ctx: &egui::Context) -> Result<Self, StartError> {
+        let (sender, requests) = mpsc::channel();
+        let watchers = Arc::new(Mutex::new(HashSet::new()));
+        let timer = Timer { status, requests: sender, ctx: ctx.clone(), watchers: Arc::clone(&watchers) };
+        let connection = ConnectionBuilder::session()
+            .and_then(|builder| builder.name(NAME))
+            .and_then(|builder| builder.serve_at(PATH, timer))
+            .and_then(|builder| builder.build());
+        let connection = connection.map_err(|err| match err {
+            zbus::Error::NameTaken => StartError::Conflict(format!("Another FerrisFocus owns {} on the session bus", NAME)),
+            err => StartError::Unavailable(err.to_string()),
+        })?;
+        let (changes, changed) = mpsc::channel();
+        let emitter = {
+            let connection = connection.clone();
+            move || emit_changes(&connection, &watchers, &changed)
+        };
+        if let Err(err) = std::thread::Builder::new().name("dbus".to_owned()).spawn(emitter) {
+            log::warn!("Cannot start D-Bus thread: {}", err);
+        }
+        Ok(Self { _connection: connection, requests, changes })
+    }
+
+    /// Requests received since the last call.
+    pub fn requests(&self) -> impl Iterator<Item = Request> + '_ {
+        self.requests.try_iter()
+    }
+
+    /// The shared status changed, so `StatusText` may have.
+    pub fn status_changed(&self) {
+        let _ = self.changes.send(());
+    }
+}
+
+/// Signals a new `StatusText` to the bus while anyone is watching, as the output tick reports
+/// changes: at most once a second, and never while the timer is idle.
+fn emit_changes(connection: &Connection, watchers: &Mutex<HashSet<String>>, changed: &Receiver<()>) {
+    let Ok(iface) = connection.object_server().interface::<_, Timer>(PATH) else {
+        return;
+    };
+    let Ok(bus) = DBusProxy::new(connection) else {
+        return;
+    };
+    let mut last = None;
+    while changed.recv().is_ok() {
+        // Several changes waiting make one signal
+        while changed.try_recv().is_ok() {}
+        let watching = match watchers.lock() {
+            Ok(mut watchers) => {
+                watchers.retain(|name| {
+                    name.as_str().try_into().is_ok_and(|name| bus.name_has_owner(name).unwrap_or(false))
+                });
+                !watchers.is_empty()
+            }
+            Err(_) => false,
+        };
+        if !watching {
+            last = None;
+            continue;
+        }
+        let timer = iface.get();
+        let text = timer.status_text();
+        if last.as_ref() != Some(&text) {
+            if let Err(err) = zbus::block_on(timer.status_text_changed(iface.signal_context())) {
+                log::debug!("Cannot signal StatusText: {}", err);
+            }
+            last = Some(text);
+        }
+    }
+}
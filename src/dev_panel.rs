@@ -0,0 +1,314 @@
+//! Self-tests and benchmarks of the whole install, in a panel shown with `--dev` or the
+//! setting: a full cycle run through on a manual clock with silent audio, frame time, how
+//! quickly the real history answers the statistics, sound and event delivery. Each says
+//! pass or fail with its timing, so bug reports can quote the same numbers.
+
+use eframe::egui;
+use std::hint::black_box;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use time::Date;
+
+use pomodoro_timer::timer::{Durations, Phase, PomodoroTimer, Transition};
+
+use crate::alerts::Sound;
+use crate::audio::{AudioBackend, Call, RecordingBackend, Role, Shape};
+use crate::clock::{Clock, ManualClock};
+use crate::events::{Bus, Event, Filter, Kind};
+use crate::history::History;
+use crate::stats::{self, StatsIndex, WeekId};
+
+// One frame at 60 Hz, which anything done while drawing has to fit in
+const FRAME_BUDGET: Duration = Duration::from_micros(16_667);
+const FRAME_SAMPLE: Duration = Duration::from_secs(5);
+// The simulated cycle is ticked like a frame would, once a second
+const STEP: Duration = Duration::from_secs(1);
+const QUERY_RUNS: u32 = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Test {
+    Cycle,
+    FrameTime,
+    HistoryQueries,
+    Audio,
+    Events,
+}
+
+impl Test {
+    pub const ALL: [Test; 5] = [
+        Test::Cycle,
+        Test::FrameTime,
+        Test::HistoryQueries,
+        Test::Audio,
+        Test::Events,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Test::Cycle => "Full cycle",
+            Test::FrameTime => "Frame time",
+            Test::HistoryQueries => "History queries",
+            Test::Audio => "Audio playback",
+            Test::Events => "Event delivery",
+        }
+    }
+}
+
+struct Outcome {
+    test: Test,
+    result: Result<String, String>,
+    took: Duration,
+}
+
+/// How long each frame took to draw, while measuring.
+struct Frames {
+    since: Instant,
+    times: Vec<Duration>,
+}
+
+#[derive(Default)]
+pub struct Panel {
+    pub open: bool,
+    /// The latest outcome of each test run so far.
+    outcomes: Vec<Outcome>,
+    frames: Option<Frames>,
+}
+
+impl Panel {
+    /// Runs `check` and keeps its outcome with how long it took.
+    pub fn run(&mut self, test: Test, check: impl FnOnce() -> Result<String, String>) {
+        let started = Instant::now();
+        let result = check();
+        self.record(Outcome { test, result, took: started.elapsed() });
+    }
+
+    /// Starts timing the frames drawn over the next few seconds.
+    pub fn measure_frames(&mut self) {
+        self.outcomes.retain(|outcome| outcome.test != Test::FrameTime);
+        self.frames = Some(Frames { since: Instant::now(), times: Vec::new() });
+    }
+
+    /// While measuring, every frame has to be drawn rather than only when something changes.
+    pub fn measuring(&self) -> bool {
+        self.frames.is_some()
+    }
+
+    /// Counts a frame that took `took` to draw.
+    pub fn frame(&mut self, took: Duration) {
+        let Some(frames) = &mut self.frames else {
+            return;
+        };
+        frames.times.push(took);
+        let measured = frames.since.elapsed();
+        if measured < FRAME_SAMPLE {
+            return;
+        }
+        let times = std::mem::take(&mut frames.times);
+        self.frames = None;
+        let average = times.iter().sum::<Duration>() / times.len() as u32;
+        let slowest = times.iter().max().copied().unwrap_or_default();
+        let text = format!("average {}, slowest {} over {} frames", millis(average), millis(slowest), times.len());
+        let result = if average <= FRAME_BUDGET { Ok(text) } else { Err(format!("slower than 60 fps: {}", text)) };
+        self.record(Outcome { test: Test::FrameTime, result, took: measured });
+    }
+
+    /// The tests with their outcomes and a button each. Returns the tests asked for.
+    pub fn show(&self, ui: &mut egui::Ui) -> Vec<Test> {
+        let mut requested = Vec::new();
+        egui::Grid::new("dev_panel").num_columns(3).striped(true).show(ui, |ui| {
+            for test in Test::ALL {
+                ui.label(test.name());
+                let running = test == Test::FrameTime && self.measuring();
+                if ui.add_enabled(!running, egui::Button::new("Run")).clicked() {
+                    requested.push(test);
+                }
+                match self.outcomes.iter().find(|outcome| outcome.test == test) {
+                    _ if running => {
+                        ui.spinner();
+                    }
+                    Some(Outcome { result: Ok(text), took, .. }) => {
+                        ui.label(format!("✔ {}", text)).on_hover_text(format!("Took {}", millis(*took)));
+                    }
+                    Some(Outcome { result: Err(problem), took, .. }) => {
+                        ui.colored_label(egui::Color32::RED, format!("⚠ {}", problem)).on_hover_text(format!("Took {}", millis(*took)));
+                    }
+                    None => {
+                        ui.weak("not run");
+                    }
+                }
+                ui.end_row();
+            }
+        });
+        ui.horizontal(|ui| {
+            if ui.button("Run all").clicked() {
+                requested = Test::ALL.to_vec();
+            }
+            if ui.add_enabled(!self.outcomes.is_empty(), egui::Button::new("Copy results")).clicked() {
+                ui.ctx().copy_text(self.report());
+            }
+        });
+        requested
+    }
+
+    fn record(&mut self, outcome: Outcome) {
+        match &outcome.result {
+            Ok(text) => log::info!("Self-test {}: {}", outcome.test.name(), text),
+            Err(problem) => log::warn!("Self-test {} failed: {}", outcome.test.name(), problem),
+        }
+        self.outcomes.retain(|old| old.test != outcome.test);
+        self.outcomes.push(outcome);
+    }
+
+    // In the order of the panel, for pasting into a bug report
+    fn report(&self) -> String {
+        let mut lines = vec![format!("FerrisFocus {} self-test", crate::about::VERSION)];
+        for test in Test::ALL {
+            if let Some(outcome) = self.outcomes.iter().find(|outcome| outcome.test == test) {
+                let (verdict, text) = match &outcome.result {
+                    Ok(text) => ("pass", text),
+                    Err(problem) => ("FAIL", problem),
+                };
+                lines.push(format!("{}: {} in {}, {}", test.name(), verdict, millis(outcome.took), text));
+            }
+        }
+        crate::diagnostics::scrub(&lines.join("\n"))
+    }
+}
+
+/// Runs a whole cycle of `cycle_length` focus sessions and their breaks on a manual clock, the
+/// way the app does, and checks every interval ended once, on time, with an alarm.
+pub fn cycle(durations: Durations, long_break: Option<Duration>, cycle_length: u32) -> Result<String, String> {
+    let clock = ManualClock::starting_now();
+    let mut audio = RecordingBackend::default();
+    let mut timer = PomodoroTimer::new(durations);
+    let (mut sessions, mut breaks, mut long_breaks) = (0, 0, 0);
+    let start = clock.instant();
+    let expected = durations.work * cycle_length + durations.short_break * (cycle_length - 1) + long_break.unwrap_or(durations.short_break);
+    timer.start(start);
+    while breaks < cycle_length {
+        if clock.instant() - start > expected + STEP * 2 * cycle_length {
+            return Err(format!("{} sessions and {} breaks ended by the time the whole cycle should have", sessions, breaks));
+        }
+        clock.advance(STEP);
+        audio.set_now(clock.instant());
+        let Some(Transition::PeriodFinished { finished, next }) = timer.tick(clock.instant()) else {
+            continue;
+        };
+        match (finished, next) {
+            (Phase::Work, Phase::Break) => {
+                sessions += 1;
+                match long_break.filter(|_| sessions == cycle_length) {
+                    Some(length) => {
+                        timer.set_duration(length);
+                        long_breaks += 1;
+                        audio.play(Role::Alarm, Sound::Fanfare, Shape::default());
+                    }
+                    None => {
+                        audio.play(Role::Alarm, Sound::Chime, Shape::default());
+                    }
+                }
+            }
+            (Phase::Break, Phase::Work) => {
+                breaks += 1;
+                audio.play(Role::Alarm, Sound::Chime, Shape::default());
+            }
+            _ => return Err(format!("unexpected transition from {:?} to {:?}", finished, next)),
+        }
+        timer.start(clock.instant());
+    }
+    let took = clock.instant() - start;
+    let alarms = audio.calls().iter().filter(|(_, call)| matches!(call, Call::Play { role: Role::Alarm, .. })).count();
+    let transitions = sessions + breaks;
+    if sessions != cycle_length || long_breaks != u32::from(long_break.is_some()) {
+        return Err(format!("{} sessions and {} long breaks instead of {} and {}", sessions, long_breaks, cycle_length, u32::from(long_break.is_some())));
+    }
+    if alarms != transitions as usize {
+        return Err(format!("{} alarms for {} transitions", alarms, transitions));
+    }
+    if took != expected {
+        return Err(format!("took {} simulated seconds instead of {}", took.as_secs(), expected.as_secs()));
+    }
+    Ok(format!("{} transitions in {} simulated minutes", transitions, took.as_secs() / 60))
+}
+
+/// Times what the statistics window asks of the history, and a rebuild of the index, which
+/// has to come out the same as the one kept up to date. The pauses kept with each session have
+/// to follow one another between its start and end.
+pub fn history_queries(history: &History, today: Date) -> Result<String, String> {
+    let started = Instant::now();
+    let index = StatsIndex::build(&history.sessions, &history.compacted, history.stats.source(), history.stats.zone(), history.stats.counting());
+    let rebuild = started.elapsed();
+    if index.days() != history.stats.days() {
+        return Err("the statistics index differs from a rebuild of the history".to_owned());
+    }
+    let misplaced = history
+        .sessions
+        .iter()
+        .filter(|session| {
+            let pauses = session.pause_segments.iter().flat_map(|pause| [pause.start, pause.end]);
+            let times: Vec<_> = std::iter::once(session.start).chain(pauses).chain([session.end()]).collect();
+            !times.is_sorted()
+        })
+        .count();
+    if misplaced > 0 {
+        return Err(format!("{} sessions have pauses out of order or outside them", misplaced));
+    }
+    let started = Instant::now();
+    for _ in 0..QUERY_RUNS {
+        let weeks = std::iter::successors(Some(WeekId::of(today)), |week| Some(week.previous())).take(8);
+        for week in weeks {
+            black_box(history.stats.week(week));
+        }
+        black_box(stats::labels(&history.sessions));
+        black_box(stats::tags(&history.sessions));
+    }
+    let query = started.elapsed() / QUERY_RUNS;
+    let text = format!("statistics in {}, rebuild in {} for {} sessions", millis(query), millis(rebuild), history.sessions.len());
+    if query <= FRAME_BUDGET {
+        Ok(text)
+    } else {
+        Err(format!("slower than a frame: {}", text))
+    }
+}
+
+/// Plays the reminder on the output in use.
+pub fn audio(audio: &mut dyn AudioBackend) -> Result<String, String> {
+    audio.play(Role::Cue, Sound::Blip, Shape::default());
+    match audio.describe() {
+        device if device == "unavailable" => Err("no output device".to_owned()),
+        device => Ok(format!("played on {}", device)),
+    }
+}
+
+/// Sends an event through a bus of its own, so hooks and webhooks don't see it.
+pub fn events() -> Result<String, String> {
+    let mut bus = Bus::default();
+    let (sender, receiver) = mpsc::channel();
+    bus.subscribe("self-test", Filter::only(&[Kind::Tick]), move |_| {
+        let _ = sender.send(Instant::now());
+    });
+    let sent = Instant::now();
+    bus.publish(Event::Tick { remaining_secs: 0 });
+    let received = receiver.recv_timeout(Duration::from_secs(1));
+    bus.shutdown();
+    match received {
+        Ok(at) => Ok(format!("delivered in {}", millis(at - sent))),
+        Err(_) => Err("not delivered within a second".to_owned()),
+    }
+}
+
+fn millis(duration: Duration) -> String {
+    format!("{:.2} ms", duration.as_secs_f64() * 1000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_cycle_rings_one_alarm_per_transition() {
+        let durations = Durations::default();
+        assert_eq!(cycle(durations, None, 4), Ok("8 transitions in 120 simulated minutes".to_owned()));
+        assert_eq!(cycle(durations, Some(Duration::from_secs(15 * 60)), 4), Ok("8 transitions in 130 simulated minutes".to_owned()));
+    }
+}
@@ -0,0 +1,238 @@
+//! Durations as people write them: `25m`, `1h30m` or `90s`, or a bare number counted in the
+//! unit of the field it is typed into, so configs written with minutes keep working. Hours,
+//! minutes and seconds come in that order, each at most once, and [`format`] writes what
+//! [`parse`] reads back:
+//!
+//! ```
+//! use pomodoro_timer::duration_text::{format, parse, Unit};
+//! use std::time::Duration;
+//!
+//! assert_eq!(parse("25m", Unit::Minutes), Ok(Duration::from_secs(25 * 60)));
+//! assert_eq!(parse("1h30m", Unit::Minutes), Ok(Duration::from_secs(90 * 60)));
+//! assert_eq!(parse("90s", Unit::Minutes), Ok(Duration::from_secs(90)));
+//! assert_eq!(parse("1h0m5s", Unit::Seconds), Ok(Duration::from_secs(3605)));
+//! // Bare numbers are in the field's unit
+//! assert_eq!(parse("25", Unit::Minutes), Ok(Duration::from_secs(25 * 60)));
+//! assert_eq!(parse(" 45 ", Unit::Seconds), Ok(Duration::from_secs(45)));
+//!
+//! assert_eq!(format(Duration::from_secs(90 * 60)), "1h30m");
+//! assert_eq!(format(Duration::from_secs(25 * 60)), "25m");
+//! assert_eq!(format(Duration::from_secs(90)), "1m30s");
+//! assert_eq!(format(Duration::ZERO), "0s");
+//! for secs in [0, 1, 59, 60, 61, 3599, 3600, 3601, 5400, 86_399, 360_000] {
+//!     let duration = Duration::from_secs(secs);
+//!     assert_eq!(parse(&format(duration), Unit::Seconds), Ok(duration));
+//! }
+//! ```
+//!
+//! Anything else is refused, with the accepted syntax in the message:
+//!
+//! ```
+//! use pomodoro_timer::duration_text::{parse, Error, Unit};
+//!
+//! assert_eq!(parse("25mm", Unit::Minutes), Err(Error::Invalid("25mm".to_owned(), Unit::Minutes)));
+//! assert_eq!(parse("30m1h", Unit::Minutes), Err(Error::Invalid("30m1h".to_owned(), Unit::Minutes)));
+//! assert_eq!(parse("5m5m", Unit::Minutes), Err(Error::Invalid("5m5m".to_owned(), Unit::Minutes)));
+//! assert_eq!(parse("1.5h", Unit::Minutes), Err(Error::Invalid("1.5h".to_owned(), Unit::Minutes)));
+//! assert_eq!(parse("m", Unit::Minutes), Err(Error::Invalid("m".to_owned(), Unit::Minutes)));
+//! assert_eq!(parse("25 m", Unit::Minutes), Err(Error::Invalid("25 m".to_owned(), Unit::Minutes)));
+//! assert_eq!(parse("", Unit::Minutes), Err(Error::Empty(Unit::Minutes)));
+//! assert_eq!(parse("-5m", Unit::Minutes), Err(Error::Negative));
+//! assert_eq!(parse("99999999999999999999s", Unit::Seconds), Err(Error::Overflow));
+//! assert_eq!(parse("5124095576030432h", Unit::Seconds), Err(Error::Overflow));
+//! assert_eq!(parse("5124095576030431h59m", Unit::Seconds), Err(Error::Overflow));
+//! assert_eq!(
+//!     parse("25mm", Unit::Minutes).unwrap_err().to_string(),
+//!     "25mm is not a duration; expected a number of minutes, or a duration like 25m, 1h30m or 90s"
+//! );
+//! assert_eq!(
+//!     parse("5ss", Unit::Seconds).unwrap_err().to_string(),
+//!     "5ss is not a duration; expected a number of seconds, or a duration like 25m, 1h30m or 90s"
+//! );
+//! ```
+//!
+//! Fields counted in whole units take a duration that fits them, via [`parse_in`] or the serde
+//! helpers, which also take plain numbers as before:
+//!
+//! ```
+//! use pomodoro_timer::duration_text::{parse_in, Error, Unit};
+//! use serde::Deserialize;
+//!
+//! assert_eq!(parse_in("1h30m", Unit::Minutes), Ok(90));
+//! assert_eq!(parse_in("2m", Unit::Seconds), Ok(120));
+//! assert_eq!(parse_in("90s", Unit::Minutes), Err(Error::NotWhole(Unit::Minutes)));
+//! assert_eq!(parse_in("9999999999m", Unit::Minutes), Err(Error::Overflow));
+//!
+//! #[derive(Deserialize)]
+//! struct Config {
+//!     #[serde(deserialize_with = "pomodoro_timer::duration_text::minutes")]
+//!     work_minutes: u32,
+//!     #[serde(default, deserialize_with = "pomodoro_timer::duration_text::optional_seconds")]
+//!     get_ready_secs: Option<u32>,
+//! }
+//! let config: Config = toml::from_str("work_minutes = \"1h\"\nget_ready_secs = \"1m\"").unwrap();
+//! assert_eq!((config.work_minutes, config.get_ready_secs), (60, Some(60)));
+//! let config: Config = toml::from_str("work_minutes = 25").unwrap();
+//! assert_eq!((config.work_minutes, config.get_ready_secs), (25, None));
+//! let config: Config = serde_json::from_str(r#"{"work_minutes": 25, "get_ready_secs": null}"#).unwrap();
+//! assert_eq!(config.get_ready_secs, None);
+//! let err = toml::from_str::<Config>("work_minutes = -5").err().unwrap();
+//! assert!(err.message().contains("negative"));
+//! let err = toml::from_str::<Config>("work_minutes = \"25mm\"").err().unwrap();
+//! assert!(err.message().contains("1h30m"));
+//! let err = toml::from_str::<Config>("work_minutes = 25\nget_ready_secs = \"soon\"").err().unwrap();
+//! assert!(err.message().contains("a number of seconds"));
+//! ```
+
+use serde::{Deserialize, Deserializer};
+use std::time::Duration;
+
+/// What a bare number counts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Unit {
+    Seconds,
+    Minutes,
+}
+
+impl Unit {
+    fn secs(self) -> u64 {
+        match self {
+            Unit::Seconds => 1,
+            Unit::Minutes => 60,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Unit::Seconds => "seconds",
+            Unit::Minutes => "minutes",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// Nothing given where a number of these was expected.
+    Empty(Unit),
+    /// The text as given, where a number of these or a duration was expected.
+    Invalid(String, Unit),
+    Negative,
+    Overflow,
+    /// A field counted in this unit was given a duration that isn't a whole number of them.
+    NotWhole(Unit),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Empty(unit) => write!(f, "no duration given; expected {}", syntax(*unit)),
+            Error::Invalid(text, unit) => write!(f, "{} is not a duration; expected {}", text, syntax(*unit)),
+            Error::Negative => f.write_str("a duration can't be negative"),
+            Error::Overflow => f.write_str("the duration is too long"),
+            Error::NotWhole(unit) => write!(f, "expected a whole number of {}", unit.name()),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// The accepted syntax, for error messages.
+pub fn syntax(bare: Unit) -> String {
+    format!("a number of {}, or a duration like 25m, 1h30m or 90s", bare.name())
+}
+
+/// Reads `text`, with a bare number counted in `bare`.
+pub fn parse(text: &str, bare: Unit) -> Result<Duration, Error> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(Error::Empty(bare));
+    }
+    if text.starts_with('-') {
+        return Err(Error::Negative);
+    }
+    let invalid = || Error::Invalid(text.to_owned(), bare);
+    if text.bytes().all(|byte| byte.is_ascii_digit()) {
+        let count: u64 = text.parse().map_err(|_| Error::Overflow)?;
+        return count.checked_mul(bare.secs()).map(Duration::from_secs).ok_or(Error::Overflow);
+    }
+    let mut secs: u64 = 0;
+    // The units still allowed, largest first, so each comes at most once and in order
+    let mut units: &[(char, u64)] = &[('h', 3600), ('m', 60), ('s', 1)];
+    let mut rest = text;
+    while !rest.is_empty() {
+        let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+        let Some(letter) = rest[digits..].chars().next().filter(|_| digits > 0) else {
+            return Err(invalid());
+        };
+        let Some(position) = units.iter().position(|(unit, _)| *unit == letter) else {
+            return Err(invalid());
+        };
+        let count: u64 = rest[..digits].parse().map_err(|_| Error::Overflow)?;
+        let part = count.checked_mul(units[position].1).ok_or(Error::Overflow)?;
+        secs = secs.checked_add(part).ok_or(Error::Overflow)?;
+        units = &units[position + 1..];
+        rest = &rest[digits + letter.len_utf8()..];
+    }
+    Ok(Duration::from_secs(secs))
+}
+
+/// Reads `text` as a whole number of `unit`s, as a field counted in them stores it.
+pub fn parse_in(text: &str, unit: Unit) -> Result<u32, Error> {
+    count(parse(text, unit)?, unit)
+}
+
+fn count(duration: Duration, unit: Unit) -> Result<u32, Error> {
+    let secs = duration.as_secs();
+    if !secs.is_multiple_of(unit.secs()) {
+        return Err(Error::NotWhole(unit));
+    }
+    u32::try_from(secs / unit.secs()).map_err(|_| Error::Overflow)
+}
+
+/// Writes `duration` in whole seconds the way [`parse`] reads it, largest units first.
+pub fn format(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let parts = [(secs / 3600, 'h'), (secs / 60 % 60, 'm'), (secs % 60, 's')];
+    let text: String = parts.iter().filter(|(count, _)| *count > 0).map(|(count, unit)| format!("{}{}", count, unit)).collect();
+    if text.is_empty() {
+        "0s".to_owned()
+    } else {
+        text
+    }
+}
+
+// What a config file may hold for a duration: the number it always could, or text
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Raw {
+    Number(i64),
+    Text(String),
+}
+
+fn from_raw(raw: Raw, unit: Unit) -> Result<u32, Error> {
+    match raw {
+        Raw::Number(number) if number < 0 => Err(Error::Negative),
+        Raw::Number(number) => u32::try_from(number).map_err(|_| Error::Overflow),
+        Raw::Text(text) => parse_in(&text, unit),
+    }
+}
+
+fn deserialize_in<'de, D: Deserializer<'de>>(deserializer: D, unit: Unit) -> Result<Option<u32>, D::Error> {
+    let raw = Option::<Raw>::deserialize(deserializer)?;
+    raw.map(|raw| from_raw(raw, unit)).transpose().map_err(serde::de::Error::custom)
+}
+
+/// For `#[serde(deserialize_with)]` on a field counted in minutes.
+pub fn minutes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    deserialize_in(deserializer, Unit::Minutes)?.ok_or_else(|| serde::de::Error::custom(format!("expected {}", syntax(Unit::Minutes))))
+}
+
+/// For an optional field counted in minutes; null is none.
+pub fn optional_minutes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u32>, D::Error> {
+    deserialize_in(deserializer, Unit::Minutes)
+}
+
+/// For an optional field counted in seconds; null is none.
+pub fn optional_seconds<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u32>, D::Error> {
+    deserialize_in(deserializer, Unit::Seconds)
+}
@@ -0,0 +1,84 @@
+use eframe::egui;
+use std::time::{Duration, Instant};
+use time::OffsetDateTime;
+
+use crate::history::EnforcedBreak;
+
+// Long enough that it can't happen by accident
+const ESCAPE_HOLD: Duration = Duration::new(5, 0);
+
+/// The start of a break during which skipping, resetting and closing are withheld.
+pub struct Enforcement {
+    start: OffsetDateTime,
+    started: Instant,
+    minimum: Duration,
+    // Real time, unlike `started`: the escape takes five seconds even in demo mode
+    escape_held_since: Option<Instant>,
+}
+
+impl Enforcement {
+    pub fn new(start: OffsetDateTime, now: Instant, minimum: Duration) -> Self {
+        Self { start, started: now, minimum, escape_held_since: None }
+    }
+
+    /// Escape is being held to leave early.
+    pub fn escaping(&self) -> bool {
+        self.escape_held_since.is_some()
+    }
+
+    pub fn remaining(&self, now: Instant) -> Duration {
+        self.minimum.saturating_sub(now.saturating_duration_since(self.started))
+    }
+
+    /// The history record for a break that was left at `now`, early if `escaped`.
+    pub fn finish(&self, now: Instant, escaped: bool) -> EnforcedBreak {
+        EnforcedBreak {
+            start: self.start,
+            enforced_secs: self.minimum.as_secs(),
+            escaped_after_secs: escaped.then(|| now.saturating_duration_since(self.started).as_secs()),
+        }
+    }
+
+    /// Covers the whole window with the countdown and swallows clicks meant for the controls
+    /// below. Returns true once Escape has been held long enough.
+    pub fn show(&mut self, ctx: &egui::Context, now: Instant) -> bool {
+        let held = ctx.input(|i| i.key_down(egui::Key::Escape));
+        let held_for = match (held, self.escape_held_since) {
+            (false, _) => {
+                self.escape_held_since = None;
+                Duration::ZERO
+            }
+            (true, None) => {
+                self.escape_held_since = Some(Instant::now());
+                Duration::ZERO
+            }
+            (true, Some(since)) => since.elapsed(),
+        };
+
+        let remaining = self.remaining(now).as_secs();
+        egui::Area::new(egui::Id::new("enforced_break"))
+            .fixed_pos(egui::Pos2::ZERO)
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                let rect = ctx.screen_rect();
+                ui.painter().rect_filled(rect, 0.0, egui::Color32::from_rgb(20, 40, 30));
+                ui.allocate_rect(rect, egui::Sense::click_and_drag());
+                ui.allocate_ui_at_rect(rect, |ui| {
+                    ui.vertical_centered(|ui| {
+                        ui.add_space(rect.height() / 3.0);
+                        ui.heading("Break time");
+                        crate::compact_text(ui.style_mut());
+                        ui.label(format!("You can skip in {}:{:02}", remaining / 60, remaining % 60));
+                        ui.add_space(20.0);
+                        if held {
+                            let progress = pomodoro_timer::timer::fraction(held_for, ESCAPE_HOLD);
+                            ui.add(egui::ProgressBar::new(progress).desired_width(200.0).text("Leaving…"));
+                        } else {
+                            ui.weak("Hold Escape for 5 seconds to leave early");
+                        }
+                    });
+                });
+            });
+        held_for >= ESCAPE_HOLD
+    }
+}
@@ -0,0 +1,360 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::actions::Action;
+use crate::settings::Settings;
+
+// Events a subscriber hasn't got to yet; beyond this new ones are dropped instead of queued
+const QUEUE: usize = 64;
+
+/// Something that happened in the app, for whoever subscribed to it.
+#[derive(Clone, Debug)]
+pub enum Event {
+    /// Once per second of the countdown while the timer runs.
+    Tick { remaining_secs: u64 },
+    Command { action: Action, settings: Box<Settings> },
+    /// The timer was started on a focus session or break; `label` is the task, if any.
+    PeriodStarted { work: bool, label: Option<String> },
+    PeriodFinished { work: bool, late_ms: u64, while_away: bool },
+    WokeUp { asleep_secs: u64 },
+    PostureSwitch { standing: bool },
+    EnforcedBreakOver { escaped: bool },
+    /// The session that met the daily goal was completed.
+    GoalReached { goal: u32 },
+    Quit { running: bool },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Kind {
+    Tick,
+    Command,
+    PeriodStarted,
+    PeriodFinished,
+    WokeUp,
+    PostureSwitch,
+    EnforcedBreakOver,
+    GoalReached,
+    Quit,
+}
+
+impl Kind {
+    pub const ALL: [Kind; 9] = [
+        Kind::Tick,
+        Kind::Command,
+        Kind::PeriodStarted,
+        Kind::PeriodFinished,
+        Kind::WokeUp,
+        Kind::PostureSwitch,
+        Kind::EnforcedBreakOver,
+        Kind::GoalReached,
+        Kind::Quit,
+    ];
+}
+
+impl Event {
+    pub fn kind(&self) -> Kind {
+        match self {
+            Event::Tick { .. } => Kind::Tick,
+            Event::Command { .. } => Kind::Command,
+            Event::PeriodStarted { .. } => Kind::PeriodStarted,
+            Event::PeriodFinished { .. } => Kind::PeriodFinished,
+            Event::WokeUp { .. } => Kind::WokeUp,
+            Event::PostureSwitch { .. } => Kind::PostureSwitch,
+            Event::EnforcedBreakOver { .. } => Kind::EnforcedBreakOver,
+            Event::GoalReached { .. } => Kind::GoalReached,
+            Event::Quit { .. } => Kind::Quit,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self.kind() {
+            Kind::Tick => "tick",
+            Kind::Command => "command",
+            Kind::PeriodStarted => "period started",
+            Kind::PeriodFinished => "period finished",
+            Kind::WokeUp => "woke up",
+            Kind::PostureSwitch => "posture switch",
+            Kind::EnforcedBreakOver => "enforced break over",
+            Kind::GoalReached => "goal reached",
+            Kind::Quit => "quit",
+        }
+    }
+}
+
+impl fmt::Display for Event {
+    /// The details, without the name.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Event::Tick { remaining_secs } => write!(f, "{} s left", remaining_secs),
+            Event::Command { action, .. } => write!(f, "{:?}", action),
+            Event::PeriodStarted { work, label } => {
+                write!(f, "{}", if *work { "focus session" } else { "break" })?;
+                match label {
+                    Some(label) => write!(f, ", label={:?}", label),
+                    None => Ok(()),
+                }
+            }
+            Event::PeriodFinished { work, late_ms, while_away } => write!(
+                f,
+                "{}, {} ms late, while away: {}",
+                if *work { "focus session" } else { "break" },
+                late_ms,
+                while_away
+            ),
+            Event::WokeUp { asleep_secs } => write!(f, "{} s asleep", asleep_secs),
+            Event::PostureSwitch { standing: true } => write!(f, "stand"),
+            Event::PostureSwitch { standing: false } => write!(f, "sit"),
+            Event::EnforcedBreakOver { escaped: true } => write!(f, "escaped early"),
+            Event::EnforcedBreakOver { escaped: false } => Ok(()),
+            Event::GoalReached { goal } => write!(f, "{} sessions", goal),
+            Event::Quit { running: true } => write!(f, "while running"),
+            Event::Quit { running: false } => Ok(()),
+        }
+    }
+}
+
+/// What a subscriber wants: which kinds of events, and at most how often per kind.
+#[derive(Clone, Debug)]
+pub struct Filter {
+    kinds: Vec<Kind>,
+    min_interval: Duration,
+}
+
+impl Filter {
+    pub fn all() -> Self {
+        Self { kinds: Kind::ALL.to_vec(), min_interval: Duration::ZERO }
+    }
+
+    pub fn only(kinds: &[Kind]) -> Self {
+        Self { kinds: kinds.to_vec(), min_interval: Duration::ZERO }
+    }
+
+    pub fn without(mut self, kind: Kind) -> Self {
+        self.kinds.retain(|k| *k != kind);
+        self
+    }
+
+    /// Drops events arriving sooner than `interval` after the last delivered one of the same kind.
+    pub fn at_most_every(mut self, interval: Duration) -> Self {
+        self.min_interval = interval;
+        self
+    }
+}
+
+#[derive(Default)]
+struct Stats {
+    filtered: u64,
+    rate_limited: u64,
+    dropped: u64,
+}
+
+struct Subscriber {
+    name: String,
+    filter: Filter,
+    sender: Option<SyncSender<Event>>,
+    last_sent: HashMap<Kind, Instant>,
+    stats: Stats,
+    delivered: Arc<AtomicU64>, // Counted by the subscriber thread once handled
+    thread: Option<JoinHandle<()>>,
+}
+
+/// Hands events to subscribers, each on its own thread behind a bounded queue, so a slow or
+/// stuck subscriber loses events instead of holding up the timer.
+#[derive(Default)]
+pub struct Bus {
+    subscribers: Vec<Subscriber>,
+}
+
+impl Bus {
+    pub fn subscribe(&mut self, name: &str, filter: Filter, mut handler: impl FnMut(Event) + Send + 'static) {
+        let (sender, receiver) = mpsc::sync_channel(QUEUE);
+        let delivered = Arc::new(AtomicU64::new(0));
+        let counter = Arc::clone(&delivered);
+        let spawned = thread::Builder::new().name(format!("events-{}", name)).spawn(move || {
+            for event in receiver {
+                handler(event);
+                counter.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+        let thread = match spawned {
+            Ok(thread) => thread,
+            Err(err) => {
+                log::warn!("Cannot start {} subscriber: {}", name, err);
+                return;
+            }
+        };
+        self.subscribers.push(Subscriber {
+            name: name.to_owned(),
+            filter,
+            sender: Some(sender),
+            last_sent: HashMap::new(),
+            stats: Stats::default(),
+            delivered,
+            thread: Some(thread),
+        });
+    }
+
+    /// Stops the named subscriber once it has handled what is already queued.
+    pub fn unsubscribe(&mut self, name: &str) {
+        let (leaving, staying) = std::mem::take(&mut self.subscribers).into_iter().partition(|s| s.name == name);
+        self.subscribers = staying;
+        for subscriber in leaving {
+            subscriber.finish();
+        }
+    }
+
+    /// Never blocks.
+    pub fn publish(&mut self, event: Event) {
+        let now = Instant::now();
+        let kind = event.kind();
+        for subscriber in &mut self.subscribers {
+            if !subscriber.filter.kinds.contains(&kind) {
+                subscriber.stats.filtered += 1;
+                continue;
+            }
+            let last = subscriber.last_sent.get(&kind);
+            if last.is_some_and(|last| now.duration_since(*last) < subscriber.filter.min_interval) {
+                subscriber.stats.rate_limited += 1;
+                continue;
+            }
+            let Some(sender) = &subscriber.sender else {
+                continue;
+            };
+            match sender.try_send(event.clone()) {
+                Ok(()) => {
+                    subscriber.last_sent.insert(kind, now);
+                }
+                Err(TrySendError::Full(_)) | Err(TrySendError::Disconnected(_)) => {
+                    if subscriber.stats.dropped == 0 {
+                        log::warn!("Event subscriber {} is falling behind, dropping events", subscriber.name);
+                    }
+                    subscriber.stats.dropped += 1;
+                }
+            }
+        }
+    }
+
+    /// One line per subscriber for the diagnostics report.
+    pub fn describe(&self) -> String {
+        if self.subscribers.is_empty() {
+            return "no subscribers".to_owned();
+        }
+        self.subscribers
+            .iter()
+            .map(|s| {
+                format!(
+                    "{}: {} delivered, {} filtered, {} rate limited, {} dropped",
+                    s.name,
+                    s.delivered.load(Ordering::Relaxed),
+                    s.stats.filtered,
+                    s.stats.rate_limited,
+                    s.stats.dropped
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+
+    /// Lets every subscriber work through its queue, e.g. before the app exits.
+    pub fn shutdown(&mut self) {
+        for subscriber in self.subscribers.drain(..) {
+            subscriber.finish();
+        }
+    }
+}
+
+impl Subscriber {
+    fn finish(mut self) {
+        self.sender = None;
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    const TICKS: usize = 200;
+
+    fn tick(remaining_secs: u64) -> Event {
+        Event::Tick { remaining_secs }
+    }
+
+    // Counts the events the handler got
+    fn counting(bus: &mut Bus, name: &str, filter: Filter) -> Arc<AtomicUsize> {
+        let count = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&count);
+        bus.subscribe(name, filter, move |_| {
+            counter.fetch_add(1, Ordering::Relaxed);
+        });
+        count
+    }
+
+    #[test]
+    fn a_stuck_subscriber_loses_events_instead_of_holding_up_the_others() {
+        let mut bus = Bus::default();
+        let (release, stuck) = mpsc::channel::<()>();
+        let slow = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&slow);
+        bus.subscribe("slow", Filter::all(), move |_| {
+            let _ = stuck.recv();
+            counter.fetch_add(1, Ordering::Relaxed);
+        });
+        let fast = counting(&mut bus, "fast", Filter::all());
+        // Paced by the fast subscriber, which keeps up while the slow one is stuck
+        for remaining in 0..TICKS {
+            bus.publish(tick(remaining as u64));
+            let published = Instant::now();
+            while fast.load(Ordering::Relaxed) <= remaining {
+                assert!(published.elapsed() < Duration::from_secs(1), "the fast subscriber was held up");
+                thread::yield_now();
+            }
+        }
+        assert!(bus.describe().contains("dropped"));
+        drop(release);
+        bus.shutdown();
+        assert_eq!(fast.load(Ordering::Relaxed), TICKS);
+        // What was queued, and the one it was stuck on if it had taken it already
+        let slow = slow.load(Ordering::Relaxed);
+        assert!((QUEUE..=QUEUE + 1).contains(&slow), "the slow subscriber got {}", slow);
+    }
+
+    #[test]
+    fn filtered_and_rate_limited_events_are_counted_apart() {
+        let mut bus = Bus::default();
+        let ticks = counting(&mut bus, "ticks", Filter::only(&[Kind::Tick]).at_most_every(Duration::from_secs(3600)));
+        for remaining in 0..3 {
+            bus.publish(tick(remaining));
+        }
+        bus.publish(Event::Quit { running: false });
+        let described = bus.describe();
+        bus.shutdown();
+        assert_eq!(ticks.load(Ordering::Relaxed), 1);
+        assert!(described.ends_with("1 filtered, 2 rate limited, 0 dropped"), "{}", described);
+    }
+
+    #[test]
+    fn leaving_subscribers_finish_what_is_queued() {
+        let mut bus = Bus::default();
+        let leaving = counting(&mut bus, "leaving", Filter::all().without(Kind::Quit));
+        let staying = counting(&mut bus, "staying", Filter::all());
+        for remaining in 0..10 {
+            bus.publish(tick(remaining));
+        }
+        bus.unsubscribe("leaving");
+        assert_eq!(leaving.load(Ordering::Relaxed), 10);
+        bus.publish(Event::Quit { running: true });
+        assert!(bus.describe().starts_with("staying: "));
+        bus.shutdown();
+        assert_eq!(staying.load(Ordering::Relaxed), 11);
+        assert_eq!(bus.describe(), "no subscribers");
+    }
+}
@@ -0,0 +1,124 @@
+//! Pausing focus while something else has the user's attention, such as a call: detectors are
+//! polled on a background thread, and while any of them is active the app pauses the running
+//! session and keeps quiet. Each detector only says whether it is active, so they share one
+//! "paused from outside" state instead of pausing and resuming over each other.
+
+use eframe::egui;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// Joining a call takes a few seconds anyway; polling faster would only cost power
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Something that can tell whether the user is busy elsewhere.
+pub trait Detector: Send {
+    /// Why the timer paused, as in "Paused while the microphone is in use".
+    fn reason(&self) -> &'static str;
+    /// `None` when it cannot tell right now, which counts as not active.
+    fn active(&mut self) -> Option<bool>;
+}
+
+/// The detectors available on this platform; none where there is no way to tell.
+fn detectors() -> Vec<Box<dyn Detector>> {
+    #[cfg(target_os = "linux")]
+    {
+        vec![Box::new(Microphone)]
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        Vec::new()
+    }
+}
+
+/// The reason of the first active detector, as last polled.
+#[derive(Default)]
+pub struct Monitor {
+    active: Arc<Mutex<Option<&'static str>>>,
+    enabled: Arc<AtomicBool>,
+}
+
+impl Monitor {
+    /// Polls only while enabled, and repaints `ctx` when the state changes.
+    pub fn start(ctx: &egui::Context, enabled: bool) -> Self {
+        let monitor = Self::default();
+        monitor.set_enabled(enabled);
+        let mut detectors = detectors();
+        if detectors.is_empty() {
+            log::info!("Pausing during calls is not supported on this platform");
+            return monitor;
+        }
+        let (active, on, ctx) = (Arc::clone(&monitor.active), Arc::clone(&monitor.enabled), ctx.clone());
+        let spawned = std::thread::Builder::new().name("external pause".to_owned()).spawn(move || loop {
+            let state = if on.load(Ordering::Relaxed) {
+                detectors.iter_mut().find_map(|detector| (detector.active() == Some(true)).then(|| detector.reason()))
+            } else {
+                None
+            };
+            match active.lock() {
+                Ok(mut last) if *last != state => {
+                    *last = state;
+                    ctx.request_repaint();
+                }
+                Ok(_) => {}
+                Err(_) => return,
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        });
+        if let Err(err) = spawned {
+            log::warn!("Cannot start call detection thread: {}", err);
+        }
+        monitor
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+        if !enabled {
+            if let Ok(mut active) = self.active.lock() {
+                *active = None;
+            }
+        }
+    }
+
+    pub fn active(&self) -> Option<&'static str> {
+        self.active.lock().ok().and_then(|active| *active)
+    }
+}
+
+/// Any program recording from a microphone, asked of PulseAudio or PipeWire's PulseAudio
+/// server. Recordings of a monitor source, such as level meters, don't count.
+#[cfg(target_os = "linux")]
+struct Microphone;
+
+#[cfg(target_os = "linux")]
+impl Microphone {
+    // `pactl list short` prints one tab-separated line per object, starting with its index
+    fn list(kind: &str) -> Option<Vec<Vec<String>>> {
+        let output = std::process::Command::new("pactl").args(["list", "short", kind]).output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let text = String::from_utf8_lossy(&output.stdout);
+        Some(text.lines().map(|line| line.split('\t').map(str::to_owned).collect()).collect())
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Detector for Microphone {
+    fn reason(&self) -> &'static str {
+        "the microphone is in use"
+    }
+
+    fn active(&mut self) -> Option<bool> {
+        let recording = Self::list("source-outputs")?;
+        if recording.is_empty() {
+            return Some(false);
+        }
+        let monitors: Vec<String> = Self::list("sources")?
+            .into_iter()
+            .filter(|source| source.get(1).is_some_and(|name| name.ends_with(".monitor")))
+            .filter_map(|source| source.into_iter().next())
+            .collect();
+        Some(recording.iter().any(|output| output.get(1).is_some_and(|source| !monitors.contains(source))))
+    }
+}
@@ -0,0 +1,88 @@
+//! When the day's planned pomodoros are likely done, judged by how the user paced themselves
+//! from this time of day on before. Pure, so it can be checked against made-up histories.
+//!
+//! With a few weeks of history, the forecast follows the user's own pace:
+//!
+//! ```
+//! use pomodoro_timer::forecast::finish_time;
+//! use std::time::Duration;
+//! use time::macros::datetime;
+//!
+//! // The ten days before, each with four sessions from 9:00, half an hour apart
+//! let now = datetime!(2024-03-01 9:00 UTC);
+//! let sessions: Vec<_> = (1..=10)
+//!     .flat_map(|day| (0..4).map(move |n| now - time::Duration::days(day) + time::Duration::minutes(30 * n)))
+//!     .map(|start| (start, Duration::from_secs(25 * 60)))
+//!     .collect();
+//! let forecast = finish_time(&sessions, 4, Duration::from_secs(30 * 60), now);
+//! assert!(forecast.from_history);
+//! assert!(forecast.likely > datetime!(2024-03-01 10:50 UTC) && forecast.likely < datetime!(2024-03-01 11:00 UTC));
+//! assert!(forecast.earliest <= forecast.likely && forecast.likely <= forecast.latest);
+//! ```
+//!
+//! Without enough history, the planned sessions are simply added up:
+//!
+//! ```
+//! use pomodoro_timer::forecast::finish_time;
+//! use std::time::Duration;
+//! use time::macros::datetime;
+//!
+//! let now = datetime!(2024-03-01 14:00 UTC);
+//! let forecast = finish_time(&[], 3, Duration::from_secs(30 * 60), now);
+//! assert!(!forecast.from_history);
+//! assert_eq!(forecast.likely, datetime!(2024-03-01 15:30 UTC));
+//! assert!(forecast.earliest < forecast.likely && forecast.latest > forecast.likely);
+//! ```
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+use time::{Date, OffsetDateTime};
+
+/// Days with sessions at this time of day needed before the user's pace is trusted.
+pub const MIN_DAYS: usize = 5;
+// Older habits say little about today
+const LOOKBACK_DAYS: i64 = 28;
+// A single session isn't a pace of one per 25 minutes
+const MIN_SPAN: Duration = Duration::from_secs(30 * 60);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Forecast {
+    pub earliest: OffsetDateTime,
+    pub likely: OffsetDateTime,
+    pub latest: OffsetDateTime,
+    /// From the user's own pace; otherwise the plan is simply added up.
+    pub from_history: bool,
+}
+
+/// When `remaining` more pomodoros are likely done, starting `now`. `sessions` are past focus
+/// sessions by start and length; `cycle` is a session and its break, used with little history.
+pub fn finish_time(sessions: &[(OffsetDateTime, Duration)], remaining: u32, cycle: Duration, now: OffsetDateTime) -> Forecast {
+    let mut rates = pace(sessions, now);
+    if rates.len() < MIN_DAYS {
+        let likely = now + cycle * remaining;
+        let spread = cycle * remaining / 5;
+        return Forecast { earliest: likely - spread, likely, latest: likely + spread, from_history: false };
+    }
+    rates.sort_by(f64::total_cmp);
+    let quantile = |share: f64| rates[((rates.len() - 1) as f64 * share).round() as usize];
+    let after = |per_hour: f64| now + Duration::from_secs_f64(f64::from(remaining) / per_hour * 3600.0);
+    // A faster day finishes earlier
+    Forecast { earliest: after(quantile(0.75)), likely: after(quantile(0.5)), latest: after(quantile(0.25)), from_history: true }
+}
+
+/// Sessions per hour from `now`'s time of day until the last session ended, for each recent
+/// day that had sessions after that time.
+fn pace(sessions: &[(OffsetDateTime, Duration)], now: OffsetDateTime) -> Vec<f64> {
+    let today = now.date();
+    let first = today - time::Duration::days(LOOKBACK_DAYS);
+    let mut days = BTreeMap::<Date, (u32, Duration)>::new();
+    for (start, duration) in sessions {
+        if start.date() >= today || start.date() < first || start.time() < now.time() {
+            continue;
+        }
+        let (count, span) = days.entry(start.date()).or_default();
+        *count += 1;
+        *span = (*span).max((start.time() - now.time()).unsigned_abs() + *duration);
+    }
+    days.values().map(|(count, span)| f64::from(*count) / span.max(&MIN_SPAN).as_secs_f64() * 3600.0).collect()
+}
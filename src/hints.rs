@@ -0,0 +1,220 @@
+//! Help on the controls and on the technique behind them. Hints are data, like the stretch
+//! routines: each may be the tooltip of a control, a tip for beginners shown as a session starts,
+//! or both, and `hints.toml` in the config dir rewords or translates them by id. Beginner tips
+//! are shown one per session, each only once, during the first week of use; which were shown is
+//! kept in the data dir.
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use time::Date;
+
+use crate::actions::Action;
+use crate::paths;
+
+const BUNDLED: &str = include_str!("../assets/hints.toml");
+/// Read from the config dir; its hints replace the bundled ones with the same id.
+const USER_FILE: &str = "hints.toml";
+const FILE_NAME: &str = "hints_shown.json";
+/// Beginner tips stop this many days after the first use.
+const BEGINNER_DAYS: i64 = 7;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Control {
+    StartPause,
+    EasyStart,
+    Reset,
+    Skip,
+    Extend,
+    Task,
+    LongBreak,
+}
+
+impl Control {
+    /// The control behind a menu item, for its tooltip.
+    pub fn of(action: Action) -> Option<Self> {
+        match action {
+            Action::StartPause => Some(Control::StartPause),
+            Action::Skip => Some(Control::Skip),
+            Action::Reset => Some(Control::Reset),
+            Action::Extend => Some(Control::Extend),
+            _ => None,
+        }
+    }
+}
+
+/// When a beginner tip fits the session starting.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum When {
+    Always,
+    /// No session in the history yet.
+    FirstSession,
+    /// Nothing typed in the task field.
+    NoTask,
+    /// The session ends the cycle.
+    LongBreakNext,
+    /// From the second day of use.
+    SecondDay,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Hint {
+    pub id: String,
+    pub control: Option<Control>,
+    /// What the control does.
+    pub text: Option<String>,
+    /// Why the technique does it that way.
+    pub technique: Option<String>,
+    /// Shown as a beginner tip when this holds; never if it is missing.
+    pub when: Option<When>,
+}
+
+impl Hint {
+    /// The tip's wording: the technique where there is one.
+    pub fn tip(&self) -> &str {
+        self.technique.as_deref().or(self.text.as_deref()).unwrap_or_default()
+    }
+}
+
+/// The session starting, as the conditions see it.
+pub struct Situation {
+    pub today: Date,
+    pub any_session: bool,
+    pub has_task: bool,
+    pub long_break_next: bool,
+}
+
+impl When {
+    fn holds(self, situation: &Situation, first_used: Date) -> bool {
+        match self {
+            When::Always => true,
+            When::FirstSession => !situation.any_session,
+            When::NoTask => !situation.has_task,
+            When::LongBreakNext => situation.long_break_next,
+            When::SecondDay => situation.today > first_used,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct File {
+    #[serde(default, rename = "hint")]
+    hints: Vec<Hint>,
+}
+
+/// Which beginner tips were shown, and since when the app is in use.
+#[derive(Default, Serialize, Deserialize)]
+struct Shown {
+    first_used: Option<Date>,
+    ids: BTreeSet<String>,
+}
+
+pub struct Hints {
+    hints: Vec<Hint>,
+    shown: Shown,
+}
+
+impl Hints {
+    /// The hints, and what was wrong with the user's file if it couldn't be used.
+    pub fn load() -> (Self, Option<String>) {
+        let mut hints = parse(BUNDLED).unwrap_or_default();
+        let problem = match paths::config_dir().map(|dir| dir.join(USER_FILE)) {
+            Some(path) => match std::fs::read_to_string(&path) {
+                Ok(text) => match parse(&text) {
+                    Ok(user) => {
+                        for hint in user {
+                            match hints.iter_mut().find(|bundled| bundled.id == hint.id) {
+                                Some(bundled) => *bundled = hint,
+                                None => hints.push(hint),
+                            }
+                        }
+                        None
+                    }
+                    Err(err) => Some(format!("Cannot use {}: {}", path.display(), err)),
+                },
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+                Err(err) => Some(format!("Cannot read {}: {}", path.display(), err)),
+            },
+            None => None,
+        };
+        let shown = shown_path()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default();
+        (Self { hints, shown }, problem)
+    }
+
+    pub fn get(&self, control: Control) -> Option<&Hint> {
+        self.hints.iter().find(|hint| hint.control == Some(control))
+    }
+
+    /// The tip for the session starting, marked as shown: the first not shown before whose
+    /// condition holds, during the first week. `first_session` is the day of the oldest session
+    /// in the history, so the week is counted from there for those who used the app before.
+    pub fn next_tip(&mut self, situation: &Situation, first_session: Option<Date>) -> Option<&Hint> {
+        let first_used = *self.shown.first_used.get_or_insert(first_session.unwrap_or(situation.today).min(situation.today));
+        if (situation.today - first_used).whole_days() >= BEGINNER_DAYS {
+            self.save();
+            return None;
+        }
+        let index = self
+            .hints
+            .iter()
+            .position(|hint| !self.shown.ids.contains(&hint.id) && hint.when.is_some_and(|when| when.holds(situation, first_used)));
+        if let Some(index) = index {
+            self.shown.ids.insert(self.hints[index].id.clone());
+        }
+        self.save();
+        index.map(|index| &self.hints[index])
+    }
+
+    fn save(&self) {
+        let Some(path) = shown_path() else {
+            return;
+        };
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let result = serde_json::to_string(&self.shown)
+            .map_err(|e| e.to_string())
+            .and_then(|json| std::fs::write(&path, json).map_err(|e| e.to_string()));
+        if let Err(err) = result {
+            log::warn!("Cannot save {}: {}", path.display(), err);
+        }
+    }
+}
+
+/// Shows `hint` when `response` is hovered, or focused with the keyboard, though not over a
+/// text field being typed in.
+pub fn explain(response: egui::Response, hint: Option<&Hint>) -> egui::Response {
+    let Some(hint) = hint else {
+        return response;
+    };
+    let contents = |ui: &mut egui::Ui| {
+        ui.set_max_width(280.0);
+        if let Some(text) = &hint.text {
+            ui.label(text);
+        }
+        if let Some(technique) = &hint.technique {
+            ui.label(egui::RichText::new(technique).italics().weak());
+        }
+    };
+    if response.has_focus() && !response.hovered() && !response.ctx.wants_keyboard_input() {
+        response.show_tooltip_ui(contents);
+        response
+    } else {
+        response.on_hover_ui(contents)
+    }
+}
+
+fn parse(text: &str) -> Result<Vec<Hint>, String> {
+    let file: File = toml::from_str(text).map_err(|e| e.to_string())?;
+    Ok(file.hints)
+}
+
+fn shown_path() -> Option<PathBuf> {
+    paths::data_dir().map(|dir| dir.join(FILE_NAME))
+}
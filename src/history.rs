@@ -0,0 +1,956 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use time::{Date, Month, OffsetDateTime, UtcOffset};
+
+use crate::{backup, clock, paths};
+use crate::stats::{Fingerprint, StatsIndex};
+
+pub const SESSIONS_FILE: &str = "history.jsonl";
+const DAILY_FILE: &str = "history_daily.json";
+const INDEX_FILE: &str = "stats_index.json";
+const BREAKS_FILE: &str = "enforced_breaks.jsonl";
+const INTENTIONS_FILE: &str = "intentions.jsonl";
+const DAY_NOTES_FILE: &str = "day_notes.jsonl";
+const NAPS_FILE: &str = "naps.jsonl";
+/// Everything recorded; the stats index is left out, being rebuilt from these when stale.
+pub const FILES: [&str; 6] = [SESSIONS_FILE, DAILY_FILE, BREAKS_FILE, INTENTIONS_FILE, DAY_NOTES_FILE, NAPS_FILE];
+// No real session comes close; anything longer is a corrupted line
+const MAX_SESSION_SECS: u64 = 24 * 60 * 60;
+// Held while a file is appended to or rewritten, so a tidy-up on another thread can't drop a
+// line appended between reading the file and replacing it
+static WRITING: Mutex<()> = Mutex::new(());
+
+/// Which day a session recorded in another time zone counts toward.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DayZone {
+    /// The local day where the session was recorded.
+    #[default]
+    Recorded,
+    /// The day it was in the time zone the computer is in now.
+    Current,
+}
+
+impl DayZone {
+    fn offset(self) -> Option<UtcOffset> {
+        match self {
+            DayZone::Recorded => None,
+            DayZone::Current => Some(clock::local_offset()),
+        }
+    }
+}
+
+/// What a session's focus time is counted as in the totals.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Counting {
+    /// Only the time spent focused, without pauses.
+    #[default]
+    Focused,
+    /// From start to end on the clock, pauses included.
+    WallClock,
+}
+
+impl Counting {
+    pub fn label(self) -> &'static str {
+        match self {
+            Counting::Focused => "time focused, without pauses",
+            Counting::WallClock => "time on the clock, pauses included",
+        }
+    }
+}
+
+/// Why a session was paused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PauseReason {
+    /// By the user.
+    Manual,
+    /// While something else had the user's attention, such as a call.
+    External,
+}
+
+/// When a session was paused and why.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Pause {
+    #[serde(with = "time::serde::rfc3339")]
+    pub start: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub end: OffsetDateTime,
+    pub reason: PauseReason,
+}
+
+impl Pause {
+    pub fn secs(&self) -> u64 {
+        u64::try_from((self.end - self.start).whole_seconds()).unwrap_or(0)
+    }
+}
+
+/// One completed focus session, stored as a line of JSON. The start keeps the UTC offset it
+/// was recorded with.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    #[serde(with = "time::serde::rfc3339")]
+    pub start: OffsetDateTime,
+    pub duration_secs: u64,
+    /// Ended while the computer was suspended, so nobody saw it end.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub while_away: bool,
+    /// The task worked on, as typed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// The `#tags` typed with the label, lowercased; not part of it.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub tags: BTreeSet<String>,
+    /// Started after the wind-down time.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub late_night: bool,
+    /// What the user meant to get done, as typed before starting.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intent: Option<String>,
+    /// Times it was paused; the time paused is on top of the duration, not part of it.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub pauses: u32,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub paused_secs: u64,
+    /// Each pause, oldest first. Sessions recorded before pauses were kept one by one have
+    /// none, and only their total above.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pause_segments: Vec<Pause>,
+    /// An easy start that ended without going on to a full session; its focus time counts,
+    /// but it isn't a session.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub easy_start: bool,
+    /// Taken as it ended, relative to the screenshots folder; it may have been deleted since.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub screenshot: Option<String>,
+}
+
+fn is_zero<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+impl Session {
+    pub fn date(&self) -> Date {
+        self.start.date()
+    }
+
+    /// When the session ended, its focus and pauses after the start. Sessions recorded before
+    /// pauses were kept one by one start as long before the end as they were focused, with the
+    /// time paused before that.
+    pub fn end(&self) -> OffsetDateTime {
+        let paused = if self.pause_segments.is_empty() { 0 } else { self.paused_secs };
+        self.start + std::time::Duration::from_secs(self.duration_secs + paused)
+    }
+
+    /// The seconds the session counts for.
+    pub fn counted_secs(&self, counting: Counting) -> u64 {
+        match counting {
+            Counting::Focused => self.duration_secs,
+            Counting::WallClock => self.duration_secs + self.paused_secs,
+        }
+    }
+
+    /// Seconds paused for `reason`, as far as the pauses were kept one by one.
+    pub fn paused_for(&self, reason: PauseReason) -> u64 {
+        self.pause_segments.iter().filter(|pause| pause.reason == reason).map(Pause::secs).sum()
+    }
+
+    /// The start as seen in `zone`.
+    pub fn local_start(&self, zone: DayZone) -> OffsetDateTime {
+        zone.offset().map_or(self.start, |offset| self.start.to_offset(offset))
+    }
+
+    /// Counted seconds per day, split at each midnight in `zone`. Durations are absolute, so a
+    /// DST change during the night doesn't add or lose time. Time focused leaves out the pauses
+    /// kept one by one; older records have it all just before their end.
+    pub fn days(&self, zone: DayZone, counting: Counting) -> Vec<(Date, u64)> {
+        let offset = self.local_start(zone).offset();
+        let end = self.end().to_offset(offset);
+        let skipped = match counting {
+            Counting::Focused => &self.pause_segments[..],
+            Counting::WallClock => &[],
+        };
+        let paused: u64 = skipped.iter().map(Pause::secs).sum();
+        let mut from = end - std::time::Duration::from_secs(self.counted_secs(counting) + paused);
+        let mut days = Vec::new();
+        for pause in skipped {
+            let until = pause.start.to_offset(offset).clamp(from, end);
+            split_at_midnight(&mut days, from, until);
+            from = pause.end.to_offset(offset).clamp(until, end);
+        }
+        split_at_midnight(&mut days, from, end);
+        days
+    }
+}
+
+// Adds the seconds from `from` to `to` to the days they fall on
+fn split_at_midnight(days: &mut Vec<(Date, u64)>, mut from: OffsetDateTime, to: OffsetDateTime) {
+    while from < to {
+        let midnight = from.date().next_day().map(|day| day.midnight().assume_offset(from.offset()));
+        let until = midnight.filter(|midnight| *midnight < to).unwrap_or(to);
+        let secs = u64::try_from((until - from).whole_seconds()).unwrap_or(0);
+        match days.last_mut() {
+            Some((date, total)) if *date == from.date() => *total += secs,
+            _ => days.push((from.date(), secs)),
+        }
+        from = until;
+    }
+}
+
+/// A break that started with controls withheld, and whether it was escaped early.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EnforcedBreak {
+    #[serde(with = "time::serde::rfc3339")]
+    pub start: OffsetDateTime,
+    pub enforced_secs: u64,
+    /// Seconds into the enforced portion when the emergency escape was used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub escaped_after_secs: Option<u64>,
+}
+
+/// A nap taken in place of the rest of a break.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Nap {
+    #[serde(with = "time::serde::rfc3339")]
+    pub start: OffsetDateTime,
+    pub minutes: u32,
+    /// Seconds from the wake alarm until being awake was confirmed.
+    pub woke_after_secs: u64,
+}
+
+/// Whether a session's intent was met, as answered when it ended. Kept apart from the session
+/// lines, which are never rewritten for a late answer.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct IntentOutcome {
+    #[serde(with = "time::serde::rfc3339")]
+    pub session_start: OffsetDateTime,
+    pub met: Met,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Met {
+    Yes,
+    Partly,
+    No,
+}
+
+/// A one-line note and a mood for a whole day. Every edit is appended and the last one for a
+/// day wins; a blank one removes the day's entry.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DayNote {
+    pub date: Date,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub note: String,
+    /// Mood or energy from 1 (low) to 5 (high).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mood: Option<u8>,
+}
+
+impl DayNote {
+    pub fn blank(date: Date) -> Self {
+        Self { date, note: String::new(), mood: None }
+    }
+
+    pub fn is_blank(&self) -> bool {
+        self.note.trim().is_empty() && self.mood.is_none()
+    }
+}
+
+/// What is left of a day's sessions after compaction.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DayTotals {
+    pub sessions: u32,
+    /// As counted; compacted totals are always without pauses.
+    pub focus_secs: u64,
+    /// Paused during the sessions that started that day.
+    #[serde(default)]
+    pub paused_secs: u64,
+    #[serde(default)]
+    pub late_sessions: u32,
+    /// Easy starts that ended there; not among the sessions.
+    #[serde(default)]
+    pub easy_starts: u32,
+}
+
+fn set_note(notes: &mut BTreeMap<Date, DayNote>, note: DayNote) {
+    if note.is_blank() {
+        notes.remove(&note.date);
+    } else {
+        notes.insert(note.date, note);
+    }
+}
+
+/// Counts `session` on the day it started, with its focus time spread over the days it ran.
+// Saturating, since hand-edited totals can already be near the limit
+pub fn add_to_days(days: &mut BTreeMap<Date, DayTotals>, session: &Session, zone: DayZone, counting: Counting) {
+    let start = days.entry(session.local_start(zone).date()).or_default();
+    if session.easy_start {
+        start.easy_starts = start.easy_starts.saturating_add(1);
+    } else {
+        start.sessions = start.sessions.saturating_add(1);
+    }
+    start.late_sessions = start.late_sessions.saturating_add(u32::from(session.late_night));
+    start.paused_secs = start.paused_secs.saturating_add(session.paused_secs);
+    for (date, secs) in session.days(zone, counting) {
+        let day = days.entry(date).or_default();
+        day.focus_secs = day.focus_secs.saturating_add(secs);
+    }
+}
+
+/// Daily totals replacing individual sessions from before `compacted_before`.
+/// Session lines older than that are ignored even if still present, so a crash halfway
+/// through compaction can never count a session twice.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Compacted {
+    pub compacted_before: Option<Date>,
+    pub days: BTreeMap<Date, DayTotals>,
+}
+
+/// Everything recorded so far.
+#[derive(Clone, Debug, Default)]
+pub struct History {
+    pub sessions: Vec<Session>,
+    pub compacted: Compacted,
+    pub stats: StatsIndex,
+    pub breaks: Vec<EnforcedBreak>,
+    pub naps: Vec<Nap>,
+    pub outcomes: Vec<IntentOutcome>,
+    /// Days with a note or mood; blank days have no entry.
+    pub notes: BTreeMap<Date, DayNote>,
+    /// What was skipped while loading, for showing to the user once.
+    pub problems: Vec<String>,
+    /// Changes, to a number no other history has had, whenever the sessions, the intent
+    /// outcomes or the daily totals do; what is worked out from them is kept until then.
+    pub revision: u64,
+}
+
+// Shared by every history, so one loaded anew never reuses the number of the one it replaced
+static REVISIONS: AtomicU64 = AtomicU64::new(1);
+
+pub fn next_revision() -> u64 {
+    REVISIONS.fetch_add(1, Ordering::Relaxed)
+}
+
+impl History {
+    pub fn load(zone: DayZone, counting: Counting) -> Self {
+        let Some(dir) = paths::data_dir() else {
+            return Self::default();
+        };
+        let mut problems = Vec::new();
+        let compacted = read_compacted(&dir.join(DAILY_FILE), &mut problems);
+        let path = dir.join(SESSIONS_FILE);
+        let bytes = fs::read(&path).unwrap_or_default();
+        let (mut sessions, skipped) = parse_lines(&String::from_utf8_lossy(&bytes), &path, parse_session);
+        problems.extend(skipped);
+        let breaks_path = dir.join(BREAKS_FILE);
+        let breaks_text = fs::read_to_string(&breaks_path).unwrap_or_default();
+        let (breaks, skipped) = parse_lines(&breaks_text, &breaks_path, |line| {
+            serde_json::from_str::<EnforcedBreak>(line).map_err(|e| e.to_string())
+        });
+        problems.extend(skipped);
+        let naps_path = dir.join(NAPS_FILE);
+        let naps_text = fs::read_to_string(&naps_path).unwrap_or_default();
+        let (naps, skipped) = parse_lines(&naps_text, &naps_path, |line| serde_json::from_str::<Nap>(line).map_err(|e| e.to_string()));
+        problems.extend(skipped);
+        let outcomes_path = dir.join(INTENTIONS_FILE);
+        let outcomes_text = fs::read_to_string(&outcomes_path).unwrap_or_default();
+        let (outcomes, skipped) = parse_lines(&outcomes_text, &outcomes_path, |line| {
+            serde_json::from_str::<IntentOutcome>(line).map_err(|e| e.to_string())
+        });
+        problems.extend(skipped);
+        let notes_path = dir.join(DAY_NOTES_FILE);
+        let notes_text = fs::read_to_string(&notes_path).unwrap_or_default();
+        let (edits, skipped) = parse_lines(&notes_text, &notes_path, parse_note);
+        problems.extend(skipped);
+        let mut notes = BTreeMap::new();
+        for note in edits {
+            set_note(&mut notes, note);
+        }
+        if let Some(cutoff) = compacted.compacted_before {
+            sessions.retain(|session| session.date() >= cutoff);
+        }
+        let source = Fingerprint::of(&bytes);
+        let stats = StatsIndex::load_or_build(&dir.join(INDEX_FILE), &sessions, &compacted, source, zone, counting);
+        Self { sessions, compacted, stats, breaks, naps, outcomes, notes, problems, revision: next_revision() }
+    }
+
+    pub fn append(&mut self, session: Session) {
+        if let Some(dir) = paths::data_dir() {
+            match append_line(&dir.join(SESSIONS_FILE), &session) {
+                Ok(line) => {
+                    self.stats.record(&session, line.as_bytes());
+                    self.stats.save(&dir.join(INDEX_FILE));
+                }
+                Err(err) => log::warn!("Cannot record session: {}", err),
+            }
+        }
+        self.sessions.push(session);
+        self.revision = next_revision();
+    }
+
+    /// Recounts the daily totals for another interpretation of days or of focus time.
+    pub fn recount(&mut self, zone: DayZone, counting: Counting) {
+        if (zone, counting) == (self.stats.zone(), self.stats.counting()) {
+            return;
+        }
+        self.stats = StatsIndex::build(&self.sessions, &self.compacted, self.stats.source(), zone, counting);
+        self.revision = next_revision();
+        if let Some(dir) = paths::data_dir() {
+            self.stats.save(&dir.join(INDEX_FILE));
+        }
+    }
+
+    /// Counts `totals` toward `date` as though compacted there, for a day carried over from a
+    /// snapshot. Kept in memory only.
+    pub fn carry_over(&mut self, date: Date, mut totals: DayTotals) {
+        if self.stats.counting() == Counting::WallClock {
+            totals.focus_secs = totals.focus_secs.saturating_sub(totals.paused_secs);
+        }
+        self.compacted.days.insert(date, totals);
+        self.stats = StatsIndex::build(&self.sessions, &self.compacted, self.stats.source(), self.stats.zone(), self.stats.counting());
+        self.revision = next_revision();
+    }
+
+    pub fn append_break(&mut self, record: EnforcedBreak) {
+        if let Some(dir) = paths::data_dir() {
+            if let Err(err) = append_line(&dir.join(BREAKS_FILE), &record) {
+                log::warn!("Cannot record enforced break: {}", err);
+            }
+        }
+        self.breaks.push(record);
+    }
+
+    pub fn append_nap(&mut self, nap: Nap) {
+        if let Some(dir) = paths::data_dir() {
+            if let Err(err) = append_line(&dir.join(NAPS_FILE), &nap) {
+                log::warn!("Cannot record nap: {}", err);
+            }
+        }
+        self.naps.push(nap);
+    }
+
+    pub fn append_outcome(&mut self, outcome: IntentOutcome) {
+        if let Some(dir) = paths::data_dir() {
+            if let Err(err) = append_line(&dir.join(INTENTIONS_FILE), &outcome) {
+                log::warn!("Cannot record intent outcome: {}", err);
+            }
+        }
+        self.outcomes.push(outcome);
+        self.revision = next_revision();
+    }
+
+    pub fn set_note(&mut self, note: DayNote) {
+        if let Some(dir) = paths::data_dir() {
+            if let Err(err) = append_line(&dir.join(DAY_NOTES_FILE), &note) {
+                log::warn!("Cannot record day note: {}", err);
+            }
+        }
+        set_note(&mut self.notes, note);
+    }
+
+    /// Folds sessions from before `cutoff` into daily totals and drops them from the session file.
+    pub fn compact(&mut self, cutoff: Date) -> Result<usize, String> {
+        let dir = paths::data_dir().ok_or("no data directory")?;
+        let _writing = WRITING.lock().unwrap_or_else(|e| e.into_inner());
+        let (old, kept): (Vec<Session>, Vec<Session>) = self.sessions.iter().cloned().partition(|s| s.date() < cutoff);
+        if old.is_empty() {
+            return Ok(0);
+        }
+
+        let mut compacted = self.compacted.clone();
+        // Totals are final once compacted, so they stay on the day the session was recorded, and
+        // keep the time paused apart for counting either way
+        for session in &old {
+            add_to_days(&mut compacted.days, session, DayZone::Recorded, Counting::Focused);
+        }
+        compacted.compacted_before = compacted.compacted_before.max(Some(cutoff));
+
+        // Totals first: until the session file is rewritten, the cutoff hides the old lines
+        let json = serde_json::to_string_pretty(&compacted).map_err(|e| e.to_string())?;
+        replace_verified(&dir.join(DAILY_FILE), &json, |text| {
+            serde_json::from_str::<Compacted>(text).is_ok_and(|read| read == compacted)
+        })?;
+        let lines: String = kept.iter().filter_map(|s| serde_json::to_string(s).ok()).map(|line| line + "\n").collect();
+        replace_verified(&dir.join(SESSIONS_FILE), &lines, |text| text.lines().count() == kept.len())?;
+
+        log::info!("Compacted {} sessions from before {}", old.len(), cutoff);
+        self.stats = StatsIndex::build(&kept, &compacted, Fingerprint::of(lines.as_bytes()), self.stats.zone(), self.stats.counting());
+        self.stats.save(&dir.join(INDEX_FILE));
+        self.compacted = compacted;
+        self.sessions = kept;
+        self.revision = next_revision();
+        Ok(old.len())
+    }
+}
+
+/// The first day whose sessions are kept individually when keeping `months` months.
+pub fn retention_cutoff(today: Date, months: u32) -> Date {
+    let total = i64::from(today.year()) * 12 + i64::from(u8::from(today.month())) - 1 - i64::from(months);
+    let Ok(year) = i32::try_from(total.div_euclid(12)) else {
+        return Date::MIN;
+    };
+    let month = Month::try_from(total.rem_euclid(12) as u8 + 1).unwrap_or(Month::January);
+    let day = today.day().min(time::util::days_in_year_month(year, month));
+    Date::from_calendar_date(year, month, day).unwrap_or(Date::MIN)
+}
+
+/// Sizes of the session and compacted files in bytes.
+pub fn file_sizes() -> (u64, u64) {
+    let size = |name: &str| {
+        paths::data_dir()
+            .and_then(|dir| fs::metadata(dir.join(name)).ok())
+            .map_or(0, |meta| meta.len())
+    };
+    (size(SESSIONS_FILE), size(DAILY_FILE))
+}
+
+/// Sessions from an exported session file, oldest first, and a note on any lines skipped.
+pub fn read_export(path: &Path) -> Result<(Vec<Session>, Option<String>), String> {
+    let bytes = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let (mut sessions, skipped) = parse_lines(&String::from_utf8_lossy(&bytes), path, parse_session);
+    sessions.sort_by_key(|session| session.start);
+    Ok((sessions, skipped))
+}
+
+// Skips lines that don't parse rather than losing the whole history to one bad line
+fn parse_lines<T>(text: &str, path: &Path, parse: impl Fn(&str) -> Result<T, String>) -> (Vec<T>, Option<String>) {
+    let mut skipped = Vec::new();
+    let items = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .filter_map(|(number, line)| match parse(line) {
+            Ok(item) => Some(item),
+            Err(err) => {
+                log::warn!("Skipping line {} of {}: {}", number + 1, path.display(), err);
+                skipped.push((number + 1, err));
+                None
+            }
+        })
+        .collect();
+    let summary = skipped.first().map(|(line, err)| {
+        format!(
+            "Skipped {} unreadable line(s) in {}, first at line {}: {}. Fix or delete them to have them counted.",
+            skipped.len(),
+            path.display(),
+            line,
+            err
+        )
+    });
+    (items, summary)
+}
+
+fn parse_session(line: &str) -> Result<Session, String> {
+    let session: Session = serde_json::from_str(line).map_err(|e| e.to_string())?;
+    if session.duration_secs > MAX_SESSION_SECS {
+        return Err(format!("duration of {} seconds is longer than a day", session.duration_secs));
+    }
+    // Keeps `Session::end` from overflowing near the end of the representable range
+    session
+        .start
+        .checked_add(time::Duration::seconds(session.duration_secs as i64))
+        .ok_or("start time is out of range")?;
+    Ok(session)
+}
+
+fn parse_note(line: &str) -> Result<DayNote, String> {
+    let note = serde_json::from_str::<DayNote>(line).map_err(|e| e.to_string())?;
+    match note.mood {
+        Some(mood) if !(1..=5).contains(&mood) => Err(format!("mood {} is not between 1 and 5", mood)),
+        _ => Ok(note),
+    }
+}
+
+/// What reading back one history file found.
+#[derive(Clone, Debug)]
+pub struct FileCheck {
+    pub name: &'static str,
+    pub bytes: u64,
+    pub records: usize,
+    /// Lines that don't parse, or 1 for a malformed daily totals file.
+    pub unreadable: usize,
+    /// Lines that still parse but no longer count, and the bytes they take up: blank lines,
+    /// sessions already folded into daily totals and day notes edited since.
+    pub obsolete: usize,
+    pub obsolete_bytes: u64,
+    pub oldest: Option<Date>,
+    pub newest: Option<Date>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Line {
+    Blank,
+    Unreadable,
+    Obsolete,
+    Record(Date),
+}
+
+/// Reads every history file back the way loading does, calling `checked` with the number of
+/// files done after each. Only reads, so it is safe on any thread.
+pub fn check(checked: impl Fn(usize)) -> Vec<FileCheck> {
+    let Some(dir) = paths::data_dir() else {
+        return Vec::new();
+    };
+    let compacted = fs::read_to_string(dir.join(DAILY_FILE)).ok().map(|text| serde_json::from_str::<Compacted>(&text));
+    let mut files = Vec::new();
+    for (done, name) in FILES.into_iter().enumerate() {
+        let bytes = fs::read(dir.join(name)).unwrap_or_default();
+        let mut file = FileCheck {
+            name,
+            bytes: bytes.len() as u64,
+            records: 0,
+            unreadable: 0,
+            obsolete: 0,
+            obsolete_bytes: 0,
+            oldest: None,
+            newest: None,
+        };
+        if name == DAILY_FILE {
+            match &compacted {
+                Some(Ok(compacted)) => {
+                    file.records = compacted.days.len();
+                    file.oldest = compacted.days.keys().next().copied();
+                    file.newest = compacted.days.keys().next_back().copied();
+                }
+                Some(Err(_)) => file.unreadable = 1,
+                None => {}
+            }
+        } else {
+            let text = String::from_utf8_lossy(&bytes);
+            let cutoff = compacted.as_ref().and_then(|read| read.as_ref().ok()).and_then(|compacted| compacted.compacted_before);
+            for (line, kind) in text.lines().zip(read_back(name, &text, cutoff)) {
+                match kind {
+                    Line::Unreadable => file.unreadable += 1,
+                    Line::Blank | Line::Obsolete => {
+                        file.obsolete += 1;
+                        file.obsolete_bytes += line.len() as u64 + 1;
+                    }
+                    Line::Record(date) => {
+                        file.records += 1;
+                        file.oldest = Some(file.oldest.map_or(date, |oldest| oldest.min(date)));
+                        file.newest = file.newest.max(Some(date));
+                    }
+                }
+            }
+        }
+        files.push(file);
+        checked(done + 1);
+    }
+    files
+}
+
+/// Rewrites the line files without their blank and obsolete lines. Unreadable lines are kept,
+/// to be fixed by hand or restored from a backup. Returns the bytes freed.
+pub fn tidy() -> Result<u64, String> {
+    let dir = paths::data_dir().ok_or("no data directory")?;
+    let _writing = WRITING.lock().unwrap_or_else(|e| e.into_inner());
+    let cutoff = fs::read_to_string(dir.join(DAILY_FILE))
+        .ok()
+        .and_then(|text| serde_json::from_str::<Compacted>(&text).ok())
+        .and_then(|compacted| compacted.compacted_before);
+    let mut freed = 0;
+    for name in FILES.into_iter().filter(|name| *name != DAILY_FILE) {
+        let path = dir.join(name);
+        let Ok(text) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let kept: Vec<&str> = text
+            .lines()
+            .zip(read_back(name, &text, cutoff))
+            .filter(|(_, kind)| matches!(kind, Line::Unreadable | Line::Record(_)))
+            .map(|(line, _)| line)
+            .collect();
+        if kept.len() == text.lines().count() {
+            continue;
+        }
+        let contents: String = kept.iter().map(|line| format!("{}\n", line)).collect();
+        replace_verified(&path, &contents, |written| written.lines().count() == kept.len())?;
+        freed += text.len().saturating_sub(contents.len()) as u64;
+    }
+    log::info!("Tidied up the history, freeing {} bytes", freed);
+    Ok(freed)
+}
+
+// Each line of the file `name`, as loading would take it
+fn read_back(name: &str, text: &str, compacted_before: Option<Date>) -> Vec<Line> {
+    match name {
+        SESSIONS_FILE => classify(text, parse_session, |session| session.date(), |_, session| Some(session.date()) < compacted_before),
+        BREAKS_FILE => classify(text, parse_json::<EnforcedBreak>, |record| record.start.date(), |_, _| false),
+        NAPS_FILE => classify(text, parse_json::<Nap>, |nap| nap.start.date(), |_, _| false),
+        INTENTIONS_FILE => classify(text, parse_json::<IntentOutcome>, |outcome| outcome.session_start.date(), |_, _| false),
+        DAY_NOTES_FILE => {
+            // Only the last edit of a day counts, and a blank one only clears the edits before it
+            let last: BTreeMap<Date, usize> =
+                text.lines().enumerate().filter_map(|(number, line)| parse_note(line).ok().map(|note| (note.date, number))).collect();
+            classify(text, parse_note, |note| note.date, |number, note| note.is_blank() || last.get(&note.date) != Some(&number))
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn classify<T>(
+    text: &str,
+    parse: impl Fn(&str) -> Result<T, String>,
+    date: impl Fn(&T) -> Date,
+    obsolete: impl Fn(usize, &T) -> bool,
+) -> Vec<Line> {
+    text.lines()
+        .enumerate()
+        .map(|(number, line)| {
+            if line.trim().is_empty() {
+                return Line::Blank;
+            }
+            match parse(line) {
+                Err(_) => Line::Unreadable,
+                Ok(record) if obsolete(number, &record) => Line::Obsolete,
+                Ok(record) => Line::Record(date(&record)),
+            }
+        })
+        .collect()
+}
+
+fn parse_json<T: serde::de::DeserializeOwned>(line: &str) -> Result<T, String> {
+    serde_json::from_str(line).map_err(|e| e.to_string())
+}
+
+fn read_compacted(path: &Path, problems: &mut Vec<String>) -> Compacted {
+    match fs::read_to_string(path) {
+        Ok(text) => serde_json::from_str(&text).unwrap_or_else(|err| {
+            log::warn!("Ignoring malformed {}: {}", path.display(), err);
+            problems.push(format!(
+                "Daily totals in {} are malformed and were ignored ({}). Restore the file from a backup to get them back.",
+                path.display(),
+                err
+            ));
+            Compacted::default()
+        }),
+        Err(_) => Compacted::default(),
+    }
+}
+
+// Returns the exact bytes written, for the stats index fingerprint
+fn append_line(path: &Path, record: &impl Serialize) -> Result<String, String> {
+    backup::before_write();
+    let _writing = WRITING.lock().unwrap_or_else(|e| e.into_inner());
+    let line = serde_json::to_string(record).map_err(|e| e.to_string())? + "\n";
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| e.to_string())?;
+    file.write_all(line.as_bytes()).map_err(|e| e.to_string())?;
+    Ok(line)
+}
+
+// Write a new file, read it back, and only then swap it in
+fn replace_verified(path: &Path, contents: &str, verify: impl Fn(&str) -> bool) -> Result<(), String> {
+    backup::before_write();
+    let temp = PathBuf::from(format!("{}.tmp", path.display()));
+    fs::write(&temp, contents).map_err(|e| e.to_string())?;
+    let written = fs::read_to_string(&temp).map_err(|e| e.to_string())?;
+    if !verify(&written) {
+        let _ = fs::remove_file(&temp);
+        return Err(format!("verification of {} failed", temp.display()));
+    }
+    fs::rename(&temp, path).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::{date, datetime, offset};
+
+    const GOOD: &str = r#"{"start":"2024-03-01T09:00:00+01:00","duration_secs":1500}"#;
+
+    fn session(start: &str, duration_secs: u64) -> Session {
+        serde_json::from_str(&format!(r#"{{"start":"{}","duration_secs":{}}}"#, start, duration_secs)).unwrap()
+    }
+
+    fn days(session: &Session) -> Vec<(Date, u64)> {
+        session.days(DayZone::Recorded, Counting::Focused)
+    }
+
+    #[test]
+    fn unreadable_session_lines_are_skipped_and_reported() {
+        let bad = [
+            "{",
+            "null",
+            "[]",
+            "\u{feff}{}",
+            r#"{"start":"yesterday","duration_secs":1500}"#,
+            r#"{"start":"2024-03-01T09:00:00+01:00","duration_secs":-5}"#,
+            r#"{"start":"2024-03-01T09:00:00+01:00","duration_secs":1e3}"#,
+            r#"{"start":"2024-03-01T09:00:00+01:00","duration_secs":18446744073709551616}"#,
+            r#"{"start":"2024-03-01T09:00:00+01:00","duration_secs":86401}"#,
+            r#"{"start":"9999-12-31T23:59:59+00:00","duration_secs":3600}"#,
+            r#"{"start":"2024-03-01T09:00:00+01:00","duration_secs":1500,"pauses":"many"}"#,
+        ];
+        let mut text = format!("{}\n\n   \n", GOOD);
+        for line in bad {
+            text.push_str(line);
+            text.push('\n');
+        }
+        text.push_str(&"[".repeat(100_000));
+        text.push('\n');
+        text.push_str(GOOD);
+        let (sessions, summary) = parse_lines(&text, Path::new("history.jsonl"), parse_session);
+        assert_eq!(sessions.len(), 2);
+        let summary = summary.unwrap();
+        assert!(summary.starts_with("Skipped 12 unreadable line(s) in history.jsonl, first at line 4:"), "{}", summary);
+    }
+
+    #[test]
+    fn a_file_that_isnt_text_loses_only_its_broken_lines() {
+        let mut bytes = GOOD.as_bytes().to_vec();
+        bytes.extend(b"\n\xff\xfe\x00garbage\n");
+        bytes.extend(GOOD.as_bytes());
+        let (sessions, summary) = parse_lines(&String::from_utf8_lossy(&bytes), Path::new("history.jsonl"), parse_session);
+        assert_eq!(sessions.len(), 2);
+        assert!(summary.is_some_and(|summary| summary.contains("first at line 2")));
+        assert_eq!(parse_lines("", Path::new("history.jsonl"), parse_session), (Vec::new(), None));
+    }
+
+    #[test]
+    fn a_session_of_a_whole_day_is_still_read() {
+        let line = r#"{"start":"2024-03-01T00:00:00+01:00","duration_secs":86400}"#;
+        assert_eq!(parse_session(line).map(|session| session.duration_secs), Ok(86_400));
+    }
+
+    #[test]
+    fn moods_outside_one_to_five_are_refused() {
+        for mood in [0, 6, 255] {
+            assert!(parse_note(&format!(r#"{{"date":"2024-03-01","mood":{}}}"#, mood)).is_err(), "mood {}", mood);
+        }
+        assert!(parse_note(r#"{"date":"2024-03-01","mood":256}"#).is_err());
+        assert_eq!(parse_note(r#"{"date":"2024-03-01","mood":5}"#).map(|note| note.mood), Ok(Some(5)));
+    }
+
+    #[test]
+    fn the_retention_cutoff_never_overflows() {
+        assert_eq!(retention_cutoff(date!(2024 - 03 - 31), 1), date!(2024 - 02 - 29));
+        assert_eq!(retention_cutoff(date!(2024 - 01 - 15), 13), date!(2022 - 12 - 15));
+        assert_eq!(retention_cutoff(date!(2024 - 01 - 15), u32::MAX), Date::MIN);
+        assert_eq!(retention_cutoff(Date::MIN, 1), Date::MIN);
+    }
+
+    #[test]
+    fn sessions_past_midnight_are_split_across_both_days() {
+        assert_eq!(days(&session("2024-03-01T23:30:00+01:00", 3600)), [(date!(2024 - 03 - 01), 1800), (date!(2024 - 03 - 02), 1800)]);
+        assert_eq!(days(&session("2024-03-01T22:00:00+01:00", 3600)), [(date!(2024 - 03 - 01), 3600)]);
+        // Ending right at midnight, or starting there, stays on one day
+        assert_eq!(days(&session("2024-03-01T23:00:00+01:00", 3600)), [(date!(2024 - 03 - 01), 3600)]);
+        assert_eq!(days(&session("2024-03-02T00:00:00+01:00", 60)), [(date!(2024 - 03 - 02), 60)]);
+        assert_eq!(days(&session("2024-03-01T12:00:00+00:00", 86_400)), [(date!(2024 - 03 - 01), 43_200), (date!(2024 - 03 - 02), 43_200)]);
+    }
+
+    #[test]
+    fn nights_with_a_dst_change_neither_add_nor_lose_time() {
+        // Clocks go forward at 02:00 in Central Europe; the session keeps the offset it started in
+        let spring = session("2024-03-30T23:00:00+01:00", 3 * 3600);
+        assert_eq!(days(&spring), [(date!(2024 - 03 - 30), 3600), (date!(2024 - 03 - 31), 7200)]);
+        // And back at 03:00 in October
+        let autumn = session("2024-10-26T23:30:00+02:00", 2 * 3600);
+        assert_eq!(days(&autumn), [(date!(2024 - 10 - 26), 1800), (date!(2024 - 10 - 27), 5400)]);
+        // Started after the change, the new offset's midnight splits it
+        let after = session("2024-10-27T23:45:00+01:00", 1800);
+        assert_eq!(days(&after), [(date!(2024 - 10 - 27), 900), (date!(2024 - 10 - 28), 900)]);
+        for session in [spring, autumn, after] {
+            assert_eq!(days(&session).iter().map(|(_, secs)| secs).sum::<u64>(), session.duration_secs);
+        }
+    }
+
+    #[test]
+    fn time_paused_before_midnight_counts_on_the_day_before() {
+        let mut paused = session("2024-03-02T00:05:00+01:00", 1500);
+        paused.paused_secs = 600;
+        assert_eq!(paused.days(DayZone::Recorded, Counting::Focused), [(date!(2024 - 03 - 02), 1500)]);
+        assert_eq!(paused.days(DayZone::Recorded, Counting::WallClock), [(date!(2024 - 03 - 01), 300), (date!(2024 - 03 - 02), 1800)]);
+    }
+
+    #[test]
+    fn a_session_counts_on_the_day_it_started() {
+        let mut days = BTreeMap::new();
+        add_to_days(&mut days, &session("2024-03-01T23:30:00+01:00", 3600), DayZone::Recorded, Counting::Focused);
+        assert_eq!(days[&date!(2024 - 03 - 01)], DayTotals { sessions: 1, focus_secs: 1800, ..DayTotals::default() });
+        assert_eq!(days[&date!(2024 - 03 - 02)], DayTotals { sessions: 0, focus_secs: 1800, ..DayTotals::default() });
+    }
+
+    #[test]
+    fn pauses_are_kept_one_by_one_and_older_records_keep_their_total() {
+        let line = concat!(
+            r#"{"start":"2024-03-01T09:00:00+01:00","duration_secs":1500,"pauses":2,"paused_secs":420,"pause_segments":["#,
+            r#"{"start":"2024-03-01T09:05:00+01:00","end":"2024-03-01T09:07:00+01:00","reason":"manual"},"#,
+            r#"{"start":"2024-03-01T09:15:00+01:00","end":"2024-03-01T09:20:00+01:00","reason":"external"}]}"#
+        );
+        let paused = parse_session(line).unwrap();
+        assert_eq!(paused.pause_segments.iter().map(Pause::secs).sum::<u64>(), paused.paused_secs);
+        assert_eq!((paused.paused_for(PauseReason::Manual), paused.paused_for(PauseReason::External)), (120, 300));
+        // The session ran from its start to after its focus and pauses
+        assert_eq!(paused.end(), datetime!(2024-03-01 09:32 +01:00));
+        assert!(paused.pause_segments.iter().all(|pause| paused.start <= pause.start && pause.end <= paused.end()));
+        assert_eq!(serde_json::to_string(&paused).unwrap(), line);
+
+        let older = parse_session(r#"{"start":"2024-03-01T09:00:00+01:00","duration_secs":1500,"pauses":1,"paused_secs":300}"#).unwrap();
+        assert!(older.pause_segments.is_empty());
+        assert_eq!((older.paused_for(PauseReason::Manual), older.paused_for(PauseReason::External)), (0, 0));
+        assert_eq!((older.counted_secs(Counting::Focused), older.counted_secs(Counting::WallClock)), (1500, 1800));
+        assert!(!serde_json::to_string(&older).unwrap().contains("pause_segments"));
+    }
+
+    #[test]
+    fn pauses_kept_one_by_one_are_left_out_of_the_day_they_fall_on() {
+        let line = concat!(
+            r#"{"start":"2024-03-01T23:50:00+01:00","duration_secs":1500,"pauses":1,"paused_secs":600,"pause_segments":["#,
+            r#"{"start":"2024-03-01T23:55:00+01:00","end":"2024-03-02T00:05:00+01:00","reason":"manual"}]}"#
+        );
+        let paused = parse_session(line).unwrap();
+        assert_eq!(paused.end(), datetime!(2024-03-02 00:25 +01:00));
+        assert_eq!(days(&paused), [(date!(2024 - 03 - 01), 300), (date!(2024 - 03 - 02), 1200)]);
+        assert_eq!(paused.days(DayZone::Recorded, Counting::WallClock), [(date!(2024 - 03 - 01), 600), (date!(2024 - 03 - 02), 1500)]);
+    }
+
+    #[test]
+    fn days_keep_the_time_paused_apart_however_focus_is_counted() {
+        let mut paused = session("2024-03-01T09:00:00+01:00", 1500);
+        paused.paused_secs = 600;
+        for counting in [Counting::Focused, Counting::WallClock] {
+            let mut days = BTreeMap::new();
+            add_to_days(&mut days, &paused, DayZone::Recorded, counting);
+            add_to_days(&mut days, &session("2024-03-01T10:00:00+01:00", 1500), DayZone::Recorded, counting);
+            let day = days[&date!(2024 - 03 - 01)];
+            assert_eq!((day.sessions, day.paused_secs), (2, 600));
+            assert_eq!(day.focus_secs, if counting == Counting::Focused { 3000 } else { 3600 });
+        }
+    }
+
+    #[test]
+    fn sessions_from_another_zone_can_count_toward_the_day_here() {
+        clock::TEST_OFFSET.set(Some(offset!(+9)));
+        // 20:00 in Berlin is 04:00 the next morning in Tokyo
+        let evening = session("2024-03-01T20:00:00+01:00", 1500);
+        assert_eq!(evening.local_start(DayZone::Current), datetime!(2024-03-02 04:00 +09:00));
+        assert_eq!(days(&evening), [(date!(2024 - 03 - 01), 1500)]);
+        assert_eq!(evening.days(DayZone::Current, Counting::Focused), [(date!(2024 - 03 - 02), 1500)]);
+        // Midnight here falls in the middle of the afternoon there
+        let afternoon = session("2024-03-01T15:30:00+01:00", 3600);
+        assert_eq!(days(&afternoon), [(date!(2024 - 03 - 01), 3600)]);
+        assert_eq!(afternoon.days(DayZone::Current, Counting::Focused), [(date!(2024 - 03 - 01), 1800), (date!(2024 - 03 - 02), 1800)]);
+        let sessions = [evening, afternoon];
+        let build = |zone| StatsIndex::build(&sessions, &Compacted::default(), Fingerprint::default(), zone, Counting::Focused);
+        assert_eq!(build(DayZone::Recorded).day(date!(2024 - 03 - 01)).sessions, 2);
+        let here = build(DayZone::Current);
+        assert_eq!((here.day(date!(2024 - 03 - 01)).sessions, here.day(date!(2024 - 03 - 02)).sessions), (1, 1));
+        assert_eq!(here.day(date!(2024 - 03 - 02)).focus_secs, 3300);
+        clock::TEST_OFFSET.set(None);
+    }
+}
@@ -0,0 +1,110 @@
+//! Checking the history from the settings: every file read back for its size, records, date
+//! range and unreadable lines, and tidied up once too much of it is lines that no longer
+//! count. Runs on a thread of its own, so the timer carries on meanwhile.
+
+use eframe::egui;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::background::Background;
+use crate::history::{self, FileCheck};
+
+// Share of the line files taken up by obsolete lines above which they are rewritten
+const TIDY_ABOVE: f64 = 0.2;
+
+struct Outcome {
+    files: Vec<FileCheck>,
+    /// Bytes freed when tidied up, or why that failed; None when not needed.
+    tidied: Option<Result<u64, String>>,
+}
+
+impl Outcome {
+    fn unreadable(&self) -> usize {
+        self.files.iter().map(|file| file.unreadable).sum()
+    }
+}
+
+pub struct Check {
+    checked: Arc<AtomicUsize>,
+    outcome: Background<Outcome>,
+}
+
+impl Check {
+    pub fn start() -> Self {
+        let checked = Arc::new(AtomicUsize::new(0));
+        let progress = checked.clone();
+        let outcome = Background::spawn("history check", move || {
+            let mut files = history::check(|done| progress.store(done, Ordering::Relaxed));
+            let total: u64 = files.iter().map(|file| file.bytes).sum();
+            let obsolete: u64 = files.iter().map(|file| file.obsolete_bytes).sum();
+            // Nothing is rewritten while something is unreadable, until it is fixed or restored
+            let unreadable = files.iter().any(|file| file.unreadable > 0);
+            let tidied = (!unreadable && obsolete as f64 > total as f64 * TIDY_ABOVE).then(history::tidy);
+            if let Some(Ok(_)) = tidied {
+                for file in &mut files {
+                    file.bytes -= file.obsolete_bytes;
+                    (file.obsolete, file.obsolete_bytes) = (0, 0);
+                }
+            }
+            Outcome { files, tidied }
+        });
+        Self { checked, outcome }
+    }
+
+    pub fn running(&self) -> bool {
+        self.outcome.peek().is_none()
+    }
+
+    /// Progress, then what was found. Returns true when restoring a backup was asked for.
+    pub fn show(&mut self, ui: &mut egui::Ui) -> bool {
+        let Some(outcome) = self.outcome.get() else {
+            let done = self.checked.load(Ordering::Relaxed);
+            let text = if done < history::FILES.len() { "Checking history…" } else { "Tidying up…" };
+            ui.add(egui::ProgressBar::new(done as f32 / history::FILES.len() as f32).text(text));
+            return false;
+        };
+        egui::Grid::new("history_check").striped(true).show(ui, |ui| {
+            for file in &outcome.files {
+                ui.label(file.name);
+                ui.label(crate::format_size(file.bytes));
+                ui.label(format!("{} records", file.records));
+                match (file.oldest, file.newest) {
+                    (Some(oldest), Some(newest)) => ui.label(format!("{} to {}", oldest, newest)),
+                    _ => ui.weak("empty"),
+                };
+                if file.unreadable > 0 {
+                    ui.colored_label(egui::Color32::RED, format!("⚠ {} unreadable", file.unreadable));
+                } else if file.obsolete > 0 {
+                    ui.weak(format!("{} lines no longer count", file.obsolete));
+                } else {
+                    ui.label("✔");
+                }
+                ui.end_row();
+            }
+        });
+        let total: u64 = outcome.files.iter().map(|file| file.bytes).sum();
+        ui.label(format!("{} in all", crate::format_size(total)));
+        match &outcome.tidied {
+            Some(Ok(freed)) => {
+                ui.label(format!("Tidied up, freeing {}", crate::format_size(*freed)));
+            }
+            Some(Err(err)) => {
+                ui.colored_label(egui::Color32::RED, format!("Cannot tidy up: {}", err));
+            }
+            None => {}
+        }
+        match outcome.unreadable() {
+            0 => {
+                ui.label("✔ Every record reads back fine");
+                false
+            }
+            unreadable => {
+                ui.colored_label(
+                    egui::Color32::RED,
+                    format!("⚠ {} records can't be read and are left out of the statistics", unreadable),
+                );
+                ui.button("Restore the newest backup…").clicked()
+            }
+        }
+    }
+}
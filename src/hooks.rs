@@ -0,0 +1,191 @@
+//! Shell commands run on timer events, e.g. to switch the window manager to a focus workspace.
+//! Each hook pairs a trigger such as `focus_started_with_label:Writing` with a command; presets
+//! fill in commands for common window managers from a few named parameters.
+
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+
+use crate::events::Event;
+
+/// What a hook reacts to.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Trigger {
+    FocusStarted,
+    /// A focus session started on a task with exactly this label.
+    FocusStartedWithLabel(String),
+    FocusFinished,
+    BreakStarted,
+    BreakFinished,
+    GoalReached,
+}
+
+impl Trigger {
+    pub const NAMES: [&'static str; 6] = [
+        "focus_started",
+        "focus_started_with_label:",
+        "focus_finished",
+        "break_started",
+        "break_finished",
+        "goal_reached",
+    ];
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        if let Some(label) = text.strip_prefix("focus_started_with_label:") {
+            return match label.trim() {
+                "" => Err("focus_started_with_label: needs a label after the colon".to_owned()),
+                label => Ok(Trigger::FocusStartedWithLabel(label.to_owned())),
+            };
+        }
+        match text {
+            "focus_started" => Ok(Trigger::FocusStarted),
+            "focus_finished" => Ok(Trigger::FocusFinished),
+            "break_started" => Ok(Trigger::BreakStarted),
+            "break_finished" => Ok(Trigger::BreakFinished),
+            "goal_reached" => Ok(Trigger::GoalReached),
+            _ => Err(format!("unknown trigger \"{}\", available: {}", text, Trigger::NAMES.join(", "))),
+        }
+    }
+
+    fn matches(&self, event: &Event) -> bool {
+        match (self, event) {
+            (Trigger::FocusStarted, Event::PeriodStarted { work: true, .. }) => true,
+            (Trigger::FocusStartedWithLabel(wanted), Event::PeriodStarted { work: true, label: Some(label) }) => {
+                label.trim() == wanted
+            }
+            (Trigger::BreakStarted, Event::PeriodStarted { work: false, .. }) => true,
+            (Trigger::FocusFinished, Event::PeriodFinished { work: true, .. }) => true,
+            (Trigger::BreakFinished, Event::PeriodFinished { work: false, .. }) => true,
+            (Trigger::GoalReached, Event::GoalReached { .. }) => true,
+            _ => false,
+        }
+    }
+}
+
+/// A hook as stored in the settings; the trigger is kept as written so a typo isn't lost.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Hook {
+    pub trigger: String,
+    pub command: String,
+}
+
+/// A named hook with `{parameter}` slots in its command, offered as a template in the settings.
+pub struct Preset {
+    pub name: &'static str,
+    pub trigger: &'static str,
+    pub command: &'static str,
+    /// Parameter names with their defaults.
+    pub params: &'static [(&'static str, &'static str)],
+}
+
+pub const PRESETS: [Preset; 6] = [
+    Preset {
+        name: "i3: focus workspace",
+        trigger: "focus_started",
+        command: "i3-msg workspace {workspace}",
+        params: &[("workspace", "focus")],
+    },
+    Preset {
+        name: "i3: layout for focus",
+        trigger: "focus_started",
+        command: "i3-msg layout {layout}",
+        params: &[("layout", "tabbed")],
+    },
+    Preset {
+        name: "i3: back to previous workspace on break",
+        trigger: "break_started",
+        command: "i3-msg workspace back_and_forth",
+        params: &[],
+    },
+    Preset {
+        name: "Hyprland: focus workspace",
+        trigger: "focus_started",
+        command: "hyprctl dispatch workspace {workspace}",
+        params: &[("workspace", "2")],
+    },
+    Preset {
+        name: "Hyprland: workspace for a task",
+        trigger: "focus_started_with_label:{label}",
+        command: "hyprctl dispatch workspace {workspace}",
+        params: &[("label", "Writing"), ("workspace", "3")],
+    },
+    Preset {
+        name: "Hyprland: show scratchpad on break",
+        trigger: "break_started",
+        command: "hyprctl dispatch togglespecialworkspace {name}",
+        params: &[("name", "magic")],
+    },
+];
+
+impl Preset {
+    /// The hook with `values` in place of the parameters, in the order of [`Preset::params`].
+    /// Values are shell-quoted in the command, so spaces and quotes stay one argument.
+    pub fn fill(&self, values: &[String]) -> Hook {
+        let mut hook = Hook { trigger: self.trigger.to_owned(), command: self.command.to_owned() };
+        for ((name, _), value) in self.params.iter().zip(values) {
+            let slot = format!("{{{}}}", name);
+            hook.trigger = hook.trigger.replace(&slot, value.trim());
+            hook.command = hook.command.replace(&slot, &shell_quote(value.trim()));
+        }
+        hook
+    }
+}
+
+fn shell_quote(value: &str) -> String {
+    if !value.is_empty() && value.chars().all(|c| c.is_ascii_alphanumeric() || "-_.:/@+=".contains(c)) {
+        value.to_owned()
+    } else {
+        format!("'{}'", value.replace('\'', r"'\''"))
+    }
+}
+
+/// Runs hooks for the events they match; invalid triggers were reported in the settings and
+/// are skipped here.
+pub struct Runner {
+    hooks: Vec<(Trigger, String)>,
+}
+
+impl Runner {
+    /// `None` when no hook is set up, so nothing needs to subscribe.
+    pub fn new(hooks: &[Hook]) -> Option<Self> {
+        let hooks: Vec<_> = hooks
+            .iter()
+            .filter(|hook| !hook.command.trim().is_empty())
+            .filter_map(|hook| Some((Trigger::parse(&hook.trigger).ok()?, hook.command.clone())))
+            .collect();
+        (!hooks.is_empty()).then_some(Self { hooks })
+    }
+
+    /// Runs on the bus thread, so waiting for a slow command delays only later hooks.
+    pub fn handle(&self, event: Event) {
+        for (trigger, command) in &self.hooks {
+            if !trigger.matches(&event) {
+                continue;
+            }
+            let mut process = shell(command);
+            process.env("FERRISFOCUS_EVENT", event.name());
+            if let Event::PeriodStarted { label: Some(label), .. } = &event {
+                process.env("FERRISFOCUS_LABEL", label);
+            }
+            match process.status() {
+                Ok(status) if status.success() => log::debug!(command:%, event = event.name(); "Hook ran"),
+                Ok(status) => log::warn!(command:%, event = event.name(), status:%; "Hook failed"),
+                Err(err) => log::warn!(command:%, event = event.name(), error:% = err; "Cannot run hook"),
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut process = Command::new("sh");
+    process.arg("-c").arg(command);
+    process
+}
+
+#[cfg(not(unix))]
+fn shell(command: &str) -> Command {
+    let mut process = Command::new("cmd");
+    process.arg("/C").arg(command);
+    process
+}
@@ -0,0 +1,19 @@
+use std::fmt;
+
+/// Why an integration such as the status socket or the D-Bus service isn't running.
+#[derive(Debug)]
+pub enum StartError {
+    /// Another instance, possibly another user's, holds the resource; worth telling the user,
+    /// who can close it and restart.
+    Conflict(String),
+    /// Not possible here, e.g. without a session bus; only worth a log line.
+    Unavailable(String),
+}
+
+impl fmt::Display for StartError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StartError::Conflict(reason) | StartError::Unavailable(reason) => f.write_str(reason),
+        }
+    }
+}
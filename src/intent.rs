@@ -0,0 +1,127 @@
+//! A one-line intent for a focus session, such as "Finish the intro section", asked for when
+//! it starts and checked off when it ends.
+
+use eframe::egui;
+use std::collections::BTreeMap;
+use time::Date;
+
+use crate::history::{IntentOutcome, Met, Session};
+
+pub enum Asked {
+    /// Start the session, with no intent if the line was left empty.
+    Start,
+    Cancel,
+}
+
+/// "What's your goal for this pomodoro?" Enter starts, Escape doesn't. The text is masked
+/// when `private`.
+pub fn ask(ctx: &egui::Context, text: &mut String, private: bool) -> Option<Asked> {
+    let mut open = true;
+    let mut asked = None;
+    egui::Window::new("Intent")
+        .open(&mut open)
+        .resizable(false)
+        .collapsible(false)
+        .title_bar(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, |ui| {
+            crate::compact_text(ui.style_mut());
+            ui.label("What's your goal for this pomodoro?");
+            let line = ui.add(egui::TextEdit::singleline(text).hint_text("Enter to skip").password(private).desired_width(240.0));
+            line.request_focus();
+            if line.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                asked = Some(Asked::Start);
+            }
+            ui.horizontal(|ui| {
+                if ui.button("Start").clicked() {
+                    asked = Some(Asked::Start);
+                }
+                if ui.button("Cancel").clicked() {
+                    asked = Some(Asked::Cancel);
+                }
+            });
+        });
+    if !open || ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+        return Some(Asked::Cancel);
+    }
+    asked
+}
+
+pub enum Review {
+    Answered(Met),
+    Skipped,
+}
+
+/// "Did you: <intent>?" at the end of the session. Enter or Escape skips.
+pub fn review(ctx: &egui::Context, intent: &str) -> Option<Review> {
+    let mut review = None;
+    egui::Window::new("Intent met?")
+        .resizable(false)
+        .collapsible(false)
+        .title_bar(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, |ui| {
+            crate::compact_text(ui.style_mut());
+            ui.label(format!("Did you: {}?", intent));
+            ui.horizontal(|ui| {
+                for (met, text) in [(Met::Yes, "Yes"), (Met::Partly, "Partially"), (Met::No, "No")] {
+                    if ui.button(text).clicked() {
+                        review = Some(Review::Answered(met));
+                    }
+                }
+                if ui.small_button("Skip").clicked() {
+                    review = Some(Review::Skipped);
+                }
+            });
+        });
+    if ctx.input(|i| i.key_pressed(egui::Key::Enter) || i.key_pressed(egui::Key::Escape)) {
+        return Some(Review::Skipped);
+    }
+    review
+}
+
+/// How the answered intents of some sessions turned out.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Tally {
+    pub met: u32,
+    pub partly: u32,
+    pub answered: u32,
+}
+
+impl Tally {
+    fn add(&mut self, met: Met) {
+        self.answered += 1;
+        match met {
+            Met::Yes => self.met += 1,
+            Met::Partly => self.partly += 1,
+            Met::No => {}
+        }
+    }
+}
+
+impl std::fmt::Display for Tally {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} of {} met", self.met, self.answered)?;
+        if self.partly > 0 {
+            write!(f, ", {} partly", self.partly)?;
+        }
+        Ok(())
+    }
+}
+
+/// Answered intents per day and per task label. Sessions that were already compacted have
+/// lost their start, so their answers no longer count.
+pub fn tally(sessions: &[Session], outcomes: &[IntentOutcome]) -> (BTreeMap<Date, Tally>, BTreeMap<String, Tally>) {
+    let mut days = BTreeMap::<Date, Tally>::new();
+    let mut tasks = BTreeMap::<String, Tally>::new();
+    for outcome in outcomes {
+        let Some(session) = sessions.iter().rev().find(|session| session.start == outcome.session_start) else {
+            continue;
+        };
+        days.entry(session.date()).or_default().add(outcome.met);
+        if let Some(label) = &session.label {
+            tasks.entry(label.clone()).or_default().add(outcome.met);
+        }
+    }
+    (days, tasks)
+}
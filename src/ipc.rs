@@ -0,0 +1,343 @@
+//! Lets other processes see what the timer is doing: a Unix socket in the user's runtime dir that answers
+//! every connection with one line of JSON, and opt-in files for callers too frequent for that or
+//! unable to speak to a socket. Commands that change the app's data send it a request over the
+//! same socket, as set out in [`crate::ownership`].
+
+use eframe::egui;
+use pomodoro_timer::tasks::TaskList;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+#[cfg(unix)]
+use crate::integration::StartError;
+use crate::paths;
+
+const SOCKET_NAME: &str = "ferrisfocus.sock";
+pub const STATUS_FILE: &str = "status.json";
+pub const SUMMARY_FILE: &str = "summary.json";
+// How long a client has to send a request after reading the status
+#[cfg(unix)]
+const REQUEST_WAIT: Duration = Duration::from_millis(200);
+// How long the app has to answer one
+const REPLY_WAIT: Duration = Duration::from_secs(5);
+
+/// The timer as seen from outside.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Status {
+    pub work_period: bool,
+    pub running: bool,
+    pub remaining_secs: u64,
+    #[serde(default)]
+    pub duration_secs: u64,
+    pub sessions_today: Option<u32>,
+}
+
+impl Status {
+    /// Values for [`crate::template`] placeholders; `None` where there is nothing to show.
+    pub fn field(&self, name: &str) -> Option<String> {
+        match name {
+            "phase" => Some(if self.work_period { "Focus" } else { "Break" }.to_owned()),
+            "icon" => Some(if self.work_period { "🍅" } else { "☕" }.to_owned()),
+            "remaining" => Some(format!("{:02}:{:02}", self.remaining_secs / 60, self.remaining_secs % 60)),
+            "paused" => (!self.running).then(|| "⏸".to_owned()),
+            "sessions_today" => self.sessions_today.map(|count| count.to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// Today at a glance for conky, Rainmeter and shell scripts. Fields are only ever added,
+/// with `schema_version` raised when they are, so readers can ignore what they don't know.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Summary {
+    pub schema_version: u32,
+    /// Local date, `YYYY-MM-DD`.
+    pub date: String,
+    pub pomodoros: u32,
+    pub focus_minutes: u64,
+    pub goal: Option<u32>,
+    /// Days in a row with at least one pomodoro, counting today once it has one.
+    pub streak_days: u32,
+    /// `work`, `break`, `paused` or `idle`, as in [`Status::class`].
+    pub phase: &'static str,
+    pub remaining_secs: u64,
+}
+
+impl Summary {
+    pub const SCHEMA_VERSION: u32 = 1;
+}
+
+/// Keeps a JSON file in the data dir in step with the app, written atomically with the time of
+/// writing, and removes it on drop so a missing file means nothing is running.
+pub struct DataFile<T> {
+    name: &'static str,
+    written: Option<T>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Stamped<T> {
+    #[serde(flatten)]
+    value: T,
+    /// Unix time of writing, for counting down between writes.
+    written_at: i64,
+}
+
+impl<T: Clone + PartialEq + Serialize> DataFile<T> {
+    pub fn new(name: &'static str) -> Self {
+        Self { name, written: None }
+    }
+
+    /// Writes only when something changed, and says whether it did; the remaining time changes
+    /// once a second at most.
+    pub fn update(&mut self, value: &T) -> bool {
+        if self.written.as_ref() == Some(value) {
+            return false;
+        }
+        self.written = Some(value.clone());
+        let Some(path) = paths::data_dir().map(|dir| dir.join(self.name)) else {
+            return false;
+        };
+        let stamped = Stamped { value, written_at: crate::clock::now().unix_timestamp() };
+        let result = serde_json::to_string(&stamped).map_err(|e| e.to_string()).and_then(|json| {
+            let temp = path.with_extension("json.tmp");
+            std::fs::write(&temp, json).and_then(|()| std::fs::rename(&temp, &path)).map_err(|e| e.to_string())
+        });
+        if let Err(err) = result {
+            log::warn!("Cannot write {}: {}", path.display(), err);
+        }
+        true
+    }
+}
+
+impl<T> DataFile<T> {
+    /// Removes the file, for when the user turns it off.
+    pub fn clear(&mut self) {
+        if self.written.take().is_some() {
+            if let Some(path) = paths::data_dir().map(|dir| dir.join(self.name)) {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+    }
+}
+
+impl<T> Drop for DataFile<T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl Status {
+    /// `idle` before the interval started, `paused` partway through, otherwise the phase.
+    pub fn class(&self) -> &'static str {
+        match (self.running, self.work_period) {
+            (true, true) => "work",
+            (true, false) => "break",
+            (false, _) if self.remaining_secs >= self.duration_secs => "idle",
+            (false, _) => "paused",
+        }
+    }
+
+    /// How much of the interval has passed, from 0 to 100.
+    pub fn percentage(&self) -> u64 {
+        let elapsed = self.duration_secs.saturating_sub(self.remaining_secs);
+        (elapsed * 100).checked_div(self.duration_secs).unwrap_or(0)
+    }
+}
+
+/// The status from the status file, with the remaining time counted down to `now` (Unix time).
+pub fn read_status_file(now: i64) -> Option<Status> {
+    let text = std::fs::read_to_string(paths::data_dir()?.join(STATUS_FILE)).ok()?;
+    let Stamped { value: mut status, written_at } = serde_json::from_str::<Stamped<Status>>(&text).ok()?;
+    if status.running {
+        let since = u64::try_from(now - written_at).unwrap_or(0);
+        status.remaining_secs = status.remaining_secs.saturating_sub(since);
+    }
+    Some(status)
+}
+
+fn socket_path() -> Option<PathBuf> {
+    paths::runtime_dir().map(|dir| dir.join(SOCKET_NAME))
+}
+
+/// A change asked of the running app by another process, sent as one line of JSON after the
+/// status line was read.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Request {
+    /// Merge these into the task list, like `import-tasks` would.
+    ImportTasks(TaskList),
+    /// Start a quick timer, like `quick` would.
+    QuickTimer { secs: u64, message: Option<String> },
+}
+
+/// What the app said to a request: a line to print, or why it couldn't.
+pub type Reply = Result<String, String>;
+
+/// A request waiting for the UI thread, which answers through `reply`.
+pub struct Forwarded {
+    pub request: Request,
+    pub reply: mpsc::Sender<Reply>,
+}
+
+/// Answers status queries until dropped, from whatever the app last put into the shared status,
+/// and hands requests to the app.
+#[cfg(unix)]
+pub struct Server {
+    path: PathBuf,
+    requests: mpsc::Receiver<Forwarded>,
+    wake: Arc<OnceLock<egui::Context>>,
+}
+
+#[cfg(unix)]
+impl Server {
+    /// Fails with a conflict if another instance is already answering.
+    pub fn start(status: Arc<Mutex<Status>>) -> Result<Self, StartError> {
+        use std::os::unix::net::UnixStream;
+
+        let path = socket_path().ok_or_else(|| StartError::Unavailable("nowhere to put the status socket".to_owned()))?;
+        if path.exists() {
+            if UnixStream::connect(&path).is_ok() {
+                return Err(StartError::Conflict(format!(
+                    "Another FerrisFocus is answering status queries at {}",
+                    path.display()
+                )));
+            }
+            // Left behind by an instance that crashed
+            let _ = std::fs::remove_file(&path);
+        }
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        Self::listen(path, status)
+    }
+
+    /// Answers at `path`, which must be free.
+    pub fn listen(path: PathBuf, status: Arc<Mutex<Status>>) -> Result<Self, StartError> {
+        use std::os::unix::net::UnixListener;
+
+        let listener = UnixListener::bind(&path)
+            .map_err(|err| StartError::Conflict(format!("Cannot listen on {}: {}", path.display(), err)))?;
+        let (sender, requests) = mpsc::channel();
+        let wake = Arc::new(OnceLock::new());
+        let waker = Arc::clone(&wake);
+        let spawned = std::thread::Builder::new().name("ipc".to_owned()).spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let status = status.lock().map(|status| status.clone()).unwrap_or_default();
+                if let Err(err) = answer(stream, &status, &sender, &waker) {
+                    log::warn!("Cannot answer over the status socket: {}", err);
+                }
+            }
+        });
+        if let Err(err) = spawned {
+            return Err(StartError::Unavailable(format!("cannot start ipc thread: {}", err)));
+        }
+        Ok(Self { path, requests, wake })
+    }
+
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+
+    /// Has the window drawn when a request comes in, so it is answered without waiting for input.
+    pub fn wake(&self, ctx: &egui::Context) {
+        let _ = self.wake.set(ctx.clone());
+    }
+
+    /// The requests that came in since last asked.
+    pub fn requests(&self) -> impl Iterator<Item = Forwarded> + '_ {
+        self.requests.try_iter()
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+// The status line first, as every client expects; then a request, if the client sends one
+// before hanging up, waits for the app's reply
+#[cfg(unix)]
+fn answer(
+    mut stream: std::os::unix::net::UnixStream,
+    status: &Status,
+    app: &mpsc::Sender<Forwarded>,
+    wake: &OnceLock<egui::Context>,
+) -> std::io::Result<()> {
+    use std::io::{BufRead, Write};
+
+    writeln!(stream, "{}", serde_json::to_string(status).unwrap_or_default())?;
+    stream.set_read_timeout(Some(REQUEST_WAIT))?;
+    let mut line = String::new();
+    // A client that only wanted the status has hung up, or stays silent
+    if std::io::BufReader::new(&stream).read_line(&mut line).unwrap_or(0) == 0 {
+        return Ok(());
+    }
+    let reply = match serde_json::from_str::<Request>(&line) {
+        Ok(request) => {
+            let (reply, replied) = mpsc::channel();
+            let _ = app.send(Forwarded { request, reply });
+            if let Some(ctx) = wake.get() {
+                ctx.request_repaint();
+            }
+            replied.recv_timeout(REPLY_WAIT).unwrap_or_else(|_| Err("the running FerrisFocus didn't answer in time".to_owned()))
+        }
+        Err(err) => Err(format!("not a request: {}", err)),
+    };
+    writeln!(stream, "{}", serde_json::to_string(&reply).unwrap_or_default())
+}
+
+/// The running instance's status, or `None` if nothing answers within `timeout`.
+#[cfg(unix)]
+pub fn query(timeout: Duration) -> Option<Status> {
+    use std::io::BufRead;
+    use std::os::unix::net::UnixStream;
+
+    let stream = UnixStream::connect(socket_path()?).ok()?;
+    stream.set_read_timeout(Some(timeout)).ok()?;
+    let mut line = String::new();
+    std::io::BufReader::new(stream).read_line(&mut line).ok()?;
+    serde_json::from_str(&line).ok()
+}
+
+#[cfg(not(unix))]
+pub fn query(_timeout: Duration) -> Option<Status> {
+    None
+}
+
+/// Hands `request` to the running instance and waits for its reply; None when none is running.
+pub fn forward(request: &Request) -> Option<Reply> {
+    forward_to(&socket_path()?, request)
+}
+
+/// Like [`forward`], to the socket at `path`.
+#[cfg(unix)]
+pub fn forward_to(path: &std::path::Path, request: &Request) -> Option<Reply> {
+    use std::os::unix::net::UnixStream;
+
+    let stream = UnixStream::connect(path).ok()?;
+    Some(exchange(stream, request).unwrap_or_else(|err| Err(format!("no answer from the running FerrisFocus: {}", err))))
+}
+
+// Skips the status line, then sends the request and reads the reply
+#[cfg(unix)]
+fn exchange(mut stream: std::os::unix::net::UnixStream, request: &Request) -> std::io::Result<Reply> {
+    use std::io::{BufRead, Write};
+
+    stream.set_read_timeout(Some(REPLY_WAIT + Duration::from_secs(1)))?;
+    let mut reader = std::io::BufReader::new(stream.try_clone()?);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    writeln!(stream, "{}", serde_json::to_string(request).unwrap_or_default())?;
+    line.clear();
+    reader.read_line(&mut line)?;
+    serde_json::from_str(&line).map_err(std::io::Error::other)
+}
+
+#[cfg(not(unix))]
+pub fn forward_to(_path: &std::path::Path, _request: &Request) -> Option<Reply> {
+    None
+}
@@ -0,0 +1,81 @@
+//! A journal of changes to settings held as a JSON object: which field went from what to what,
+//! when, and from where. Walking it backwards gives the settings as they were at any earlier
+//! time, as far back as the journal goes:
+//!
+//! ```
+//! use pomodoro_timer::journal::{as_of, changes};
+//! use serde_json::{json, Map, Value};
+//! use time::macros::datetime;
+//!
+//! let object = |value: Value| -> Map<String, Value> { value.as_object().cloned().unwrap() };
+//! let monday = object(json!({ "work_minutes": 25, "volume": 1.0, "daily_goal": null }));
+//! let tuesday = object(json!({ "work_minutes": 20, "volume": 1.0, "daily_goal": 8 }));
+//! let wednesday = object(json!({ "work_minutes": 20, "volume": 0.5, "daily_goal": 8 }));
+//! let mut journal = changes(&monday, &tuesday, datetime!(2024-06-04 9:00 UTC), "settings");
+//! journal.extend(changes(&tuesday, &wednesday, datetime!(2024-06-05 9:00 UTC), "import"));
+//! assert_eq!(journal.len(), 3);
+//!
+//! assert_eq!(as_of(&wednesday, &journal, datetime!(2024-06-04 12:00 UTC)), tuesday);
+//! assert_eq!(as_of(&wednesday, &journal, datetime!(2024-06-03 12:00 UTC)), monday);
+//! assert_eq!(as_of(&wednesday, &journal, datetime!(2024-06-05 9:00 UTC)), wednesday);
+//!
+//! // When did the work duration become 20?
+//! let change = journal.iter().rev().find(|entry| entry.field == "work_minutes").unwrap();
+//! assert_eq!((change.at, &change.new), (datetime!(2024-06-04 9:00 UTC), &json!(20)));
+//! ```
+//!
+//! Fields only one side has, such as a setting added by an update, are not changes. A field
+//! changed back and forth is put back step by step:
+//!
+//! ```
+//! use pomodoro_timer::journal::{as_of, changes};
+//! use serde_json::{json, Map, Value};
+//! use time::macros::datetime;
+//!
+//! let object = |value: Value| -> Map<String, Value> { value.as_object().cloned().unwrap() };
+//! let old = object(json!({ "volume": 1.0 }));
+//! let new = object(json!({ "volume": 1.0, "theme": "light" }));
+//! assert!(changes(&old, &new, datetime!(2024-06-04 9:00 UTC), "settings").is_empty());
+//!
+//! let (quiet, loud) = (object(json!({ "volume": 0.2 })), object(json!({ "volume": 0.8 })));
+//! let mut journal = changes(&old, &quiet, datetime!(2024-06-04 9:00 UTC), "settings");
+//! journal.extend(changes(&quiet, &loud, datetime!(2024-06-04 10:00 UTC), "settings"));
+//! journal.extend(changes(&loud, &quiet, datetime!(2024-06-04 11:00 UTC), "undo"));
+//! assert_eq!(as_of(&quiet, &journal, datetime!(2024-06-04 8:00 UTC)), old);
+//! assert_eq!(as_of(&quiet, &journal, datetime!(2024-06-04 9:30 UTC)), quiet);
+//! assert_eq!(as_of(&quiet, &journal, datetime!(2024-06-04 10:30 UTC)), loud);
+//! ```
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use time::OffsetDateTime;
+
+/// One field's change.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    #[serde(with = "time::serde::rfc3339")]
+    pub at: OffsetDateTime,
+    /// Where the change was made, e.g. the settings window or an import.
+    pub source: String,
+    pub field: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+/// An entry for each field that differs between `old` and `new`, in field order.
+pub fn changes(old: &Map<String, Value>, new: &Map<String, Value>, at: OffsetDateTime, source: &str) -> Vec<Entry> {
+    old.iter()
+        .filter_map(|(field, old)| Some((field, old, new.get(field)?)))
+        .filter(|(_, old, new)| old != new)
+        .map(|(field, old, new)| Entry { at, source: source.to_owned(), field: field.clone(), old: old.clone(), new: new.clone() })
+        .collect()
+}
+
+/// `current` with every change in `journal` made after `at` undone, newest first.
+pub fn as_of(current: &Map<String, Value>, journal: &[Entry], at: OffsetDateTime) -> Map<String, Value> {
+    let mut settings = current.clone();
+    for entry in journal.iter().rev().filter(|entry| entry.at > at) {
+        settings.insert(entry.field.clone(), entry.old.clone());
+    }
+    settings
+}
@@ -0,0 +1,127 @@
+//! A wall display for workshops and meeting rooms, started with `--kiosk`: digits filling the
+//! window, intervals following each other on their own, no buttons, and a screen that stays on.
+
+use eframe::egui;
+use pomodoro_timer::timer::Phase;
+use std::time::Duration;
+
+/// The only way out; closing the window otherwise is refused, so a stray click can't end it.
+pub const EXIT_KEYS: &str = "Ctrl+Shift+Q";
+
+pub fn exit_requested(ctx: &egui::Context) -> bool {
+    ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND.plus(egui::Modifiers::SHIFT), egui::Key::Q))
+}
+
+/// The phase, the remaining time as large as the window allows, and a bar of the progress.
+/// While `paused`, the digits are dimmed, the bar is striped and the phase says for how long.
+pub fn view(ui: &mut egui::Ui, remaining: Duration, phase: Phase, progress: f32, paused: Option<Duration>) {
+    let rect = ui.max_rect();
+    let painter = ui.painter();
+    let text = match paused {
+        Some(_) => ui.visuals().weak_text_color(),
+        None => ui.visuals().strong_text_color(),
+    };
+    // Monospace digits are about 0.6 em wide, and "00:00" is five of them
+    let size = (rect.width() * 0.9 / (5.0 * 0.6)).min(rect.height() * 0.6);
+    let secs = remaining.as_secs();
+    painter.text(
+        rect.center(),
+        egui::Align2::CENTER_CENTER,
+        format!("{}{:02}:{:02}", if paused.is_some() { "⏸ " } else { "" }, secs / 60, secs % 60),
+        egui::FontId::monospace(size),
+        text,
+    );
+    painter.text(
+        rect.center() - egui::vec2(0.0, size * 0.6),
+        egui::Align2::CENTER_BOTTOM,
+        match (paused, phase) {
+            (Some(paused), _) => crate::paused_text(paused),
+            (None, Phase::Work) => "Focus".to_owned(),
+            (None, Phase::Break) => "Break".to_owned(),
+            (None, Phase::GetReady) => "Get ready".to_owned(),
+            (None, Phase::EasyStart) => "Easy start".to_owned(),
+        },
+        egui::FontId::proportional(size * 0.15),
+        ui.visuals().weak_text_color(),
+    );
+    let track = egui::Rect::from_center_size(
+        rect.center() + egui::vec2(0.0, size * 0.65),
+        egui::vec2(rect.width() * 0.8, (size * 0.04).max(4.0)),
+    );
+    let filled = egui::Rect::from_min_size(track.min, egui::vec2(track.width() * progress, track.height()));
+    painter.rect_filled(track, track.height() / 2.0, ui.visuals().extreme_bg_color);
+    match paused {
+        Some(_) => {
+            painter.rect_filled(filled, track.height() / 2.0, ui.visuals().widgets.inactive.bg_fill);
+            crate::hatch(painter, track, ui.visuals().weak_text_color());
+        }
+        None => {
+            painter.rect_filled(filled, track.height() / 2.0, ui.visuals().selection.bg_fill);
+        }
+    }
+}
+
+/// Keeps the screen from blanking until dropped.
+pub struct KeepAwake {
+    #[cfg(target_os = "linux")]
+    inhibited: Option<(zbus::blocking::Connection, u32)>,
+    #[cfg(target_os = "macos")]
+    caffeinate: Option<std::process::Child>,
+}
+
+#[cfg(target_os = "linux")]
+const SCREEN_SAVER: &str = "org.freedesktop.ScreenSaver";
+
+impl KeepAwake {
+    /// Asks the desktop's screen saver to stay off; freedesktop desktops on Linux, and
+    /// `caffeinate` on macOS. Elsewhere the screen's power settings still apply.
+    pub fn start() -> Self {
+        #[cfg(target_os = "linux")]
+        {
+            let inhibited = zbus::blocking::Connection::session().and_then(|connection| {
+                let reply = connection.call_method(
+                    Some(SCREEN_SAVER),
+                    "/org/freedesktop/ScreenSaver",
+                    Some(SCREEN_SAVER),
+                    "Inhibit",
+                    &("FerrisFocus", "Showing the timer in kiosk mode"),
+                )?;
+                let cookie: u32 = reply.body()?;
+                Ok((connection, cookie))
+            });
+            if let Err(err) = &inhibited {
+                log::warn!("Cannot keep the screen on: {}", err);
+            }
+            Self { inhibited: inhibited.ok() }
+        }
+        #[cfg(target_os = "macos")]
+        {
+            let caffeinate = std::process::Command::new("caffeinate")
+                .args(["-d", "-w", &std::process::id().to_string()])
+                .spawn();
+            if let Err(err) = &caffeinate {
+                log::warn!("Cannot keep the screen on: {}", err);
+            }
+            Self { caffeinate: caffeinate.ok() }
+        }
+        #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+        {
+            log::warn!("Keeping the screen on is not supported on this platform");
+            Self {}
+        }
+    }
+}
+
+impl Drop for KeepAwake {
+    fn drop(&mut self) {
+        #[cfg(target_os = "linux")]
+        if let Some((connection, cookie)) = &self.inhibited {
+            let _ = connection.call_method(Some(SCREEN_SAVER), "/org/freedesktop/ScreenSaver", Some(SCREEN_SAVER), "UnInhibit", cookie);
+        }
+        #[cfg(target_os = "macos")]
+        if let Some(caffeinate) = &mut self.caffeinate {
+            let _ = caffeinate.kill();
+            let _ = caffeinate.wait();
+        }
+    }
+}
@@ -0,0 +1,140 @@
+//! Fitting the timer into whatever room the window gets. Tiling window managers hand out narrow
+//! columns and short strips regardless of the size the app asks for, so the timer panel picks
+//! its layout from the room it has: narrow, the controls share one row and the bar spans the
+//! width; short, everything but the timer and its controls is left out. Anything that still
+//! doesn't fit scrolls.
+
+use eframe::egui;
+
+/// Asked of the window manager, which may not listen.
+pub const MIN_SIZE: [f32; 2] = [160.0, 120.0];
+/// Narrower than this, the controls share a row.
+const NARROW: f32 = 320.0;
+/// Shorter than this, only the timer and its controls are shown.
+const SHORT: f32 = 300.0;
+/// The width of the task field and the progress bar, where there is room.
+const WIDTH: f32 = 300.0;
+const ROW_WIDTH: f32 = 220.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Layout {
+    pub narrow: bool,
+    pub short: bool,
+}
+
+impl Layout {
+    /// The layout for a panel with `size` to fill.
+    pub fn of(size: egui::Vec2) -> Self {
+        Self { narrow: size.x < NARROW, short: size.y < SHORT }
+    }
+
+    /// Whether the controls share a row rather than each taking a line.
+    pub fn row(self) -> bool {
+        self.narrow || self.short
+    }
+
+    /// Whether there is room for what isn't the timer or its controls.
+    pub fn secondary(self) -> bool {
+        !self.short
+    }
+
+    /// Space between the sections of the panel.
+    pub fn gap(self) -> f32 {
+        if self.short {
+            4.0
+        } else {
+            20.0
+        }
+    }
+
+    /// The width of the task field and the progress bar: all there is when narrow.
+    pub fn width(self, ui: &egui::Ui) -> f32 {
+        if self.narrow {
+            ui.available_width()
+        } else {
+            WIDTH.min(ui.available_width())
+        }
+    }
+}
+
+/// Buttons side by side in a centered row, each given an equal share of it.
+pub fn row(ui: &mut egui::Ui, labels: &[&str]) -> Vec<egui::Response> {
+    let size = egui::vec2(ROW_WIDTH.min(ui.available_width()), ui.spacing().interact_size.y);
+    let mut responses = Vec::new();
+    ui.allocate_ui_with_layout(size, egui::Layout::top_down(egui::Align::Center), |ui| {
+        ui.columns(labels.len(), |columns| {
+            for (column, label) in columns.iter_mut().zip(labels) {
+                responses.push(column.vertical_centered(|ui| ui.button(*label)).inner);
+            }
+        });
+    });
+    responses
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Lays the timer, the task field, its controls and the bar out offscreen in a window of
+    // `width`×`height`: the layout picked, and where each part ended up
+    fn lay_out(width: f32, height: f32) -> (Layout, Vec<(&'static str, egui::Rect)>) {
+        let ctx = egui::Context::default();
+        let screen = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(width, height));
+        let (mut rects, mut picked) = (Vec::new(), None);
+        let mut task = "Write the report".to_owned();
+        // The second frame, once the first has measured everything
+        for _ in 0..2 {
+            rects.clear();
+            let input = egui::RawInput { screen_rect: Some(screen), ..Default::default() };
+            let _ = ctx.run(input, |ctx| {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    let layout = Layout::of(ui.available_size());
+                    picked = Some(layout);
+                    ui.vertical_centered(|ui| {
+                        rects.push(("the timer", ui.heading("24:59").rect));
+                        rects.push(("the task field", ui.add(egui::TextEdit::singleline(&mut task).desired_width(layout.width(ui))).rect));
+                        if layout.row() {
+                            rects.extend(row(ui, &["Resume", "Skip"]).iter().map(|button| ("a button", button.rect)));
+                        } else {
+                            rects.push(("a button", ui.button("Resume").rect));
+                            rects.push(("a button", ui.button("Reset").rect));
+                        }
+                        rects.push(("the bar", ui.add(egui::ProgressBar::new(0.4).desired_width(layout.width(ui))).rect));
+                    });
+                });
+            });
+        }
+        (picked.unwrap(), rects)
+    }
+
+    fn assert_inside(width: f32, height: f32, rects: &[(&str, egui::Rect)]) {
+        let screen = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(width, height)).expand(0.5);
+        for (what, rect) in rects {
+            assert!(screen.contains_rect(*rect), "{} reaches past {}×{}, to {:?}", what, width, height, rect.max);
+        }
+    }
+
+    #[test]
+    fn a_narrow_column_shares_a_row_and_stays_inside() {
+        let (layout, rects) = lay_out(200.0, 400.0);
+        assert_eq!(layout, Layout { narrow: true, short: false });
+        assert!(layout.row() && layout.secondary());
+        assert_inside(200.0, 400.0, &rects);
+    }
+
+    #[test]
+    fn a_short_strip_leaves_the_rest_out_and_stays_inside() {
+        let (layout, rects) = lay_out(800.0, 200.0);
+        assert_eq!(layout, Layout { narrow: false, short: true });
+        assert!(layout.row() && !layout.secondary());
+        assert_inside(800.0, 200.0, &rects);
+    }
+
+    #[test]
+    fn a_roomy_window_stacks_the_controls() {
+        let (layout, rects) = lay_out(400.0, 500.0);
+        assert_eq!(layout, Layout { narrow: false, short: false });
+        assert_eq!(rects.iter().filter(|(what, _)| *what == "a button").count(), 2);
+        assert_inside(400.0, 500.0, &rects);
+    }
+}
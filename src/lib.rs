@@ -0,0 +1,22 @@
+//! The timer core of FerrisFocus, usable without the GUI.
+//!
+//! [`timer::PomodoroTimer`] is the runtime state machine the app drives every frame;
+//! [`typed_timer::Timer`] wraps it in a typestate API for embedding in other tools;
+//! [`forecast`] projects when planned work will be done; [`tasks`] is the portable task list;
+//! [`tags`] reads the `#tags` typed into a task label; [`sun`] works out sunrise and sunset;
+//! [`local_api`] tells browser extensions whether a focus session is running; [`journal`]
+//! keeps track of settings changes; [`pacing`] spreads a weekly goal over the workdays;
+//! [`calibration`] finds the focus length that suits someone; [`duration_text`] reads and
+//! writes durations such as `1h30m`.
+
+pub mod calibration;
+pub mod duration_text;
+pub mod forecast;
+pub mod journal;
+pub mod local_api;
+pub mod pacing;
+pub mod sun;
+pub mod tags;
+pub mod tasks;
+pub mod timer;
+pub mod typed_timer;
@@ -0,0 +1,297 @@
+//! A small HTTP API on localhost for browser extensions and other tools that can't use the
+//! status socket: `GET /focus` answers whether a focus session is running and until when, and
+//! `GET /events` streams phase changes as server-sent events. Every request needs the token,
+//! as `Authorization: Bearer …` or, for `EventSource` which can't set headers, `?token=…`.
+//! Browsers may only call it from pages on localhost and from origins on the allowlist, such
+//! as an extension's `chrome-extension://…`.
+//!
+//! ```
+//! use pomodoro_timer::local_api::{Config, Focus, Server};
+//! use std::io::{Read, Write};
+//! use std::net::TcpStream;
+//!
+//! let config = Config { token: "secret".to_owned(), origins: vec!["moz-extension://focus-helper".to_owned()] };
+//! let server = Server::start(0, config).unwrap();
+//! let get = |request: &str| {
+//!     let mut stream = TcpStream::connect(server.addr()).unwrap();
+//!     stream.write_all(request.as_bytes()).unwrap();
+//!     let mut response = String::new();
+//!     stream.read_to_string(&mut response).unwrap();
+//!     response
+//! };
+//!
+//! let response = get("GET /focus HTTP/1.1\r\nAuthorization: Bearer secret\r\nOrigin: moz-extension://focus-helper\r\n\r\n");
+//! assert!(response.starts_with("HTTP/1.1 200"));
+//! assert!(response.contains("Access-Control-Allow-Origin: moz-extension://focus-helper\r\n"));
+//! assert!(response.ends_with(r#"{"phase":"idle","focusing":false,"until":null}"#));
+//!
+//! assert!(get("GET /focus HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 401"));
+//! assert!(get("GET /focus?token=secret HTTP/1.1\r\nOrigin: https://example.com\r\n\r\n").starts_with("HTTP/1.1 403"));
+//! assert!(get("GET /focus?token=secret HTTP/1.1\r\nOrigin: http://localhost:8080\r\n\r\n").starts_with("HTTP/1.1 200"));
+//! ```
+//!
+//! Events arrive in the order the phases changed, starting with the phase at the time of
+//! connecting:
+//!
+//! ```
+//! use pomodoro_timer::local_api::{Config, Focus, Server};
+//! use std::io::{BufRead, BufReader, Write};
+//! use std::net::TcpStream;
+//! use time::macros::datetime;
+//!
+//! let server = Server::start(0, Config { token: "secret".to_owned(), origins: Vec::new() }).unwrap();
+//! let mut stream = TcpStream::connect(server.addr()).unwrap();
+//! stream.write_all(b"GET /events?token=secret HTTP/1.1\r\n\r\n").unwrap();
+//! let mut events = BufReader::new(stream).lines().map(Result::unwrap);
+//! let mut next_data = || events.by_ref().find_map(|line| line.strip_prefix("data: ").map(str::to_owned)).unwrap();
+//! assert!(next_data().contains(r#""phase":"idle""#));
+//!
+//! let until = datetime!(2024-03-01 9:25 UTC);
+//! server.update(Focus { phase: "work".to_owned(), focusing: true, until: Some(until) });
+//! server.update(Focus { phase: "work".to_owned(), focusing: true, until: Some(until) });
+//! server.update(Focus { phase: "paused".to_owned(), focusing: false, until: None });
+//! server.update(Focus { phase: "break".to_owned(), focusing: false, until: None });
+//! assert_eq!(next_data(), r#"{"phase":"work","focusing":true,"until":"2024-03-01T09:25:00Z"}"#);
+//! assert!(next_data().contains(r#""phase":"paused""#));
+//! assert!(next_data().contains(r#""phase":"break""#));
+//! ```
+
+use serde::Serialize;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use time::OffsetDateTime;
+
+// Proxies and browsers drop event streams that stay silent for long
+const KEEP_ALIVE: Duration = Duration::from_secs(15);
+// A client that doesn't finish its request in this time is dropped
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Who may call the API.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Config {
+    pub token: String,
+    /// Browser origins allowed besides pages on localhost, e.g. `chrome-extension://<id>`.
+    pub origins: Vec<String>,
+}
+
+/// What `GET /focus` answers and `GET /events` sends on each change.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Focus {
+    /// `work`, `break`, `paused` or `idle`.
+    pub phase: String,
+    /// A focus session is running.
+    pub focusing: bool,
+    /// When the running focus session ends.
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub until: Option<OffsetDateTime>,
+}
+
+#[derive(Default)]
+struct Shared {
+    config: Mutex<Config>,
+    focus: Mutex<Focus>,
+    // One per open event stream; dropped once its client is gone
+    streams: Mutex<Vec<Sender<String>>>,
+    stopped: AtomicBool,
+}
+
+/// Serves the API on a port of 127.0.0.1 until dropped.
+pub struct Server {
+    addr: SocketAddr,
+    shared: Arc<Shared>,
+    accepting: Option<std::thread::JoinHandle<()>>,
+}
+
+impl Server {
+    /// Listens on `port`, or on any free one if it is 0.
+    pub fn start(port: u16, config: Config) -> std::io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        let addr = listener.local_addr()?;
+        let shared = Arc::new(Shared { config: Mutex::new(config), focus: Mutex::new(idle()), ..Shared::default() });
+        let listening = Arc::clone(&shared);
+        let accepting = std::thread::Builder::new().name("local api".to_owned()).spawn(move || {
+            for stream in listener.incoming() {
+                if listening.stopped.load(Ordering::Relaxed) {
+                    return;
+                }
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                let shared = Arc::clone(&listening);
+                let spawned = std::thread::Builder::new().name("local api client".to_owned()).spawn(move || serve(stream, &shared));
+                if let Err(err) = spawned {
+                    log::warn!("Cannot answer a local API request: {}", err);
+                }
+            }
+        })?;
+        Ok(Self { addr, shared, accepting: Some(accepting) })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn configure(&self, config: Config) {
+        if let Ok(mut current) = self.shared.config.lock() {
+            *current = config;
+        }
+    }
+
+    /// Takes the latest state, sending it to every event stream when the phase changed; whether
+    /// it did.
+    pub fn update(&self, focus: Focus) -> bool {
+        let Ok(mut current) = self.shared.focus.lock() else {
+            return false;
+        };
+        let changed = current.phase != focus.phase || current.focusing != focus.focusing;
+        *current = focus;
+        if changed {
+            let event = event(&current);
+            if let Ok(mut streams) = self.shared.streams.lock() {
+                streams.retain(|stream| stream.send(event.clone()).is_ok());
+            }
+        }
+        changed
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.shared.stopped.store(true, Ordering::Relaxed);
+        if let Ok(mut streams) = self.shared.streams.lock() {
+            streams.clear();
+        }
+        // Wakes the listener so it sees it is stopped, and waits for it to let go of the port
+        if TcpStream::connect(self.addr).is_ok() {
+            if let Some(accepting) = self.accepting.take() {
+                let _ = accepting.join();
+            }
+        }
+    }
+}
+
+fn idle() -> Focus {
+    Focus { phase: "idle".to_owned(), focusing: false, until: None }
+}
+
+fn event(focus: &Focus) -> String {
+    format!("event: phase\ndata: {}\n\n", serde_json::to_string(focus).unwrap_or_default())
+}
+
+struct Request {
+    method: String,
+    path: String,
+    token: Option<String>,
+    origin: Option<String>,
+}
+
+fn read_request(stream: &TcpStream) -> Option<Request> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).ok()?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?.to_owned();
+    let target = parts.next()?;
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let mut request = Request {
+        method,
+        path: path.to_owned(),
+        token: query.split('&').find_map(|pair| pair.strip_prefix("token=")).map(str::to_owned),
+        origin: None,
+    };
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).ok()? == 0 || line.trim().is_empty() {
+            return Some(request);
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match name.trim().to_ascii_lowercase().as_str() {
+            "authorization" => request.token = value.strip_prefix("Bearer ").map(str::to_owned).or(request.token),
+            "origin" => request.origin = Some(value.to_owned()),
+            _ => {}
+        }
+    }
+}
+
+// Pages served from this computer, and whatever the user allowed
+fn allowed(origin: &str, config: &Config) -> bool {
+    let host = origin.strip_prefix("http://").or_else(|| origin.strip_prefix("https://"));
+    let host = host.map(|host| host.rsplit_once(':').filter(|(_, port)| port.parse::<u16>().is_ok()).map_or(host, |(host, _)| host));
+    matches!(host, Some("localhost" | "127.0.0.1" | "[::1]")) || config.origins.iter().any(|allowed| allowed == origin)
+}
+
+fn serve(mut stream: TcpStream, shared: &Shared) {
+    let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+    let Some(request) = read_request(&stream) else {
+        return;
+    };
+    let config = shared.config.lock().map(|config| config.clone()).unwrap_or_default();
+    let cors = match &request.origin {
+        Some(origin) if !allowed(origin, &config) => return respond(&mut stream, "403 Forbidden", "", "text/plain", "origin not allowed"),
+        Some(origin) => format!("Access-Control-Allow-Origin: {}\r\nVary: Origin\r\n", origin),
+        None => String::new(),
+    };
+    if request.method == "OPTIONS" {
+        let preflight = format!("{}Access-Control-Allow-Methods: GET\r\nAccess-Control-Allow-Headers: Authorization\r\n", cors);
+        return respond(&mut stream, "204 No Content", &preflight, "text/plain", "");
+    }
+    if config.token.is_empty() || request.token.as_deref() != Some(config.token.as_str()) {
+        return respond(&mut stream, "401 Unauthorized", &cors, "text/plain", "token missing or wrong");
+    }
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/focus") => {
+            let focus = shared.focus.lock().map(|focus| focus.clone()).unwrap_or_else(|_| idle());
+            respond(&mut stream, "200 OK", &cors, "application/json", &serde_json::to_string(&focus).unwrap_or_default());
+        }
+        ("GET", "/events") => stream_events(stream, shared, &cors),
+        ("GET", _) => respond(&mut stream, "404 Not Found", &cors, "text/plain", "no such endpoint"),
+        _ => respond(&mut stream, "405 Method Not Allowed", &cors, "text/plain", "only GET"),
+    }
+}
+
+fn respond(stream: &mut TcpStream, status: &str, headers: &str, content_type: &str, body: &str) {
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {}\r\n{}Content-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        headers,
+        content_type,
+        body.len(),
+        body
+    );
+}
+
+fn stream_events(mut stream: TcpStream, shared: &Shared, cors: &str) {
+    let (sender, receiver) = mpsc::channel();
+    // Registered while the state is held, so no change falls between the first event and the rest
+    let first = {
+        let Ok(focus) = shared.focus.lock() else {
+            return;
+        };
+        if let Ok(mut streams) = shared.streams.lock() {
+            streams.push(sender);
+        }
+        event(&focus)
+    };
+    let head = format!("HTTP/1.1 200 OK\r\n{}Content-Type: text/event-stream\r\nCache-Control: no-cache\r\n\r\n", cors);
+    if stream.write_all(head.as_bytes()).and_then(|()| stream.write_all(first.as_bytes())).is_err() {
+        return;
+    }
+    loop {
+        let written = match receiver.recv_timeout(KEEP_ALIVE) {
+            Ok(event) => stream.write_all(event.as_bytes()),
+            Err(RecvTimeoutError::Timeout) => stream.write_all(b": keep-alive\n\n"),
+            Err(RecvTimeoutError::Disconnected) => return,
+        };
+        if written.and_then(|()| stream.flush()).is_err() {
+            return;
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use log::kv::{Error, Key, Value, VisitSource};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+const KEPT_LINES: usize = 200;
+/// Fields whose name contains any of these are logged as [`REDACTED`].
+const SECRET_NAMES: [&str; 6] = ["token", "secret", "password", "signature", "authorization", "api_key"];
+pub const REDACTED: &str = "[redacted]";
+
+/// How log lines are written to stderr.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum Format {
+    /// `[LEVEL] target: message key=value`
+    #[default]
+    Text,
+    /// One [`Entry`] per line, for jq or a log collector.
+    Json,
+}
+
+/// One log line in the JSON format. The field names are kept as they are, so scripts reading
+/// them keep working.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    /// RFC 3339, in UTC.
+    pub timestamp: String,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+    /// The key-value pairs logged with the message, secrets redacted.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, String>,
+}
+
+impl Entry {
+    pub fn new(record: &Record) -> Self {
+        let mut fields = Fields::default();
+        // Only fails when the visitor does, and it never does
+        let _ = record.key_values().visit(&mut fields);
+        let timestamp = time::OffsetDateTime::now_utc().format(&time::format_description::well_known::Rfc3339).unwrap_or_default();
+        Self {
+            timestamp,
+            level: record.level().to_string(),
+            target: record.target().to_owned(),
+            message: record.args().to_string(),
+            fields: fields.0,
+        }
+    }
+
+    pub fn json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    pub fn text(&self) -> String {
+        let mut line = format!("[{}] {}: {}", self.level, self.target, self.message);
+        for (key, value) in &self.fields {
+            line.push_str(&format!(" {}={}", key, value));
+        }
+        line
+    }
+}
+
+// Collects a record's fields, redacting secrets on the way so neither format ever sees them
+#[derive(Default)]
+struct Fields(BTreeMap<String, String>);
+
+impl<'kvs> VisitSource<'kvs> for Fields {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), Error> {
+        let name = key.as_str().to_lowercase();
+        let value = if SECRET_NAMES.iter().any(|secret| name.contains(secret)) { REDACTED.to_owned() } else { value.to_string() };
+        self.0.insert(key.as_str().to_owned(), value);
+        Ok(())
+    }
+}
+
+/// Logs to stderr and remembers the most recent lines for the diagnostics report.
+struct Logger {
+    recent: Mutex<VecDeque<String>>,
+    json: AtomicBool,
+}
+
+static LOGGER: Logger = Logger { recent: Mutex::new(VecDeque::new()), json: AtomicBool::new(false) };
+// Set by --log-format, which wins over the setting
+static CHOSEN: OnceLock<Format> = OnceLock::new();
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Debug
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let entry = Entry::new(record);
+        // The diagnostics report is read by people, so it keeps the text either way
+        let line = entry.text();
+        if self.json.load(Ordering::Relaxed) {
+            eprintln!("{}", entry.json());
+        } else {
+            eprintln!("{}", line);
+        }
+
+        let mut recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        if recent.len() == KEPT_LINES {
+            recent.pop_front();
+        }
+        recent.push_back(line);
+    }
+
+    fn flush(&self) {}
+}
+
+pub fn init() {
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(LevelFilter::Debug);
+    }
+}
+
+/// Uses `format` from now on, ignoring the setting.
+pub fn choose(format: Format) {
+    let _ = CHOSEN.set(format);
+    LOGGER.json.store(format == Format::Json, Ordering::Relaxed);
+}
+
+/// Uses the format from the settings, unless one was chosen on the command line.
+pub fn follow_setting(format: Format) {
+    if CHOSEN.get().is_none() {
+        LOGGER.json.store(format == Format::Json, Ordering::Relaxed);
+    }
+}
+
+/// The last `count` log lines, oldest first.
+pub fn recent(count: usize) -> Vec<String> {
+    let recent = LOGGER.recent.lock().unwrap_or_else(|e| e.into_inner());
+    recent.iter().skip(recent.len().saturating_sub(count)).cloned().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn webhook_error() -> Entry {
+        let fields = [("url", "https://example.com/hook"), ("attempt", "2"), ("token", "hunter2")];
+        Entry::new(
+            &Record::builder()
+                .level(Level::Warn)
+                .target("pomodoro_timer::webhooks")
+                .args(format_args!("Webhook answered with an error"))
+                .key_values(&fields)
+                .build(),
+        )
+    }
+
+    #[test]
+    fn a_json_line_reads_back_whole_but_the_secret() {
+        let entry = webhook_error();
+        let line = entry.json();
+        assert!(!line.contains("hunter2"), "the token was logged: {}", line);
+        let read: Entry = serde_json::from_str(&line).unwrap();
+        assert_eq!(read, entry);
+        assert_eq!(read.fields.get("token").map(String::as_str), Some(REDACTED));
+        assert_eq!(read.fields.get("attempt").map(String::as_str), Some("2"));
+        assert_eq!(read.fields.get("url").map(String::as_str), Some("https://example.com/hook"));
+    }
+
+    #[test]
+    fn json_lines_keep_the_field_names_scripts_rely_on() {
+        let line = webhook_error().json();
+        let object: serde_json::Map<String, serde_json::Value> = serde_json::from_str(&line).unwrap();
+        let names: Vec<&str> = object.keys().map(String::as_str).collect();
+        assert_eq!(names, ["fields", "level", "message", "target", "timestamp"]);
+        let timestamp = object["timestamp"].as_str().unwrap();
+        assert!(time::OffsetDateTime::parse(timestamp, &time::format_description::well_known::Rfc3339).is_ok(), "{}", timestamp);
+    }
+}
@@ -1,216 +1,4709 @@
+mod about;
+mod accent;
+mod actions;
+mod alarm_queue;
+mod alerts;
+mod archive;
+mod assets;
+mod audio;
+mod away;
+mod background;
+mod backup;
+#[cfg(feature = "games")]
+mod breathing_game;
+mod calibrate;
+mod capabilities;
+mod changelog;
+mod cli;
+mod clock;
+mod clock_check;
+#[cfg(target_os = "linux")]
+mod companion;
+mod compare;
+#[cfg(target_os = "linux")]
+mod compositor;
+mod connectivity;
+mod day_log;
+mod day_note;
+#[cfg(target_os = "linux")]
+mod dbus;
+mod dev_panel;
+mod diagnostics;
+mod enforced_break;
+mod events;
+mod external_pause;
+mod hints;
+mod history;
+mod history_check;
+mod hooks;
+mod integration;
+mod intent;
+mod ipc;
+mod kiosk;
+mod layout;
+mod logging;
+mod mascot;
+mod nap;
+mod outputs;
+mod ownership;
+mod paths;
+mod plan;
+mod posture;
+mod power;
+mod preset;
+mod quick;
+mod quotes;
+mod recording;
+mod repaint;
+mod schedule;
+mod screenshots;
+mod settings;
+mod settings_journal;
+mod snapshot;
+mod standup;
+mod stats;
+mod stretches;
+mod summary;
+mod template;
+mod theme;
+mod timer_presets;
+mod toast;
+mod undo;
+mod warm_start;
+mod watchdog;
+mod webhooks;
+mod weekly;
+mod wind_down;
+
 use eframe::egui;
-use std::time::{Duration, Instant};
-use rodio::{OutputStream, source::SineWave, Sink};
+use std::collections::{BTreeSet, VecDeque};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use actions::{Action, Menu};
+use theme::Theme;
+use alerts::Alert;
+use assets::Asset;
+use audio::{AudioBackend, Role, Shape};
+use background::Background;
+use clap::Parser;
+use clock::Clock;
+use day_log::DayLog;
+use enforced_break::Enforcement;
+use events::{Event, Filter, Kind};
+use history::{Counting, DayZone, History, Pause, PauseReason, Session};
+#[cfg(unix)]
+use integration::StartError;
+use layout::Layout;
+use mascot::{Mascot, Mood};
+use outputs::{OutputTick, Sink};
+use pomodoro_timer::calibration::Trial;
+use pomodoro_timer::duration_text;
+use pomodoro_timer::local_api;
+use pomodoro_timer::tags;
+use pomodoro_timer::tasks::{Merge, TaskList};
+use pomodoro_timer::timer::{Durations, Phase, PomodoroTimer, State, Transition};
+use posture::Posture;
+use capabilities::Capability;
+use quotes::{Quote, Quotes};
+use recording::Recorder;
+use settings::Settings;
+use settings_journal::Source;
+use snapshot::Snapshot;
+use template::Template;
+use toast::Toasts;
+use undo::Undo;
+use warm_start::LastContext;
+use watchdog::Watchdog;
+
+// The wall clock running this far ahead of the monotonic clock between two frames means a suspend
+const SLEEP_THRESHOLD: Duration = Duration::new(30, 0);
+
+// How long the machine slept between the frames read at `last` and `now`, if it did
+fn asleep(last: (Instant, time::OffsetDateTime), now: (Instant, time::OffsetDateTime)) -> Option<Duration> {
+    let wall = Duration::try_from(now.1 - last.1).unwrap_or_default();
+    let missed = wall.saturating_sub(now.0.saturating_duration_since(last.0));
+    Some(missed).filter(|missed| *missed >= SLEEP_THRESHOLD)
+}
+
+/// How this run was started from the command line.
+enum RunMode {
+    Normal,
+    Demo,
+    /// Recorded sessions not yet fed into the history, oldest first.
+    Replay(VecDeque<Session>),
+    /// Started from a saved snapshot.
+    Restored,
+}
+
+impl RunMode {
+    // Shown in the title and as a watermark, so a demo is never mistaken for real use
+    fn label(&self) -> Option<&'static str> {
+        match self {
+            RunMode::Normal => None,
+            RunMode::Demo => Some("DEMO"),
+            RunMode::Replay(_) => Some("REPLAY"),
+            RunMode::Restored => Some("SNAPSHOT"),
+        }
+    }
+}
+
+struct PomodoroApp {
+    timer: PomodoroTimer,
+    cycle_sessions: u32,       // Focus sessions completed since the last long break
+    long_break: bool,          // The current break is the long one ending a cycle
+    timer_ended: bool,
+    audio: Option<Box<dyn AudioBackend>>, // Opened after the first frame, see `audio()`
+    toasts: Toasts,
+    show_menu_bar: bool,
+    show_shortcuts: bool,      // Whether the shortcut overview window is open
+    show_about: bool,
+    whats_new: Vec<&'static changelog::Release>, // Shown while not empty
+    settings: Settings,
+    show_settings: bool,
+    alarm_file_input: String,
+    alarm_file_error: Option<String>,
+    show_quit_confirmation: bool,
+    quit_confirmed: bool,
+    terminated: Arc<AtomicBool>, // Set by the SIGINT/SIGTERM handler, which skips the confirmation
+    last_frame: (Instant, time::OffsetDateTime),
+    clock_check: clock_check::ClockCheck, // Holds off time-of-day automations after the clock jumped
+    history: Background<History>,
+    clock: Box<dyn Clock>,
+    mode: RunMode,
+    show_stats: bool,
+    events: events::Bus,
+    last_tick: Option<u64>, // Remaining seconds at the last published tick
+    watchdog: Watchdog,
+    enforcement: Option<Enforcement>, // Set during the withheld start of a break
+    nap: Option<nap::Nap>, // In place of the rest of a break, until being awake is confirmed
+    posture: Posture,
+    quotes: Quotes,
+    quote: Option<Quote>, // Shown during the current break
+    stretches: Vec<stretches::Routine>,
+    next_stretch: usize, // Where the rotation through the routines is
+    stretch: Option<stretches::Player>, // Routine being followed this break
+    hints: hints::Hints,
+    tip: Option<String>, // Beginner tip for the session running
+    #[cfg(feature = "games")]
+    breathing: Option<breathing_game::Game>, // Played this break
+    #[cfg(feature = "games")]
+    breathing_scores: breathing_game::Scores,
+    mascot: Mascot,
+    title_template_input: String,
+    title_template_error: Option<String>,
+    title: String, // Last title sent to the window
+    show_import: bool,
+    show_backups: bool,
+    privacy: bool, // Task names, intents and statistics are masked while screen sharing; never saved
+    backups: Vec<backup::Backup>, // Listed when the restore window opens
+    restore_confirm: Option<usize>, // Backup picked, waiting for confirmation
+    history_check: Option<history_check::Check>,
+    import_input: String,
+    task: String, // Label for the next focus session
+    warm_start: Option<LastContext>, // Offered until a session starts or the card is dismissed
+    power: power::Monitor,
+    external: external_pause::Monitor,
+    away: away::Monitor,
+    quick: quick::QuickTimers, // Reminders set with `quick`, apart from the cycle
+    ended_at: Option<Instant>, // When the interval waiting to be acknowledged ended, until the return from being away is greeted
+    checks: Vec<capabilities::Check>, // Latest self-test of each capability run so far
+    show_troubleshoot: bool,
+    dev_panel: dev_panel::Panel,
+    alarm_queue: alarm_queue::AlarmQueue<Alert>,
+    repaint: repaint::AnimationScheduler,
+    dev_mode: bool, // Set with --dev, which shows the developer panel whatever the setting
+    paused_by: Option<&'static str>, // Why the app paused the session itself, until it resumes it
+    period_started: Option<time::OffsetDateTime>, // When the running interval started on the wall clock, for its record
+    pause_segments: Vec<Pause>, // The pauses of the running interval, for its record
+    paused_at: Option<(time::OffsetDateTime, PauseReason)>, // The pause going on, until it is one of them
+    accent: Option<accent::Monitor>, // Only while the system accent is used
+    dark: bool, // The look in effect, which follows the theme setting when nobody is busy
+    night: bool, // Night by the automatic theme's schedule
+    theme_override: Option<(bool, time::OffsetDateTime)>, // Dark or light picked by hand, until the next change by the schedule
+    kiosk: Option<kiosk::KeepAwake>, // Set with --kiosk
+    settings_undo: Undo<Settings>, // Changes made in the settings window
+    settings_journal: settings_journal::Journal,
+    show_settings_journal: bool,
+    sound: Option<timer_presets::Sound>, // What the audio backend was last given
+    show_wind_down: bool,
+    keep_going: Option<Session>, // An easy start that ended, recorded unless it keeps going
+    shorten: Option<schedule::Conflict>, // Offered a shorter session ending before an appointment
+    comparison: compare::Comparison,
+    tag_filter: Vec<String>, // Reports only count sessions with all of these tags
+    summary: Option<summary::Summary>, // Read off the history's sessions, until they change
+    plan: plan::Plan, // Today's planned tasks
+    archive: archive::Archive,
+    include_archived: bool, // Show archived tasks and labels in the plan and reports
+    day_detail: Option<history::DayNote>, // Day opened from the statistics, with its note being edited
+    thumbnails: Option<(time::Date, Vec<(String, egui::TextureHandle)>)>, // Screenshots of the day opened
+    intent: Option<String>, // This focus session's intent; empty if skipped, None until asked
+    intent_draft: Option<String>, // While the intent prompt is open
+    intent_review: Option<(time::OffsetDateTime, String)>, // Start of the session whose intent to check off
+    calibration: Option<calibrate::Calibration>, // The week of trying out focus lengths, while it runs
+    show_calibration: bool,
+    rate_focus: Option<u32>, // Minutes of the calibration session waiting for its rating
+    wind_down_asked: Option<time::Date>, // Evening of the last late-night prompt
+    hook_preset: usize, // Template picked in the hook settings, and its parameters
+    hook_params: Vec<String>,
+    webhooks: webhooks::Webhooks,
+    connectivity: connectivity::Connectivity, // Consulted by the webhooks; started once there is a window
+    status: Arc<Mutex<ipc::Status>>, // What status queries from other processes are answered with
+    status_file: ipc::DataFile<ipc::Status>, // Written only when the user opted in
+    summary_file: ipc::DataFile<ipc::Summary>,
+    #[cfg(unix)]
+    ipc_server: Result<ipc::Server, StartError>, // Answers status queries
+    local_api: Option<local_api::Server>, // While turned on and listening
+    local_api_error: Option<String>,
+    #[cfg(target_os = "linux")]
+    border_tint: Option<compositor::BorderTint>, // Only when a supported compositor is running
+    #[cfg(target_os = "linux")]
+    companion: Option<companion::Companion>, // During focus sessions, with a monitor chosen
+    #[cfg(target_os = "linux")]
+    monitors: Option<Background<Result<Vec<compositor::Monitor>, String>>>, // Listed once the settings show them
+    #[cfg(target_os = "linux")]
+    dbus: Result<dbus::Service, StartError>, // Only with a session bus
+    outputs: OutputTick,
+}
+
+impl PomodoroApp {
+    fn new(terminated: Arc<AtomicBool>, clock: Box<dyn Clock>, mode: RunMode) -> Self {
+        let (settings, settings_problem) = Settings::load();
+        backup::set_keep(settings.keep_backups);
+        logging::follow_setting(settings.log_format);
+        let history = load_history(&settings);
+
+        let mut toasts = Toasts::default();
+        toasts.extend(settings_problem);
+        let (routines, stretches_problem) = stretches::load();
+        toasts.extend(stretches_problem);
+        let (hints, hints_problem) = hints::Hints::load();
+        toasts.extend(hints_problem);
+        let status = Arc::new(Mutex::new(ipc::Status::default()));
+        let mut events = events::Bus::default();
+        // A minute-by-minute trace of running timers, so the log shows what the countdown did
+        events.subscribe("log", Filter::only(&[Kind::Tick]).at_most_every(Duration::from_secs(60)), |event| {
+            log::debug!("Timer: {}", event);
+        });
+        subscribe_recorder(&mut events, &settings);
+        subscribe_hooks(&mut events, &settings);
+        let connectivity = connectivity::Connectivity::default();
+        let webhooks = webhooks::Webhooks::start(&settings.webhooks, connectivity.clone());
+        subscribe_webhooks(&mut events, &settings, &webhooks);
+        let settings_undo = Undo::new(settings.clone());
+        let settings_journal = settings_journal::Journal::load(&settings);
+        let warm_start = matches!(mode, RunMode::Normal).then(|| LastContext::load(clock.wall())).flatten();
+        let plan = plan::Plan::load();
+
+        let mut app = Self {
+            timer: PomodoroTimer::new(Durations::default()),
+            cycle_sessions: 0,
+            long_break: false,
+            timer_ended: false,
+            audio: None,
+            toasts,
+            show_menu_bar: true,
+            show_shortcuts: false,
+            show_about: false,
+            whats_new: if matches!(mode, RunMode::Normal) { changelog::after_update() } else { Vec::new() },
+            alarm_file_input: settings.alarm_file.as_ref().map(|p| p.display().to_string()).unwrap_or_default(),
+            alarm_file_error: None,
+            title_template_input: settings.title_template.clone().unwrap_or_default(),
+            settings,
+            show_settings: false,
+            show_quit_confirmation: false,
+            quit_confirmed: false,
+            terminated,
+            last_frame: (clock.instant(), clock.wall()),
+            clock_check: clock_check::ClockCheck::default(),
+            history,
+            clock,
+            show_stats: matches!(mode, RunMode::Replay(_)),
+            mode,
+            events,
+            last_tick: None,
+            watchdog: Watchdog::default(),
+            enforcement: None,
+            nap: None,
+            posture: Posture::default(),
+            quotes: Quotes::load(),
+            quote: None,
+            stretches: routines,
+            next_stretch: 0,
+            stretch: None,
+            hints,
+            tip: None,
+            #[cfg(feature = "games")]
+            breathing: None,
+            #[cfg(feature = "games")]
+            breathing_scores: breathing_game::Scores::load(),
+            mascot: Mascot::default(),
+            title_template_error: None,
+            title: String::new(),
+            show_import: false,
+            show_backups: false,
+            privacy: false,
+            backups: Vec::new(),
+            restore_confirm: None,
+            history_check: None,
+            import_input: String::new(),
+            task: String::new(),
+            warm_start,
+            power: power::Monitor::default(),
+            external: external_pause::Monitor::default(),
+            away: away::Monitor::default(),
+            quick: quick::QuickTimers::default(),
+            ended_at: None,
+            checks: Vec::new(),
+            show_troubleshoot: false,
+            dev_panel: dev_panel::Panel::default(),
+            alarm_queue: alarm_queue::AlarmQueue::default(),
+            repaint: repaint::AnimationScheduler::default(),
+            dev_mode: false,
+            paused_by: None,
+            period_started: None,
+            pause_segments: Vec::new(),
+            paused_at: None,
+            accent: None,
+            dark: true,
+            night: false,
+            theme_override: None,
+            kiosk: None,
+            settings_undo,
+            settings_journal,
+            show_settings_journal: false,
+            sound: None,
+            show_wind_down: false,
+            keep_going: None,
+            shorten: None,
+            comparison: compare::Comparison::default(),
+            tag_filter: Vec::new(),
+            summary: None,
+            day_detail: None,
+            thumbnails: None,
+            plan,
+            archive: archive::Archive::load(),
+            include_archived: false,
+            intent: None,
+            intent_draft: None,
+            intent_review: None,
+            calibration: calibrate::Calibration::load(),
+            show_calibration: false,
+            rate_focus: None,
+            wind_down_asked: None,
+            hook_preset: 0,
+            hook_params: preset_defaults(&hooks::PRESETS[0]),
+            webhooks,
+            connectivity,
+            #[cfg(unix)]
+            ipc_server: ipc::Server::start(Arc::clone(&status)),
+            local_api: None,
+            local_api_error: None,
+            status,
+            status_file: ipc::DataFile::new(ipc::STATUS_FILE),
+            summary_file: ipc::DataFile::new(ipc::SUMMARY_FILE),
+            #[cfg(target_os = "linux")]
+            border_tint: compositor::BorderTint::detect(),
+            #[cfg(target_os = "linux")]
+            companion: None,
+            #[cfg(target_os = "linux")]
+            monitors: None,
+            #[cfg(target_os = "linux")]
+            dbus: Err(StartError::Unavailable("not started".to_owned())),
+            outputs: OutputTick::default(),
+        };
+        // Durations come from the preset in use, if any
+        app.apply_durations();
+        app.sync_local_api();
+        #[cfg(unix)]
+        if let Err(err) = &app.ipc_server {
+            report_start_error(&mut app.toasts, "status socket", err);
+        }
+        app
+    }
+
+    // Opening the device can take a noticeable moment, so it happens on first use
+    // (or the second frame) instead of before the window shows up
+    fn audio(&mut self) -> &mut dyn AudioBackend {
+        let (settings, applied, night) = (&self.settings, &mut self.sound, self.night);
+        self.audio
+            .get_or_insert_with(|| {
+                let sound = at_night(timer_presets::resolve(settings), settings, night);
+                let backend = audio::backend(sound.alarm_file.as_deref(), sound.volume);
+                *applied = Some(sound);
+                backend
+            })
+            .as_mut()
+    }
+
+    /// Hands the sound settings in effect to the audio backend whenever they change, whether
+    /// in the settings, by switching presets or by undoing.
+    fn sync_sound(&mut self) {
+        let sound = at_night(timer_presets::resolve(&self.settings), &self.settings, self.night);
+        if self.audio.is_none() || self.sound.as_ref() == Some(&sound) {
+            return;
+        }
+        let previous = self.sound.replace(sound.clone());
+        if previous.as_ref().map(|previous| previous.volume) != Some(sound.volume) {
+            self.audio().set_volume(sound.volume);
+        }
+        if previous.as_ref().map(|previous| &previous.alarm_file) != Some(&sound.alarm_file) {
+            if let Err(err) = self.audio().set_alarm_file(sound.alarm_file.as_deref()) {
+                self.toasts.push(format!("Cannot use the alarm sound: {}", err));
+            }
+        }
+    }
+
+    /// Switches to the named preset's durations and sound, or back to the global settings.
+    fn activate_preset(&mut self, name: Option<String>) {
+        self.settings.active_preset = name;
+        self.save_settings();
+        self.apply_durations();
+    }
+
+    fn apply_durations(&mut self) {
+        let mut durations = self.settings.durations();
+        // A calibration picks each focus length itself, whatever the preset says
+        if let Some(minutes) = self.calibration.as_ref().and_then(|calibration| calibration.next_minutes(self.clock.wall().date())) {
+            durations.work = Duration::from_secs(u64::from(minutes) * 60);
+        }
+        self.set_durations(durations);
+    }
+
+    /// Counts a calibration session towards its length, then sets the next one's.
+    fn record_trial(&mut self, completed: bool) -> Option<u32> {
+        let today = self.clock.wall().date();
+        let calibration = self.calibration.as_mut()?;
+        let minutes = calibration.next_minutes(today)?;
+        calibration.record(Trial { minutes, completed, focus: None });
+        self.apply_durations();
+        Some(minutes)
+    }
+
+    // An interval that hasn't started yet takes the new length, a long break included; one that
+    // has keeps its length, so shortening it never ends it early
+    fn set_durations(&mut self, durations: Durations) {
+        self.timer.set_durations(durations);
+        if self.timer.state() == State::Idle && !self.work_period() {
+            self.timer.set_duration(self.break_duration());
+        }
+    }
+
+    fn break_duration(&self) -> Duration {
+        match self.settings.long_break_minutes.filter(|_| self.long_break) {
+            Some(minutes) => Duration::from_secs(u64::from(minutes) * 60),
+            None => self.timer.durations().short_break,
+        }
+    }
+
+    fn work_period(&self) -> bool {
+        self.timer.phase() == Phase::Work
+    }
+
+    /// Whether the focus session now running ends the cycle, so a long break follows.
+    fn long_break_next(&self) -> bool {
+        self.settings.long_break_minutes.is_some() && self.cycle_sessions + 1 >= CYCLE_LENGTH
+    }
+
+    // A ringing alarm goes on until the user acknowledges it by starting, skipping or resetting
+    fn alert(&mut self, ctx: &egui::Context, alert: Alert) {
+        let profile = self.settings.alerts.profile(alert);
+        // A chime would go out to everyone else in the call
+        let sound = if self.external.active().is_some() { alerts::Sound::Silent } else { profile.sound };
+        self.play(sound, Shape::ramped(profile.ramp()));
+        self.attention(ctx, profile);
+    }
+
+    /// [`Self::alert`] at the end of an interval. The sound queued ahead to start right then
+    /// is already playing if it is the one called for, and taken back if not.
+    fn end_alert(&mut self, ctx: &egui::Context, alert: Alert, queued: Option<Alert>) {
+        if queued == Some(alert) && self.external.active().is_none() {
+            self.attention(ctx, self.settings.alerts.profile(alert));
+            return;
+        }
+        if queued.is_some() {
+            self.audio().stop();
+        }
+        self.alert(ctx, alert);
+    }
+
+    fn attention(&mut self, ctx: &egui::Context, profile: alerts::Profile) {
+        if let Some(attention) = profile.urgency.attention() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::RequestUserAttention(attention));
+        }
+        if let Some(notice) = self.audio().take_recovery_notice() {
+            self.toasts.push(notice);
+        }
+    }
+
+    /// Queues the alarm shortly before the running interval ends, so it starts on time even
+    /// when this frame's successor is drawn late, and takes it back if the end moves.
+    fn queue_alarm(&mut self) {
+        let now = self.clock.instant();
+        let remaining = self.timer.is_running().then(|| self.timer.remaining(now));
+        match self.alarm_queue.update(now, remaining) {
+            alarm_queue::Step::Queue(delay) => {
+                if let Some(alert) = self.upcoming_alert() {
+                    let profile = self.settings.alerts.profile(alert);
+                    if self.play(profile.sound, Shape { ramp: profile.ramp(), delay: Some(delay) }) {
+                        self.alarm_queue.queued(now + delay, alert);
+                    }
+                }
+            }
+            alarm_queue::Step::Cancel => self.audio().stop(),
+            alarm_queue::Step::Nothing => {}
+        }
+    }
+
+    // What `finish_period` will most likely alert with; a wrong guess is only taken back and
+    // played late. None where nothing is worth queuing.
+    fn upcoming_alert(&self) -> Option<Alert> {
+        if self.nap.is_some() || self.external.active().is_some() {
+            return None;
+        }
+        let alert = match self.timer.phase() {
+            Phase::Work => {
+                let day = (self.clock.wall() + self.remaining() - self.timer.duration()).date();
+                let today = self.history.peek().map(|history| history.stats.day(day).sessions);
+                match (self.settings.daily_goal, today) {
+                    (Some(goal), Some(today)) if today + 1 == goal => Alert::GoalReached,
+                    _ => Alert::period_end(true, self.long_break_next()),
+                }
+            }
+            Phase::Break if (self.settings.auto_start_next || self.kiosk.is_some()) && self.settings.get_ready_secs.is_some() => Alert::GetReady,
+            Phase::Break => Alert::period_end(false, self.long_break),
+            Phase::EasyStart => Alert::EasyStartEnds,
+            Phase::GetReady => return None,
+        };
+        (self.settings.alerts.profile(alert).sound != alerts::Sound::Silent).then_some(alert)
+    }
+
+    // Whatever announces the timer plays as an alarm, whichever sound it is
+    fn play(&mut self, sound: alerts::Sound, shape: Shape) -> bool {
+        self.audio().play(Role::Alarm, sound, shape)
+    }
+
+    fn perform(&mut self, ctx: &egui::Context, action: Action) {
+        self.events.publish(Event::Command { action, settings: Box::new(self.settings.clone()) });
+        if self.nap.is_some() && matches!(action, Action::StartPause | Action::Skip | Action::Reset | Action::Extend) {
+            self.toasts.push("Confirm you are awake first");
+            return;
+        }
+        if let Some(enforcement) = &self.enforcement {
+            if matches!(action, Action::StartPause | Action::Skip | Action::Reset | Action::Extend) {
+                let left = enforcement.remaining(self.clock.instant()).as_secs();
+                self.toasts.push(format!("Controls return in {}:{:02}", left / 60, left % 60));
+                return;
+            }
+        }
+        match action {
+            Action::StartPause if self.ask_wind_down() => self.show_wind_down = true,
+            Action::StartPause => match self.conflict() {
+                Some(conflict) => self.shorten = Some(conflict),
+                None => self.start_or_ask_intent(),
+            },
+            Action::Skip => self.skip(),
+            Action::Reset => self.reset(),
+            Action::Extend => self.timer.set_duration(self.timer.duration() + Duration::new(5 * 60, 0)),
+            Action::ToggleMenuBar => self.show_menu_bar = !self.show_menu_bar,
+            Action::ToggleDarkMode => self.toggle_dark_mode(),
+            Action::ShowStats => self.show_stats = !self.show_stats,
+            Action::CopyStandup => match self.standup_report() {
+                Some(report) => {
+                    ctx.copy_text(report);
+                    self.toasts.push(format!("Copied {} for standup", self.settings.standup.range.label().to_lowercase()));
+                }
+                None => self.toasts.push("The history is still loading"),
+            },
+            Action::ShowSettings => self.show_settings = !self.show_settings,
+            Action::TogglePrivacy => self.privacy = !self.privacy,
+            Action::ToggleWorkOffline => {
+                self.settings.work_offline = !self.settings.work_offline;
+                self.save_settings();
+                subscribe_webhooks(&mut self.events, &self.settings, &self.webhooks);
+            }
+            Action::ToggleMute => {
+                self.settings.muted = !self.settings.muted;
+                self.save_settings();
+                if self.settings.muted {
+                    self.audio().stop();
+                }
+            }
+            Action::ShowShortcuts => self.show_shortcuts = !self.show_shortcuts,
+            Action::OpenConfigFolder => open_folder(paths::config_dir()),
+            Action::OpenDataFolder => open_folder(paths::data_dir()),
+            Action::CopyDiagnostics => ctx.copy_text(self.diagnostics()),
+            Action::SaveSnapshot => match self.snapshot().save() {
+                Ok(path) => self.toasts.push(format!("Snapshot saved to {}", path.display())),
+                Err(err) => self.toasts.push(format!("Cannot save snapshot: {}", err)),
+            },
+            Action::WhatsNew => self.whats_new = changelog::RELEASES.iter().collect(),
+            Action::About => self.show_about = !self.show_about,
+        }
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let elapsed = self.timer.elapsed(self.clock.instant());
+        Snapshot {
+            version: snapshot::VERSION,
+            taken_at: self.clock.wall(),
+            timer: snapshot::TimerState {
+                work_period: self.work_period(),
+                running: self.timer.is_running(),
+                ended: self.timer_ended,
+                duration_secs: self.timer.duration().as_secs_f64(),
+                elapsed_secs: elapsed.as_secs_f64(),
+            },
+            task: self.task.clone(),
+            settings: self.settings.clone(),
+            today: self.history.peek().map(|history| history.stats.day(self.clock.wall().date())).unwrap_or_default(),
+            toasts: self.toasts.texts().map(str::to_owned).collect(),
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        let timer = snapshot.timer;
+        let durations = self.timer.durations();
+        let phase = if timer.work_period { Phase::Work } else { Phase::Break };
+        let length = Duration::try_from_secs_f64(timer.duration_secs).unwrap_or(durations.of(phase));
+        let elapsed = Duration::try_from_secs_f64(timer.elapsed_secs).unwrap_or_default();
+        self.timer = PomodoroTimer::with_progress(durations, phase, length, elapsed, timer.running, self.clock.instant());
+        self.timer_ended = timer.ended;
+        self.task = snapshot.task;
+        self.title_template_input = snapshot.settings.title_template.clone().unwrap_or_default();
+        self.settings = snapshot.settings;
+        subscribe_recorder(&mut self.events, &self.settings);
+        if let Some(history) = self.history.wait() {
+            history.recount(self.settings.day_zone, self.settings.focus_counting);
+            history.carry_over(self.clock.wall().date(), snapshot.today);
+        }
+        self.toasts.extend(snapshot.toasts);
+        log::info!("Restored snapshot taken at {}", snapshot.taken_at);
+    }
+
+    fn diagnostics(&self) -> String {
+        let audio = self.audio.as_ref().map_or_else(|| "not opened yet".to_owned(), |audio| audio.describe());
+        diagnostics::report(&diagnostics::Status {
+            audio,
+            transition_latency: self.watchdog.to_string(),
+            events: self.events.describe(),
+            dbus: self.dbus_state(),
+            status_socket: self.status_socket_state(),
+        })
+    }
+
+    #[cfg(unix)]
+    fn status_socket_state(&self) -> String {
+        match &self.ipc_server {
+            Ok(server) => format!("listening at {}", server.path().display()),
+            Err(err) => format!("not listening: {}", err),
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn status_socket_state(&self) -> String {
+        "not supported on this platform".to_owned()
+    }
+
+    #[cfg(target_os = "linux")]
+    fn dbus_state(&self) -> String {
+        match &self.dbus {
+            Ok(_) => format!("serving; try\n  {}", dbus::examples().join("\n  ")),
+            Err(err) => format!("not connected: {}", err),
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn dbus_state(&self) -> String {
+        "not supported on this platform".to_owned()
+    }
+
+    #[cfg(target_os = "linux")]
+    fn dbus_requests(&mut self, ctx: &egui::Context) {
+        let Ok(service) = &self.dbus else {
+            return;
+        };
+        let requests: Vec<_> = service.requests().collect();
+        for request in requests {
+            match request {
+                dbus::Request::Toggle => self.perform(ctx, Action::StartPause),
+                dbus::Request::OpenWindow => {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Visible(true));
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+                }
+            }
+        }
+    }
+
+    // Changes other processes asked for, made here since this instance owns the data
+    #[cfg(unix)]
+    fn ipc_requests(&mut self) {
+        let Ok(server) = &self.ipc_server else {
+            return;
+        };
+        let requests: Vec<_> = server.requests().collect();
+        for ipc::Forwarded { request, reply } in requests {
+            let answer = match request {
+                ipc::Request::ImportTasks(list) => {
+                    let merge = self.plan.import(list);
+                    self.plan.save();
+                    self.toasts.push(format!("Imported tasks: {}", merged(merge)));
+                    Ok(merged(merge))
+                }
+                ipc::Request::QuickTimer { secs, message } => {
+                    let length = Duration::from_secs(secs);
+                    let reply = format!("{} in {}", message.as_deref().unwrap_or("Quick timer"), duration_text::format(length));
+                    // The message is the user's own words, which the log shared in diagnostics leaves out
+                    self.quick.start(SystemTime::from(self.clock.wall()), length, message).map(|()| {
+                        log::info!("Quick timer set for {}", duration_text::format(length));
+                        reply
+                    })
+                }
+            };
+            let _ = reply.send(answer);
+        }
+    }
+
+    fn toggle_running(&mut self) {
+        self.audio().stop();
+        match self.timer.state() {
+            State::Running { .. } => self.pause(PauseReason::Manual),
+            State::Paused { .. } => self.resume(),
+            State::Idle => self.start_period(),
+        }
+    }
+
+    /// The wall clock to the second, as sessions and their pauses are recorded.
+    fn timestamp(&self) -> time::OffsetDateTime {
+        let now = self.clock.wall();
+        now.replace_nanosecond(0).unwrap_or(now)
+    }
+
+    // Pauses the running interval, noting when and why for its record
+    fn pause(&mut self, reason: PauseReason) {
+        self.timer.pause(self.clock.instant());
+        self.paused_at = Some((self.timestamp(), reason));
+    }
+
+    fn resume(&mut self) {
+        self.timer.resume(self.clock.instant());
+        if let Some((start, reason)) = self.paused_at.take() {
+            self.pause_segments.push(Pause { start, end: self.timestamp(), reason });
+        }
+    }
+
+    // When the interval that just ended started, and its pauses, taken for its record. Without
+    // a start noted, as after restoring a snapshot, it is worked out back from now.
+    fn take_record(&mut self, focused: Duration) -> (time::OffsetDateTime, Vec<Pause>) {
+        let pauses = std::mem::take(&mut self.pause_segments);
+        let paused = Duration::from_secs(pauses.iter().map(Pause::secs).sum());
+        let start = self.period_started.take().unwrap_or_else(|| self.timestamp() - focused - paused);
+        (start, pauses)
+    }
+
+    // Starts the current interval from the beginning, by hand or right after the last one ended
+    fn start_period(&mut self) {
+        self.begin_period(Duration::ZERO);
+        self.announce_period();
+    }
+
+    // Starts the current interval as if `ago`, for one that began while the computer slept
+    fn begin_period(&mut self, ago: Duration) {
+        self.timer.start(self.clock.instant());
+        self.timer.credit(ago);
+        self.period_started = Some(self.timestamp() - Duration::from_secs(ago.as_secs()));
+        self.pause_segments.clear();
+        self.paused_at = None;
+        self.timer_ended = false;
+    }
+
+    fn announce_period(&mut self) {
+        let work = self.work_period() || self.timer.phase() == Phase::EasyStart;
+        let label = if work { self.label() } else { None };
+        if work && matches!(self.mode, RunMode::Normal) {
+            self.warm_start = None;
+            let context = LastContext {
+                task: label.clone(),
+                preset: self.settings.active_preset().map(|preset| preset.name.clone()),
+                work_secs: self.timer.durations().work.as_secs(),
+                started: self.clock.wall(),
+                dismissed: false,
+            };
+            context.save();
+            if self.settings.beginner_hints {
+                self.tip = self.beginner_tip(label.is_some());
+            }
+        }
+        self.events.publish(Event::PeriodStarted { work, label });
+    }
+
+    fn beginner_tip(&mut self, has_task: bool) -> Option<String> {
+        let first_session = self.history.peek().and_then(|history| history.sessions.iter().map(Session::date).min());
+        let situation = hints::Situation {
+            today: self.clock.wall().date(),
+            any_session: first_session.is_some(),
+            has_task,
+            long_break_next: self.long_break_next(),
+        };
+        self.hints.next_tip(&situation, first_session).map(|hint| hint.tip().to_owned())
+    }
+
+    fn easy_start(&mut self) {
+        self.timer.easy_start(EASY_START, self.clock.instant());
+        self.start_period();
+    }
+
+    fn keep_going_prompt(&mut self, ctx: &egui::Context) {
+        let Some(session) = &self.keep_going else {
+            return;
+        };
+        // Started afresh or skipped instead of answering
+        let answered = self.timer.state() != State::Idle || !self.work_period();
+        let mut keep_going = None;
+        if !answered {
+            let mut open = true;
+            egui::Window::new("Keep going?")
+                .open(&mut open)
+                .resizable(false)
+                .collapsible(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    compact_text(ui.style_mut());
+                    ui.label(format!("{} minutes done. Keep going for a full session?", session.duration_secs / 60));
+                    ui.horizontal(|ui| {
+                        if ui.button("Keep going").clicked() {
+                            keep_going = Some(true);
+                        }
+                        if ui.button("Stop here").clicked() {
+                            keep_going = Some(false);
+                        }
+                    });
+                });
+            if !open || ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+                keep_going = Some(false);
+            }
+        }
+        match (answered, keep_going) {
+            (false, None) => return,
+            (false, Some(true)) => self.continue_easy_start(),
+            _ => {
+                if let Some(history) = self.history.wait() {
+                    history.append(session.clone());
+                }
+            }
+        }
+        self.audio().stop();
+        self.keep_going = None;
+    }
+
+    // The minutes already focused count toward the session, which started with them and keeps
+    // their pauses
+    fn continue_easy_start(&mut self) {
+        let Some(session) = self.keep_going.take() else {
+            return;
+        };
+        self.timer.start_with(Duration::from_secs(session.duration_secs), self.clock.instant());
+        self.period_started = Some(session.start);
+        self.pause_segments = session.pause_segments;
+        self.timer_ended = false;
+    }
+
+    /// Whether starting now should ask first; true at most once per evening.
+    fn ask_wind_down(&mut self) -> bool {
+        let Some(from) = self.settings.wind_down_at else {
+            return false;
+        };
+        let now = self.clock.wall();
+        if self.timer.state() != State::Idle || !self.work_period() || !wind_down::is_late(now, from) {
+            return false;
+        }
+        if self.clock_check.suspended(self.clock.instant()) {
+            return false;
+        }
+        let evening = wind_down::evening(now);
+        self.wind_down_asked.replace(evening) != Some(evening)
+    }
+
+    fn wind_down_prompt(&mut self, ctx: &egui::Context) {
+        if !self.show_wind_down {
+            return;
+        }
+        let Some(choice) = wind_down::prompt(ctx) else {
+            return;
+        };
+        self.show_wind_down = false;
+        match choice {
+            wind_down::Choice::Proceed => self.start_or_ask_intent(),
+            wind_down::Choice::HalfLength => {
+                self.timer.set_duration(self.timer.durations().work / 2);
+                self.start_or_ask_intent();
+            }
+            wind_down::Choice::NotNow => {}
+        }
+    }
+
+    /// An appointment a focus session started now would run into.
+    fn conflict(&self) -> Option<schedule::Conflict> {
+        if self.timer.state() != State::Idle || !self.work_period() || self.clock_check.suspended(self.clock.instant()) {
+            return None;
+        }
+        schedule::conflict(&self.settings.appointments, self.settings.wind_down_at, self.timer.duration(), self.clock.wall())
+    }
+
+    fn shorten_prompt(&mut self, ctx: &egui::Context) {
+        let Some(conflict) = &self.shorten else {
+            return;
+        };
+        let Some(choice) = schedule::prompt(ctx, conflict) else {
+            return;
+        };
+        let fits = conflict.fits;
+        self.shorten = None;
+        match choice {
+            schedule::Choice::Shorten => {
+                // This interval only; the session is recorded with the length it was started with
+                self.timer.set_duration(fits);
+                self.start_or_ask_intent();
+            }
+            schedule::Choice::FullLength => self.start_or_ask_intent(),
+            schedule::Choice::NotNow => {}
+        }
+    }
+
+    fn start_or_ask_intent(&mut self) {
+        if self.settings.ask_intent && self.timer.state() == State::Idle && self.work_period() && self.intent.is_none() {
+            self.intent_draft = Some(String::new());
+        } else {
+            self.toggle_running();
+        }
+    }
+
+    fn intent_prompts(&mut self, ctx: &egui::Context) {
+        if let Some(draft) = &mut self.intent_draft {
+            match intent::ask(ctx, draft, self.privacy) {
+                Some(intent::Asked::Start) => {
+                    self.intent = Some(draft.trim().to_owned());
+                    self.intent_draft = None;
+                    self.toggle_running();
+                }
+                Some(intent::Asked::Cancel) => self.intent_draft = None,
+                None => {}
+            }
+        }
+        if let Some((start, intent)) = &self.intent_review {
+            match intent::review(ctx, if self.privacy { PRIVATE } else { intent }) {
+                Some(intent::Review::Answered(met)) => {
+                    let outcome = history::IntentOutcome { session_start: *start, met };
+                    if let Some(history) = self.history.wait() {
+                        history.append_outcome(outcome);
+                    }
+                    self.intent_review = None;
+                }
+                Some(intent::Review::Skipped) => self.intent_review = None,
+                None => {}
+            }
+        }
+        if let Some(minutes) = self.rate_focus {
+            if let Some(rating) = calibrate::ask_rating(ctx, minutes) {
+                if let (Some(focus), Some(calibration)) = (rating, &mut self.calibration) {
+                    calibration.rate(focus);
+                }
+                self.rate_focus = None;
+            }
+        }
+    }
+
+    fn warm_start_card(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
+        let Some(context) = self.warm_start.as_mut().filter(|_| !self.timer.is_running()) else {
+            return;
+        };
+        match warm_start::card(ui, context, self.privacy) {
+            Some(warm_start::Choice::Resume) => {
+                self.task = context.task.clone().unwrap_or_default();
+                let preset = context.preset.clone().filter(|name| self.settings.presets.iter().any(|p| p.name == *name));
+                if preset.is_some() || self.settings.active_preset.is_some() {
+                    self.activate_preset(preset);
+                } else {
+                    let work = Duration::from_secs(context.work_secs);
+                    self.set_durations(Durations { work, ..self.timer.durations() });
+                }
+                self.perform(ctx, Action::StartPause);
+            }
+            Some(warm_start::Choice::Dismiss) => {
+                context.dismissed = true;
+                context.save();
+                self.warm_start = None;
+            }
+            None => {}
+        }
+        ui.add_space(10.0);
+    }
+
+    // The task as typed, without its #tags
+    fn label(&self) -> Option<String> {
+        let label = tags::parse(&self.task).label;
+        (!label.is_empty()).then_some(label)
+    }
+
+    fn tags(&self) -> BTreeSet<String> {
+        tags::parse(&self.task).tags
+    }
+
+    fn reset(&mut self) {
+        self.audio().stop();
+        if self.timer.is_running() && self.work_period() {
+            self.mascot.react(Mood::Worried);
+        }
+        if self.timer.state() != State::Idle && self.work_period() {
+            self.record_trial(false);
+        }
+        self.timer.reset();
+        self.enter_period();
+        self.timer_ended = false;
+    }
+
+    // Sets up the interval the timer has just moved on to
+    fn enter_period(&mut self) {
+        if self.work_period() {
+            if self.long_break {
+                self.cycle_sessions = 0;
+                self.long_break = false;
+            }
+        } else {
+            self.long_break = self.settings.long_break_minutes.is_some() && self.cycle_sessions >= CYCLE_LENGTH;
+            self.timer.set_duration(self.break_duration());
+            self.quote = self.quotes.next();
+            if let Some(secs) = self.settings.enforced_break_secs {
+                let minimum = Duration::from_secs(secs.into()).min(self.timer.duration());
+                self.enforcement = Some(Enforcement::new(self.clock.wall(), self.clock.instant(), minimum));
+            }
+        }
+        self.stretch = None;
+        #[cfg(feature = "games")]
+        {
+            self.breathing = None;
+        }
+        self.intent = None;
+        self.tip = None;
+    }
+
+    // Moves on to the next interval, which keeps running if this one was
+    fn skip(&mut self) {
+        self.audio().stop();
+        let running = self.timer.is_running();
+        if running && self.work_period() {
+            self.mascot.react(Mood::Worried);
+        }
+        if self.timer.state() != State::Idle && self.work_period() {
+            self.record_trial(false);
+        }
+        self.timer.skip();
+        self.enter_period();
+        if running {
+            self.start_period();
+        }
+        self.timer_ended = false;
+    }
+
+    fn enforced_break_overlay(&mut self, ctx: &egui::Context) {
+        let now = self.clock.instant();
+        let work = self.work_period();
+        let Some(enforcement) = &mut self.enforcement else {
+            return;
+        };
+        let escaped = enforcement.show(ctx, now);
+        if enforcement.escaping() {
+            self.repaint.animate("escape hold", repaint::SMOOTH, Instant::now() + repaint::SMOOTH * 2);
+        }
+        let over = escaped || enforcement.remaining(now).is_zero() || work;
+        if !over {
+            if ctx.input(|i| i.viewport().fullscreen) == Some(false) {
+                ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(true));
+            }
+            return;
+        }
+        let record = enforcement.finish(now, escaped);
+        self.enforcement = None;
+        ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(false));
+        self.events.publish(Event::EnforcedBreakOver { escaped });
+        if let Some(history) = self.history.wait() {
+            history.append_break(record);
+        }
+    }
+
+    fn remaining(&self) -> Duration {
+        self.timer.remaining(self.clock.instant())
+    }
+
+    // The nap replaces whatever was left of the break, which runs on until it is over
+    fn start_nap(&mut self, minutes: u32) {
+        let nap = nap::Nap::new(self.clock.wall(), minutes);
+        let now = self.clock.instant();
+        self.timer.set_duration(self.timer.elapsed(now) + nap.length());
+        self.nap = Some(nap);
+        match self.timer.state() {
+            State::Running { .. } => {}
+            State::Paused { .. } => self.resume(),
+            State::Idle => self.start_period(),
+        }
+        self.audio().stop();
+        self.timer_ended = false;
+    }
+
+    fn nap_overlay(&mut self, ctx: &egui::Context) {
+        let now = self.clock.instant();
+        let Some(nap) = &self.nap else {
+            return;
+        };
+        let remaining = if self.work_period() { Duration::ZERO } else { self.remaining() };
+        let awake = nap.show(ctx, remaining);
+        // Muting and the night limit don't apply: the alarm has to wake
+        if let Some(share) = nap.volume(now) {
+            let volume = self.settings.volume * share;
+            self.audio().set_volume(volume);
+            if share < 1.0 {
+                self.repaint.animate("nap alarm", repaint::PROGRESS, Instant::now() + repaint::PROGRESS * 2);
+            }
+        }
+        if !awake {
+            return;
+        }
+        let Some(nap) = self.nap.take() else {
+            return;
+        };
+        self.audio().stop();
+        // Puts the alarm volume back
+        self.sound = None;
+        if let Some(history) = self.history.wait() {
+            history.append_nap(nap.finish(now));
+        }
+        // Woken before the alarm, the break is over all the same; focus waits to be started
+        if !self.work_period() {
+            self.timer.skip();
+            self.enter_period();
+        }
+        self.timer_ended = false;
+    }
+
+    // Recomputed every frame from the user's level, so moving the slider mid-fade keeps the fade
+    fn ambient_sound(&mut self) {
+        let level = match timer_presets::resolve(&self.settings).ambient_volume {
+            Some(volume) if self.timer.is_running() && self.work_period() && !self.saving_power() => {
+                Some(volume * audio::ambient_duck(self.remaining()))
+            }
+            _ => None,
+        };
+        if level.is_some() && audio::ambient_fading(self.remaining()) {
+            self.repaint.animate("ambient fade", repaint::PROGRESS, Instant::now() + repaint::PROGRESS * 2);
+        }
+        if level.is_some() || self.audio.is_some() {
+            self.audio().set_ambient(level);
+        }
+    }
+
+    fn saving_power(&self) -> bool {
+        let battery = self.power.battery();
+        self.settings.power_saving_below.is_some_and(|threshold| battery.is_some_and(|battery| battery.low(threshold)))
+    }
+
+    fn publish_tick(&mut self) {
+        let tick = self.timer.is_running().then(|| self.remaining().as_secs());
+        if tick != self.last_tick {
+            self.last_tick = tick;
+            if let Some(remaining_secs) = tick {
+                self.events.publish(Event::Tick { remaining_secs });
+            }
+        }
+    }
+
+    fn window_title(&self) -> String {
+        let title = match self.settings.title_template.as_deref().map(Template::parse) {
+            Some(Ok(template)) => template.render(|field| self.placeholder(field)),
+            _ => "Pomodoro Timer".to_owned(),
+        };
+        match self.mode.label() {
+            Some(label) => format!("{} — {}", title, label),
+            None => title,
+        }
+    }
+
+    fn ipc_status(&self) -> ipc::Status {
+        ipc::Status {
+            work_period: self.work_period(),
+            running: self.timer.is_running(),
+            remaining_secs: self.remaining().as_secs(),
+            duration_secs: self.timer.duration().as_secs(),
+            sessions_today: self.history.peek().map(|history| history.stats.day(self.clock.wall().date()).sessions),
+        }
+    }
+
+    /// `None` until the history has loaded.
+    fn summary(&self, status: &ipc::Status) -> Option<ipc::Summary> {
+        let stats = &self.history.peek()?.stats;
+        let today = self.clock.wall().date();
+        let totals = stats.day(today);
+        // Today doesn't break the streak before its first pomodoro
+        let mut day = if totals.sessions > 0 { Some(today) } else { today.previous_day() };
+        let mut streak_days = 0;
+        while let Some(date) = day.filter(|date| stats.day(*date).sessions > 0) {
+            streak_days += 1;
+            day = date.previous_day();
+        }
+        Some(ipc::Summary {
+            schema_version: ipc::Summary::SCHEMA_VERSION,
+            date: today.to_string(),
+            pomodoros: totals.sessions,
+            focus_minutes: totals.focus_secs / 60,
+            goal: self.settings.daily_goal,
+            streak_days,
+            phase: status.class(),
+            remaining_secs: status.remaining_secs,
+        })
+    }
+
+    fn placeholder(&self, field: &str) -> Option<String> {
+        match field {
+            "goal" => self.settings.daily_goal.map(|goal| goal.to_string()),
+            "task" => self.label().filter(|_| !self.privacy),
+            _ => self.ipc_status().field(field),
+        }
+    }
+
+    fn update_title(&mut self, ctx: &egui::Context) -> bool {
+        let title = self.window_title();
+        if title == self.title {
+            return false;
+        }
+        ctx.send_viewport_cmd(egui::ViewportCommand::Title(title.clone()));
+        self.title = title;
+        true
+    }
+
+    // The output tick: every sink at once, each written only if what it shows changed
+    fn refresh_outputs(&mut self, ctx: &egui::Context, status: ipc::Status) {
+        let now = Instant::now();
+        if self.settings.status_files {
+            let wrote = self.status_file.update(&status);
+            self.outputs.record(Sink::StatusFile, now, wrote);
+            if let Some(summary) = self.summary(&status) {
+                let wrote = self.summary_file.update(&summary);
+                self.outputs.record(Sink::SummaryFile, now, wrote);
+            }
+        } else {
+            self.status_file.clear();
+            self.summary_file.clear();
+        }
+        if let Some(api) = &self.local_api {
+            let focusing = status.running && status.work_period;
+            let until = focusing.then(|| self.clock.wall() + self.remaining());
+            let wrote = api.update(local_api::Focus { phase: status.class().to_owned(), focusing, until });
+            self.outputs.record(Sink::LocalApi, now, wrote);
+        }
+        let wrote = self.status.lock().is_ok_and(|mut shared| {
+            let changed = *shared != status;
+            *shared = status;
+            changed
+        });
+        #[cfg(target_os = "linux")]
+        if let (true, Ok(dbus)) = (wrote, &self.dbus) {
+            dbus.status_changed();
+        }
+        self.outputs.record(Sink::Shared, now, wrote);
+        let wrote = self.update_title(ctx);
+        self.outputs.record(Sink::Title, now, wrote);
+    }
+
+    // Pauses focus while a detector is active and resumes it after, unless the user took over
+    // in between by resuming, resetting or skipping
+    fn external_pause(&mut self) {
+        self.external.set_enabled(self.settings.pause_during_calls);
+        if self.paused_by.is_some() && (!matches!(self.timer.state(), State::Paused { .. }) || !self.work_period()) {
+            self.paused_by = None;
+        }
+        match (self.external.active(), self.paused_by) {
+            (Some(reason), None) if self.timer.is_running() && self.work_period() => {
+                self.audio().stop();
+                self.pause(PauseReason::External);
+                self.paused_by = Some(reason);
+                self.toasts.push(format!("Paused while {}", reason));
+            }
+            (None, Some(_)) => {
+                self.resume();
+                self.paused_by = None;
+                self.toasts.push("Focus resumed");
+            }
+            _ => {}
+        }
+    }
+
+    fn posture_reminder(&mut self, ctx: &egui::Context) {
+        let Some(minutes) = self.settings.posture_minutes else {
+            return;
+        };
+        let interval = Duration::from_secs(u64::from(minutes) * 60);
+        let counting = self.timer.is_running() && self.work_period();
+        if self.posture.tick(self.clock.instant(), counting, interval) {
+            self.toasts.push(self.posture.suggestion());
+            self.events.publish(Event::PostureSwitch { standing: self.posture.standing });
+            if timer_presets::resolve(&self.settings).posture_sound {
+                self.audio().play(Role::Cue, alerts::Sound::Blip, Shape::default());
+            }
+        }
+        self.posture.show(ctx, interval);
+    }
+
+    fn quote_view(&mut self, ui: &mut egui::Ui) {
+        let Some(quote) = &self.quote else {
+            return;
+        };
+        let text = quote.to_string();
+        // Long quotes get smaller before they wrap onto many lines
+        let size = (22.0 - text.len() as f32 / 15.0).clamp(12.0, 20.0);
+        let label = ui.add(egui::Label::new(egui::RichText::new(&text).italics().size(size)).wrap());
+        label.context_menu(|ui| {
+            compact_text(ui.style_mut());
+            if ui.button("Copy quote").clicked() {
+                ui.ctx().copy_text(text.clone());
+                ui.close_menu();
+            }
+        });
+        if ui.button(egui::RichText::new("↻").size(16.0)).on_hover_text("Another quote").clicked() {
+            self.quote = self.quotes.next();
+        }
+    }
+
+    fn stretch_view(&mut self, ui: &mut egui::Ui) {
+        let now = self.clock.instant();
+        let Some(player) = &mut self.stretch else {
+            // Short breaks get the short routines, taking turns with each other
+            let length = if self.long_break { stretches::Length::Long } else { stretches::Length::Short };
+            let fitting: Vec<_> = self.stretches.iter().filter(|routine| routine.length == length).collect();
+            let Some(routine) = fitting.get(self.next_stretch % fitting.len().max(1)) else {
+                return;
+            };
+            if ui.button(format!("🤸 Stretch: {}", routine.name)).clicked() {
+                self.stretch = Some(stretches::Player::new((*routine).clone(), now));
+                self.next_stretch += 1;
+            }
+            return;
+        };
+        let stepped = player.tick(now);
+        let stopped = matches!(stretches::show(ui, player, now), Some(stretches::Control::Stop)) || player.finished();
+        if stopped {
+            self.stretch = None;
+        } else if stepped {
+            self.audio().play(Role::Cue, alerts::Sound::Blip, Shape::default());
+        }
+    }
+
+    #[cfg(feature = "games")]
+    fn breathing_view(&mut self, ui: &mut egui::Ui) {
+        let (now, today) = (self.clock.instant(), self.clock.wall().date());
+        let Some(game) = &mut self.breathing else {
+            let typing = ui.ctx().wants_keyboard_input();
+            let start = ui.button("🫁 Breathing game").on_hover_text("A minute of tapping Space in time with your breath, or press G");
+            if start.clicked() || (!typing && ui.input(|i| i.key_pressed(egui::Key::G))) {
+                self.breathing = Some(breathing_game::Game::new(now));
+            }
+            if let Some((rounds, best)) = self.breathing_scores.day(today).filter(|_| self.settings.keep_game_scores) {
+                ui.weak(format!("Today: {} rounds, best {} %", rounds, best));
+            }
+            return;
+        };
+        match breathing_game::show(ui, game, now, self.settings.reduce_motion) {
+            Some(breathing_game::Outcome::Finished(score)) if self.settings.keep_game_scores => self.breathing_scores.add(today, score),
+            Some(breathing_game::Outcome::Closed) => self.breathing = None,
+            _ => {}
+        }
+        if self.playing() {
+            self.repaint.animate("breathing game", repaint::SMOOTH, Instant::now() + repaint::SMOOTH * 2);
+        }
+    }
+
+    #[cfg(feature = "games")]
+    fn playing(&self) -> bool {
+        self.breathing.as_ref().is_some_and(breathing_game::Game::running)
+    }
+
+    #[cfg(not(feature = "games"))]
+    fn playing(&self) -> bool {
+        false
+    }
+
+    fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        // Plain keys belong to a text field being typed in, e.g. spaces in the task, and Space
+        // to the breathing game while it runs
+        let typing = ctx.wants_keyboard_input() || self.playing();
+        for action in Action::ALL {
+            if let Some(shortcut) = action.shortcut().filter(|shortcut| !typing || !shortcut.modifiers.is_none()) {
+                if ctx.input_mut(|i| i.consume_shortcut(&shortcut)) {
+                    self.perform(ctx, action);
+                }
+            }
+        }
+    }
+
+    fn menu_bar(&mut self, ctx: &egui::Context) {
+        egui::TopBottomPanel::top("menu_bar").show(ctx, |ui| {
+            compact_text(ui.style_mut());
+            egui::menu::bar(ui, |ui| {
+                for menu in Menu::ALL {
+                    ui.menu_button(menu.title(), |ui| {
+                        compact_text(ui.style_mut());
+                        for action in Action::ALL.into_iter().filter(|a| a.menu() == menu) {
+                            if self.menu_item(ui, action) {
+                                self.perform(ui.ctx(), action);
+                                ui.close_menu();
+                            }
+                        }
+                        if menu == Menu::Help && (self.dev_mode || self.settings.developer_panel) && ui.button("Developer panel").clicked() {
+                            self.dev_panel.open = true;
+                            ui.close_menu();
+                        }
+                    });
+                }
+            });
+        });
+    }
+
+    fn menu_item(&self, ui: &mut egui::Ui, action: Action) -> bool {
+        let shortcut = action.shortcut().map(|s| ui.ctx().format_shortcut(&s)).unwrap_or_default();
+        let checked = match action {
+            Action::ToggleMenuBar => self.show_menu_bar,
+            Action::TogglePrivacy => self.privacy,
+            Action::ToggleDarkMode => self.dark,
+            Action::ToggleMute => self.settings.muted,
+            Action::ToggleWorkOffline => self.settings.work_offline,
+            _ => false,
+        };
+        let button = ui.add(egui::Button::new(action.label()).selected(checked).shortcut_text(shortcut));
+        hints::explain(button, hints::Control::of(action).and_then(|control| self.hints.get(control))).clicked()
+    }
+
+    // Replayed sessions enter the history at the moment they ended, as they did when recorded
+    fn feed_replay(&mut self) {
+        let RunMode::Replay(pending) = &mut self.mode else {
+            return;
+        };
+        let now = self.clock.wall();
+        while let Some(session) = pending.pop_front() {
+            if session.end() > now {
+                pending.push_front(session);
+                break;
+            }
+            if let Some(history) = self.history.wait() {
+                history.append(session);
+            }
+        }
+    }
+
+    fn stats_window(&mut self, ctx: &egui::Context) {
+        let today = self.clock.wall().date();
+        let mut open = self.show_stats;
+        let mut baseline_changed = false;
+        egui::Window::new("Statistics")
+            .open(&mut open)
+            .resizable(false)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                compact_text(ui.style_mut());
+                if self.privacy {
+                    ui.weak("Hidden in privacy mode");
+                    return;
+                }
+                let Some(history) = self.history.get() else {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("Loading history…");
+                    });
+                    return;
+                };
+                if let Some(goal) = self.settings.weekly_goal {
+                    let done: u64 = history.stats.week(stats::WeekId::of(today)).iter().flatten().map(|day| day.focus_secs / 60).sum();
+                    let progress = done as f32 / goal as f32;
+                    ui.add(egui::ProgressBar::new(progress.min(1.0)).text(format!("This week: {} of {} min", done, goal)));
+                }
+                let days: Vec<_> = (0..14).map(|back| today - time::Duration::days(back)).collect();
+                let longest = days.iter().map(|day| history.stats.day(*day).focus_secs).max().unwrap_or(0).max(1);
+                let summary = summary::of(&mut self.summary, history);
+                let fortnight = summary.fortnight(today);
+                if fortnight.pauses > 0 {
+                    let (pauses, interrupted, paused) = (fortnight.pauses, fortnight.interrupted, fortnight.paused_secs);
+                    let label = ui.label(format!("Last 14 days: {} pauses in {} of {} sessions, {} min paused", pauses, interrupted, fortnight.sessions, paused / 60));
+                    let (by_hand, external) = (fortnight.by_hand_secs, fortnight.during_calls_secs);
+                    if by_hand + external > 0 {
+                        label.on_hover_text(format!("{} min paused by hand, {} min during calls", by_hand / 60, external / 60));
+                    }
+                }
+                if !history.breaks.is_empty() {
+                    let escaped = history.breaks.iter().filter(|b| b.escaped_after_secs.is_some()).count();
+                    ui.label(format!("Enforced breaks: {}, escaped early: {}", history.breaks.len(), escaped));
+                }
+                if !history.naps.is_empty() {
+                    let minutes: u32 = history.naps.iter().map(|nap| nap.minutes).sum();
+                    ui.label(format!("Naps: {}, {} min", history.naps.len(), minutes));
+                }
+                let known_tags = &summary.tags;
+                self.tag_filter.retain(|tag| known_tags.contains(tag));
+                if !known_tags.is_empty() {
+                    ui.horizontal_wrapped(|ui| {
+                        ui.label("Only sessions tagged").on_hover_text("With all of the tags picked; applies to the comparison and intentions by task");
+                        for tag in known_tags {
+                            let mut picked = self.tag_filter.contains(tag);
+                            if ui.toggle_value(&mut picked, format!("#{}", tag)).changed() {
+                                if picked {
+                                    self.tag_filter.push(tag.clone());
+                                } else {
+                                    self.tag_filter.retain(|t| t != tag);
+                                }
+                            }
+                        }
+                    });
+                }
+                summary.tag(history, &self.tag_filter);
+                let summary = &*summary;
+                let intent_days = &summary.intents.0;
+                let (sessions, revision, intent_tasks) = match summary.tagged() {
+                    Some(tagged) => (&tagged.sessions[..], tagged.revision, &tagged.intent_tasks),
+                    None => (&history.sessions[..], history.revision, &summary.intents.1),
+                };
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.include_archived, "Include archived");
+                    let stale = format!("Archive everything not used in {} days", archive::STALE_DAYS);
+                    if ui.button(stale).on_hover_text("Task labels and tasks; their sessions stay in the history").clicked() {
+                        let labels = self.archive.archive_stale(&history.sessions, today);
+                        let tasks = self.plan.archive_stale(self.clock.wall());
+                        self.archive.save();
+                        self.plan.save();
+                        self.toasts.push(format!("Archived {} labels and {} tasks", labels, tasks));
+                    }
+                });
+                ui.collapsing("Weeks", |ui| {
+                    baseline_changed = weekly::show(ui, &history.stats, today, &mut self.settings.baseline_week);
+                });
+                ui.collapsing("Compare tasks", |ui| {
+                    let include = self.include_archived;
+                    if compare::show(ui, &mut self.comparison, sessions, revision, today, &history.stats, &mut self.archive, include) {
+                        self.archive.save();
+                    }
+                });
+                let moods = stats::focus_by_mood(&history.stats, &history.notes);
+                if !moods.is_empty() {
+                    ui.collapsing("Focus by mood", |ui| {
+                        egui::Grid::new("focus_by_mood").striped(true).show(ui, |ui| {
+                            for (mood, (days, secs)) in moods.iter().rev() {
+                                ui.label(day_note::face(*mood));
+                                ui.label(format!("{} days", days));
+                                ui.label(format!("{} min on average", secs / 60));
+                                ui.end_row();
+                            }
+                        });
+                    });
+                }
+                if !intent_tasks.is_empty() {
+                    ui.collapsing("Intentions by task", |ui| {
+                        egui::Grid::new("intent_tasks").striped(true).show(ui, |ui| {
+                            for (task, tally) in intent_tasks.iter().filter(|(task, _)| self.include_archived || !self.archive.contains(task)) {
+                                ui.label(task);
+                                ui.label(tally.to_string());
+                                ui.end_row();
+                            }
+                        });
+                    });
+                }
+                egui::Grid::new("daily_stats").striped(true).show(ui, |ui| {
+                    for day in days {
+                        let totals = history.stats.day(day);
+                        ui.label(if day == today { "Today".to_owned() } else { format!("{} {}", day.weekday(), day) });
+                        ui.label(format!("{} sessions", totals.sessions));
+                        ui.label(format!("{} min", totals.focus_secs / 60));
+                        match totals.late_sessions {
+                            0 => ui.label(""),
+                            late => ui.weak(format!("{} late", late)),
+                        };
+                        match totals.easy_starts {
+                            0 => ui.label(""),
+                            easy => ui.weak(format!("{} easy starts", easy)),
+                        };
+                        match intent_days.get(&day) {
+                            Some(tally) => ui.weak(format!("intents {}", tally)),
+                            None => ui.label(""),
+                        };
+                        ui.add(egui::ProgressBar::new(totals.focus_secs as f32 / longest as f32).desired_width(100.0));
+                        let note = history.notes.get(&day);
+                        let button = ui.small_button(note.and_then(|note| note.mood).map_or("✏", day_note::face));
+                        let button = match note.filter(|note| !note.note.is_empty()) {
+                            Some(note) => button.on_hover_text(&note.note),
+                            None => button.on_hover_text("Note and mood for this day"),
+                        };
+                        if button.clicked() {
+                            self.day_detail = Some(note.cloned().unwrap_or_else(|| history::DayNote::blank(day)));
+                        }
+                        ui.end_row();
+                    }
+                });
+            });
+        self.show_stats = open;
+        if baseline_changed {
+            self.save_settings();
+        }
+    }
+
+    fn day_detail_window(&mut self, ctx: &egui::Context) {
+        let Some(draft) = self.day_detail.as_mut().filter(|_| !self.privacy) else {
+            return;
+        };
+        let totals = self.history.peek().map(|history| history.stats.day(draft.date)).unwrap_or_default();
+        if self.thumbnails.as_ref().map(|(date, _)| *date) != Some(draft.date) {
+            let names: Vec<String> = self.history.peek().map_or(Vec::new(), |history| {
+                history.stats.sessions_on(&history.sessions, draft.date).into_iter().filter_map(|session| session.screenshot.clone()).collect()
+            });
+            let loaded = names.into_iter().filter_map(|name| Some((name.clone(), screenshots::thumbnail(ctx, &name)?))).collect();
+            self.thumbnails = Some((draft.date, loaded));
+        }
+        let thumbnails = self.thumbnails.as_ref().map_or(&[][..], |(_, thumbnails)| &thumbnails[..]);
+        match day_note::detail(ctx, draft, totals, thumbnails) {
+            Some(day_note::Detail::Save) => {
+                if let (Some(mut note), Some(history)) = (self.day_detail.take(), self.history.wait()) {
+                    note.note = note.note.trim().to_owned();
+                    history.set_note(note);
+                }
+            }
+            Some(day_note::Detail::Close) => self.day_detail = None,
+            Some(day_note::Detail::Print) => {
+                if let Some((history, date)) = self.history.peek().zip(self.day_detail.as_ref().map(|draft| draft.date)) {
+                    if let Err(err) = day_log::open(date, &DayLog::of(history, date).render()) {
+                        log::warn!("{}", err);
+                        self.toasts.push(err);
+                    }
+                }
+            }
+            None => {}
+        }
+        if self.day_detail.is_none() {
+            self.thumbnails = None;
+        }
+    }
+
+    fn settings_window(&mut self, ctx: &egui::Context) {
+        if self.show_settings {
+            self.undo_settings(ctx);
+        }
+        let mut open = self.show_settings;
+        // Kept within the window however it is tiled, scrolling what doesn't fit either way
+        let room = ctx.screen_rect().shrink(8.0).size();
+        egui::Window::new("Settings")
+            .open(&mut open)
+            .resizable(false)
+            .collapsible(false)
+            .constrain(true)
+            .max_width(room.x)
+            .max_height(room.y)
+            .show(ctx, |ui| egui::ScrollArea::both().max_height(room.y - 40.0).show(ui, |ui| {
+                compact_text(ui.style_mut());
+                ui.label("Alarm sound file (wav, ogg, mp3, flac)");
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut self.alarm_file_input).hint_text("Bundled chime"));
+                    if ui.button("Apply").clicked() {
+                        self.apply_alarm_file();
+                    }
+                });
+                if let Some(error) = &self.alarm_file_error {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
+
+                self.title_settings(ui);
+                self.theme_settings(ui);
+                self.accent_settings(ui);
+                #[cfg(target_os = "linux")]
+                self.border_tint_settings(ui);
+                #[cfg(target_os = "linux")]
+                self.companion_settings(ui);
+                self.restore_defaults_button(ui, settings::Section::AlarmAndWindow);
+                ui.separator();
+
+                self.goal_settings(ui);
+                self.history_settings(ui);
+                self.standup_settings(ui);
+                self.restore_defaults_button(ui, settings::Section::GoalsAndHistory);
+                ui.separator();
+
+                if ui.checkbox(&mut self.settings.confirm_quit, "Ask before quitting during a focus session").changed() {
+                    self.save_settings();
+                }
+                if ui.checkbox(&mut self.settings.ask_intent, "Ask for a goal when starting a focus session").changed() {
+                    self.save_settings();
+                }
+                if ui.checkbox(&mut self.settings.auto_start_next, "Start the next session or break right away").changed() {
+                    self.save_settings();
+                }
+                let greet = ui.checkbox(&mut self.settings.greet_return, "Remind me once I'm back if a session or break ended while I was away");
+                if greet.on_hover_text("Needs GNOME or xprintidle to tell when you are back").changed() {
+                    self.save_settings();
+                }
+                self.get_ready_settings(ui);
+                if ui.checkbox(&mut self.settings.show_quotes, "Show a quote during breaks").changed() {
+                    self.save_settings();
+                }
+                #[cfg(feature = "games")]
+                ui.horizontal(|ui| {
+                    if ui.checkbox(&mut self.settings.break_game, "Offer a breathing game during breaks").changed() {
+                        self.save_settings();
+                    }
+                    if self.settings.break_game && ui.checkbox(&mut self.settings.keep_game_scores, "Keep scores by day").changed() {
+                        self.save_settings();
+                    }
+                });
+                if ui.checkbox(&mut self.settings.beginner_hints, "Show a tip on the technique as sessions start, in the first week").changed() {
+                    self.save_settings();
+                }
+                ui.horizontal(|ui| {
+                    if ui.checkbox(&mut self.settings.show_mascot, "Show Ferris next to the timer").changed() {
+                        self.save_settings();
+                    }
+                    if ui.checkbox(&mut self.settings.reduce_motion, "Reduce motion").changed() {
+                        self.save_settings();
+                    }
+                });
+                self.duration_settings(ui);
+                self.long_break_settings(ui);
+                self.enforced_break_settings(ui);
+                self.posture_settings(ui);
+                self.wind_down_settings(ui);
+                self.appointment_settings(ui);
+                self.power_settings(ui);
+                if ui
+                    .checkbox(&mut self.settings.pause_during_calls, "Pause focus and stay quiet while the microphone is in use")
+                    .on_hover_text("Checked every few seconds with pactl; focus resumes when the call ends")
+                    .changed()
+                {
+                    self.save_settings();
+                    if self.settings.pause_during_calls {
+                        self.run_check(Capability::Microphone);
+                    }
+                }
+                if self.settings.pause_during_calls {
+                    self.check_result(ui, Capability::Microphone);
+                }
+                self.recording_settings(ui);
+                self.status_file_settings(ui);
+                if ui.button("Troubleshoot integrations").clicked() {
+                    for capability in Capability::ALL {
+                        self.run_check(capability);
+                    }
+                    self.show_troubleshoot = true;
+                }
+                self.restore_defaults_button(ui, settings::Section::Behaviour);
+                ui.separator();
+
+                self.hook_settings(ui);
+                self.webhook_settings(ui);
+                self.local_api_settings(ui);
+                self.screenshot_settings(ui);
+                self.restore_defaults_button(ui, settings::Section::Hooks);
+                ui.separator();
+
+                self.preset_settings(ui);
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    let volume = ui.add(egui::Slider::new(&mut self.settings.volume, 0.0..=1.0).text("Volume"));
+                    // Heard right away through sync_sound; save once the slider is let go, not on every step of the drag
+                    if volume.drag_stopped() || (volume.changed() && !volume.dragged()) {
+                        self.save_settings();
+                    }
+                    if ui.button("Test sound").clicked() {
+                        self.play(alerts::Sound::Chime, Shape::default());
+                    }
+                    if ui.checkbox(&mut self.settings.muted, "Mute").changed() {
+                        self.save_settings();
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    let mut enabled = self.settings.ambient_volume.is_some();
+                    if ui.checkbox(&mut enabled, "Brown noise while focusing").changed() {
+                        self.settings.ambient_volume = enabled.then_some(0.5);
+                        self.save_settings();
+                    }
+                    if let Some(volume) = &mut self.settings.ambient_volume {
+                        let slider = ui.add(egui::Slider::new(volume, 0.0..=1.0));
+                        if slider.drag_stopped() || (slider.changed() && !slider.dragged()) {
+                            self.save_settings();
+                        }
+                    }
+                });
+                let edit = alerts::matrix(ui, &mut self.settings.alerts);
+                if edit.changed {
+                    self.save_settings();
+                }
+                if let Some(alert) = edit.preview {
+                    // Rings only once, so the preview ends by itself
+                    let profile = self.settings.alerts.profile(alert);
+                    let sound = if profile.sound == alerts::Sound::Ring { alerts::Sound::Chime } else { profile.sound };
+                    self.audio().stop();
+                    self.play(sound, Shape::ramped(profile.ramp()));
+                }
+                self.restore_defaults_button(ui, settings::Section::Sound);
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("Copy settings as text").clicked() {
+                        ui.ctx().copy_text(preset::export(&self.settings));
+                        self.toasts.push("Settings copied, file paths are left out");
+                    }
+                    if ui.button("Import settings from text…").clicked() {
+                        self.show_import = true;
+                    }
+                    if ui.button("Settings history…").clicked() {
+                        self.show_settings_journal = true;
+                    }
+                });
+            }));
+        self.show_settings = open;
+    }
+
+    fn duration_settings(&mut self, ui: &mut egui::Ui) {
+        let preset = self.settings.active_preset().map(|preset| preset.name.clone());
+        ui.add_enabled_ui(preset.is_none(), |ui| {
+            ui.horizontal(|ui| {
+                let range = 1..=settings::MAX_MINUTES;
+                ui.label("Focus for");
+                let work = ui.add(egui::DragValue::new(&mut self.settings.work_minutes).range(range.clone()).suffix(" min"));
+                ui.label("then break for");
+                let rest = ui.add(egui::DragValue::new(&mut self.settings.break_minutes).range(range).suffix(" min"));
+                if [work, rest].iter().any(|response| response.drag_stopped() || response.lost_focus()) {
+                    self.save_settings();
+                    self.apply_durations();
+                }
+            });
+        })
+        .response
+        .on_disabled_hover_text(format!("The preset \"{}\" sets its own lengths", preset.unwrap_or_default()));
+    }
+
+    fn long_break_settings(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let mut enabled = self.settings.long_break_minutes.is_some();
+            let checkbox = ui.checkbox(&mut enabled, format!("Long break after every {} sessions", CYCLE_LENGTH));
+            if hints::explain(checkbox, self.hints.get(hints::Control::LongBreak)).changed() {
+                self.settings.long_break_minutes = enabled.then_some(15);
+                self.save_settings();
+            }
+            if let Some(minutes) = &mut self.settings.long_break_minutes {
+                let response = ui.add(egui::DragValue::new(minutes).range(1..=settings::MAX_MINUTES).suffix(" min"));
+                if response.drag_stopped() || response.lost_focus() {
+                    self.save_settings();
+                }
+            }
+            if self.settings.long_break_too_short() {
+                ui.colored_label(egui::Color32::YELLOW, "⚠ shorter than the short break");
+            }
+        });
+    }
+
+    fn get_ready_settings(&mut self, ui: &mut egui::Ui) {
+        ui.add_enabled_ui(self.settings.auto_start_next, |ui| {
+            ui.horizontal(|ui| {
+                let mut enabled = self.settings.get_ready_secs.is_some();
+                if ui.checkbox(&mut enabled, "Count down before focus starts").changed() {
+                    self.settings.get_ready_secs = enabled.then_some(30);
+                    self.save_settings();
+                }
+                if let Some(secs) = &mut self.settings.get_ready_secs {
+                    let response = ui.add(egui::DragValue::new(secs).range(10..=120).suffix(" s"));
+                    if response.drag_stopped() || response.lost_focus() {
+                        self.save_settings();
+                    }
+                }
+            });
+        });
+    }
+
+    fn enforced_break_settings(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let mut enforced = self.settings.enforced_break_secs.is_some();
+            if ui.checkbox(&mut enforced, "Withhold controls at the start of breaks").changed() {
+                self.settings.enforced_break_secs = enforced.then_some(60);
+                self.save_settings();
+            }
+            if let Some(secs) = &mut self.settings.enforced_break_secs {
+                let response = ui.add(egui::DragValue::new(secs).range(10..=300).suffix(" s"));
+                if response.drag_stopped() || response.lost_focus() {
+                    self.save_settings();
+                }
+            }
+        });
+    }
+
+    fn backup_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_backups;
+        let mut restore = None;
+        egui::Window::new("Restore from backup")
+            .open(&mut open)
+            .resizable(false)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                compact_text(ui.style_mut());
+                if self.backups.is_empty() {
+                    ui.label("No backups yet. One is taken each day before the history or settings first change.");
+                    return;
+                }
+                ui.label("History and settings as they were at the start of each day:");
+                egui::Grid::new("backups").striped(true).show(ui, |ui| {
+                    for (index, backup) in self.backups.iter().enumerate() {
+                        ui.label(format!("{} {}", backup.date.weekday(), backup.date));
+                        match backup.sessions {
+                            Some(sessions) => ui.label(format!("{} sessions", sessions)),
+                            None => ui.weak("no history"),
+                        };
+                        if self.restore_confirm == Some(index) {
+                            if ui.button("Replace current history and settings").clicked() {
+                                restore = Some(index);
+                            }
+                            if ui.button("Cancel").clicked() {
+                                self.restore_confirm = None;
+                            }
+                        } else if ui.button("Restore").clicked() {
+                            self.restore_confirm = Some(index);
+                        }
+                        ui.end_row();
+                    }
+                });
+                ui.weak("The current files are copied aside first.");
+            });
+        self.show_backups = open;
+        let Some(backup) = restore.and_then(|index| self.backups.get(index)) else {
+            return;
+        };
+        match backup::restore(backup) {
+            Ok(safety) => {
+                self.toasts.push(format!("Restored {}, the replaced files are in {}", backup.date, safety.display()));
+                let (settings, problem) = Settings::load();
+                self.toasts.extend(problem);
+                self.history = load_history(&settings);
+                self.apply_settings(settings, Source::Backup);
+                self.show_backups = false;
+            }
+            Err(err) => self.toasts.push(format!("Cannot restore backup: {}", err)),
+        }
+    }
+
+    /// One line for every integration that cannot reach the network, none while online.
+    fn offline_text(&self) -> Option<String> {
+        let status = self.connectivity.status();
+        if status == connectivity::Status::Online {
+            return None;
+        }
+        let what = if status == connectivity::Status::WorkingOffline { "Working offline" } else { "Offline" };
+        Some(match self.webhooks.log().1 {
+            0 => format!("📴 {}", what),
+            1 => format!("📴 {} — 1 delivery queued", what),
+            queued => format!("📴 {} — {} deliveries queued", what, queued),
+        })
+    }
+
+    fn settings_journal_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_settings_journal;
+        let mut revert = None;
+        egui::Window::new("Settings history")
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                compact_text(ui.style_mut());
+                let entries = self.settings_journal.entries();
+                if entries.is_empty() {
+                    ui.label("No changes yet. Every change to the settings is listed here from now on.");
+                    return;
+                }
+                egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                    let offset = clock::local_offset();
+                    let mut day = None;
+                    for entry in entries.iter().rev() {
+                        let at = entry.at.to_offset(offset);
+                        if day != Some(at.date()) {
+                            day = Some(at.date());
+                            ui.horizontal(|ui| {
+                                ui.strong(format!("{} {}", at.weekday(), at.date()));
+                                // The newest change of the day comes first, so this is how the day ended
+                                if ui.small_button("Revert to the end of this day").clicked() {
+                                    revert = Some(entry.at);
+                                }
+                            });
+                        }
+                        ui.label(format!(
+                            "{:02}:{:02}  {}: {} → {}  ({})",
+                            at.hour(),
+                            at.minute(),
+                            entry.field,
+                            preset::show(&entry.field, Some(&entry.old)),
+                            preset::show(&entry.field, Some(&entry.new)),
+                            entry.source
+                        ));
+                    }
+                });
+            });
+        self.show_settings_journal = open;
+        let Some(at) = revert else {
+            return;
+        };
+        match self.settings_journal.as_of(&self.settings, at) {
+            Ok(settings) => {
+                self.apply_settings(settings, Source::Revert);
+                let date = at.to_offset(clock::local_offset()).date();
+                self.toasts.push(format!("Settings are back to how they were at the end of {}", date));
+            }
+            Err(err) => self.toasts.push(format!("Cannot revert the settings: {}", err)),
+        }
+    }
+
+    fn import_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_import;
+        let mut apply = None;
+        egui::Window::new("Import settings")
+            .open(&mut open)
+            .resizable(false)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                compact_text(ui.style_mut());
+                ui.label("Paste the text someone copied with \"Copy settings as text\":");
+                ui.add(egui::TextEdit::multiline(&mut self.import_input).desired_rows(3));
+                if self.import_input.trim().is_empty() {
+                    return;
+                }
+                match preset::import(&self.import_input, &self.settings) {
+                    Err(err) => {
+                        ui.colored_label(egui::Color32::RED, format!("Cannot import: {}", err));
+                    }
+                    Ok(import) if import.changes.is_empty() => {
+                        ui.label("These are the settings you already have.");
+                    }
+                    Ok(import) => {
+                        ui.label("This will change:");
+                        for change in &import.changes {
+                            ui.label(format!("• {}", change));
+                        }
+                        if let Some(note) = &import.note {
+                            ui.weak(format!("Note: {}", note));
+                        }
+                        if ui.button("Apply").clicked() {
+                            apply = Some(import.settings);
+                        }
+                    }
+                }
+            });
+        if let Some(settings) = apply {
+            self.apply_settings(settings, Source::Import);
+            self.import_input.clear();
+            self.toasts.push("Settings imported");
+            open = false;
+        }
+        self.show_import = open;
+    }
+
+    #[cfg(target_os = "linux")]
+    fn border_tint_settings(&mut self, ui: &mut egui::Ui) {
+        let Some(compositor) = self.border_tint.as_ref().map(|tint| tint.name()) else {
+            return;
+        };
+        ui.horizontal(|ui| {
+            let label = format!("Tint window borders during focus ({})", compositor);
+            if ui.checkbox(&mut self.settings.tint_borders, label).changed() {
+                self.save_settings();
+                if self.settings.tint_borders {
+                    self.run_check(Capability::WindowBorders);
+                }
+            }
+            if ui.color_edit_button_srgb(&mut self.settings.focus_border_color).changed() {
+                self.save_settings();
+            }
+        });
+        if self.settings.tint_borders {
+            self.check_result(ui, Capability::WindowBorders);
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn companion_settings(&mut self, ui: &mut egui::Ui) {
+        let mut monitors = self.monitors.take().unwrap_or_else(companion::list_monitors);
+        ui.horizontal(|ui| {
+            ui.label("Focus companion on");
+            let selected = self.settings.companion_monitor.clone().unwrap_or_else(|| "Off".to_owned());
+            let mut changed = false;
+            egui::ComboBox::from_id_source("companion_monitor").selected_text(selected).show_ui(ui, |ui| {
+                changed |= ui.selectable_value(&mut self.settings.companion_monitor, None, "Off").changed();
+                if let Some(Ok(found)) = monitors.get() {
+                    for monitor in found.iter() {
+                        let label = format!("{} ({}×{})", monitor.name, monitor.width, monitor.height);
+                        changed |= ui.selectable_value(&mut self.settings.companion_monitor, Some(monitor.name.clone()), label).changed();
+                    }
+                }
+            });
+            if ui.small_button("⟳").on_hover_text("Look for monitors again").clicked() {
+                monitors = companion::list_monitors();
+            }
+            if changed {
+                self.save_settings();
+            }
+        });
+        match monitors.get() {
+            None => {
+                ui.weak("Looking for monitors…");
+            }
+            Some(Err(err)) => {
+                ui.weak(format!("Cannot list monitors: {}", err));
+            }
+            Some(Ok(_)) => {
+                ui.weak("Shows the countdown, task and cycle fullscreen there while you focus");
+            }
+        }
+        self.monitors = Some(monitors);
+    }
+
+    // Open while a focus session runs, on the monitor chosen; closed on a break or pause
+    #[cfg(target_os = "linux")]
+    fn focus_companion(&mut self, ctx: &egui::Context) {
+        let focusing = self.timer.is_running() && self.work_period();
+        let Some(monitor) = self.settings.companion_monitor.clone().filter(|_| focusing) else {
+            self.companion = None;
+            return;
+        };
+        let now = Instant::now();
+        if self.companion.as_ref().is_none_or(|companion| companion.monitor() != monitor) {
+            self.companion = Some(companion::Companion::open(monitor.clone(), now));
+        }
+        let status = companion::Status {
+            remaining: self.remaining(),
+            task: self.label().filter(|_| !self.privacy),
+            cycle: self.settings.long_break_minutes.map(|_| (self.cycle_sessions.min(CYCLE_LENGTH), CYCLE_LENGTH)),
+        };
+        let Some(companion) = &mut self.companion else {
+            return;
+        };
+        if companion.disconnected(now) {
+            self.toasts.push(format!("Monitor {} was disconnected, so the focus companion closed", monitor));
+        }
+        companion.show(ctx, now, &status);
+    }
+
+    fn accent_settings(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Accent color");
+            let system = self.accent.as_ref().and_then(|monitor| monitor.accent()).filter(|_| self.settings.system_accent);
+            match system {
+                Some(color) => {
+                    let (rect, _) = ui.allocate_exact_size(ui.spacing().interact_size, egui::Sense::hover());
+                    ui.painter().rect_filled(rect, 2.0, color);
+                }
+                None => {
+                    if ui.color_edit_button_srgb(&mut self.settings.accent_color).changed() {
+                        self.save_settings();
+                    }
+                }
+            }
+            if ui.checkbox(&mut self.settings.system_accent, "Use system accent color").changed() {
+                self.save_settings();
+            }
+            if self.settings.system_accent && system.is_none() {
+                ui.weak("(not available, using this one)");
+            }
+        });
+    }
+
+    /// Follows the theme setting, changing the look only while nobody is busy with the window.
+    fn apply_theme(&mut self, ctx: &egui::Context) {
+        // The schedule waits for a clock that jumped; the look stays as it is meanwhile
+        if self.settings.theme == Theme::Automatic && self.clock_check.suspended(self.clock.instant()) {
+            return;
+        }
+        let now = self.clock.wall();
+        let (night, until) = theme::night(self.settings.theme_schedule, now);
+        self.night = self.settings.theme == Theme::Automatic && night;
+        if self.theme_override.is_some_and(|(_, until)| now >= until) {
+            self.theme_override = None;
+        }
+        let dark = match self.settings.theme {
+            Theme::Dark => true,
+            Theme::Light => false,
+            Theme::Automatic => self.theme_override.map_or(night, |(dark, _)| dark),
+        };
+        if dark != self.dark && (ctx.frame_nr() == 0 || theme::idle(ctx)) {
+            self.dark = dark;
+        }
+        if self.settings.theme == Theme::Automatic {
+            let wait = Duration::try_from(until - now).unwrap_or_default();
+            self.repaint.animate("theme change", wait, Instant::now() + wait);
+        }
+    }
+
+    /// Switches between dark and light; while the theme is automatic, only until the next
+    /// sunrise or sunset.
+    fn toggle_dark_mode(&mut self) {
+        let dark = !self.dark;
+        if self.settings.theme == Theme::Automatic {
+            let (_, until) = theme::night(self.settings.theme_schedule, self.clock.wall());
+            self.theme_override = Some((dark, until));
+        } else {
+            self.settings.theme = if dark { Theme::Dark } else { Theme::Light };
+            self.save_settings();
+        }
+        self.dark = dark;
+    }
+
+    fn theme_settings(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Theme");
+            for theme in Theme::ALL {
+                if ui.selectable_value(&mut self.settings.theme, theme, theme.label()).changed() {
+                    self.theme_override = None;
+                    self.save_settings();
+                }
+            }
+        });
+        if self.settings.theme != Theme::Automatic {
+            return;
+        }
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            let by_sun = matches!(self.settings.theme_schedule, theme::Schedule::Sun { .. });
+            if ui.selectable_label(by_sun, "At sunrise and sunset").clicked() && !by_sun {
+                self.settings.theme_schedule = theme::Schedule::Sun { latitude: 0.0, longitude: 0.0 };
+                changed = true;
+            }
+            if ui.selectable_label(!by_sun, "At fixed times").clicked() && by_sun {
+                self.settings.theme_schedule = theme::Schedule::default();
+                changed = true;
+            }
+        });
+        ui.horizontal(|ui| match &mut self.settings.theme_schedule {
+            theme::Schedule::Sun { latitude, longitude } => {
+                let latitude = ui.add(egui::DragValue::new(latitude).range(-90.0..=90.0).speed(0.1).suffix("° N"));
+                let longitude = ui.add(egui::DragValue::new(longitude).range(-180.0..=180.0).speed(0.1).suffix("° E"));
+                changed |= [latitude, longitude].iter().any(|response| response.drag_stopped() || response.lost_focus());
+                ui.weak("(south and west are negative)");
+            }
+            theme::Schedule::Times { light_at, dark_at } => {
+                for (label, at) in [("Light from", light_at), ("dark from", dark_at)] {
+                    ui.label(label);
+                    let [hour, minute] = at;
+                    let hour = ui.add(egui::DragValue::new(hour).range(0..=23));
+                    ui.label(":");
+                    let minute = ui.add(egui::DragValue::new(minute).range(0..=59).custom_formatter(|m, _| format!("{:02}", m)));
+                    changed |= [hour, minute].iter().any(|response| response.drag_stopped() || response.lost_focus());
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            let mut enabled = self.settings.night_volume.is_some();
+            if ui.checkbox(&mut enabled, "Lower the alarm at night to").changed() {
+                self.settings.night_volume = enabled.then_some(0.3);
+                changed = true;
+            }
+            if let Some(volume) = &mut self.settings.night_volume {
+                let slider = ui.add(egui::Slider::new(volume, 0.0..=1.0));
+                changed |= slider.drag_stopped() || (slider.changed() && !slider.dragged());
+            }
+        });
+        if changed {
+            self.theme_override = None;
+            self.save_settings();
+        }
+    }
+
+    /// The accent in effect, made readable on the background. Reads the system accent
+    /// only while it is wanted.
+    fn accent_color(&mut self, ctx: &egui::Context, background: egui::Color32) -> egui::Color32 {
+        match (self.settings.system_accent, &self.accent) {
+            (true, None) => self.accent = Some(accent::Monitor::start(ctx)),
+            (false, Some(_)) => self.accent = None,
+            _ => {}
+        }
+        let [r, g, b] = self.settings.accent_color;
+        let color = self.accent.as_ref().and_then(|monitor| monitor.accent()).unwrap_or(egui::Color32::from_rgb(r, g, b));
+        accent::readable(color, background)
+    }
+
+    fn title_settings(&mut self, ui: &mut egui::Ui) {
+        ui.label(format!("Window title (placeholders: {})", template::FIELDS.map(|f| format!("{{{}}}", f)).join(" ")));
+        let edit = ui.add(egui::TextEdit::singleline(&mut self.title_template_input).hint_text("Pomodoro Timer"));
+        if edit.changed() {
+            let text = self.title_template_input.trim();
+            match Template::parse(text) {
+                Ok(_) => {
+                    self.title_template_error = None;
+                    self.settings.title_template = (!text.is_empty()).then(|| text.to_owned());
+                }
+                Err(err) => self.title_template_error = Some(err),
+            }
+        }
+        if edit.lost_focus() {
+            self.save_settings();
+        }
+        match &self.title_template_error {
+            Some(error) => ui.colored_label(egui::Color32::RED, error),
+            None => ui.weak(format!("Preview: {}", self.window_title())),
+        };
+    }
+
+    fn posture_settings(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let mut enabled = self.settings.posture_minutes.is_some();
+            if ui.checkbox(&mut enabled, "Remind me to switch between sitting and standing every").changed() {
+                self.settings.posture_minutes = enabled.then_some(30);
+                self.save_settings();
+            }
+            if let Some(minutes) = &mut self.settings.posture_minutes {
+                let response = ui.add(egui::DragValue::new(minutes).range(5..=240).suffix(" min"));
+                if response.drag_stopped() || response.lost_focus() {
+                    self.save_settings();
+                }
+                if ui.checkbox(&mut self.settings.posture_sound, "with sound").changed() {
+                    self.save_settings();
+                }
+            }
+        });
+    }
+
+    fn power_settings(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let mut enabled = self.settings.power_saving_below.is_some();
+            if ui.checkbox(&mut enabled, "Save power on battery below").changed() {
+                self.settings.power_saving_below = enabled.then_some(20);
+                self.save_settings();
+                if enabled {
+                    self.run_check(Capability::Battery);
+                }
+            }
+            if let Some(percent) = &mut self.settings.power_saving_below {
+                let response = ui.add(egui::DragValue::new(percent).range(5..=100).suffix(" %"));
+                if response.drag_stopped() || response.lost_focus() {
+                    self.save_settings();
+                }
+                if ui.checkbox(&mut self.settings.power_saving_icon, "show an icon").changed() {
+                    self.save_settings();
+                }
+            }
+        });
+        if self.settings.power_saving_below.is_some() {
+            self.check_result(ui, Capability::Battery);
+        }
+    }
+
+    fn run_check(&mut self, capability: Capability) {
+        let check = capability.check();
+        if let Err(problem) = &check.result {
+            log::info!("{} is not working: {}", capability.name(), problem);
+        }
+        self.checks.retain(|old| old.capability != capability);
+        self.checks.push(check);
+    }
+
+    // Only once the user turned the feature on here, or asked for all checks
+    fn check_result(&self, ui: &mut egui::Ui, capability: Capability) {
+        if let Some(check) = self.checks.iter().find(|check| check.capability == capability) {
+            ui.indent(capability.name(), |ui| check.show(ui));
+        }
+    }
+
+    fn troubleshoot_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_troubleshoot;
+        let mut again = false;
+        egui::Window::new("Troubleshoot integrations").open(&mut open).collapsible(false).default_width(360.0).show(ctx, |ui| {
+            compact_text(ui.style_mut());
+            for capability in Capability::ALL {
+                ui.strong(capability.name());
+                self.check_result(ui, capability);
+                ui.add_space(4.0);
+            }
+            again = ui.button("Check again").clicked();
+        });
+        if again {
+            for capability in Capability::ALL {
+                self.run_check(capability);
+            }
+        }
+        self.show_troubleshoot = open;
+    }
+
+    fn dev_panel_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.dev_panel.open;
+        let mut requested = Vec::new();
+        egui::Window::new("Developer panel").open(&mut open).collapsible(false).default_width(420.0).show(ctx, |ui| {
+            compact_text(ui.style_mut());
+            requested = self.dev_panel.show(ui);
+            ui.separator();
+            self.repaint.show(ui);
+            ui.separator();
+            self.outputs.show(ui, Instant::now());
+        });
+        self.dev_panel.open = open;
+        // Taken out for the tests that need other parts of the app
+        let mut panel = std::mem::take(&mut self.dev_panel);
+        for test in requested {
+            match test {
+                dev_panel::Test::Cycle => {
+                    let (durations, long_break) = (self.timer.durations(), self.settings.long_break_minutes);
+                    let long_break = long_break.map(|minutes| Duration::from_secs(u64::from(minutes) * 60));
+                    panel.run(test, || dev_panel::cycle(durations, long_break, CYCLE_LENGTH));
+                }
+                dev_panel::Test::FrameTime => panel.measure_frames(),
+                dev_panel::Test::HistoryQueries => {
+                    let today = self.clock.wall().date();
+                    let history = self.history.wait();
+                    panel.run(test, || history.map_or_else(|| Err("the history isn't loaded".to_owned()), |history| dev_panel::history_queries(history, today)));
+                }
+                dev_panel::Test::Audio => {
+                    let audio = self.audio();
+                    panel.run(test, || dev_panel::audio(audio));
+                }
+                dev_panel::Test::Events => panel.run(test, dev_panel::events),
+            }
+        }
+        self.dev_panel = panel;
+    }
+
+    fn wind_down_settings(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let mut enabled = self.settings.wind_down_at.is_some();
+            if ui.checkbox(&mut enabled, "Ask before focus sessions after").changed() {
+                self.settings.wind_down_at = enabled.then_some([22, 30]);
+                self.save_settings();
+            }
+            if let Some([hour, minute]) = &mut self.settings.wind_down_at {
+                let hour = ui.add(egui::DragValue::new(hour).range(12..=23));
+                ui.label(":");
+                let minute = ui.add(egui::DragValue::new(minute).range(0..=59).custom_formatter(|m, _| format!("{:02}", m)));
+                if [hour, minute].iter().any(|response| response.drag_stopped() || response.lost_focus()) {
+                    self.save_settings();
+                }
+            }
+        });
+    }
+
+    fn appointment_settings(&mut self, ui: &mut egui::Ui) {
+        ui.label("Daily appointments").on_hover_text("Starting a focus session that would run into one offers a shorter session");
+        let mut removed = None;
+        let mut changed = false;
+        for (index, appointment) in self.settings.appointments.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                let name = ui.add(egui::TextEdit::singleline(&mut appointment.name).desired_width(140.0));
+                let [hour, minute] = &mut appointment.at;
+                let hour = ui.add(egui::DragValue::new(hour).range(0..=23));
+                ui.label(":");
+                let minute = ui.add(egui::DragValue::new(minute).range(0..=59).custom_formatter(|m, _| format!("{:02}", m)));
+                changed |= [name, hour, minute].iter().any(|response| response.drag_stopped() || response.lost_focus());
+                if ui.small_button("🗑").clicked() {
+                    removed = Some(index);
+                }
+            });
+        }
+        if ui.button("Add appointment").clicked() {
+            self.settings.appointments.push(schedule::Appointment { name: "Stand-up".to_owned(), at: [10, 0] });
+            changed = true;
+        }
+        if let Some(index) = removed {
+            self.settings.appointments.remove(index);
+            changed = true;
+        }
+        if changed {
+            self.save_settings();
+        }
+    }
+
+    fn goal_settings(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let mut enabled = self.settings.daily_goal.is_some();
+            if ui.checkbox(&mut enabled, "Daily goal").changed() {
+                self.settings.daily_goal = enabled.then_some(8);
+                self.save_settings();
+            }
+            if let Some(goal) = &mut self.settings.daily_goal {
+                let response = ui.add(egui::DragValue::new(goal).range(1..=48).suffix(" pomodoros"));
+                if response.drag_stopped() || response.lost_focus() {
+                    self.save_settings();
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            let mut enabled = self.settings.weekly_goal.is_some();
+            if ui.checkbox(&mut enabled, "Weekly goal").changed() {
+                self.settings.weekly_goal = enabled.then_some(600);
+                self.save_settings();
+            }
+            if let Some(goal) = &mut self.settings.weekly_goal {
+                let response = ui.add(egui::DragValue::new(goal).range(30..=6000).speed(10).suffix(" min"));
+                if response.drag_stopped() || response.lost_focus() {
+                    self.save_settings();
+                }
+            }
+        });
+        if self.settings.weekly_goal.is_none() {
+            return;
+        }
+        ui.horizontal(|ui| {
+            ui.label("Workdays");
+            let mut changed = false;
+            for (day, name) in self.settings.workdays.iter_mut().zip(["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"]) {
+                changed |= ui.toggle_value(day, name).changed();
+            }
+            if changed {
+                self.save_settings();
+            }
+        });
+        ui.horizontal(|ui| {
+            let mut enabled = self.settings.carry_over_minutes.is_some();
+            if ui
+                .checkbox(&mut enabled, "Carry what a workday fell short into the next, up to")
+                .on_hover_text("Otherwise each workday asks for the same share of the weekly goal")
+                .changed()
+            {
+                self.settings.carry_over_minutes = enabled.then_some(60);
+                self.save_settings();
+            }
+            if let Some(cap) = &mut self.settings.carry_over_minutes {
+                let response = ui.add(egui::DragValue::new(cap).range(5..=480).suffix(" min"));
+                if response.drag_stopped() || response.lost_focus() {
+                    self.save_settings();
+                }
+            }
+        });
+    }
+
+    // Where today stands against the weekly goal, if there is one
+    fn pace(&self) -> Option<pomodoro_timer::pacing::Pace> {
+        let goal = self.settings.weekly_goal()?;
+        let today = self.clock.wall().date();
+        let week = self.history.peek()?.stats.week(stats::WeekId::of(today));
+        let minutes = week.map(|day| day.map_or(0, |day| (day.focus_secs / 60) as u32));
+        Some(pomodoro_timer::pacing::today(&goal, &minutes, today.weekday()))
+    }
+
+    fn hook_settings(&mut self, ui: &mut egui::Ui) {
+        ui.label("Hooks (shell commands run on timer events)");
+        let mut changed = false;
+        let mut removed = None;
+        for (index, hook) in self.settings.hooks.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                let trigger = egui::TextEdit::singleline(&mut hook.trigger).hint_text("focus_started").desired_width(160.0);
+                changed |= ui.add(trigger).lost_focus();
+                let command = egui::TextEdit::singleline(&mut hook.command).hint_text("Command");
+                changed |= ui.add(command).lost_focus();
+                if ui.button("Remove").clicked() {
+                    removed = Some(index);
+                }
+            });
+            if let Err(err) = hooks::Trigger::parse(&hook.trigger) {
+                ui.colored_label(egui::Color32::RED, err);
+            }
+        }
+        if let Some(index) = removed {
+            self.settings.hooks.remove(index);
+            changed = true;
+        }
+        ui.horizontal(|ui| {
+            let selected = &hooks::PRESETS[self.hook_preset];
+            egui::ComboBox::from_id_source("hook preset").selected_text(selected.name).show_ui(ui, |ui| {
+                for (index, preset) in hooks::PRESETS.iter().enumerate() {
+                    if ui.selectable_label(index == self.hook_preset, preset.name).clicked() {
+                        self.hook_preset = index;
+                        self.hook_params = preset_defaults(preset);
+                    }
+                }
+            });
+            let preset = &hooks::PRESETS[self.hook_preset];
+            for ((name, _), value) in preset.params.iter().zip(&mut self.hook_params) {
+                ui.add(egui::TextEdit::singleline(value).hint_text(*name).desired_width(80.0));
+            }
+            if ui.button("Add from template").clicked() {
+                self.settings.hooks.push(preset.fill(&self.hook_params));
+                changed = true;
+            }
+            if ui.button("Add empty").clicked() {
+                self.settings.hooks.push(hooks::Hook::default());
+            }
+        });
+        if changed {
+            self.save_settings();
+            subscribe_hooks(&mut self.events, &self.settings);
+        }
+    }
+
+    fn screenshot_settings(&mut self, ui: &mut egui::Ui) {
+        let mut enabled = self.settings.screenshots.is_some();
+        let mut changed = ui.checkbox(&mut enabled, "Take a screenshot at the end of each focus session").changed();
+        if changed {
+            self.settings.screenshots = enabled.then(screenshots::Screenshots::default);
+        }
+        match &mut self.settings.screenshots {
+            None => {
+                ui.weak("Off unless turned on. Screenshots show whatever is on screen, messages and passwords included.");
+            }
+            Some(config) => {
+                ui.colored_label(egui::Color32::from_rgb(220, 50, 47), "📷 On: each one shows whatever is on screen as the session ends");
+                ui.horizontal(|ui| {
+                    ui.label("Window");
+                    let window = egui::TextEdit::singleline(&mut config.window).hint_text("Part of the title; empty for the whole screen").desired_width(220.0);
+                    changed |= ui.add(window).lost_focus();
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Keep up to");
+                    let keep = ui.add(egui::DragValue::new(&mut config.keep_mb).range(10..=100_000).suffix(" MB"));
+                    changed |= keep.drag_stopped() || keep.lost_focus();
+                    ui.weak("the oldest are deleted first");
+                });
+                if let Some(dir) = screenshots::dir() {
+                    ui.weak(format!("Kept only on this computer, in {}", dir.display()));
+                }
+            }
+        }
+        if changed {
+            self.save_settings();
+        }
+    }
+
+    fn local_api_settings(&mut self, ui: &mut egui::Ui) {
+        let mut enabled = self.settings.local_api.is_some();
+        let mut changed = false;
+        if ui
+            .checkbox(&mut enabled, "Local API for browser extensions")
+            .on_hover_text("GET /focus and the event stream GET /events on 127.0.0.1, for extensions that block sites during focus")
+            .changed()
+        {
+            self.settings.local_api = enabled.then(settings::LocalApi::new);
+            changed = true;
+        }
+        if let Some(api) = &mut self.settings.local_api {
+            ui.horizontal(|ui| {
+                ui.label("Port");
+                let port = ui.add(egui::DragValue::new(&mut api.port).range(1024..=65535));
+                changed |= port.drag_stopped() || port.lost_focus();
+                ui.label("Token");
+                ui.monospace(&api.token);
+                if ui.small_button("Copy").clicked() {
+                    ui.ctx().copy_text(api.token.clone());
+                }
+                if ui.small_button("New token").on_hover_text("Extensions using the old one stop working").clicked() {
+                    api.token = settings::new_token();
+                    changed = true;
+                }
+            });
+            ui.label("Allowed extension origins, besides pages on localhost");
+            let mut removed = None;
+            for (index, origin) in api.origins.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    let edit = egui::TextEdit::singleline(origin).hint_text("chrome-extension://…").desired_width(260.0);
+                    changed |= ui.add(edit).lost_focus();
+                    if ui.button("Remove").clicked() {
+                        removed = Some(index);
+                    }
+                });
+            }
+            if let Some(index) = removed {
+                api.origins.remove(index);
+                changed = true;
+            }
+            if ui.button("Add origin").clicked() {
+                api.origins.push(String::new());
+            }
+        }
+        if let Some(error) = &self.local_api_error {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+        if changed {
+            self.save_settings();
+            self.sync_local_api();
+        }
+    }
+
+    fn webhook_settings(&mut self, ui: &mut egui::Ui) {
+        ui.label("Webhooks (timer events sent as JSON)").on_hover_text(format!(
+            "Each request carries {} with the HMAC-SHA256 of the body, keyed with the secret, so the receiver can check it came from here",
+            webhooks::SIGNATURE_HEADER
+        ));
+        let mut changed = false;
+        let mut removed = None;
+        for (index, webhook) in self.settings.webhooks.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                let url = egui::TextEdit::singleline(&mut webhook.url).hint_text("https://…").desired_width(220.0);
+                changed |= ui.add(url).lost_focus();
+                let secret = egui::TextEdit::singleline(&mut webhook.secret).hint_text("Secret").password(true).desired_width(120.0);
+                changed |= ui.add(secret).lost_focus();
+                if ui.button("Remove").clicked() {
+                    removed = Some(index);
+                }
+            });
+        }
+        if let Some(index) = removed {
+            self.settings.webhooks.remove(index);
+            changed = true;
+        }
+        if ui.button("Add webhook").clicked() {
+            self.settings.webhooks.push(webhooks::Webhook::default());
+        }
+        if changed {
+            self.save_settings();
+            subscribe_webhooks(&mut self.events, &self.settings, &self.webhooks);
+        }
+        if ui.checkbox(&mut self.settings.work_offline, "Work offline").on_hover_text("Nothing is sent until this is off again; webhook deliveries wait").changed() {
+            self.save_settings();
+            subscribe_webhooks(&mut self.events, &self.settings, &self.webhooks);
+        }
+        let (log, waiting) = self.webhooks.log();
+        if log.is_empty() && waiting == 0 {
+            return;
+        }
+        ui.collapsing(format!("Delivery log (last {})", webhooks::LOG_LENGTH), |ui| {
+            if waiting > 0 {
+                ui.weak(format!("{} waiting to be retried", waiting));
+            }
+            egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                egui::Grid::new("delivery log").striped(true).show(ui, |ui| {
+                    for delivery in &log {
+                        ui.label(delivery.at.format(time::macros::format_description!("[hour]:[minute]:[second]")).unwrap_or_default())
+                            .on_hover_text(delivery.at.date().to_string());
+                        ui.label(&delivery.url).on_hover_text(&delivery.payload);
+                        let status = delivery.status.map_or("—".to_owned(), |status| status.to_string());
+                        if delivery.succeeded() {
+                            ui.label(status);
+                        } else {
+                            ui.colored_label(egui::Color32::RED, status);
+                        }
+                        ui.label(format!("{} ms", delivery.latency_ms));
+                        let response = delivery.response.lines().next().unwrap_or_default();
+                        ui.weak(response).on_hover_text(format!("Attempt {}\n{}", delivery.attempt, delivery.response));
+                        if ui.small_button("Retry").clicked() {
+                            self.webhooks.retry(delivery);
+                        }
+                        ui.end_row();
+                    }
+                });
+            });
+        });
+    }
+
+    fn preset_settings(&mut self, ui: &mut egui::Ui) {
+        ui.label("Presets (durations, and sound that differs from the settings below)");
+        let global = timer_presets::global(&self.settings);
+        let active = self.settings.active_preset.clone();
+        let mut changed = false;
+        let mut removed = None;
+        for (index, preset) in self.settings.presets.iter_mut().enumerate() {
+            let old_name = preset.name.clone();
+            let edit = timer_presets::editor(ui, preset, &global);
+            changed |= edit.changed;
+            if edit.removed {
+                removed = Some(index);
+            }
+            if edit.changed && active.as_ref() == Some(&old_name) && preset.name != old_name {
+                self.settings.active_preset = Some(preset.name.clone());
+            }
+        }
+        if let Some(index) = removed {
+            let preset = self.settings.presets.remove(index);
+            if active == Some(preset.name) {
+                self.settings.active_preset = None;
+            }
+            changed = true;
+        }
+        if ui.button("Add preset").clicked() {
+            self.settings.presets.push(timer_presets::TimerPreset::default());
+            changed = true;
+        }
+        let calibrate = if self.calibration.is_some() { "Calibration…" } else { "Calibrate the focus length…" };
+        let hint = "For a week, focus sessions take turns at 15, 20, 25 and 35 minutes and you rate each one";
+        if ui.button(calibrate).on_hover_text(hint).clicked() {
+            if self.calibration.is_none() {
+                let previous = self.settings.active_preset.clone();
+                self.calibration = Some(calibrate::Calibration::start(self.clock.wall().date(), previous));
+                self.apply_durations();
+            }
+            self.show_calibration = true;
+        }
+        if changed {
+            self.save_settings();
+            self.apply_durations();
+        }
+    }
+
+    fn preset_picker(&mut self, ui: &mut egui::Ui) {
+        if self.settings.presets.is_empty() {
+            return;
+        }
+        let mut picked = None;
+        // The global lengths, whatever they were set to
+        let default = format!("Default ({}/{})", self.settings.work_minutes, self.settings.break_minutes);
+        let current = self.settings.active_preset().map_or(default.as_str(), |preset| preset.name.as_str());
+        ui.horizontal(|ui| {
+            compact_text(ui.style_mut());
+            egui::ComboBox::from_id_source("preset").selected_text(current).show_ui(ui, |ui| {
+                if ui.selectable_label(self.settings.active_preset.is_none(), &default).clicked() {
+                    picked = Some(None);
+                }
+                for preset in &self.settings.presets {
+                    let selected = self.settings.active_preset.as_ref() == Some(&preset.name);
+                    if ui.selectable_label(selected, &preset.name).clicked() {
+                        picked = Some(Some(preset.name.clone()));
+                    }
+                }
+            });
+        });
+        if let Some(name) = picked {
+            self.activate_preset(name);
+        }
+    }
+
+    fn calibration_status(&mut self, ui: &mut egui::Ui) {
+        let Some(calibration) = &self.calibration else {
+            return;
+        };
+        let text = match calibration.next_minutes(self.clock.wall().date()) {
+            Some(minutes) => format!("Calibrating: {} minute sessions next", minutes),
+            None => "Calibration done, see what suits you".to_owned(),
+        };
+        if ui.link(egui::RichText::new(text).small()).clicked() {
+            self.show_calibration = true;
+        }
+    }
+
+    fn calibration_window(&mut self, ctx: &egui::Context) {
+        let Some(calibration) = &self.calibration else {
+            return;
+        };
+        let mut open = self.show_calibration;
+        let mut choice = None;
+        egui::Window::new("Calibrate the focus length").open(&mut open).resizable(false).collapsible(false).show(ctx, |ui| {
+            compact_text(ui.style_mut());
+            choice = calibration.show(ui, self.clock.wall().date());
+        });
+        self.show_calibration = open;
+        let Some(choice) = choice else {
+            return;
+        };
+        let previous = calibration.previous_preset.clone();
+        calibrate::Calibration::remove();
+        self.calibration = None;
+        self.show_calibration = false;
+        match choice {
+            calibrate::Choice::Apply(minutes) => {
+                // Keeps the break and sound of the preset used before
+                let mut preset = self.settings.active_preset().cloned().unwrap_or_default();
+                preset.name = CALIBRATED.to_owned();
+                preset.work_minutes = minutes;
+                preset.break_minutes = (self.settings.durations().short_break.as_secs() / 60) as u32;
+                self.settings.presets.retain(|existing| existing.name != CALIBRATED);
+                self.settings.presets.push(preset);
+                self.activate_preset(Some(CALIBRATED.to_owned()));
+                self.toasts.push(format!("Focus sessions are {} minutes from now on", minutes));
+            }
+            calibrate::Choice::Abandon => {
+                let previous = previous.filter(|name| self.settings.presets.iter().any(|preset| preset.name == *name));
+                self.activate_preset(previous);
+            }
+        }
+    }
+
+    fn status_file_settings(&mut self, ui: &mut egui::Ui) {
+        if ui.checkbox(&mut self.settings.status_files, "Write status files for scripts and widgets").changed() {
+            self.save_settings();
+        }
+        if let (true, Some(dir)) = (self.settings.status_files, paths::data_dir()) {
+            ui.weak(format!("{} and {} in {}", ipc::STATUS_FILE, ipc::SUMMARY_FILE, dir.display()));
+        }
+    }
+
+    fn recording_settings(&mut self, ui: &mut egui::Ui) {
+        if ui.checkbox(&mut self.settings.debug_recording, "Record timer events for bug reports").changed() {
+            self.save_settings();
+            subscribe_recorder(&mut self.events, &self.settings);
+        }
+        if let (true, Some(dir)) = (self.settings.debug_recording, paths::data_dir()) {
+            ui.weak(format!("Attach {} to your report", dir.join(recording::FILE_NAME).display()));
+        }
+        if ui
+            .checkbox(&mut self.settings.developer_panel, "Show the developer panel in the Help menu")
+            .on_hover_text("Self-tests and benchmarks to quote in bug reports")
+            .changed()
+        {
+            self.save_settings();
+        }
+        let mut json = self.settings.log_format == logging::Format::Json;
+        let hint = "One JSON object per line with timestamp, level, target, message and fields, for jq or a log collector; --log-format overrides this";
+        if ui.checkbox(&mut json, "Write the log as JSON lines").on_hover_text(hint).changed() {
+            self.settings.log_format = if json { logging::Format::Json } else { logging::Format::Text };
+            logging::follow_setting(self.settings.log_format);
+            self.save_settings();
+        }
+    }
+
+    fn history_settings(&mut self, ui: &mut egui::Ui) {
+        ui.strong("History");
+        ui.horizontal(|ui| {
+            let mut forever = self.settings.keep_sessions_months.is_none();
+            if ui.checkbox(&mut forever, "Keep individual sessions forever").changed() {
+                self.settings.keep_sessions_months = if forever { None } else { Some(12) };
+                self.save_settings();
+            }
+            if let Some(months) = &mut self.settings.keep_sessions_months {
+                let response = ui.add(egui::DragValue::new(months).range(1..=120).suffix(" months"));
+                if response.drag_stopped() || response.lost_focus() {
+                    self.save_settings();
+                }
+            }
+        });
+        ui.label("Older sessions are reduced to daily totals, which is all the statistics need.");
+        ui.horizontal(|ui| {
+            ui.label("Sessions from other time zones count toward");
+            let mut zone = self.settings.day_zone;
+            egui::ComboBox::from_id_source("day zone")
+                .selected_text(match zone {
+                    DayZone::Recorded => "the local day then",
+                    DayZone::Current => "the day here",
+                })
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut zone, DayZone::Recorded, "the local day then");
+                    ui.selectable_value(&mut zone, DayZone::Current, "the day here");
+                });
+            if zone != self.settings.day_zone {
+                self.settings.day_zone = zone;
+                self.save_settings();
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Focus time counts");
+            let mut counting = self.settings.focus_counting;
+            egui::ComboBox::from_id_source("focus counting").selected_text(counting.label()).show_ui(ui, |ui| {
+                for choice in [Counting::Focused, Counting::WallClock] {
+                    ui.selectable_value(&mut counting, choice, choice.label());
+                }
+            });
+            if counting != self.settings.focus_counting {
+                self.settings.focus_counting = counting;
+                self.save_settings();
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Daily backups to keep");
+            let response = ui.add(egui::DragValue::new(&mut self.settings.keep_backups).range(0..=90));
+            if response.drag_stopped() || response.lost_focus() {
+                self.save_settings();
+            }
+            if ui.button("Restore from backup…").clicked() {
+                self.backups = backup::list();
+                self.restore_confirm = None;
+                self.show_backups = true;
+            }
+        });
+        let checking = self.history_check.as_ref().is_some_and(|check| check.running());
+        if ui.add_enabled(!checking, egui::Button::new("Check history")).clicked() {
+            self.history_check = Some(history_check::Check::start());
+        }
+        if self.history_check.as_mut().is_some_and(|check| check.show(ui)) {
+            self.backups = backup::list();
+            self.restore_confirm = Some(0).filter(|_| !self.backups.is_empty());
+            self.show_backups = true;
+        }
+        if self.history_check.as_ref().is_some_and(|check| check.running()) {
+            self.repaint.animate("history check", repaint::PROGRESS, Instant::now() + repaint::PROGRESS * 2);
+        }
+
+        if self.privacy {
+            ui.weak("Statistics are hidden in privacy mode");
+            return;
+        }
+        let Some(history) = self.history.get() else {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Loading history…");
+            });
+            return;
+        };
+        let today = history.stats.day(self.clock.wall().date());
+        ui.label(format!("Today: {} sessions, {} min", today.sessions, today.focus_secs / 60));
+        let days = history.stats.days();
+        let focus_hours = days.values().map(|day| day.focus_secs).sum::<u64>() as f64 / 3600.0;
+        ui.label(format!("Focus recorded on {} days, {:.1} hours in total", days.len(), focus_hours));
+        let (sessions_size, daily_size) = history::file_sizes();
+        ui.label(format!(
+            "{} sessions ({}), {} compacted days ({})",
+            history.sessions.len(),
+            format_size(sessions_size),
+            history.compacted.days.len(),
+            format_size(daily_size),
+        ));
+        let compact = ui.add_enabled(self.settings.keep_sessions_months.is_some(), egui::Button::new("Compact now"));
+        if let (true, Some(months)) = (compact.clicked(), self.settings.keep_sessions_months) {
+            let count = compact_history(history, months);
+            self.toasts.push(format!("Compacted {} sessions", count));
+        }
+    }
+
+    fn standup_settings(&mut self, ui: &mut egui::Ui) {
+        ui.strong("Standup report");
+        ui.horizontal(|ui| {
+            ui.label("\"Copy for standup\" reports");
+            let mut range = self.settings.standup.range;
+            egui::ComboBox::from_id_source("standup range").selected_text(range.label().to_lowercase()).show_ui(ui, |ui| {
+                for option in standup::Range::ALL {
+                    ui.selectable_value(&mut range, option, option.label().to_lowercase());
+                }
+            });
+            if range != self.settings.standup.range {
+                self.settings.standup.range = range;
+                self.save_settings();
+            }
+            if ui.checkbox(&mut self.settings.standup.markdown, "as Markdown").changed() {
+                self.save_settings();
+            }
+        });
+        ui.label(format!("Template (placeholders: {})", standup::FIELDS.map(|f| format!("{{{}}}", f)).join(" ")));
+        let edit = ui.add(egui::TextEdit::multiline(&mut self.settings.standup.template).desired_rows(3));
+        if edit.lost_focus() {
+            self.save_settings();
+        }
+        if let Err(err) = self.settings.standup.parse() {
+            ui.colored_label(egui::Color32::RED, format!("{}; the default is used meanwhile", err));
+        }
+        if let Some(report) = self.standup_report() {
+            ui.weak(report);
+        }
+        if self.privacy {
+            ui.weak("Tasks, notes and statistics are left out in privacy mode");
+        }
+    }
+
+    /// The standup report of the day picked in the settings; None while the history loads.
+    fn standup_report(&mut self) -> Option<String> {
+        let config = &self.settings.standup;
+        let date = config.range.date(self.clock.wall().date(), &self.settings.workdays);
+        let history = self.history.get()?;
+        let done = |task: &str| self.plan.tasks.iter().any(|planned| planned.done && planned.name == task);
+        Some(config.report(date, &history.sessions, &history.stats, done, history.notes.get(&date), self.privacy))
+    }
+
+    /// Saves a change made in the settings window as one undo step.
+    fn save_settings(&mut self) {
+        self.save_settings_from(Source::App);
+    }
+
+    fn save_settings_from(&mut self, source: Source) {
+        if self.kiosk.is_some() {
+            // Kiosk runs on a preset picked on the command line, not the user's own choice
+            return;
+        }
+        backup::set_keep(self.settings.keep_backups);
+        self.settings.save();
+        self.settings_undo.record(&self.settings);
+        self.settings_journal.record(&self.settings, source, self.clock.wall());
+    }
+
+    /// Switches to other settings as a whole, redoing what changing each of them by hand would
+    /// have done on the side.
+    fn apply_settings(&mut self, settings: Settings, source: Source) {
+        let old = std::mem::replace(&mut self.settings, settings);
+        self.save_settings_from(source);
+        if self.settings.alarm_file != old.alarm_file {
+            self.alarm_file_input = self.settings.alarm_file.as_ref().map(|p| p.display().to_string()).unwrap_or_default();
+            self.alarm_file_error = None;
+        }
+        if self.settings.durations() != old.durations() {
+            self.apply_durations();
+        }
+        self.title_template_input = self.settings.title_template.clone().unwrap_or_default();
+        self.title_template_error = None;
+        if self.settings.debug_recording != old.debug_recording || self.settings.record_unredacted != old.record_unredacted {
+            subscribe_recorder(&mut self.events, &self.settings);
+        }
+        if self.settings.log_format != old.log_format {
+            logging::follow_setting(self.settings.log_format);
+        }
+        if self.settings.hooks != old.hooks {
+            subscribe_hooks(&mut self.events, &self.settings);
+        }
+        if self.settings.webhooks != old.webhooks || self.settings.work_offline != old.work_offline {
+            subscribe_webhooks(&mut self.events, &self.settings, &self.webhooks);
+        }
+        if self.settings.local_api != old.local_api {
+            self.sync_local_api();
+        }
+    }
+
+    /// Starts, stops or reconfigures the local API to match the settings; a new port means a
+    /// new server.
+    fn sync_local_api(&mut self) {
+        let Some(api) = &self.settings.local_api else {
+            self.local_api = None;
+            self.local_api_error = None;
+            return;
+        };
+        let config = local_api::Config { token: api.token.clone(), origins: api.origins.clone() };
+        if let Some(server) = self.local_api.as_ref().filter(|server| server.addr().port() == api.port) {
+            server.configure(config);
+            return;
+        }
+        // Lets go of the old port before taking the new one
+        self.local_api = None;
+        match local_api::Server::start(api.port, config) {
+            Ok(server) => {
+                self.local_api = Some(server);
+                self.local_api_error = None;
+            }
+            Err(err) => {
+                log::warn!("Cannot start the local API on port {}: {}", api.port, err);
+                self.local_api_error = Some(format!("Cannot listen on port {}: {}", api.port, err));
+            }
+        }
+    }
+
+    fn undo_settings(&mut self, ctx: &egui::Context) {
+        let undo = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Z);
+        let redo = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, egui::Key::Z);
+        // Text fields have their own undo
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        // Redo first, as the undo shortcut also matches with Shift held
+        let settings = if ctx.input_mut(|i| i.consume_shortcut(&redo)) {
+            self.settings_undo.redo()
+        } else if ctx.input_mut(|i| i.consume_shortcut(&undo)) {
+            self.settings_undo.undo()
+        } else {
+            return;
+        };
+        match settings {
+            Some(settings) => self.apply_settings(settings, Source::Undo),
+            None => self.toasts.push("Nothing to undo"),
+        }
+    }
+
+    fn restore_defaults_button(&mut self, ui: &mut egui::Ui, section: settings::Section) {
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
+            if ui.small_button("Restore defaults").clicked() {
+                let mut settings = self.settings.clone();
+                settings.restore_defaults(section);
+                self.apply_settings(settings, Source::Defaults);
+            }
+        });
+    }
+
+    fn apply_alarm_file(&mut self) {
+        let input = self.alarm_file_input.trim();
+        let path = (!input.is_empty()).then(|| PathBuf::from(input));
+        match self.audio().set_alarm_file(path.as_deref()) {
+            Ok(()) => {
+                self.alarm_file_error = None;
+                self.settings.alarm_file = path;
+                self.save_settings();
+                // A preset may override the file just loaded
+                self.sound = None;
+            }
+            Err(err) => self.alarm_file_error = Some(format!("Cannot use this sound: {}", err)),
+        }
+    }
+
+    // The monotonic clock stops while the machine is suspended (on Linux), the wall clock doesn't.
+    // Returns how long we were asleep since the last frame, after crediting that time to the
+    // running interval so it ends when it would have on the wall clock. A wall clock that was
+    // set forward instead (`jumped`) gives no one time off.
+    fn detect_sleep(&mut self, jumped: bool) -> Option<Duration> {
+        let now = (self.clock.instant(), self.clock.wall());
+        let last = std::mem::replace(&mut self.last_frame, now);
+        let missed = asleep(last, now).filter(|_| !jumped)?;
+        self.timer.credit(missed);
+        log::info!("Woke up after {} s asleep", missed.as_secs());
+        self.events.publish(Event::WokeUp { asleep_secs: missed.as_secs() });
+        Some(missed)
+    }
+
+    // Quick timers that ran out alert on their own, with nothing recorded and the cycle untouched
+    fn quick_timers(&mut self, ctx: &egui::Context) {
+        for timer in self.quick.ended(SystemTime::from(self.clock.wall())) {
+            log::info!("Quick timer ended");
+            self.toasts.push(format!("⏲ {}", timer.text()));
+            self.alert(ctx, Alert::QuickTimer);
+        }
+    }
+
+    // One soft word when the user comes back to an interval that ended while they were away and
+    // still waits to be acknowledged, instead of the stale end just sitting there
+    fn greet_return(&mut self, ctx: &egui::Context) {
+        let waiting = self.timer_ended && self.timer.state() == State::Idle && self.nap.is_none();
+        let ended_at = self.ended_at.filter(|_| waiting && self.settings.greet_return);
+        self.away.set_enabled(ended_at.is_some());
+        let (Some(ended_at), Some(away_since)) = (ended_at, self.away.returned()) else {
+            return;
+        };
+        // The end is on the app's clock and the absence on the real one, so they meet as spans
+        let ago = self.clock.instant().saturating_duration_since(ended_at);
+        if ago > away_since.elapsed() {
+            return;
+        }
+        self.ended_at = None;
+        let ago = ago.as_secs() / 60;
+        let ago = if ago == 0 { "just now".to_owned() } else { format!("{} min ago", ago) };
+        log::info!("Back after an interval ended {}", ago);
+        let text = if self.work_period() {
+            format!("Your break ended {} while you were away — ready to focus?", ago)
+        } else {
+            format!("Your focus session ended {} while you were away — ready for the next one?", ago)
+        };
+        self.toasts.push(text);
+        if self.external.active().is_none() {
+            self.audio().play(Role::Cue, alerts::Sound::Blip, Shape::default());
+        }
+        ctx.send_viewport_cmd(egui::ViewportCommand::RequestUserAttention(egui::UserAttentionType::Informational));
+    }
+
+    // Says why time-of-day automations hold off after the wall clock jumped; true if it just did
+    fn check_clock(&mut self) -> bool {
+        let Some(jumped) = self.clock_check.observe(clock_check::Reading::of(self.clock.as_ref())) else {
+            return false;
+        };
+        log::warn!(jumped; "The wall clock jumped");
+        self.toasts.push(format!(
+            "The clock jumped {}, so the automatic theme, the late-night prompt and the appointment check wait until it keeps steady",
+            clock_check::describe(jumped)
+        ));
+        true
+    }
+
+    // While time-of-day automations hold off, with a way to vouch for the clock
+    fn clock_warning(&mut self, ui: &mut egui::Ui) {
+        if !self.clock_check.suspended(self.clock.instant()) {
+            return;
+        }
+        ui.horizontal(|ui| {
+            compact_text(ui.style_mut());
+            ui.colored_label(ui.visuals().warn_fg_color, "⚠ The clock jumped, time-of-day features wait");
+            if ui.small_button("The clock is right").clicked() {
+                self.clock_check.trust();
+            }
+        });
+        // Shown again as soon as it is steady long enough
+        self.repaint.animate("clock check", Duration::from_secs(10), Instant::now() + Duration::from_secs(10));
+    }
+
+    fn tip_card(&mut self, ui: &mut egui::Ui) {
+        let Some(tip) = &self.tip else {
+            return;
+        };
+        let mut dismissed = false;
+        egui::Frame::group(ui.style()).show(ui, |ui| {
+            ui.set_max_width(300.0);
+            compact_text(ui.style_mut());
+            ui.label(format!("💡 {}", tip));
+            dismissed = ui.small_button("Got it").clicked();
+        });
+        if dismissed {
+            self.tip = None;
+        }
+    }
+
+    // Where the current cycle stands, e.g. "Pomodoro 3 of 4 ●●○○", and today's totals
+    fn day_progress(&self, ui: &mut egui::Ui) {
+        if self.settings.long_break_minutes.is_some() {
+            let done = self.cycle_sessions.min(CYCLE_LENGTH);
+            let current = if self.work_period() { (done + 1).min(CYCLE_LENGTH) } else { done.max(1) };
+            let dots: String = (0..CYCLE_LENGTH).map(|n| if n < done { '●' } else { '○' }).collect();
+            ui.weak(format!("Pomodoro {} of {}  {}", current, CYCLE_LENGTH, dots));
+        }
+        if let (false, Some(history)) = (self.privacy, self.history.peek()) {
+            let today = history.stats.day(self.clock.wall().date());
+            ui.weak(format!("Today: {} pomodoros / {} min", today.sessions, today.focus_secs / 60));
+        }
+        if let (false, Some(pace)) = (self.privacy, self.pace()) {
+            let work_minutes = (self.timer.durations().work.as_secs() / 60) as u32;
+            let text = match (pace.target, pace.needed) {
+                (0, _) => "A day off from the weekly goal".to_owned(),
+                (_, 0) => "On track for the week today".to_owned(),
+                (_, needed) => format!("To stay on track today you need ~{} pomodoros ({} min)", pace.pomodoros(work_minutes), needed),
+            };
+            let tip = match pace.carried {
+                0 => format!("{} min of today's {} min are done", pace.target - pace.needed, pace.target),
+                carried => format!("{} min of today's {} min are done, {} min carried over from earlier this week", pace.target - pace.needed, pace.target, carried),
+            };
+            ui.weak(text).on_hover_text(tip);
+        }
+    }
+
+    fn plan_view(&mut self, ui: &mut egui::Ui) {
+        let now = self.clock.wall();
+        let remaining = self.plan.remaining();
+        let forecast = (remaining > 0).then(|| {
+            let summary = self.history.peek().map(|history| &*summary::of(&mut self.summary, history));
+            let past = summary.map_or(&[][..], |summary| &summary.past[..]);
+            let durations = self.timer.durations();
+            pomodoro_timer::forecast::finish_time(past, remaining, durations.work + durations.short_break, now)
+        });
+        let edit = plan::show(ui, &mut self.plan, now, self.include_archived, forecast);
+        if self.plan.tasks.iter().any(|task| task.archived) {
+            ui.checkbox(&mut self.include_archived, "Show archived tasks");
+        }
+        if edit.changed {
+            self.plan.save();
+        }
+        if let Some(task) = edit.picked {
+            self.task = task;
+        }
+    }
+
+    // Open tasks and recent labels matching what is typed, while the task field has focus;
+    // archived ones are left out. While a #tag is being typed, tags used before instead.
+    fn task_suggestions(&mut self, ui: &mut egui::Ui, field: &egui::Response) {
+        let popup = egui::Id::new("task suggestions");
+        if field.gained_focus() || field.changed() {
+            ui.memory_mut(|memory| memory.open_popup(popup));
+        }
+        let summary = self.history.peek().map(|history| &*summary::of(&mut self.summary, history));
+        let (known_tags, labels) = summary.map_or((&[][..], &[][..]), |summary| (&summary.tags[..], &summary.labels[..]));
+        let typed = tags::parse(&self.task);
+        let word_start = self.task.char_indices().rev().find(|(_, c)| c.is_whitespace()).map_or(0, |(i, c)| i + c.len_utf8());
+        let (stem, word) = self.task.split_at(word_start);
+        // What to show, and the text of the field once picked
+        let mut names: Vec<(String, String)> = Vec::new();
+        if let Some(prefix) = word.strip_prefix('#') {
+            let prefix = prefix.trim_start_matches('#').to_lowercase();
+            let taken = tags::parse(stem).tags;
+            for tag in known_tags.iter().filter(|tag| tag.starts_with(&prefix) && !taken.contains(*tag)).take(8) {
+                names.push((format!("#{}", tag), format!("{}#{} ", stem, tag)));
+            }
+        } else {
+            let wanted = typed.label.to_lowercase();
+            let tagged: String = typed.tags.iter().map(|tag| format!(" #{}", tag)).collect();
+            let candidates = self.plan.open_tasks().map(str::to_owned).chain(self.archive.recent_labels(labels, 10));
+            for name in candidates {
+                let matches = name.to_lowercase().contains(&wanted) && name != typed.label;
+                if matches && !self.archive.contains(&name) && !names.iter().any(|(known, _)| *known == name) && names.len() < 8 {
+                    let text = format!("{}{}", name, tagged);
+                    names.push((name, text));
+                }
+            }
+        }
+        if self.privacy || names.is_empty() {
+            if ui.memory(|memory| memory.is_popup_open(popup)) {
+                ui.memory_mut(|memory| memory.close_popup());
+            }
+            return;
+        }
+        let mut picked = None;
+        egui::popup_below_widget(ui, popup, field, egui::PopupCloseBehavior::CloseOnClickOutside, |ui| {
+            compact_text(ui.style_mut());
+            for (name, text) in names {
+                if ui.selectable_label(false, &name).clicked() {
+                    picked = Some(text);
+                }
+            }
+        });
+        if let Some(text) = picked {
+            self.task = text;
+            ui.memory_mut(|memory| memory.close_popup());
+        }
+    }
+
+    fn timer_panel(&mut self, ctx: &egui::Context) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let layout = Layout::of(ui.available_size());
+            // The last resort, for a window too small even for the compact layout
+            egui::ScrollArea::vertical().auto_shrink(false).show(ui, |ui| ui.vertical_centered(|ui| {
+                ui.add_space(layout.gap());
+                if layout.secondary() {
+                    self.warm_start_card(ctx, ui);
+                }
+                self.quick.show(ui, SystemTime::from(self.clock.wall()));
+
+                // Timer display
+                let remaining = self.remaining();
+                let (minutes, seconds) = (remaining.as_secs() / 60, remaining.as_secs() % 60);
+
+                let paused = self.timer.paused_for(self.clock.instant());
+                let heading = match paused {
+                    Some(_) => ui.heading(egui::RichText::new(format!("⏸ {:02}:{:02}", minutes, seconds)).weak()),
+                    None => ui.heading(format!("{:02}:{:02}", minutes, seconds)),
+                };
+                if let Some(paused) = paused {
+                    ui.weak(paused_text(paused));
+                }
+                if self.settings.show_mascot && !layout.narrow {
+                    let mood = if self.work_period() { Mood::Working } else { Mood::Napping };
+                    let steady = self.settings.reduce_motion || !self.timer.is_running() || self.saving_power();
+                    self.mascot.paint(ui, heading.rect, mood, steady);
+                    if !steady {
+                        self.repaint.animate("mascot", mascot::FRAME_TIME, Instant::now() + mascot::FRAME_TIME * 2);
+                    }
+                }
+                if self.timer.phase() == Phase::GetReady {
+                    ui.label(format!("Focus starts in {}:{:02} — close your tabs", minutes, seconds));
+                }
+                if let Some(intent) = self.intent.as_deref().filter(|intent| self.timer.is_running() && !intent.is_empty()) {
+                    ui.weak(if self.privacy { PRIVATE } else { intent });
+                }
+                let task = egui::TextEdit::singleline(&mut self.task)
+                    .hint_text("What are you working on?")
+                    .password(self.privacy)
+                    .font(egui::FontId::proportional(18.0))
+                    .desired_width(layout.width(ui));
+                let task = hints::explain(ui.add(task), self.hints.get(hints::Control::Task));
+                self.task_suggestions(ui, &task);
+                if layout.secondary() {
+                    self.preset_picker(ui);
+                    self.calibration_status(ui);
+                }
+                self.clock_warning(ui);
+                if layout.secondary() {
+                    self.tip_card(ui);
+                }
+                ui.add_space(layout.gap());
+
+                // Start/Pause button
+                let start_pause = match self.timer.state() {
+                    State::Running { .. } => "Pause",
+                    State::Paused { .. } => "Resume",
+                    State::Idle => "Start",
+                };
+                let idle = self.timer.state() == State::Idle && self.work_period() && self.enforcement.is_none();
+                if layout.row() {
+                    // Skip takes Reset's place, which is left to the menu
+                    let controls = [(hints::Control::StartPause, Action::StartPause), (hints::Control::Skip, Action::Skip)];
+                    for (response, (control, action)) in layout::row(ui, &[start_pause, "Skip"]).into_iter().zip(controls) {
+                        if hints::explain(response, self.hints.get(control)).clicked() {
+                            self.perform(ctx, action);
+                        }
+                    }
+                } else if hints::explain(ui.button(start_pause), self.hints.get(hints::Control::StartPause)).clicked() {
+                    self.perform(ctx, Action::StartPause);
+                }
+                if idle && layout.secondary() && hints::explain(ui.button("Easy start"), self.hints.get(hints::Control::EasyStart)).clicked() {
+                    self.easy_start();
+                }
+
+                if !layout.row() {
+                    ui.add_space(10.0);
+
+                    // Reset button
+                    if hints::explain(ui.button("Reset"), self.hints.get(hints::Control::Reset)).clicked() {
+                        self.perform(ctx, Action::Reset);
+                    }
+                }
+
+                ui.add_space(layout.gap());
+
+                // Display a progress bar
+                let progress = self.timer.progress(self.clock.instant());
+                match paused {
+                    Some(_) => {
+                        let gray = ui.visuals().widgets.inactive.bg_fill;
+                        let bar = ui.add(egui::ProgressBar::new(progress).desired_width(layout.width(ui)).fill(gray));
+                        hatch(ui.painter(), bar.rect, ui.visuals().weak_text_color());
+                    }
+                    None => {
+                        ui.add(egui::ProgressBar::new(progress).desired_width(layout.width(ui)));
+                    }
+                }
+                if layout.secondary() {
+                    self.day_progress(ui);
+                }
+                if !self.privacy && layout.secondary() {
+                    ui.collapsing("Plan for today", |ui| self.plan_view(ui));
+                }
+
+                if self.timer_ended {
+                    ui.colored_label(egui::Color32::RED, "Timer Ended");
+                }
+
+                if self.timer.phase() == Phase::Break && self.settings.show_quotes && layout.secondary() {
+                    ui.add_space(10.0);
+                    self.quote_view(ui);
+                }
+                if self.timer.phase() == Phase::Break && !self.stretches.is_empty() && layout.secondary() {
+                    ui.add_space(10.0);
+                    self.stretch_view(ui);
+                }
+                #[cfg(feature = "games")]
+                if self.timer.phase() == Phase::Break && self.settings.break_game && layout.secondary() {
+                    ui.add_space(10.0);
+                    self.breathing_view(ui);
+                }
+                if self.timer.phase() == Phase::Break && self.enforcement.is_none() && self.nap.is_none() {
+                    ui.add_space(10.0);
+                    let mut picked = None;
+                    ui.menu_button("😴 Nap", |ui| {
+                        compact_text(ui.style_mut());
+                        for minutes in nap::MINUTES {
+                            if ui.button(format!("{} minutes", minutes)).clicked() {
+                                picked = Some(minutes);
+                                ui.close_menu();
+                            }
+                        }
+                    })
+                    .response
+                    .on_hover_text("Instead of the rest of the break; focus waits until you confirm you are awake");
+                    if let Some(minutes) = picked {
+                        self.start_nap(minutes);
+                    }
+                }
+
+                if self.privacy {
+                    ui.add_space(10.0);
+                    ui.label(egui::RichText::new("🔒 Privacy mode").weak().size(16.0))
+                        .on_hover_text("Task names and statistics are hidden, but still recorded");
+                }
+                if let Some(offline) = self.offline_text().filter(|_| layout.secondary()) {
+                    ui.add_space(10.0);
+                    ui.label(egui::RichText::new(offline).weak().size(16.0))
+                        .on_hover_text("Webhook deliveries wait and go out once the network is back");
+                }
+                if self.settings.screenshots.is_some() && layout.secondary() {
+                    ui.add_space(10.0);
+                    ui.label(egui::RichText::new("📷 Screenshots on").weak().size(16.0))
+                        .on_hover_text("A screenshot is taken as each focus session ends; turn it off in Settings → Hooks");
+                }
+                if self.saving_power() && self.settings.power_saving_icon && layout.secondary() {
+                    ui.add_space(10.0);
+                    ui.label(egui::RichText::new("🔋 Saving power").weak().size(16.0))
+                        .on_hover_text("The battery is low, so the timer redraws less often and animations and ambient sound are off");
+                }
+
+                ui.add_space(layout.gap());
+            }));
+        });
+    }
+
+    /// Ends the interval once it has run out: records and announces it, then moves on to the
+    /// next one, which starts right away only if the user wants it to. On waking up, every
+    /// interval the time asleep ran through ends in turn, recorded as while away, and only
+    /// where that left the timer is announced.
+    fn finish_period(&mut self, ctx: &egui::Context, woke_up: bool) {
+        if !woke_up {
+            self.end_period(ctx, false);
+            return;
+        }
+        let mut ended = Vec::new();
+        while let Some(finished) = self.end_period(ctx, true) {
+            ended.push(finished);
+            // An interval with no length would end again and again
+            if finished.1.is_zero() {
+                break;
+            }
+        }
+        let intervals: Vec<Phase> = ended.iter().map(|(phase, _)| *phase).filter(|phase| *phase != Phase::GetReady).collect();
+        let Some(&(last, late)) = ended.last() else {
+            return;
+        };
+        // Nobody was there to hear it; one quiet summary instead of a ringing alarm for each
+        match intervals[..] {
+            [] => {}
+            [Phase::Work] => self.toasts.push("Your focus session ended while your computer was asleep"),
+            [_] => self.toasts.push("Your break ended while your computer was asleep"),
+            _ => self.toasts.push(format!("{} intervals elapsed while your computer was asleep", intervals.len())),
+        }
+        if last != Phase::GetReady && last != Phase::EasyStart {
+            self.events.publish(Event::PeriodFinished { work: last == Phase::Work, late_ms: late.as_millis() as u64, while_away: true });
+        }
+        if self.timer.is_running() && self.timer.phase() != Phase::GetReady {
+            self.announce_period();
+        }
+    }
 
-struct PomodoroApp {
-    start_time: Option<Instant>,
-    work_duration: Duration,   // Duration for concentration (work) period
-    pause_duration: Duration,  // Duration for break (pause) period
-    current_duration: Duration, // The duration for the current interval (work or break)
-    timer_running: bool,
-    is_work_period: bool,      // Flag to track if it's a work period or break period
-    timer_ended: bool,
-    sink: Option<Sink>,
-    _stream: Option<OutputStream>, // Keep the stream alive
-}
+    // Ends the interval if it has run out, returning which it was and how long ago it ended.
+    // One ended `away` is recorded and moved on from without a word, and the next starts as if
+    // it had on time.
+    fn end_period(&mut self, ctx: &egui::Context, away: bool) -> Option<(Phase, Duration)> {
+        let now = self.clock.instant();
+        let duration = self.timer.duration();
+        let late = self.timer.elapsed(now).saturating_sub(duration);
+        let Some(Transition::PeriodFinished { finished, .. }) = self.timer.tick(now) else {
+            return None;
+        };
+        // Nobody is rung at for an end slept through
+        let queued = self.alarm_queue.take();
+        if queued.is_some() && away {
+            self.audio().stop();
+        }
+        let queued = queued.filter(|_| !away);
+        if finished == Phase::GetReady {
+            // Neither focus nor break, so nothing to record or announce
+            if away {
+                self.begin_period(late);
+            } else {
+                self.start_period();
+            }
+            return Some((finished, late));
+        }
+        if finished == Phase::EasyStart {
+            let (start, pauses) = self.take_record(duration);
+            self.keep_going = Some(Session {
+                start,
+                duration_secs: duration.as_secs(),
+                while_away: away,
+                label: self.label(),
+                tags: self.tags(),
+                late_night: self.settings.wind_down_at.is_some_and(|from| wind_down::is_late(start, from)),
+                intent: None,
+                pauses: pauses.len() as u32,
+                paused_secs: pauses.iter().map(Pause::secs).sum(),
+                pause_segments: pauses,
+                easy_start: true,
+                screenshot: None,
+            });
+            if !away {
+                self.end_alert(ctx, Alert::EasyStartEnds, queued);
+            }
+            return Some((finished, late));
+        }
+        let work = finished == Phase::Work;
+        if !away {
+            self.watchdog.record(late);
+            self.events.publish(Event::PeriodFinished { work, late_ms: late.as_millis() as u64, while_away: false });
+            if work {
+                self.mascot.react(Mood::Celebrating);
+            }
+        }
+        let (label, tags) = (self.label(), self.tags());
+        let intent = self.intent.clone().filter(|intent| !intent.is_empty());
+        let long_break = if work { self.long_break_next() } else { self.long_break };
+        let mut alert = Alert::period_end(work, long_break);
+        if work {
+            self.cycle_sessions += 1;
+        }
+        let (start, pauses) = self.take_record(duration);
+        if let (true, Some(history)) = (work, self.history.wait()) {
+            let id = start.format(&time::format_description::well_known::Rfc3339).unwrap_or_default();
+            if label.as_ref().is_some_and(|label| self.plan.record_session(label, &id, self.clock.wall())) {
+                self.plan.save();
+            }
+            // Nothing worth keeping is on screen while the computer sleeps
+            let screenshot = self.settings.screenshots.as_ref().filter(|_| !away).map(|config| {
+                let name = screenshots::name(self.clock.wall());
+                screenshots::capture(config, name.clone());
+                name
+            });
+            history.append(Session {
+                start,
+                duration_secs: duration.as_secs(),
+                while_away: away,
+                label,
+                tags,
+                late_night: self.settings.wind_down_at.is_some_and(|from| wind_down::is_late(start, from)),
+                intent: intent.clone(),
+                pauses: pauses.len() as u32,
+                paused_secs: pauses.iter().map(Pause::secs).sum(),
+                pause_segments: pauses,
+                easy_start: false,
+                screenshot,
+            });
+            if let (true, Some(intent)) = (self.settings.ask_intent, intent) {
+                self.intent_review = Some((start, intent));
+            }
+            let today = history.stats.day(start.date()).sessions;
+            if let Some(goal) = self.settings.daily_goal.filter(|goal| *goal == today && !away) {
+                self.events.publish(Event::GoalReached { goal });
+                alert = Alert::GoalReached;
+            }
+        }
+        // Nobody to ask after an end slept through, so it is counted unrated
+        if let Some(minutes) = work.then(|| self.record_trial(true)).flatten() {
+            self.rate_focus = Some(minutes).filter(|_| !away);
+        }
+        // Never underneath someone asleep
+        let napping = !work && self.nap.is_some();
+        let auto_start = (self.settings.auto_start_next || self.kiosk.is_some()) && !napping;
+        let get_ready = self.settings.get_ready_secs.filter(|_| auto_start && !work);
+        if get_ready.is_some() {
+            alert = Alert::GetReady;
+        }
+        if napping {
+            if let Some(nap) = &mut self.nap {
+                nap.wake(self.clock.instant());
+            }
+            self.play(alerts::Sound::Ring, Shape::default());
+        } else if !away {
+            self.end_alert(ctx, alert, queued);
+        }
 
-impl PomodoroApp {
-    fn new() -> Self {
-        let (_stream, stream_handle) = OutputStream::try_default().unwrap();
-        let sink = Sink::try_new(&stream_handle).unwrap();
-
-        Self {
-            start_time: None,
-            work_duration: Duration::new(25 * 60, 0),   // 25 minutes for work
-            pause_duration: Duration::new(5 * 60, 0),   // 5 minutes for break
-            current_duration: Duration::new(25 * 60, 0), // Initially set to work duration
-            timer_running: false,
-            is_work_period: true,   // Start with work period
-            timer_ended: false,
-            sink: Some(sink),
-            _stream: Some(_stream), // Keep the stream alive
+        self.enter_period();
+        match get_ready {
+            Some(secs) => {
+                self.timer.get_ready(Duration::from_secs(secs.into()), self.clock.instant());
+                self.timer.credit(if away { late } else { Duration::ZERO });
+            }
+            None if auto_start && away => self.begin_period(late),
+            None if auto_start => self.start_period(),
+            None => {}
         }
+        // Shown until the alarm is acknowledged, even once the next interval runs
+        self.timer_ended = true;
+        self.ended_at = Some(self.clock.instant()).filter(|_| !away);
+        Some((finished, late))
     }
 
-    fn play_end_sound(&mut self) {
-        if let Some(sink) = &self.sink {
-            if sink.empty() {
-                sink.append(SineWave::new(440.0)); // Append a sound at 440 Hz
-                sink.play();
-                println!("Playing sound..."); // Debug print
+    /// Starts alternating focus and breaks right away, using `preset` if given. The preset is
+    /// only picked for this run, and no setting is saved, not even on quitting.
+    fn enter_kiosk(&mut self, preset: Option<String>) {
+        if let Some(name) = preset {
+            if self.settings.presets.iter().any(|preset| preset.name == name) {
+                self.settings.active_preset = Some(name);
+                self.apply_durations();
+            } else {
+                self.toasts.push(format!("There is no preset named \"{}\"", name));
             }
-        } else {
-            println!("Sink is None, cannot play sound."); // Debug print
+        }
+        log::info!("Kiosk mode, press {} to leave", kiosk::EXIT_KEYS);
+        self.kiosk = Some(kiosk::KeepAwake::start());
+        // A wall display has no one to read it; it is offered again from Help
+        self.whats_new.clear();
+        self.start_period();
+    }
+
+    // Fullscreen for as long as kiosk mode lasts, in case the window manager took it away
+    fn kiosk_window(&mut self, ctx: &egui::Context) {
+        if self.kiosk.is_none() {
+            return;
+        }
+        if kiosk::exit_requested(ctx) {
+            self.quit_confirmed = true;
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        } else if ctx.input(|i| i.viewport().fullscreen) == Some(false) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(true));
         }
     }
+
+    fn handle_close_request(&mut self, ctx: &egui::Context) {
+        if !ctx.input(|i| i.viewport().close_requested()) || self.quit_confirmed || self.terminated.load(Ordering::Relaxed) {
+            return;
+        }
+        if self.enforcement.is_some() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.toasts.push("Enjoy your break first, or hold Escape to leave it");
+            return;
+        }
+        if self.kiosk.is_some() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.toasts.push(format!("Press {} to leave kiosk mode", kiosk::EXIT_KEYS));
+            return;
+        }
+        if self.settings.confirm_quit && self.timer.is_running() && self.work_period() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.show_quit_confirmation = true;
+        }
+    }
+
+    fn quit_confirmation_window(&mut self, ctx: &egui::Context) {
+        if !self.show_quit_confirmation {
+            return;
+        }
+        egui::Window::new("Quit FerrisFocus?")
+            .resizable(false)
+            .collapsible(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                compact_text(ui.style_mut());
+                ui.label("A focus session is running — quit anyway?");
+                ui.horizontal(|ui| {
+                    if ui.button("Quit").clicked() {
+                        self.quit_confirmed = true;
+                        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.show_quit_confirmation = false;
+                    }
+                });
+            });
+    }
+
+    fn shortcuts_window(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Keyboard shortcuts")
+            .open(&mut self.show_shortcuts)
+            .resizable(false)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                compact_text(ui.style_mut());
+                egui::Grid::new("shortcuts").striped(true).show(ui, |ui| {
+                    for action in Action::ALL {
+                        if let Some(shortcut) = action.shortcut() {
+                            ui.label(action.label());
+                            ui.label(ctx.format_shortcut(&shortcut));
+                            ui.end_row();
+                        }
+                    }
+                });
+            });
+    }
 }
 
 impl eframe::App for PomodoroApp {
     fn update(&mut self, ctx: &egui::Context, _: &mut eframe::Frame) {
+        let frame_started = Instant::now();
         let mut style: egui::Style = (*ctx.style()).clone();
         style.text_styles.get_mut(&egui::TextStyle::Body).unwrap().size = 60.0;
         style.text_styles.get_mut(&egui::TextStyle::Heading).unwrap().size = 80.0;
         style.text_styles.get_mut(&egui::TextStyle::Button).unwrap().size = 40.0;
 
-        style.visuals = egui::Visuals {
-            dark_mode: true,
-            widgets: egui::style::Widgets {
-                inactive: egui::style::WidgetVisuals {
-                    bg_fill: egui::Color32::from_gray(40),
-                    bg_stroke: egui::Stroke::new(1.0, egui::Color32::WHITE),
-                    fg_stroke: egui::Stroke::new(1.5, egui::Color32::WHITE),
-                    rounding: egui::Rounding::same(5.0),
-                    weak_bg_fill: egui::Color32::TRANSPARENT,
-                    expansion: 0.0,
-                },
-                active: egui::style::WidgetVisuals {
-                    bg_fill: egui::Color32::from_gray(70),
-                    bg_stroke: egui::Stroke::new(2.0, egui::Color32::WHITE),
-                    fg_stroke: egui::Stroke::new(2.0, egui::Color32::WHITE),
-                    rounding: egui::Rounding::same(5.0),
-                    weak_bg_fill: egui::Color32::TRANSPARENT,
-                    expansion: 0.0,
-                },
-                hovered: egui::style::WidgetVisuals {
-                    bg_fill: egui::Color32::from_gray(50),
-                    bg_stroke: egui::Stroke::new(1.5, egui::Color32::WHITE),
-                    fg_stroke: egui::Stroke::new(1.5, egui::Color32::WHITE),
-                    rounding: egui::Rounding::same(5.0),
-                    weak_bg_fill: egui::Color32::TRANSPARENT,
-                    expansion: 0.0,
-                },
-                noninteractive: egui::style::WidgetVisuals {
-                    bg_fill: egui::Color32::from_gray(30),
-                    bg_stroke: egui::Stroke::new(1.0, egui::Color32::WHITE),
-                    fg_stroke: egui::Stroke::new(1.0, egui::Color32::WHITE),
-                    rounding: egui::Rounding::same(5.0),
-                    weak_bg_fill: egui::Color32::TRANSPARENT,
-                    expansion: 0.0,
-                },
-                open: egui::style::WidgetVisuals {
-                    bg_fill: egui::Color32::from_gray(60),
-                    bg_stroke: egui::Stroke::new(1.0, egui::Color32::WHITE),
-                    fg_stroke: egui::Stroke::new(1.0, egui::Color32::WHITE),
-                    rounding: egui::Rounding::same(5.0),
-                    weak_bg_fill: egui::Color32::TRANSPARENT,
-                    expansion: 0.0,
-                },
-            },
-            ..egui::Visuals::default()
-        };
+        self.apply_theme(ctx);
+        style.visuals = theme::visuals(self.dark);
+        // Progress bars and selections take the accent, whichever phase is running
+        let accent = self.accent_color(ctx, style.visuals.window_fill);
+        style.visuals.selection.bg_fill = accent;
+        style.visuals.widgets.active.bg_stroke.color = accent;
         ctx.set_style(style);
 
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.vertical_centered(|ui| {
-                ui.add_space(20.0);
+        let jumped = self.check_clock();
+        let woke_up = self.detect_sleep(jumped).is_some();
+        if ctx.frame_nr() > 0 {
+            self.audio();
+        }
+        self.finish_period(ctx, woke_up);
+        self.external_pause();
+        self.greet_return(ctx);
+        self.quick_timers(ctx);
+        self.handle_close_request(ctx);
+        self.handle_shortcuts(ctx);
+        #[cfg(target_os = "linux")]
+        self.dbus_requests(ctx);
+        #[cfg(unix)]
+        self.ipc_requests();
+        if self.show_menu_bar && self.kiosk.is_none() {
+            self.menu_bar(ctx);
+        }
+        self.kiosk_window(ctx);
+        self.feed_replay();
+        if let Some(history) = self.history.get() {
+            self.toasts.extend(history.problems.drain(..));
+            // Follows the setting however it changed, including by undo
+            history.recount(self.settings.day_zone, self.settings.focus_counting);
+        }
+        self.stats_window(ctx);
+        self.day_detail_window(ctx);
+        self.settings_window(ctx);
+        self.import_window(ctx);
+        self.backup_window(ctx);
+        self.settings_journal_window(ctx);
+        self.troubleshoot_window(ctx);
+        self.dev_panel_window(ctx);
+        self.sync_sound();
+        self.shortcuts_window(ctx);
+        about::window(ctx, &mut self.show_about);
+        if !self.whats_new.is_empty() {
+            let mut open = true;
+            changelog::window(ctx, &mut open, &self.whats_new);
+            if !open {
+                self.whats_new.clear();
+            }
+        }
+        self.quit_confirmation_window(ctx);
+        self.wind_down_prompt(ctx);
+        self.shorten_prompt(ctx);
+        self.keep_going_prompt(ctx);
+        self.intent_prompts(ctx);
+        self.calibration_window(ctx);
+        self.ambient_sound();
+        self.publish_tick();
+        let status = self.ipc_status();
+        // Typing or clicking may change the title or the settings; the pointer moving doesn't
+        let input = ctx.input(|i| i.events.iter().any(|event| !matches!(event, egui::Event::PointerMoved(_) | egui::Event::MouseMoved(_))));
+        if self.outputs.due(Instant::now(), &status, input) {
+            self.refresh_outputs(ctx, status);
+        }
+        #[cfg(target_os = "linux")]
+        let focusing = self.settings.tint_borders && self.timer.is_running() && self.work_period();
+        #[cfg(target_os = "linux")]
+        if let Some(tint) = &mut self.border_tint {
+            tint.apply(focusing.then_some(self.settings.focus_border_color));
+        }
+        #[cfg(target_os = "linux")]
+        self.focus_companion(ctx);
+        self.posture_reminder(ctx);
+        self.enforced_break_overlay(ctx);
+        self.nap_overlay(ctx);
+        self.toasts.show(ctx);
+        if let Some(at) = self.toasts.expires() {
+            self.repaint.animate("toasts", at.saturating_duration_since(Instant::now()), at);
+        }
+        if let Some(label) = self.mode.label() {
+            watermark(ctx, label);
+        }
 
-                // Timer display
-                let (minutes, seconds) = if self.timer_running {
-                    if let Some(start_time) = self.start_time {
-                        let elapsed = start_time.elapsed();
-                        let remaining = if self.current_duration > elapsed {
-                            self.current_duration - elapsed
-                        } else {
-                            Duration::new(0, 0)
-                        };
+        if self.kiosk.is_some() {
+            let remaining = self.remaining();
+            let (phase, progress) = (self.timer.phase(), self.timer.progress(self.clock.instant()));
+            let paused = self.timer.paused_for(self.clock.instant());
+            egui::CentralPanel::default().show(ctx, |ui| kiosk::view(ui, remaining, phase, progress, paused));
+        } else {
+            self.timer_panel(ctx);
+        }
+        // After anything this frame that paused or moved the end
+        self.queue_alarm();
 
-                        if remaining.as_secs() == 0 {
-                            // Timer has ended
-                            self.timer_running = false;
-                            self.timer_ended = true;
-
-                            // Switch between work and break intervals
-                            if self.is_work_period {
-                                self.current_duration = self.pause_duration; // Switch to break
-                                self.is_work_period = false;
-                            } else {
-                                self.current_duration = self.work_duration; // Switch to work
-                                self.is_work_period = true;
-                            }
+        // Redrawn right as the digits change so none is skipped or held, else now and then
+        let wait = if self.timer.is_running() {
+            pomodoro_timer::timer::until_next_second(self.remaining())
+        } else if let Some(paused) = self.timer.paused_for(self.clock.instant()) {
+            pomodoro_timer::timer::until_second_passes(paused)
+        } else if self.saving_power() {
+            Duration::from_secs(2)
+        } else {
+            Duration::from_secs(1)
+        };
+        if self.dev_panel.measuring() {
+            self.repaint.animate("frame timing", Duration::ZERO, Instant::now() + Duration::from_secs(1));
+        }
+        self.repaint.request(ctx, Instant::now(), Some(wait));
+        self.dev_panel.frame(frame_started.elapsed());
+    }
 
-                            // Restart the timer after switching periods
-                            self.start_time = Some(Instant::now());
-                        }
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        if let Some(audio) = &mut self.audio {
+            audio.stop();
+            audio.set_ambient(None);
+        }
+        if self.timer.is_running() && self.work_period() {
+            log::info!("Quitting during a focus session, the session is abandoned");
+        }
+        self.events.publish(Event::Quit { running: self.timer.is_running() });
+        if let (Some(enforcement), Some(history)) = (self.enforcement.take(), self.history.wait()) {
+            history.append_break(enforcement.finish(self.clock.instant(), true));
+        }
+        self.events.shutdown();
+        #[cfg(target_os = "linux")]
+        if let Some(tint) = &mut self.border_tint {
+            tint.apply(None);
+        }
+        // Through the same guard as every other change, so kiosk mode's preset stays its own
+        if self.kiosk.is_none() {
+            self.settings.save();
+        }
+        log::logger().flush();
+    }
+}
 
-                        (remaining.as_secs() / 60, remaining.as_secs() % 60)
-                    } else {
-                        (0, 0)
-                    }
-                } else {
-                    // If timer is paused or not running, show the remaining time
-                    let remaining = self.current_duration;
-                    (remaining.as_secs() / 60, remaining.as_secs() % 60)
-                };
+// The timer's large text styles are far too big for menus and dialogs
+pub(crate) fn compact_text(style: &mut egui::Style) {
+    for (text_style, font) in style.text_styles.iter_mut() {
+        font.size = match text_style {
+            egui::TextStyle::Heading => 20.0,
+            egui::TextStyle::Small => 10.0,
+            _ => 14.0,
+        };
+    }
+}
 
-                ui.heading(format!("{:02}:{:02}", minutes, seconds));
-                ui.add_space(20.0);
+// Hands the configured hooks to a new runner, and runs them on period and goal events only
+// while any are set
+fn subscribe_hooks(events: &mut events::Bus, settings: &Settings) {
+    events.unsubscribe("hooks");
+    if let Some(runner) = hooks::Runner::new(&settings.hooks) {
+        let kinds = [Kind::PeriodStarted, Kind::PeriodFinished, Kind::GoalReached];
+        events.subscribe("hooks", Filter::only(&kinds), move |event| runner.handle(event));
+    }
+}
 
-                // Start/Pause button
-                if ui.button(if self.timer_running { "Pause" } else { "Start" }).clicked() {
-                    if self.timer_running {
-                        // Pausing the timer
-                        self.timer_running = false;
-                    } else {
-                        // Starting the timer
-                        self.timer_running = true;
-                        self.start_time = Some(Instant::now());
-                        self.timer_ended = false;
-                    }
-                }
+// Tells the sender about changed webhooks, and sends events only while there are some
+fn subscribe_webhooks(events: &mut events::Bus, settings: &Settings, webhooks: &webhooks::Webhooks) {
+    events.unsubscribe("webhooks");
+    webhooks.configure(&settings.webhooks, settings.work_offline);
+    if settings.webhooks.iter().any(|webhook| !webhook.url.trim().is_empty()) {
+        let (sender, list) = (webhooks.clone(), settings.webhooks.clone());
+        let kinds = [Kind::PeriodStarted, Kind::PeriodFinished, Kind::GoalReached];
+        events.subscribe("webhooks", Filter::only(&kinds), move |event| sender.handle(&list, event));
+    }
+}
 
-                ui.add_space(10.0);
+// Another instance holding a socket or bus name is worth a toast, since closing it fixes
+// things; a missing session bus is just how some systems are
+#[cfg(unix)]
+fn report_start_error(toasts: &mut Toasts, integration: &str, err: &StartError) {
+    match err {
+        StartError::Conflict(reason) => {
+            log::warn!("{} not started: {}", integration, reason);
+            toasts.push(reason.clone());
+        }
+        StartError::Unavailable(reason) => log::info!("{} not available: {}", integration, reason),
+    }
+}
 
-                // Reset button
-                if ui.button("Reset").clicked() {
-                    self.timer_running = false;
-                    self.start_time = None;
-                    self.current_duration = self.work_duration;
-                    self.timer_ended = false;
-                }
+fn preset_defaults(preset: &hooks::Preset) -> Vec<String> {
+    preset.params.iter().map(|(_, default)| (*default).to_owned()).collect()
+}
 
-                ui.add_space(20.0);
+// Restarts the recording subscriber so it picks up changed settings, or stops it
+fn subscribe_recorder(events: &mut events::Bus, settings: &Settings) {
+    events.unsubscribe("recording");
+    if let Some(recorder) = Recorder::start(settings) {
+        events.subscribe("recording", Filter::all().without(Kind::Tick), move |event| recorder.handle(event));
+    }
+}
 
-                // Display a progress bar
-                let total_elapsed = if self.timer_running {
-                    self.start_time.unwrap_or_else(Instant::now).elapsed()
-                } else {
-                    Duration::new(0, 0)
-                };
-                let remaining = if self.current_duration > total_elapsed {
-                    self.current_duration - total_elapsed
-                } else {
-                    Duration::new(0, 0)
-                };
-                let progress = if self.current_duration.as_secs() > 0 {
-                    1.0 - remaining.as_secs_f32() / self.current_duration.as_secs_f32()
-                } else {
-                    0.0
-                };
+fn watermark(ctx: &egui::Context, text: &str) {
+    let painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("watermark")));
+    painter.text(
+        ctx.screen_rect().right_bottom() - egui::vec2(10.0, 10.0),
+        egui::Align2::RIGHT_BOTTOM,
+        text,
+        egui::FontId::proportional(32.0),
+        egui::Color32::from_rgba_unmultiplied(255, 80, 80, 140),
+    );
+}
 
-                ui.add(egui::ProgressBar::new(progress).desired_width(300.0));
+/// "paused 3:12", counting up from the start of a pause.
+pub(crate) fn paused_text(paused: Duration) -> String {
+    let secs = paused.as_secs();
+    match secs / 3600 {
+        0 => format!("paused {}:{:02}", secs / 60, secs % 60),
+        hours => format!("paused {}:{:02}:{:02}", hours, secs / 60 % 60, secs % 60),
+    }
+}
 
-                if self.timer_ended {
-                    ui.colored_label(egui::Color32::RED, "Timer Ended");
-                    self.play_end_sound();
-                }
+/// Diagonal stripes across `rect`, so a paused bar reads as stopped even without color.
+pub(crate) fn hatch(painter: &egui::Painter, rect: egui::Rect, color: egui::Color32) {
+    let painter = painter.with_clip_rect(rect);
+    let gap = (rect.height() * 0.8).max(6.0);
+    let mut x = rect.left() - rect.height();
+    while x < rect.right() {
+        painter.line_segment([egui::pos2(x, rect.bottom()), egui::pos2(x + rect.height(), rect.top())], egui::Stroke::new(1.5, color));
+        x += gap;
+    }
+}
 
-                ui.add_space(20.0);
-            });
-        });
+/// `sound` with the alarm no louder than the night volume, if it is night.
+fn at_night(sound: timer_presets::Sound, settings: &Settings, night: bool) -> timer_presets::Sound {
+    match settings.night_volume.filter(|_| night) {
+        Some(quiet) => timer_presets::Sound { volume: sound.volume.min(quiet), ..sound },
+        None => sound,
+    }
+}
+
+// Focus sessions before a long break
+const CYCLE_LENGTH: u32 = 4;
+
+// Short enough that starting is easy, long enough to get into it
+const EASY_START: Duration = Duration::from_secs(2 * 60);
+
+// Shown instead of anything typed while in privacy mode
+const PRIVATE: &str = "•••••";
+
+// The preset a calibration's recommendation is applied as, replaced by the next one
+const CALIBRATED: &str = "Calibrated";
+
+fn load_history(settings: &Settings) -> Background<History> {
+    let keep_months = settings.keep_sessions_months;
+    let (day_zone, counting) = (settings.day_zone, settings.focus_counting);
+    Background::spawn("history", move || {
+        let mut history = History::load(day_zone, counting);
+        if let Some(months) = keep_months {
+            compact_history(&mut history, months);
+        }
+        history
+    })
+}
+
+fn compact_history(history: &mut History, months: u32) -> usize {
+    let cutoff = history::retention_cutoff(clock::now().date(), months);
+    history.compact(cutoff).unwrap_or_else(|err| {
+        log::warn!("Cannot compact history: {}", err);
+        0
+    })
+}
+
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        0..=1023 => format!("{} B", bytes),
+        1024..=1_048_575 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+// Draw the countdown digits with the bundled (or overridden) timer font
+fn install_fonts(ctx: &egui::Context) {
+    let mut fonts = egui::FontDefinitions::default();
+    let timer_font = egui::FontFamily::Name("timer".into());
+    fonts.font_data.insert("timer".to_owned(), egui::FontData::from_owned(assets::load(Asset::TimerFont).into_owned()));
+    fonts.families.insert(timer_font.clone(), vec!["timer".to_owned()]);
+    ctx.set_fonts(fonts);
+
+    ctx.style_mut(|style| {
+        style.text_styles.insert(egui::TextStyle::Heading, egui::FontId::new(80.0, timer_font));
+    });
+}
+
+// Folders may not exist yet on a fresh install, so create them before handing them to the file manager
+fn open_folder(dir: Option<PathBuf>) {
+    let Some(dir) = dir else {
+        log::warn!("No home directory found, cannot open folder");
+        return;
+    };
+    if let Err(err) = std::fs::create_dir_all(&dir) {
+        log::warn!("Failed to create {}: {}", dir.display(), err);
+    } else if let Err(err) = opener::open(&dir) {
+        log::warn!("Failed to open {}: {}", dir.display(), err);
+    }
+}
 
+// Ctrl+C in the terminal or a SIGTERM from the session manager closes the window the normal way,
+// so on_exit still gets to clean up
+fn install_signal_handler(ctx: &egui::Context) -> Arc<AtomicBool> {
+    let terminated = Arc::new(AtomicBool::new(false));
+    let flag = terminated.clone();
+    let ctx = ctx.clone();
+    if let Err(err) = ctrlc::set_handler(move || {
+        flag.store(true, Ordering::Relaxed);
+        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
         ctx.request_repaint();
+    }) {
+        log::warn!("Cannot install signal handler: {}", err);
+    }
+    terminated
+}
+
+fn icon(viewport: egui::ViewportBuilder) -> egui::ViewportBuilder {
+    match eframe::icon_data::from_png_bytes(&assets::load(Asset::Icon)) {
+        Ok(icon) => viewport.with_icon(icon),
+        Err(err) => {
+            log::warn!("Cannot decode app icon: {}", err);
+            viewport
+        }
+    }
+}
+
+// The JSON shape Waybar's custom modules expect; CSS classes are work, break, paused and idle
+fn waybar(follow: bool) {
+    let line = |status: Option<&ipc::Status>| {
+        let value = match status {
+            Some(status) => {
+                let remaining = status.field("remaining").unwrap_or_default();
+                let mut tooltip = format!("{}, {} left", status.field("phase").unwrap_or_default(), remaining);
+                if let Some(sessions) = status.sessions_today {
+                    tooltip += &format!(", {} sessions today", sessions);
+                }
+                serde_json::json!({
+                    "text": format!("{} {}", status.field("icon").unwrap_or_default(), remaining),
+                    "tooltip": tooltip,
+                    "class": status.class(),
+                    "percentage": status.percentage(),
+                })
+            }
+            None => serde_json::json!({ "text": "", "tooltip": "FerrisFocus is not running", "class": "idle" }),
+        };
+        println!("{}", value);
+    };
+
+    let mut last = ipc::read_status_file(clock::now().unix_timestamp());
+    line(last.as_ref());
+    if !follow {
+        return;
+    }
+    while last.is_some() {
+        std::thread::sleep(Duration::from_millis(500));
+        let status = ipc::read_status_file(clock::now().unix_timestamp());
+        if status != last {
+            line(status.as_ref());
+            last = status;
+        }
+    }
+}
+
+// Called on every prompt, so it only reads the status file: no socket, no waiting
+fn prompt(threshold: Option<Duration>) {
+    let Some(status) = ipc::read_status_file(clock::now().unix_timestamp()) else {
+        return;
+    };
+    if !status.running || threshold.is_some_and(|threshold| status.remaining_secs >= threshold.as_secs()) {
+        return;
+    }
+    println!("{}{}m", status.field("icon").unwrap_or_default(), status.remaining_secs.div_ceil(60));
+}
+
+// Prints nothing when no instance answers, so tmux shows an empty segment instead of an error
+fn tmux_status(no_color: bool, format: &str) -> i32 {
+    let template = match template::Template::parse(format) {
+        Ok(template) => template,
+        Err(err) => {
+            eprintln!("Invalid --format: {}", err);
+            return 2;
+        }
+    };
+    let Some(status) = ipc::query(Duration::from_millis(200)) else {
+        println!();
+        return 0;
+    };
+    let text = template.render(|field| status.field(field));
+    let text = text.trim_end();
+    if no_color {
+        println!("{}", text);
+    } else {
+        let color = if status.work_period { "red" } else { "green" };
+        println!("#[fg={}]{}#[default]", color, text);
+    }
+    0
+}
+
+// The running app owns the task list and merges the import itself; only with no app running is
+// the file written from here
+fn import_tasks(list: TaskList) -> i32 {
+    let reply = ipc::forward(&ipc::Request::ImportTasks(list.clone())).unwrap_or_else(|| {
+        let _lock = ownership::Lock::take().map_err(|refused| match refused {
+            ownership::Refused::Held(dir) => format!(
+                "a running FerrisFocus owns {} but can't be reached over its status socket; quit it and import again",
+                dir.display()
+            ),
+            refused => refused.to_string(),
+        })?;
+        let mut plan = plan::Plan::load();
+        let merge = plan.import(list);
+        plan.save();
+        Ok(merged(merge))
+    });
+    match reply {
+        Ok(merged) => {
+            println!("{}", merged);
+            0
+        }
+        Err(err) => {
+            eprintln!("Cannot import: {}", err);
+            1
+        }
+    }
+}
+
+// A running app keeps the timer; otherwise it is counted down here, with the app's sound
+fn quick_timer(length: Duration, message: Option<String>) -> i32 {
+    let text = message.clone().unwrap_or_else(|| "Quick timer".to_owned());
+    match ipc::forward(&ipc::Request::QuickTimer { secs: length.as_secs(), message }) {
+        Some(Ok(reply)) => {
+            println!("{}", reply);
+            0
+        }
+        Some(Err(err)) => {
+            eprintln!("Cannot start a quick timer: {}", err);
+            1
+        }
+        None => {
+            let (settings, _) = settings::Settings::load();
+            let sound = timer_presets::resolve(&settings);
+            match quick::countdown(length, &text, sound.alarm_file.as_deref(), sound.volume) {
+                Ok(()) => 0,
+                Err(err) => {
+                    eprintln!("Cannot start a quick timer: {}", err);
+                    1
+                }
+            }
+        }
     }
 }
 
+fn merged(merge: Merge) -> String {
+    format!("{} tasks added, {} updated", merge.added, merge.updated)
+}
+
 fn main() -> Result<(), eframe::Error> {
+    logging::init();
+    clock::init();
+    let cli = cli::Cli::parse();
+    if let Some(format) = cli.log_format {
+        logging::choose(format);
+    }
+
+    if let Some(cli::Command::Waybar { follow }) = &cli.command {
+        waybar(*follow);
+        return Ok(());
+    }
+    if let Some(cli::Command::Prompt { threshold }) = &cli.command {
+        prompt(*threshold);
+        return Ok(());
+    }
+    if let Some(cli::Command::TmuxStatus { no_color, format }) = &cli.command {
+        std::process::exit(tmux_status(*no_color, format));
+    }
+    if let Some(cli::Command::ExportTasks { file }) = &cli.command {
+        let json = plan::Plan::load().export().to_json();
+        match file {
+            None => println!("{}", json),
+            Some(file) => {
+                if let Err(err) = std::fs::write(file, json) {
+                    eprintln!("Cannot write {}: {}", file.display(), err);
+                    std::process::exit(1);
+                }
+            }
+        }
+        std::process::exit(0);
+    }
+    if let Some(cli::Command::ImportTasks { file }) = &cli.command {
+        let list = std::fs::read_to_string(file).map_err(|e| e.to_string()).and_then(|text| TaskList::from_json(&text));
+        match list {
+            Ok(list) => std::process::exit(import_tasks(list)),
+            Err(err) => {
+                eprintln!("Cannot import {}: {}", file.display(), err);
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(cli::Command::Quick { length, message }) = &cli.command {
+        std::process::exit(quick_timer(*length, message.clone()));
+    }
+    if let Some(cli::Command::Inspect { file }) = &cli.command {
+        match recording::read(file) {
+            Ok((entries, problem)) => {
+                for entry in entries {
+                    println!("{}", entry);
+                }
+                if let Some(problem) = problem {
+                    eprintln!("{}", problem);
+                }
+                std::process::exit(0);
+            }
+            Err(err) => {
+                eprintln!("Cannot read {}", err);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let mut restore = None;
+    let (title, clock, mode): (&str, Box<dyn Clock>, RunMode) = match cli.command {
+        Some(cli::Command::Replay { file, speed }) => {
+            let sessions = match history::read_export(&file) {
+                Ok((sessions, skipped)) if !sessions.is_empty() => {
+                    if let Some(skipped) = skipped {
+                        eprintln!("{}", skipped);
+                    }
+                    sessions
+                }
+                Ok(_) => {
+                    eprintln!("{} contains no sessions", file.display());
+                    std::process::exit(1);
+                }
+                Err(err) => {
+                    eprintln!("Cannot read {}", err);
+                    std::process::exit(1);
+                }
+            };
+            let dir = paths::use_throwaway_data_dir();
+            log::info!("Replaying {} sessions at {}x speed into {}", sessions.len(), speed, dir.display());
+            let clock = clock::ScaledClock::starting_at(sessions[0].start, speed);
+            ("Pomodoro Timer — REPLAY", Box::new(clock), RunMode::Replay(sessions.into()))
+        }
+        Some(cli::Command::Restore { ref file }) => {
+            restore = match Snapshot::read(file) {
+                Ok(snapshot) => Some(snapshot),
+                Err(err) => {
+                    eprintln!("Cannot restore {}", err);
+                    std::process::exit(1);
+                }
+            };
+            let dir = paths::use_throwaway_data_dir();
+            paths::use_throwaway_config_dir();
+            log::info!("Restoring {} with throwaway data in {}", file.display(), dir.display());
+            ("Pomodoro Timer — SNAPSHOT", Box::new(clock::SystemClock), RunMode::Restored)
+        }
+        Some(
+            cli::Command::Inspect { .. }
+            | cli::Command::TmuxStatus { .. }
+            | cli::Command::Prompt { .. }
+            | cli::Command::Waybar { .. }
+            | cli::Command::ExportTasks { .. }
+            | cli::Command::ImportTasks { .. }
+            | cli::Command::Quick { .. },
+        ) => {
+            unreachable!("handled before the window opens")
+        }
+        None if cli.demo => {
+            let dir = paths::use_throwaway_data_dir();
+            log::info!("Demo mode at {}x speed, recording to {}", cli.speed, dir.display());
+            ("Pomodoro Timer — DEMO", Box::new(clock::ScaledClock::new(cli.speed)), RunMode::Demo)
+        }
+        None => ("Pomodoro Timer", Box::new(clock::SystemClock), RunMode::Normal),
+    };
+    // Held until the window closes; a second app writing the same history would lose sessions
+    let _owner = match ownership::Lock::take() {
+        Ok(lock) => Some(lock),
+        Err(ownership::Refused::Held(dir)) => {
+            eprintln!("Another FerrisFocus is running and owns the history in {}; switch to that one", dir.display());
+            std::process::exit(1);
+        }
+        // Say on a read-only file system, where nothing gets written anyway
+        Err(err) => {
+            log::warn!("Running without the data folder lock: {}", err);
+            None
+        }
+    };
+    let (kiosk, preset, dev) = (cli.kiosk, cli.preset, cli.dev);
     let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default().with_inner_size([400.0, 350.0]),
+        viewport: icon(egui::ViewportBuilder::default().with_inner_size([400.0, 350.0]).with_min_inner_size(layout::MIN_SIZE).with_fullscreen(kiosk)),
+        // The wall display's size is no size for the next normal run
+        persist_window: !kiosk,
         ..Default::default()
     };
     eframe::run_native(
-        "Pomodoro Timer",
+        title,
         options,
-        Box::new(|_cc| Ok(Box::new(PomodoroApp::new()))),
+        Box::new(move |cc| {
+            install_fonts(&cc.egui_ctx);
+            let mut app = PomodoroApp::new(install_signal_handler(&cc.egui_ctx), clock, mode);
+            app.power = power::Monitor::start(&cc.egui_ctx);
+            app.external = external_pause::Monitor::start(&cc.egui_ctx, app.settings.pause_during_calls);
+            app.away = away::Monitor::start(&cc.egui_ctx);
+            app.connectivity.start(&cc.egui_ctx);
+            #[cfg(unix)]
+            if let Ok(server) = &app.ipc_server {
+                server.wake(&cc.egui_ctx);
+            }
+            #[cfg(target_os = "linux")]
+            {
+                app.dbus = dbus::Service::start(Arc::clone(&app.status), &cc.egui_ctx);
+                if let Err(err) = &app.dbus {
+                    report_start_error(&mut app.toasts, "D-Bus service", err);
+                }
+            }
+            if let Some(snapshot) = restore {
+                app.restore(snapshot);
+            }
+            if kiosk {
+                app.enter_kiosk(preset);
+            }
+            app.dev_mode = dev;
+            Ok(Box::new(app))
+        }),
     )
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clock::ManualClock;
+    use std::rc::Rc;
+    use std::sync::{MutexGuard, PoisonError};
+
+    // Apps in tests share one throwaway data and config folder, so they take turns, each
+    // starting from an empty one that is removed afterwards
+    static TURN: Mutex<()> = Mutex::new(());
+
+    struct Turn {
+        _guard: MutexGuard<'static, ()>,
+    }
+
+    impl Turn {
+        fn take() -> Self {
+            let guard = TURN.lock().unwrap_or_else(PoisonError::into_inner);
+            let _ = std::fs::remove_dir_all(paths::use_throwaway_data_dir());
+            paths::use_throwaway_config_dir();
+            Self { _guard: guard }
+        }
+    }
+
+    impl Drop for Turn {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(paths::use_throwaway_data_dir());
+        }
+    }
+
+    // An app run on `clock`, which the test moves on, playing nothing
+    fn app(clock: &Rc<ManualClock>) -> PomodoroApp {
+        let mut app = PomodoroApp::new(Arc::new(AtomicBool::new(false)), Box::new(Rc::clone(clock)), RunMode::Demo);
+        app.audio = Some(Box::<audio::RecordingBackend>::default());
+        app
+    }
+
+    fn minutes(minutes: u64) -> Duration {
+        Duration::from_secs(minutes * 60)
+    }
+
+    #[test]
+    fn an_easy_start_kept_going_keeps_its_pauses() {
+        let _turn = Turn::take();
+        let started = time::macros::datetime!(2024-03-04 9:00 +01:00);
+        let clock = Rc::new(ManualClock::starting_at(started));
+        let (mut app, ctx) = (app(&clock), egui::Context::default());
+        app.easy_start();
+        clock.advance(minutes(1));
+        app.pause(PauseReason::Manual);
+        clock.advance(minutes(3));
+        app.resume();
+        clock.advance(EASY_START - minutes(1));
+        app.finish_period(&ctx, false);
+        assert!(app.keep_going.is_some());
+        app.continue_easy_start();
+        clock.advance(minutes(25) - EASY_START);
+        app.finish_period(&ctx, false);
+
+        let session = app.history.wait().unwrap().sessions.last().cloned().unwrap();
+        assert!(!session.easy_start);
+        assert_eq!((session.start, session.duration_secs, session.pauses, session.paused_secs), (started, 25 * 60, 1, 3 * 60));
+        assert_eq!(session.pause_segments[0].start, started + minutes(1));
+        assert_eq!(session.end(), started + minutes(28));
+    }
+
+    #[test]
+    fn a_paused_session_is_recorded_from_its_start_and_read_back() {
+        let _turn = Turn::take();
+        let started = time::macros::datetime!(2024-03-04 9:00 +01:00);
+        let clock = Rc::new(ManualClock::starting_at(started));
+        let (mut app, ctx) = (app(&clock), egui::Context::default());
+        app.start_period();
+        clock.advance(minutes(5));
+        app.pause(PauseReason::Manual);
+        clock.advance(minutes(2));
+        app.resume();
+        clock.advance(minutes(10));
+        app.pause(PauseReason::External);
+        clock.advance(minutes(5));
+        app.resume();
+        clock.advance(minutes(10));
+        app.finish_period(&ctx, false);
+
+        let session = app.history.wait().unwrap().sessions.last().cloned().unwrap();
+        assert_eq!((session.start, session.end()), (started, started + minutes(32)));
+        assert_eq!((session.duration_secs, session.pauses, session.paused_secs), (25 * 60, 2, 7 * 60));
+        let pauses: Vec<_> = session.pause_segments.iter().map(|pause| (pause.start, pause.end, pause.reason)).collect();
+        let at = |offset: u64| started + minutes(offset);
+        assert_eq!(pauses, [(at(5), at(7), PauseReason::Manual), (at(17), at(22), PauseReason::External)]);
+        assert_eq!(session.days(DayZone::Recorded, Counting::Focused), [(started.date(), 25 * 60)]);
+        assert_eq!(History::load(DayZone::Recorded, Counting::Focused).sessions, [session]);
+    }
+
+    // Ten minutes into a focus session on a day with three behind it
+    fn taken_at(taken_at: time::OffsetDateTime) -> Snapshot {
+        Snapshot {
+            version: snapshot::VERSION,
+            taken_at,
+            timer: snapshot::TimerState { work_period: true, running: true, ended: false, duration_secs: 1500.0, elapsed_secs: 600.0 },
+            task: "Quarterly report".to_owned(),
+            settings: Settings::default(),
+            today: history::DayTotals { sessions: 3, focus_secs: 4500, paused_secs: 120, late_sessions: 0, easy_starts: 1 },
+            toasts: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn a_restored_snapshot_carries_on_from_where_it_was_taken() {
+        let _turn = Turn::take();
+        let taken = time::macros::datetime!(2024-03-04 9:10 +01:00);
+        let clock = Rc::new(ManualClock::starting_at(taken));
+        let (mut app, ctx) = (app(&clock), egui::Context::default());
+        app.restore(taken_at(taken));
+        // Past the end of the session, which then rings, and a minute into the break
+        clock.advance(Duration::from_secs(1000));
+        app.finish_period(&ctx, false);
+        clock.advance(Duration::from_secs(60));
+
+        let mut expected = taken_at(taken + Duration::from_secs(1060));
+        expected.timer = snapshot::TimerState { work_period: false, running: true, ended: true, duration_secs: 300.0, elapsed_secs: 60.0 };
+        expected.today.sessions += 1;
+        expected.today.focus_secs += 1500;
+        let json = |snapshot: &Snapshot| serde_json::to_string_pretty(snapshot).unwrap();
+        assert_eq!(json(&app.snapshot()), json(&expected));
+    }
+
+    #[test]
+    fn quitting_in_kiosk_mode_leaves_the_settings_file_alone() {
+        let _turn = Turn::take();
+        let path = paths::config_dir().unwrap().join(settings::FILE_NAME);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let text = "work_minutes = 50\n\n[[presets]]\nname = \"Short\"\nwork_minutes = 10\nbreak_minutes = 2\n";
+        std::fs::write(&path, text).unwrap();
+        let clock = Rc::new(ManualClock::starting_now());
+        let mut app = app(&clock);
+        app.enter_kiosk(Some("Short".to_owned()));
+        assert_eq!(app.timer.duration(), Duration::from_secs(10 * 60));
+        app.settings.volume = 0.5;
+        eframe::App::on_exit(&mut app, None);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), text);
+    }
+
+    #[test]
+    fn a_clock_set_back_holds_off_the_late_night_prompt_until_it_keeps_steady() {
+        let _turn = Turn::take();
+        let clock = Rc::new(ManualClock::starting_at(time::macros::datetime!(2024-03-04 22:00 UTC)));
+        let mut app = app(&clock);
+        app.settings.wind_down_at = Some([21, 0]);
+        assert!(!app.check_clock());
+        clock.advance(Duration::from_secs(1));
+        assert!(!app.check_clock());
+
+        clock.set_wall(clock.wall() - Duration::from_secs(30 * 60));
+        assert!(app.check_clock());
+        assert!(!app.ask_wind_down());
+        // A frame a second for as long as it takes to trust the clock again
+        let frames = clock_check::STEADY.as_secs();
+        for _ in 0..frames - 1 {
+            clock.advance(Duration::from_secs(1));
+            assert!(!app.check_clock());
+        }
+        assert!(!app.ask_wind_down());
+        clock.advance(Duration::from_secs(1));
+        assert!(!app.check_clock());
+        assert!(app.ask_wind_down());
+        // Waking from sleep is no jump
+        clock.sleep(Duration::from_secs(3600));
+        assert!(!app.check_clock());
+    }
+
+    #[test]
+    fn hours_on_the_wall_clock_between_two_frames_are_sleep() {
+        let clock = ManualClock::starting_now();
+        let last = (clock.instant(), clock.wall());
+        clock.advance(Duration::from_secs(1));
+        clock.sleep(Duration::from_secs(3 * 3600));
+        assert_eq!(asleep(last, (clock.instant(), clock.wall())), Some(Duration::from_secs(3 * 3600)));
+    }
+
+    #[test]
+    fn slow_frames_and_clocks_set_back_are_not_sleep() {
+        let clock = ManualClock::starting_now();
+        let last = (clock.instant(), clock.wall());
+        clock.advance(Duration::from_secs(20));
+        let wall = |secs: i64| last.1 + time::Duration::seconds(secs);
+        // A frame twenty seconds late, with the wall clock keeping up or slightly ahead
+        assert_eq!(asleep(last, (clock.instant(), wall(20))), None);
+        assert_eq!(asleep(last, (clock.instant(), wall(45))), None);
+        assert_eq!(asleep(last, (clock.instant(), wall(-3600))), None);
+    }
+
+    #[test]
+    fn every_interval_slept_through_is_recorded_and_only_the_last_announced() {
+        let _turn = Turn::take();
+        let started = time::macros::datetime!(2024-03-04 9:00 +01:00);
+        let clock = Rc::new(ManualClock::starting_at(started));
+        let (mut app, ctx) = (app(&clock), egui::Context::default());
+        assert!(app.settings.auto_start_next);
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let kinds = [Kind::PeriodStarted, Kind::PeriodFinished, Kind::WokeUp];
+        let sink = Arc::clone(&seen);
+        app.events.subscribe("test", Filter::only(&kinds), move |event| sink.lock().unwrap().push(event.kind()));
+        app.start_period();
+        clock.advance(minutes(5));
+        // An hour asleep: the rest of one focus session, a break, another session and a break
+        clock.sleep(minutes(60));
+        let woke_up = app.detect_sleep(false).is_some();
+        app.finish_period(&ctx, woke_up);
+
+        let sessions = app.history.wait().unwrap().sessions.clone();
+        let recorded: Vec<_> = sessions.iter().map(|session| (session.start, session.duration_secs, session.while_away)).collect();
+        assert_eq!(recorded, [(started, 25 * 60, true), (started + minutes(30), 25 * 60, true)]);
+        assert_eq!((app.timer.phase(), app.timer.is_running()), (Phase::Work, true));
+        assert_eq!(app.timer.elapsed(clock.instant()), minutes(5));
+        assert_eq!(app.period_started, Some(started + minutes(60)));
+        assert_eq!(app.toasts.texts().collect::<Vec<_>>(), ["4 intervals elapsed while your computer was asleep"]);
+        app.events.unsubscribe("test");
+        let announced = [Kind::PeriodStarted, Kind::WokeUp, Kind::PeriodFinished, Kind::PeriodStarted];
+        assert_eq!(*seen.lock().unwrap(), announced);
+    }
+
+    #[test]
+    fn an_interval_slept_through_ends_once_on_waking_without_auto_start() {
+        let _turn = Turn::take();
+        let started = time::macros::datetime!(2024-03-04 9:00 +01:00);
+        let clock = Rc::new(ManualClock::starting_at(started));
+        let (mut app, ctx) = (app(&clock), egui::Context::default());
+        app.settings.auto_start_next = false;
+        app.start_period();
+        clock.advance(minutes(5));
+        clock.sleep(Duration::from_secs(3 * 3600));
+        let woke_up = app.detect_sleep(false).is_some();
+        app.finish_period(&ctx, woke_up);
+
+        let sessions = app.history.wait().unwrap().sessions.clone();
+        assert_eq!(sessions.iter().map(|session| (session.start, session.while_away)).collect::<Vec<_>>(), [(started, true)]);
+        // The break waits to be started rather than ending unseen too
+        assert_eq!((app.timer.phase(), app.timer.is_running()), (Phase::Break, false));
+        assert_eq!(app.toasts.texts().collect::<Vec<_>>(), ["Your focus session ended while your computer was asleep"]);
+    }
+}
@@ -0,0 +1,92 @@
+use eframe::egui;
+use std::time::{Duration, Instant};
+
+use crate::assets::{self, Asset};
+
+const FRAME_SIZE: u32 = 32;
+const FRAMES: u32 = 2;
+// Independent of how often the window repaints
+pub const FRAME_TIME: Duration = Duration::from_millis(400);
+// How long a reaction plays before the mascot goes back to what the timer is doing
+const REACTION_TIME: Duration = Duration::new(3, 0);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mood {
+    Working,
+    Napping,
+    Celebrating,
+    Worried,
+}
+
+impl Mood {
+    fn row(self) -> u32 {
+        match self {
+            Mood::Working => 0,
+            Mood::Napping => 1,
+            Mood::Celebrating => 2,
+            Mood::Worried => 3,
+        }
+    }
+}
+
+/// A small Ferris next to the timer, animated from a sprite sheet.
+#[derive(Default)]
+pub struct Mascot {
+    texture: Option<egui::TextureHandle>,
+    broken: bool, // The sprite sheet failed to decode, don't retry every frame
+    reaction: Option<(Mood, Instant)>,
+}
+
+impl Mascot {
+    /// Plays a short one-off mood, such as celebrating a finished session.
+    pub fn react(&mut self, mood: Mood) {
+        self.reaction = Some((mood, Instant::now()));
+    }
+
+    /// Paints next to `anchor` without taking up layout space. `steady` shows the first frame only.
+    pub fn paint(&mut self, ui: &egui::Ui, anchor: egui::Rect, base: Mood, steady: bool) {
+        self.reaction = self.reaction.filter(|(_, since)| since.elapsed() < REACTION_TIME);
+        let mood = self.reaction.map_or(base, |(mood, _)| mood);
+        let Some(texture) = self.texture(ui.ctx()) else {
+            return;
+        };
+        let frame = if steady {
+            0
+        } else {
+            let ticks = ui.input(|i| i.time) / FRAME_TIME.as_secs_f64();
+            ticks as u32 % FRAMES
+        };
+
+        let [width, height] = texture.size().map(|side| side as f32);
+        let uv = egui::Rect::from_min_size(
+            egui::pos2(
+                (frame * FRAME_SIZE) as f32 / width,
+                (mood.row() * FRAME_SIZE) as f32 / height,
+            ),
+            egui::vec2(FRAME_SIZE as f32 / width, FRAME_SIZE as f32 / height),
+        );
+        let side = FRAME_SIZE as f32 * 1.5;
+        let rect = egui::Rect::from_min_size(
+            egui::pos2(anchor.right() + 8.0, anchor.center().y - side / 2.0),
+            egui::vec2(side, side),
+        );
+        ui.painter().image(texture.id(), rect, uv, egui::Color32::WHITE);
+    }
+
+    fn texture(&mut self, ctx: &egui::Context) -> Option<&egui::TextureHandle> {
+        if self.texture.is_none() && !self.broken {
+            let icon = match eframe::icon_data::from_png_bytes(&assets::load(Asset::MascotSprites)) {
+                Ok(icon) => icon,
+                Err(err) => {
+                    log::warn!("Cannot decode mascot sprites: {}", err);
+                    self.broken = true;
+                    return None;
+                }
+            };
+            let image = egui::ColorImage::from_rgba_unmultiplied([icon.width as usize, icon.height as usize], &icon.rgba);
+            // Nearest filtering keeps the pixel art crisp when scaled up
+            self.texture = Some(ctx.load_texture("mascot", image, egui::TextureOptions::NEAREST));
+        }
+        self.texture.as_ref()
+    }
+}
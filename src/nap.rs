@@ -0,0 +1,80 @@
+//! A power nap in place of the rest of a break. The window goes dark but for a faint
+//! countdown, the wake alarm grows louder over twenty seconds, and nothing starts until being
+//! awake is confirmed, so a focus session never begins under someone still asleep.
+
+use eframe::egui;
+use std::time::{Duration, Instant};
+use time::OffsetDateTime;
+
+use crate::history;
+
+pub const MINUTES: [u32; 3] = [10, 15, 20];
+const RAMP: Duration = Duration::new(20, 0);
+// Where the alarm starts, as a share of the alarm volume
+const FIRST_VOLUME: f32 = 0.1;
+const FAINT: egui::Color32 = egui::Color32::from_gray(70);
+
+pub struct Nap {
+    start: OffsetDateTime,
+    minutes: u32,
+    /// Set once the nap is over and the wake alarm started.
+    ringing_since: Option<Instant>,
+}
+
+impl Nap {
+    pub fn new(start: OffsetDateTime, minutes: u32) -> Self {
+        Self { start, minutes, ringing_since: None }
+    }
+
+    pub fn length(&self) -> Duration {
+        Duration::from_secs(u64::from(self.minutes) * 60)
+    }
+
+    pub fn wake(&mut self, now: Instant) {
+        self.ringing_since.get_or_insert(now);
+    }
+
+    /// How loud the wake alarm plays at `now`, as a share of the alarm volume; None while
+    /// still asleep.
+    pub fn volume(&self, now: Instant) -> Option<f32> {
+        let ringing = now.saturating_duration_since(self.ringing_since?);
+        Some(FIRST_VOLUME + (1.0 - FIRST_VOLUME) * pomodoro_timer::timer::fraction(ringing, RAMP))
+    }
+
+    /// The history record for a nap left at `now`.
+    pub fn finish(&self, now: Instant) -> history::Nap {
+        let woke_after = self.ringing_since.map_or(Duration::ZERO, |since| now.saturating_duration_since(since));
+        history::Nap { start: self.start, minutes: self.minutes, woke_after_secs: woke_after.as_secs() }
+    }
+
+    /// Covers the whole window, swallowing clicks meant for the controls below. Returns true
+    /// once being awake was confirmed, whether the nap was over or not.
+    pub fn show(&self, ctx: &egui::Context, remaining: Duration) -> bool {
+        let mut awake = false;
+        egui::Area::new(egui::Id::new("nap"))
+            .fixed_pos(egui::Pos2::ZERO)
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                let rect = ctx.screen_rect();
+                ui.painter().rect_filled(rect, 0.0, egui::Color32::BLACK);
+                ui.allocate_rect(rect, egui::Sense::click_and_drag());
+                ui.allocate_ui_at_rect(rect, |ui| {
+                    ui.vertical_centered(|ui| {
+                        ui.add_space(rect.height() / 3.0);
+                        crate::compact_text(ui.style_mut());
+                        let secs = remaining.as_secs();
+                        ui.label(egui::RichText::new(format!("{}:{:02}", secs / 60, secs % 60)).size(32.0).color(FAINT));
+                        ui.add_space(20.0);
+                        if self.ringing_since.is_some() {
+                            ui.label("Time to wake up");
+                            awake = ui.button("I'm awake").clicked();
+                            ui.weak("Focus starts once you are back");
+                        } else {
+                            awake = ui.add(egui::Button::new(egui::RichText::new("Wake up now").color(FAINT)).frame(false)).clicked();
+                        }
+                    });
+                });
+            });
+        awake
+    }
+}
@@ -0,0 +1,95 @@
+//! The outputs that follow the countdown: the status and summary files, the window title, the
+//! local API, and the status the D-Bus service and the status socket answer from. Rather than
+//! each being refreshed on every frame, one output tick refreshes them together when the status
+//! changes, which is once a second while the timer counts, on input, and once a minute
+//! otherwise, so an idle timer refreshes nothing. Each sink is only written when what it
+//! renders changed, and the writes and skips of the last minute are counted for the developer
+//! panel.
+
+use eframe::egui;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::ipc;
+
+/// Refreshed at least this often, for what changes with the date rather than the status.
+const AT_LEAST: Duration = Duration::from_secs(60);
+const WINDOW: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sink {
+    StatusFile,
+    SummaryFile,
+    Title,
+    LocalApi,
+    /// Read by the D-Bus service and the status socket.
+    Shared,
+}
+
+impl Sink {
+    pub const ALL: [Sink; 5] = [Sink::StatusFile, Sink::SummaryFile, Sink::Title, Sink::LocalApi, Sink::Shared];
+
+    fn name(self) -> &'static str {
+        match self {
+            Sink::StatusFile => "status.json",
+            Sink::SummaryFile => "summary.json",
+            Sink::Title => "window title",
+            Sink::LocalApi => "local API",
+            Sink::Shared => "D-Bus and socket",
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct OutputTick {
+    /// When the outputs were last refreshed, and from what.
+    last: Option<(Instant, ipc::Status)>,
+    /// Within the last minute: the ticks, and each sink's writes and skips.
+    ticks: VecDeque<Instant>,
+    writes: [VecDeque<Instant>; 5],
+    skips: [VecDeque<Instant>; 5],
+}
+
+impl OutputTick {
+    /// Whether the outputs are refreshed this frame: when `status` differs from the last one,
+    /// on `input`, or once the last refresh is a minute old.
+    pub fn due(&mut self, now: Instant, status: &ipc::Status, input: bool) -> bool {
+        let due = match &self.last {
+            Some((at, last)) => last != status || input || now.saturating_duration_since(*at) >= AT_LEAST,
+            None => true,
+        };
+        if due {
+            self.last = Some((now, status.clone()));
+            self.ticks.push_back(now);
+        }
+        due
+    }
+
+    /// Notes whether `sink` was written on this tick or left as it was.
+    pub fn record(&mut self, sink: Sink, now: Instant, wrote: bool) {
+        let index = Sink::ALL.iter().position(|each| *each == sink).unwrap_or_default();
+        if wrote {
+            self.writes[index].push_back(now);
+        } else {
+            self.skips[index].push_back(now);
+        }
+    }
+
+    /// Ticks and, per sink, writes and skips in the last minute, for the developer panel.
+    pub fn show(&mut self, ui: &mut egui::Ui, now: Instant) {
+        for times in std::iter::once(&mut self.ticks).chain(&mut self.writes).chain(&mut self.skips) {
+            while times.front().is_some_and(|at| now.saturating_duration_since(*at) > WINDOW) {
+                times.pop_front();
+            }
+        }
+        ui.label(format!("Outputs refreshed {} times a minute", self.ticks.len()));
+        egui::Grid::new("output sinks").show(ui, |ui| {
+            for (index, sink) in Sink::ALL.into_iter().enumerate() {
+                ui.weak(sink.name());
+                ui.weak(format!("{} writes", self.writes[index].len()));
+                ui.weak(format!("{} unchanged", self.skips[index].len()));
+                ui.end_row();
+            }
+        });
+    }
+}
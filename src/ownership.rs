@@ -0,0 +1,126 @@
+//! Who may write the data folder. The running app owns it: it takes an advisory lock on
+//! `owner.lock` as it starts and holds it until it quits, and only it writes the history and the
+//! task list. A command that changes them, such as `import-tasks`, forwards the change to the
+//! running app over the status socket, and writes the files itself only once it holds the lock,
+//! with no app running. Commands that only read, such as `tmux-status` or `export-tasks`, never
+//! need it. Settings live in the config folder and are only written by the app.
+
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::{Path, PathBuf};
+
+use crate::paths;
+
+const FILE_NAME: &str = "owner.lock";
+
+/// Why the data folder can't be written.
+#[derive(Debug)]
+pub enum Refused {
+    /// Another process holds the lock, usually a running app.
+    Held(PathBuf),
+    Failed(String),
+}
+
+impl std::fmt::Display for Refused {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Refused::Held(dir) => write!(f, "a running FerrisFocus owns {}", dir.display()),
+            Refused::Failed(reason) => f.write_str(reason),
+        }
+    }
+}
+
+/// Held for as long as this process writes the data folder; dropping it lets go.
+pub struct Lock {
+    _file: File,
+}
+
+impl Lock {
+    /// Takes the data folder, refusing while another process holds it.
+    pub fn take() -> Result<Self, Refused> {
+        let dir = paths::data_dir().ok_or_else(|| Refused::Failed("there is no data folder".to_owned()))?;
+        let _ = std::fs::create_dir_all(&dir);
+        Self::take_at(&dir.join(FILE_NAME))
+    }
+
+    /// Takes the lock file at `path`.
+    pub fn take_at(path: &Path) -> Result<Self, Refused> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)
+            .map_err(|err| Refused::Failed(format!("Cannot open {}: {}", path.display(), err)))?;
+        match file.try_lock() {
+            Ok(()) => Ok(Self { _file: file }),
+            Err(TryLockError::WouldBlock) => Err(Refused::Held(path.parent().unwrap_or(path).to_path_buf())),
+            Err(TryLockError::Error(err)) => Err(Refused::Failed(format!("Cannot lock {}: {}", path.display(), err))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A folder of its own for each test, removed once it is done
+    struct Scratch(PathBuf);
+
+    impl Scratch {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("ferrisfocus-{}-{}", name, std::process::id()));
+            std::fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn a_second_taker_is_refused_until_the_first_lets_go() {
+        let scratch = Scratch::new("contention");
+        let path = scratch.0.join(FILE_NAME);
+        let held = Lock::take_at(&path).unwrap();
+        match Lock::take_at(&path) {
+            Err(Refused::Held(dir)) => assert_eq!(dir, scratch.0),
+            Ok(_) => panic!("a second taker got the lock too"),
+            Err(err) => panic!("{}", err),
+        }
+        drop(held);
+        assert!(Lock::take_at(&path).is_ok(), "the lock wasn't let go of");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn an_import_is_forwarded_to_the_running_app_and_answered() {
+        use crate::ipc::{self, Request};
+        use pomodoro_timer::tasks::{Task, TaskList};
+        use std::sync::Arc;
+        use std::time::{Duration, Instant};
+
+        let scratch = Scratch::new("forwarding");
+        let socket = scratch.0.join("ferrisfocus.sock");
+        let list = TaskList::new(vec![Task::new("Write the report", 2, time::OffsetDateTime::UNIX_EPOCH)]);
+        let request = Request::ImportTasks(list);
+        assert!(ipc::forward_to(&socket, &request).is_none(), "forwarded with no app running");
+        let server = ipc::Server::listen(socket.clone(), Arc::default()).unwrap();
+        let client = std::thread::spawn(move || ipc::forward_to(&socket, &request));
+        let started = Instant::now();
+        let forwarded = loop {
+            if let Some(forwarded) = server.requests().next() {
+                break forwarded;
+            }
+            assert!(started.elapsed() < Duration::from_secs(2), "the request never reached the app");
+            std::thread::sleep(Duration::from_millis(5));
+        };
+        let Request::ImportTasks(list) = &forwarded.request else {
+            panic!("{:?} arrived instead of the import", forwarded.request);
+        };
+        let answer = format!("{} tasks added, 0 updated", list.tasks.len());
+        let _ = forwarded.reply.send(Ok(answer.clone()));
+        assert_eq!(client.join().unwrap(), Some(Ok(answer)));
+    }
+}
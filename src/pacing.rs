@@ -0,0 +1,106 @@
+//! How much focus today asks for to stay on track for a weekly goal. The goal is spread evenly
+//! over the workdays, days off ask for nothing, and minutes an earlier workday fell short by
+//! can be carried into today, up to a cap. Pure, so it can be checked against made-up weeks.
+//!
+//! Ten hours over a Monday-to-Friday week is two hours a day; one short on Tuesday is made up
+//! on Wednesday as far as the cap allows:
+//!
+//! ```
+//! use pomodoro_timer::pacing::{today, WeeklyGoal};
+//! use time::Weekday;
+//!
+//! let mut goal = WeeklyGoal { minutes: 600, workdays: [true, true, true, true, true, false, false], carry_over_cap: None };
+//! let week = [120, 60, 30, 0, 0, 0, 0];
+//! let pace = today(&goal, &week, Weekday::Wednesday);
+//! assert_eq!((pace.done, pace.target, pace.carried, pace.needed), (210, 120, 0, 90));
+//!
+//! goal.carry_over_cap = Some(45);
+//! let pace = today(&goal, &week, Weekday::Wednesday);
+//! assert_eq!((pace.target, pace.carried, pace.needed), (165, 45, 135));
+//! assert_eq!(pace.pomodoros(25), 6);
+//!
+//! // The weekend asks for nothing, and what was missed waits for Monday's goal to start over
+//! assert_eq!(today(&goal, &week, Weekday::Saturday).target, 0);
+//! ```
+//!
+//! A goal that doesn't divide evenly still adds up, and one already met asks for no more:
+//!
+//! ```
+//! use pomodoro_timer::pacing::{today, WeeklyGoal};
+//! use time::Weekday;
+//!
+//! let goal = WeeklyGoal { minutes: 100, workdays: [true, false, true, false, true, false, false], carry_over_cap: Some(60) };
+//! let days = [Weekday::Monday, Weekday::Wednesday, Weekday::Friday];
+//! let mut week = [0; 7];
+//! let mut total = 0;
+//! for day in days {
+//!     let pace = today(&goal, &week, day);
+//!     week[usize::from(day.number_days_from_monday())] = pace.target;
+//!     total += pace.target;
+//! }
+//! assert_eq!(total, 100);
+//!
+//! let week = [90, 0, 20, 0, 0, 0, 0];
+//! let pace = today(&goal, &week, Weekday::Wednesday);
+//! assert_eq!((pace.target, pace.needed), (10, 0));
+//! assert_eq!(today(&goal, &[0; 7], Weekday::Tuesday).target, 0);
+//! ```
+
+use time::Weekday;
+
+/// Focus minutes to aim for in a week, Monday to Sunday.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WeeklyGoal {
+    pub minutes: u32,
+    /// Which days the goal is spread over, Monday first.
+    pub workdays: [bool; 7],
+    /// The most minutes an earlier shortfall adds to a day; nothing is carried over when unset.
+    pub carry_over_cap: Option<u32>,
+}
+
+/// Where today stands against the weekly goal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pace {
+    /// Focus minutes this week so far, today's included.
+    pub done: u32,
+    /// What today asks for in all, carried-over minutes included; nothing on a day off.
+    pub target: u32,
+    /// The part of `target` carried over from earlier workdays.
+    pub carried: u32,
+    /// What is left of `target` today.
+    pub needed: u32,
+}
+
+impl Pace {
+    /// `needed` in sessions of `work_minutes`, rounded up.
+    pub fn pomodoros(&self, work_minutes: u32) -> u32 {
+        self.needed.div_ceil(work_minutes.max(1))
+    }
+}
+
+/// Today's pace towards `goal`, from the focus minutes of each day of this week, Monday first;
+/// days after `today` are ignored.
+pub fn today(goal: &WeeklyGoal, week: &[u32; 7], today: Weekday) -> Pace {
+    let today = usize::from(today.number_days_from_monday());
+    let done_before: u32 = week[..today].iter().sum();
+    let done = done_before + week[today];
+    let expected_before: u32 = (0..today).map(|day| share(goal, day)).sum();
+    let carried = match goal.carry_over_cap {
+        Some(cap) if goal.workdays[today] => expected_before.saturating_sub(done_before).min(cap),
+        _ => 0,
+    };
+    // Never more than is left of the week's goal
+    let target = (share(goal, today) + carried).min(goal.minutes.saturating_sub(done_before));
+    Pace { done, target, carried: carried.min(target), needed: target.saturating_sub(week[today]) }
+}
+
+// The goal split over the workdays so that the shares add up to it exactly
+fn share(goal: &WeeklyGoal, day: usize) -> u32 {
+    if !goal.workdays[day] {
+        return 0;
+    }
+    let workdays = goal.workdays.iter().filter(|workday| **workday).count() as u64;
+    let before = goal.workdays[..day].iter().filter(|workday| **workday).count() as u64;
+    let minutes = u64::from(goal.minutes);
+    (minutes * (before + 1) / workdays - minutes * before / workdays) as u32
+}
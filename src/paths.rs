@@ -0,0 +1,49 @@
+use directories::ProjectDirs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+static DATA_DIR_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+static CONFIG_DIR_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+fn project_dirs() -> Option<ProjectDirs> {
+    ProjectDirs::from("org", "FerrisFocus", "FerrisFocus")
+}
+
+/// Where settings and user overrides live, e.g. `~/.config/ferrisfocus` on Linux.
+pub fn config_dir() -> Option<PathBuf> {
+    if let Some(dir) = CONFIG_DIR_OVERRIDE.get() {
+        return Some(dir.clone());
+    }
+    project_dirs().map(|dirs| dirs.config_dir().to_path_buf())
+}
+
+/// Where recorded data lives, e.g. `~/.local/share/ferrisfocus` on Linux.
+pub fn data_dir() -> Option<PathBuf> {
+    if let Some(dir) = DATA_DIR_OVERRIDE.get() {
+        return Some(dir.clone());
+    }
+    project_dirs().map(|dirs| dirs.data_dir().to_path_buf())
+}
+
+/// Where sockets live: `$XDG_RUNTIME_DIR` where set, which is private to the user and session,
+/// otherwise the data dir.
+pub fn runtime_dir() -> Option<PathBuf> {
+    if DATA_DIR_OVERRIDE.get().is_none() {
+        if let Some(dir) = std::env::var_os("XDG_RUNTIME_DIR").filter(|dir| !dir.is_empty()) {
+            return Some(PathBuf::from(dir).join("ferrisfocus"));
+        }
+    }
+    data_dir()
+}
+
+/// Sends all recorded data to a fresh temporary folder for the rest of the run.
+pub fn use_throwaway_data_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ferrisfocus-demo-{}", std::process::id()));
+    DATA_DIR_OVERRIDE.get_or_init(|| dir).clone()
+}
+
+/// Keeps settings changes away from the real config for the rest of the run.
+pub fn use_throwaway_config_dir() -> PathBuf {
+    let dir = use_throwaway_data_dir().join("config");
+    CONFIG_DIR_OVERRIDE.get_or_init(|| dir).clone()
+}
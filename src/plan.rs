@@ -0,0 +1,179 @@
+//! The task list, each task with an estimate in pomodoros, and when today's are likely done.
+//! Tasks stay until removed; finished ones drop out of view the day after.
+
+use eframe::egui;
+use pomodoro_timer::forecast::Forecast;
+use pomodoro_timer::tasks::{Merge, Task, TaskList};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use time::macros::format_description;
+use time::OffsetDateTime;
+
+use crate::{archive, paths};
+
+const FILE_NAME: &str = "plan.json";
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Plan {
+    pub tasks: Vec<Task>,
+}
+
+impl Plan {
+    pub fn load() -> Self {
+        path()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|text| serde_json::from_str::<Self>(&text).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        let Some(path) = path() else {
+            return;
+        };
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let result = serde_json::to_string(self)
+            .map_err(|e| e.to_string())
+            .and_then(|json| std::fs::write(&path, json).map_err(|e| e.to_string()));
+        if let Err(err) = result {
+            log::warn!("Cannot save {}: {}", path.display(), err);
+        }
+    }
+
+    /// Pomodoros still to do over the unfinished tasks.
+    pub fn remaining(&self) -> u32 {
+        self.tasks.iter().map(Task::remaining).sum()
+    }
+
+    fn unfinished(&self) -> usize {
+        self.tasks.iter().filter(|task| task.remaining() > 0).count()
+    }
+
+    /// Counts a finished session towards the first unfinished task of its label, if any.
+    pub fn record_session(&mut self, label: &str, session: &str, now: OffsetDateTime) -> bool {
+        let Some(task) = self.tasks.iter_mut().find(|task| !task.done && task.name == label) else {
+            return false;
+        };
+        task.record_session(session, now);
+        true
+    }
+
+    /// Archives the tasks left untouched for [`archive::STALE_DAYS`]; returns how many.
+    pub fn archive_stale(&mut self, now: OffsetDateTime) -> usize {
+        let cutoff = now - time::Duration::days(archive::STALE_DAYS);
+        let mut count = 0;
+        for task in self.tasks.iter_mut().filter(|task| !task.archived && task.edited < cutoff) {
+            task.archived = true;
+            task.edited = now;
+            count += 1;
+        }
+        count
+    }
+
+    /// Unarchived tasks that still need work, for suggestions.
+    pub fn open_tasks(&self) -> impl Iterator<Item = &str> {
+        self.tasks.iter().filter(|task| task.remaining() > 0).map(|task| task.name.as_str())
+    }
+
+    pub fn export(&self) -> TaskList {
+        TaskList::new(self.tasks.clone())
+    }
+
+    /// Merges a list exported elsewhere into this one.
+    pub fn import(&mut self, list: TaskList) -> Merge {
+        let mut tasks = self.export();
+        let merge = tasks.merge(list);
+        self.tasks = tasks.tasks;
+        merge
+    }
+}
+
+// Whether the task belongs in today's view: unfinished, or finished today; archived ones
+// only when asked for
+fn current(task: &Task, now: OffsetDateTime, include_archived: bool) -> bool {
+    if task.archived {
+        include_archived
+    } else {
+        task.remaining() > 0 || task.edited.date() == now.date()
+    }
+}
+
+fn path() -> Option<PathBuf> {
+    paths::data_dir().map(|dir| dir.join(FILE_NAME))
+}
+
+/// What the user did in [`show`].
+#[derive(Default)]
+pub struct Edit {
+    /// A change worth saving.
+    pub changed: bool,
+    /// A task to work on next.
+    pub picked: Option<String>,
+}
+
+/// Today's tasks with progress per task, and the forecast for the unfinished ones.
+pub fn show(ui: &mut egui::Ui, plan: &mut Plan, now: OffsetDateTime, include_archived: bool, forecast: Option<Forecast>) -> Edit {
+    let mut edit = Edit::default();
+    let mut removed = None;
+    egui::Grid::new("plan").show(ui, |ui| {
+        for (index, task) in plan.tasks.iter_mut().enumerate().filter(|(_, task)| current(task, now, include_archived)) {
+            let mut changed = ui.checkbox(&mut task.done, "").on_hover_text("Done").changed();
+            let name = ui.add_enabled(!task.archived, egui::TextEdit::singleline(&mut task.name).desired_width(160.0));
+            changed |= name.lost_focus();
+            name.context_menu(|ui| {
+                crate::compact_text(ui.style_mut());
+                if ui.button(if task.archived { "Unarchive task" } else { "Archive task" }).clicked() {
+                    task.archived = !task.archived;
+                    changed = true;
+                    ui.close_menu();
+                }
+            });
+            ui.label(format!("{} of", task.consumed));
+            let estimate = ui.add(egui::DragValue::new(&mut task.estimate).range(1..=16).suffix(" 🍅"));
+            changed |= estimate.drag_stopped() || estimate.lost_focus();
+            if ui.small_button("▶").on_hover_text("Work on this next").clicked() {
+                edit.picked = Some(task.name.clone());
+            }
+            if ui.small_button("✖").clicked() {
+                removed = Some(index);
+            }
+            if changed {
+                task.edited = now;
+                edit.changed = true;
+            }
+            ui.end_row();
+        }
+    });
+    if let Some(index) = removed {
+        plan.tasks.remove(index);
+        edit.changed = true;
+    }
+    if ui.small_button("Add task").clicked() {
+        let count = plan.tasks.iter().filter(|task| current(task, now, false)).count();
+        plan.tasks.push(Task::new(format!("Task {}", count + 1), 1, now));
+        edit.changed = true;
+    }
+    if let Some(forecast) = forecast {
+        let time = |at: OffsetDateTime| at.format(format_description!("[hour]:[minute]")).unwrap_or_default();
+        let tasks = plan.unfinished();
+        let pace = if forecast.from_history { "At your usual pace" } else { "Adding up the estimates" };
+        ui.weak(format!(
+            "≈ {}, you'll finish {} {} around {} ({}–{})",
+            pace,
+            if tasks == 1 { "this" } else { "these" },
+            if tasks == 1 { "task".to_owned() } else { format!("{} tasks", tasks) },
+            time(forecast.likely),
+            time(forecast.earliest),
+            time(forecast.latest),
+        ))
+        .on_hover_text(if forecast.from_history {
+            "An estimate from how many sessions you finished after this time of day on recent days"
+        } else {
+            "An estimate; with a few more days of history it follows your own pace"
+        });
+    } else if plan.tasks.iter().any(|task| current(task, now, false)) {
+        ui.weak("All planned tasks are done");
+    }
+    edit
+}
@@ -0,0 +1,50 @@
+use eframe::egui;
+use std::time::{Duration, Instant};
+
+/// Alternates between suggesting sitting and standing after every so much running work time,
+/// independently of the interval the timer is in.
+#[derive(Default)]
+pub struct Posture {
+    pub standing: bool,
+    worked: Duration,
+    last_tick: Option<Instant>,
+}
+
+impl Posture {
+    /// Counts time only while `counting`, so the cycle pauses with the timer.
+    /// Returns true when it is time to switch.
+    pub fn tick(&mut self, now: Instant, counting: bool, interval: Duration) -> bool {
+        let last = self.last_tick.replace(now);
+        if !counting {
+            return false;
+        }
+        self.worked += last.map_or(Duration::ZERO, |last| now.saturating_duration_since(last));
+        if self.worked < interval {
+            return false;
+        }
+        self.worked = Duration::ZERO;
+        self.standing = !self.standing;
+        true
+    }
+
+    /// What to say when switching to the current posture.
+    pub fn suggestion(&self) -> &'static str {
+        if self.standing {
+            "Time to stand"
+        } else {
+            "Time to sit down"
+        }
+    }
+
+    pub fn show(&self, ctx: &egui::Context, interval: Duration) {
+        let left = interval.saturating_sub(self.worked).as_secs().div_ceil(60);
+        let (icon, now, next) = if self.standing { ("⬆", "Standing", "sit") } else { ("⬇", "Sitting", "stand") };
+        egui::Area::new(egui::Id::new("posture"))
+            .anchor(egui::Align2::LEFT_BOTTOM, [10.0, -10.0])
+            .interactable(false)
+            .show(ctx, |ui| {
+                crate::compact_text(ui.style_mut());
+                ui.weak(format!("{} {} · {} in {} min", icon, now, next, left));
+            });
+    }
+}
@@ -0,0 +1,79 @@
+use eframe::egui;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// Battery levels change slowly; checking more often would cost the power we try to save
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Battery {
+    pub percent: u8,
+    pub discharging: bool,
+}
+
+impl Battery {
+    /// Running on battery at or below `threshold` percent.
+    pub fn low(&self, threshold: u8) -> bool {
+        self.discharging && self.percent <= threshold
+    }
+}
+
+/// The battery state as last polled on a background thread; `None` on desktops and where
+/// it can't be read.
+#[derive(Default)]
+pub struct Monitor {
+    battery: Arc<Mutex<Option<Battery>>>,
+}
+
+impl Monitor {
+    /// Repaints `ctx` when the state changes, so plugging in restores smooth drawing at once.
+    pub fn start(ctx: &egui::Context) -> Self {
+        let monitor = Self::default();
+        let (battery, ctx) = (Arc::clone(&monitor.battery), ctx.clone());
+        let spawned = std::thread::Builder::new().name("battery".to_owned()).spawn(move || loop {
+            let state = read();
+            match battery.lock() {
+                Ok(mut last) if *last != state => {
+                    *last = state;
+                    ctx.request_repaint();
+                }
+                Ok(_) => {}
+                Err(_) => return,
+            }
+            if state.is_none() {
+                // No battery now means none later either
+                return;
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        });
+        if let Err(err) = spawned {
+            log::warn!("Cannot start battery thread: {}", err);
+        }
+        monitor
+    }
+
+    pub fn battery(&self) -> Option<Battery> {
+        self.battery.lock().ok().and_then(|battery| *battery)
+    }
+}
+
+/// The first battery under `/sys/class/power_supply`.
+#[cfg(target_os = "linux")]
+pub fn read() -> Option<Battery> {
+    let entries = std::fs::read_dir("/sys/class/power_supply").ok()?;
+    entries.flatten().find_map(|entry| {
+        let dir = entry.path();
+        let kind = std::fs::read_to_string(dir.join("type")).ok()?;
+        if kind.trim() != "Battery" {
+            return None;
+        }
+        let percent = std::fs::read_to_string(dir.join("capacity")).ok()?.trim().parse::<u8>().ok()?;
+        let status = std::fs::read_to_string(dir.join("status")).ok()?;
+        Some(Battery { percent: percent.min(100), discharging: status.trim() == "Discharging" })
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn read() -> Option<Battery> {
+    None
+}
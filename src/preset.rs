@@ -0,0 +1,237 @@
+use pomodoro_timer::duration_text;
+use serde_json::{Map, Value};
+use std::time::Duration;
+
+use crate::settings::Settings;
+use crate::stats::Fingerprint;
+
+const PREFIX: &str = "ferrisfocus-settings:";
+const VERSION: u64 = 1;
+/// Settings that make sense on someone else's machine: no file paths, nothing about debugging.
+/// Keys missing from a preset keep their current value, so presets from older versions still apply.
+const SHAREABLE: [&str; 17] = [
+    "volume",
+    "ambient_volume",
+    "confirm_quit",
+    "keep_sessions_months",
+    "daily_goal",
+    "work_minutes",
+    "break_minutes",
+    "long_break_minutes",
+    "enforced_break_secs",
+    "title_template",
+    "tint_borders",
+    "focus_border_color",
+    "show_mascot",
+    "reduce_motion",
+    "show_quotes",
+    "posture_minutes",
+    "posture_sound",
+];
+
+/// The shareable settings as one line of text: a prefix, then base64 of the JSON and a checksum.
+pub fn export(settings: &Settings) -> String {
+    let mut map: Map<String, Value> = to_map(settings).into_iter().filter(|(key, _)| SHAREABLE.contains(&key.as_str())).collect();
+    map.insert("v".to_owned(), Value::from(VERSION));
+    let mut bytes = serde_json::to_vec(&map).unwrap_or_default();
+    let checksum = Fingerprint::of(&bytes).hash as u32;
+    bytes.extend(checksum.to_le_bytes());
+    format!("{}{}", PREFIX, encode(&bytes))
+}
+
+/// A preset that was read and checked, ready to preview and apply.
+pub struct Import {
+    pub settings: Settings,
+    /// One line per changed setting, `name: old → new`.
+    pub changes: Vec<String>,
+    /// Set when the preset needed adjusting, e.g. it came from a newer version.
+    pub note: Option<String>,
+}
+
+pub fn import(text: &str, current: &Settings) -> Result<Import, String> {
+    let encoded = text.trim().strip_prefix(PREFIX).ok_or("this is not a FerrisFocus settings text")?;
+    let bytes = decode(encoded).ok_or("the text contains characters that don't belong there")?;
+    if bytes.len() < 4 {
+        return Err("the text is cut off".to_owned());
+    }
+    let (json, checksum) = bytes.split_at(bytes.len() - 4);
+    if checksum != (Fingerprint::of(json).hash as u32).to_le_bytes() {
+        return Err("the text is damaged or incomplete, copy it again".to_owned());
+    }
+    let preset: Map<String, Value> = serde_json::from_slice(json).map_err(|_| "the text is damaged".to_owned())?;
+
+    let mut notes = Vec::new();
+    if preset.get("v").and_then(Value::as_u64).is_some_and(|v| v > VERSION) {
+        notes.push("it comes from a newer version, unknown settings are skipped".to_owned());
+    }
+    let old = to_map(current);
+    let mut merged = old.clone();
+    for (key, value) in preset {
+        if SHAREABLE.contains(&key.as_str()) {
+            merged.insert(key, value);
+        }
+    }
+    let settings: Settings =
+        serde_json::from_value(Value::Object(merged)).map_err(|e| format!("a setting has the wrong type: {}", e))?;
+    let (settings, adjusted) = settings.sanitized();
+    notes.extend(adjusted);
+
+    let new = to_map(&settings);
+    let changes = SHAREABLE
+        .iter()
+        .filter(|key| old.get(**key) != new.get(**key))
+        .map(|key| format!("{}: {} → {}", key, show(key, old.get(*key)), show(key, new.get(*key))))
+        .collect();
+    let note = (!notes.is_empty()).then(|| notes.join("; "));
+    Ok(Import { settings, changes, note })
+}
+
+pub fn to_map(settings: &Settings) -> Map<String, Value> {
+    match serde_json::to_value(settings) {
+        Ok(Value::Object(map)) => map,
+        _ => Map::new(),
+    }
+}
+
+/// `value` of the setting `key` as people read it, with lengths such as `1h30m`.
+pub fn show(key: &str, value: Option<&Value>) -> String {
+    let unit = match key {
+        "weekly_goal" => Some(60),
+        key if key.ends_with("_minutes") => Some(60),
+        key if key.ends_with("_secs") => Some(1),
+        _ => None,
+    };
+    match (value, unit) {
+        (None | Some(Value::Null), _) => "off".to_owned(),
+        (Some(Value::Number(number)), Some(unit)) => match number.as_u64() {
+            Some(count) => duration_text::format(Duration::from_secs(count.saturating_mul(unit))),
+            None => number.to_string(),
+        },
+        (Some(value), _) => value.to_string(),
+    }
+}
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+// URL-safe base64 without padding, so the text survives chat apps and URLs
+fn encode(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, b)| n | u32::from(*b) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+        }
+    }
+    out
+}
+
+fn decode(text: &str) -> Option<Vec<u8>> {
+    let digits: Vec<u32> = text
+        .bytes()
+        .map(|c| ALPHABET.iter().position(|a| *a == c).map(|p| p as u32))
+        .collect::<Option<_>>()?;
+    let mut out = Vec::new();
+    for chunk in digits.chunks(4) {
+        if chunk.len() == 1 {
+            return None;
+        }
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, d)| n | d << (18 - 6 * i));
+        for i in 0..chunk.len() - 1 {
+            out.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Seals `json` as export does, for presets export wouldn't write
+    fn seal(json: &[u8]) -> String {
+        let mut bytes = json.to_vec();
+        bytes.extend((Fingerprint::of(json).hash as u32).to_le_bytes());
+        format!("{}{}", PREFIX, encode(&bytes))
+    }
+
+    #[test]
+    fn base64_round_trips_every_length() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        // Lengths 0, 1 and 2 past a multiple of three, with every byte value in them
+        for len in [0, 1, 2, 3, 4, 5, 254, 255, 256] {
+            let encoded = encode(&bytes[..len]);
+            assert_eq!(encoded.len(), (len * 4).div_ceil(3));
+            assert_eq!(decode(&encoded).as_deref(), Some(&bytes[..len]), "length {}", len);
+        }
+    }
+
+    #[test]
+    fn base64_is_the_url_safe_alphabet_without_padding() {
+        assert_eq!(encode(b"Man"), "TWFu");
+        assert_eq!(encode(b"Ma"), "TWE");
+        assert_eq!(encode(b"M"), "TQ");
+        assert_eq!(encode(&[0xfb, 0xff]), "-_8");
+        assert_eq!(decode("TWE="), None);
+        assert_eq!(decode("TW+u"), None);
+        // A lone digit can't hold a byte
+        assert_eq!(decode("TWFuT"), None);
+    }
+
+    #[test]
+    fn the_checksum_is_fnv_1a() {
+        assert_eq!(Fingerprint::of(b"").hash, 0xcbf2_9ce4_8422_2325);
+        assert_eq!(Fingerprint::of(b"a").hash, 0xaf63_dc4c_8601_ec8c);
+        let mut pieces = Fingerprint::of(b"ferris");
+        pieces.extend(b"focus");
+        assert_eq!(pieces, Fingerprint::of(b"ferrisfocus"));
+    }
+
+    #[test]
+    fn an_export_imports_back() {
+        let settings = Settings { volume: 0.3, work_minutes: 50, ..Settings::default() };
+        let text = export(&settings);
+        let imported = import(&format!("  {}\n", text), &Settings::default()).unwrap();
+        assert_eq!(imported.settings.volume, 0.3);
+        assert_eq!(imported.settings.work_minutes, 50);
+        assert_eq!(imported.changes.len(), 2, "{:?}", imported.changes);
+        assert!(imported.changes.contains(&"work_minutes: 25m → 50m".to_owned()), "{:?}", imported.changes);
+        assert_eq!(imported.note, None);
+        assert!(import(&text, &settings).unwrap().changes.is_empty());
+    }
+
+    #[test]
+    fn a_flipped_character_is_refused() {
+        let text = export(&Settings::default());
+        for position in PREFIX.len()..text.len() {
+            let mut flipped = text.clone().into_bytes();
+            let digit = ALPHABET.iter().position(|a| *a == flipped[position]).unwrap();
+            // The top bit of a digit always lands in a byte
+            flipped[position] = ALPHABET[digit ^ 32];
+            let flipped = String::from_utf8(flipped).unwrap();
+            assert!(import(&flipped, &Settings::default()).is_err(), "flipped at {}", position);
+        }
+    }
+
+    #[test]
+    fn a_truncated_tail_is_refused() {
+        let text = export(&Settings::default());
+        for len in 0..text.len() {
+            assert!(import(&text[..len], &Settings::default()).is_err(), "cut to {}", len);
+        }
+    }
+
+    #[test]
+    fn a_preset_from_a_newer_version_applies_what_it_can() {
+        let text = seal(br#"{"v":2,"volume":0.3,"warp_drive":true,"alarm_file":"/etc/passwd"}"#);
+        let imported = import(&text, &Settings::default()).unwrap();
+        assert_eq!(imported.settings.volume, 0.3);
+        assert_eq!(imported.settings.alarm_file, Settings::default().alarm_file);
+        assert!(imported.note.is_some_and(|note| note.contains("newer version")));
+    }
+
+    #[test]
+    fn a_setting_of_the_wrong_type_is_refused() {
+        let text = seal(br#"{"v":1,"volume":"loud"}"#);
+        assert!(import(&text, &Settings::default()).err().is_some_and(|err| err.contains("wrong type")));
+    }
+}
@@ -0,0 +1,101 @@
+//! Quick timers, for "remind me in 12 minutes" without the pomodoro cycle. `ferrisfocus quick
+//! 12m --message tea` hands one to the running app, which shows it as a chip under the menu bar
+//! until it ends with the quick timer alert, or until the chip is clicked to cancel it. Any
+//! number run side by side, and none is recorded in the history. With no app running, the
+//! command counts down in the terminal itself.
+
+use eframe::egui;
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use pomodoro_timer::{duration_text, timer};
+
+use crate::alerts::Sound;
+use crate::audio::{self, Role, Shape};
+
+// How long the terminal waits for the chime to finish before exiting
+const CHIME_WAIT: Duration = Duration::from_secs(10);
+
+pub struct QuickTimer {
+    pub message: Option<String>,
+    /// On the wall clock, so a reminder set before a suspend still ends on time.
+    pub ends: SystemTime,
+}
+
+impl QuickTimer {
+    pub fn text(&self) -> &str {
+        self.message.as_deref().unwrap_or("Quick timer")
+    }
+}
+
+#[derive(Default)]
+pub struct QuickTimers {
+    /// The soonest to end first.
+    timers: Vec<QuickTimer>,
+}
+
+impl QuickTimers {
+    pub fn start(&mut self, now: SystemTime, length: Duration, message: Option<String>) -> Result<(), String> {
+        let ends = ends(now, length)?;
+        let index = self.timers.partition_point(|timer| timer.ends <= ends);
+        self.timers.insert(index, QuickTimer { message, ends });
+        Ok(())
+    }
+
+    /// The timers that ran out by `now`, taken off the list.
+    pub fn ended(&mut self, now: SystemTime) -> Vec<QuickTimer> {
+        let count = self.timers.partition_point(|timer| timer.ends <= now);
+        self.timers.drain(..count).collect()
+    }
+
+    /// A chip per timer with what is left of it; clicking one cancels it.
+    pub fn show(&mut self, ui: &mut egui::Ui, now: SystemTime) {
+        let mut cancelled = None;
+        for (index, timer) in self.timers.iter().enumerate() {
+            let left = timer.ends.duration_since(now).unwrap_or_default().as_secs();
+            let chip = egui::Button::new(format!("⏲ {} {}:{:02} ✖", timer.text(), left / 60, left % 60)).small().rounding(8.0);
+            if ui.add(chip).on_hover_text("Cancel this quick timer").clicked() {
+                cancelled = Some(index);
+            }
+        }
+        if let Some(index) = cancelled {
+            self.timers.remove(index);
+            log::info!("Quick timer cancelled");
+        }
+    }
+}
+
+/// Refuses a length no interval could have.
+pub fn check(length: Duration) -> Result<Duration, String> {
+    if length > timer::MAX_LENGTH {
+        return Err(format!("a quick timer runs at most {}", duration_text::format(timer::MAX_LENGTH)));
+    }
+    Ok(length)
+}
+
+fn ends(now: SystemTime, length: Duration) -> Result<SystemTime, String> {
+    check(length)?;
+    now.checked_add(length).ok_or_else(|| "the clock can't count that far".to_owned())
+}
+
+/// Counts `length` down on one terminal line, then says `text` and chimes.
+pub fn countdown(length: Duration, text: &str, alarm_file: Option<&Path>, volume: f32) -> Result<(), String> {
+    let ends = ends(SystemTime::now(), length)?;
+    let mut stdout = std::io::stdout();
+    while let Ok(left) = ends.duration_since(SystemTime::now()) {
+        let secs = left.as_secs();
+        let _ = write!(stdout, "\r⏲ {} {}:{:02} ", text, secs / 60, secs % 60);
+        let _ = stdout.flush();
+        std::thread::sleep(timer::until_next_second(left));
+    }
+    println!("\r⏲ {} — time's up\x07", text);
+    let mut backend = audio::backend(alarm_file, volume);
+    if backend.play(Role::Alarm, Sound::Chime, Shape::default()) {
+        let started = std::time::Instant::now();
+        while backend.busy(Role::Alarm) && started.elapsed() < CHIME_WAIT {
+            std::thread::sleep(Duration::from_millis(100));
+        }
+    }
+    Ok(())
+}
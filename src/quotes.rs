@@ -0,0 +1,97 @@
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::paths;
+
+const BUNDLED: &str = include_str!("../assets/quotes.txt");
+/// Read from the config dir and used alongside the bundled quotes.
+pub const USER_FILE: &str = "quotes.txt";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Quote {
+    pub text: String,
+    pub author: Option<String>,
+}
+
+impl Quote {
+    /// Parses `text — author`; lines without a separator have no author.
+    fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        if line.is_empty() {
+            return None;
+        }
+        match line.rsplit_once(" — ").or_else(|| line.rsplit_once(" -- ")) {
+            Some((text, author)) if !text.trim().is_empty() && !author.trim().is_empty() => Some(Self {
+                text: text.trim().to_owned(),
+                author: Some(author.trim().to_owned()),
+            }),
+            _ => Some(Self { text: line.to_owned(), author: None }),
+        }
+    }
+}
+
+impl std::fmt::Display for Quote {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.author {
+            Some(author) => write!(f, "{} — {}", self.text, author),
+            None => write!(f, "{}", self.text),
+        }
+    }
+}
+
+/// Hands out quotes in random order, without repeats until every quote has been shown.
+pub struct Quotes {
+    all: Vec<Quote>,
+    deck: Vec<usize>,
+    seed: u64,
+}
+
+impl Quotes {
+    pub fn load() -> Self {
+        let mut all = parse(BUNDLED);
+        if let Some(path) = paths::config_dir().map(|dir| dir.join(USER_FILE)) {
+            all.extend(read_user_file(&path));
+        }
+        let seed = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map_or(1, |d| d.as_nanos() as u64) | 1;
+        Self { all, deck: Vec::new(), seed }
+    }
+
+    pub fn next(&mut self) -> Option<Quote> {
+        if self.deck.is_empty() {
+            self.shuffle();
+        }
+        self.deck.pop().and_then(|index| self.all.get(index).cloned())
+    }
+
+    fn shuffle(&mut self) {
+        self.deck = (0..self.all.len()).collect();
+        for i in (1..self.deck.len()).rev() {
+            let j = (self.random() % (i as u64 + 1)) as usize;
+            self.deck.swap(i, j);
+        }
+    }
+
+    // xorshift64, plenty for picking quotes
+    fn random(&mut self) -> u64 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+        self.seed
+    }
+}
+
+fn read_user_file(path: &Path) -> Vec<Quote> {
+    match std::fs::read(path) {
+        Ok(bytes) => parse(&String::from_utf8_lossy(&bytes)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(err) => {
+            log::warn!("Cannot read {}: {}", path.display(), err);
+            Vec::new()
+        }
+    }
+}
+
+// One quote per line; blank lines and a leading byte order mark are ignored
+fn parse(text: &str) -> Vec<Quote> {
+    text.trim_start_matches('\u{feff}').lines().filter_map(Quote::parse).collect()
+}
@@ -0,0 +1,214 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use time::OffsetDateTime;
+
+use crate::actions::Action;
+use crate::events::Event;
+use crate::settings::Settings;
+use crate::{clock, diagnostics, paths};
+
+pub const FILE_NAME: &str = "recording.bin";
+// Once the file grows past this, the oldest half is dropped
+const MAX_BYTES: u64 = 4 * 1024 * 1024;
+
+/// One recorded command or event. On disk each entry is its JSON preceded by its length
+/// as a little-endian u32, so a torn final write only loses that entry.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Entry {
+    #[serde(with = "time::serde::rfc3339")]
+    pub at: OffsetDateTime,
+    pub kind: Kind,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    Started { version: String, settings: Snapshot },
+    Command { action: String, settings: Snapshot },
+    Event { name: String, detail: String },
+}
+
+/// The settings that influence timer behaviour, as they were when something happened.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub volume: f32,
+    pub confirm_quit: bool,
+    pub keep_sessions_months: Option<u32>,
+    /// The custom alarm path, or just whether there is one when redacted.
+    pub alarm: String,
+}
+
+/// Appends timer commands and events to a capped file in the data dir while
+/// `debug_recording` is on, for attaching to bug reports.
+pub struct Recorder {
+    path: PathBuf,
+    unredacted: bool,
+}
+
+impl Recorder {
+    /// `None` unless recording is switched on.
+    pub fn start(settings: &Settings) -> Option<Self> {
+        if !settings.debug_recording {
+            return None;
+        }
+        let dir = paths::data_dir()?;
+        if let Err(err) = fs::create_dir_all(&dir) {
+            log::warn!("Cannot create {}: {}", dir.display(), err);
+            return None;
+        }
+        let recorder = Self { path: dir.join(FILE_NAME), unredacted: settings.record_unredacted };
+        log::info!("Recording timer events to {}", recorder.path.display());
+        recorder.write(Kind::Started {
+            version: crate::about::VERSION.to_owned(),
+            settings: recorder.snapshot(settings),
+        });
+        Some(recorder)
+    }
+
+    /// Writes an event from the bus; this is all a recording subscriber does.
+    pub fn handle(&self, event: Event) {
+        match event {
+            Event::Command { action, settings } => self.command(action, &settings),
+            event => self.event(event.name(), event.to_string()),
+        }
+    }
+
+    fn command(&self, action: Action, settings: &Settings) {
+        self.write(Kind::Command { action: format!("{:?}", action), settings: self.snapshot(settings) });
+    }
+
+    fn event(&self, name: &str, detail: impl Into<String>) {
+        let detail = detail.into();
+        let detail = if self.unredacted { detail } else { diagnostics::scrub(&detail) };
+        self.write(Kind::Event { name: name.to_owned(), detail });
+    }
+
+    fn snapshot(&self, settings: &Settings) -> Snapshot {
+        let alarm = match &settings.alarm_file {
+            None => "bundled".to_owned(),
+            Some(path) if self.unredacted => path.display().to_string(),
+            Some(_) => "custom".to_owned(),
+        };
+        Snapshot {
+            volume: settings.volume,
+            confirm_quit: settings.confirm_quit,
+            keep_sessions_months: settings.keep_sessions_months,
+            alarm,
+        }
+    }
+
+    // Recording is best effort: a failure is logged and the app carries on
+    fn write(&self, kind: Kind) {
+        if let Err(err) = self.append(&Entry { at: clock::now(), kind }) {
+            log::warn!("Cannot write to {}: {}", self.path.display(), err);
+        }
+    }
+
+    fn append(&self, entry: &Entry) -> Result<(), String> {
+        let json = serde_json::to_vec(entry).map_err(|e| e.to_string())?;
+        let size = fs::metadata(&self.path).map_or(0, |meta| meta.len());
+        if size + json.len() as u64 + 4 > MAX_BYTES {
+            self.drop_oldest_half()?;
+        }
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| e.to_string())?;
+        let mut frame = (json.len() as u32).to_le_bytes().to_vec();
+        frame.extend(json);
+        file.write_all(&frame).map_err(|e| e.to_string())
+    }
+
+    fn drop_oldest_half(&self) -> Result<(), String> {
+        let bytes = fs::read(&self.path).map_err(|e| e.to_string())?;
+        let frames = frames(&bytes);
+        let mut kept = 0;
+        let start = frames
+            .iter()
+            .rposition(|frame| {
+                kept += frame.len() + 4;
+                kept as u64 > MAX_BYTES / 2
+            })
+            .map_or(0, |index| index + 1);
+        let mut rest = Vec::new();
+        for frame in &frames[start..] {
+            rest.extend((frame.len() as u32).to_le_bytes());
+            rest.extend_from_slice(frame);
+        }
+        let temp = self.path.with_extension("bin.tmp");
+        fs::write(&temp, rest).map_err(|e| e.to_string())?;
+        fs::rename(&temp, &self.path).map_err(|e| e.to_string())
+    }
+}
+
+/// Every readable entry of a recording, with a note on what could not be read.
+pub fn read(path: &Path) -> Result<(Vec<Entry>, Option<String>), String> {
+    let bytes = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let frames = frames(&bytes);
+    let framed: usize = frames.iter().map(|frame| frame.len() + 4).sum();
+    let mut problems = Vec::new();
+    if framed < bytes.len() {
+        problems.push(format!("{} trailing bytes are cut off", bytes.len() - framed));
+    }
+    let entries = frames
+        .iter()
+        .enumerate()
+        .filter_map(|(number, frame)| match serde_json::from_slice(frame) {
+            Ok(entry) => Some(entry),
+            Err(err) => {
+                problems.push(format!("entry {}: {}", number + 1, err));
+                None
+            }
+        })
+        .collect();
+    if problems.is_empty() {
+        return Ok((entries, None));
+    }
+    Ok((entries, Some(format!("Parts of {} are unreadable: {}", path.display(), problems.join("; ")))))
+}
+
+// Splits into length-prefixed frames, stopping at the first one that runs past the end
+fn frames(bytes: &[u8]) -> Vec<&[u8]> {
+    let mut frames = Vec::new();
+    let mut rest = bytes;
+    while let Some((length, body)) = rest.split_first_chunk::<4>() {
+        let length = u32::from_le_bytes(*length) as usize;
+        if length > body.len() {
+            break;
+        }
+        let (frame, tail) = body.split_at(length);
+        frames.push(frame);
+        rest = tail;
+    }
+    frames
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let at = self.at.format(&time::format_description::well_known::Rfc3339).map_err(|_| fmt::Error)?;
+        match &self.kind {
+            Kind::Started { version, settings } => write!(f, "{}  started   version {}, {}", at, version, settings),
+            Kind::Command { action, settings } => write!(f, "{}  command   {} ({})", at, action, settings),
+            Kind::Event { name, detail } if detail.is_empty() => write!(f, "{}  event     {}", at, name),
+            Kind::Event { name, detail } => write!(f, "{}  event     {}: {}", at, name, detail),
+        }
+    }
+}
+
+impl fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let keep = self.keep_sessions_months.map_or_else(|| "forever".to_owned(), |months| format!("{} months", months));
+        write!(
+            f,
+            "volume {:.0}%, confirm quit {}, keep sessions {}, alarm {}",
+            self.volume * 100.0,
+            self.confirm_quit,
+            keep,
+            self.alarm
+        )
+    }
+}
@@ -0,0 +1,126 @@
+//! The one place that decides when the window is drawn next. Anything animating asks for a
+//! frame at its own interval for as long as it runs, and the shortest interval wins. With
+//! nothing animating, the window is drawn as the digits change while the timer counts, and
+//! otherwise only now and then, or when something happens.
+
+use eframe::egui;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Fast enough for anything that moves smoothly.
+pub const SMOOTH: Duration = Duration::from_millis(33);
+/// Often enough for progress and fades.
+pub const PROGRESS: Duration = Duration::from_millis(100);
+
+struct Animation {
+    name: &'static str,
+    interval: Duration,
+    until: Instant,
+}
+
+#[derive(Default)]
+pub struct AnimationScheduler {
+    animations: Vec<Animation>,
+    /// The wait asked of egui last frame; None when it wasn't asked for a frame at all.
+    effective: Option<Duration>,
+    /// When the frames of the last second were drawn.
+    frames: VecDeque<Instant>,
+}
+
+impl AnimationScheduler {
+    /// Asks for a frame at least every `interval` until `until`. Asking again under the same
+    /// name replaces the earlier request, so an animation renewed on each frame it draws stops
+    /// asking soon after it is no longer drawn.
+    pub fn animate(&mut self, name: &'static str, interval: Duration, until: Instant) {
+        self.stop(name);
+        self.animations.push(Animation { name, interval, until });
+    }
+
+    /// Takes back what `name` asked for before its time is up.
+    pub fn stop(&mut self, name: &str) {
+        self.animations.retain(|animation| animation.name != name);
+    }
+
+    /// How soon the next frame is due at `now`, given when the timer next needs one: the
+    /// shortest interval asked for, but no later than the end of any animation, so the frame
+    /// that leaves it at rest is drawn too. None when nothing needs a frame.
+    pub fn next_frame(&mut self, now: Instant, timer: Option<Duration>) -> Option<Duration> {
+        self.animations.retain(|animation| animation.until > now);
+        let animations = self.animations.iter().map(|animation| animation.interval.min(animation.until - now));
+        animations.chain(timer).min()
+    }
+
+    /// Asks egui for the next frame, once for everything.
+    pub fn request(&mut self, ctx: &egui::Context, now: Instant, timer: Option<Duration>) {
+        self.frames.push_back(now);
+        while self.frames.front().is_some_and(|drawn| now - *drawn > Duration::from_secs(1)) {
+            self.frames.pop_front();
+        }
+        self.effective = self.next_frame(now, timer);
+        match self.effective {
+            Some(wait) if wait.is_zero() => ctx.request_repaint(),
+            Some(wait) => ctx.request_repaint_after(wait),
+            None => {}
+        }
+    }
+
+    /// The frame rate and what is animating, for the developer panel.
+    pub fn show(&self, ui: &mut egui::Ui) {
+        let next = match self.effective {
+            Some(wait) if wait.is_zero() => "next right away".to_owned(),
+            Some(wait) => format!("next in {} ms", wait.as_millis()),
+            None => "next on input".to_owned(),
+        };
+        ui.label(format!("Repainting at {} fps, {}", self.frames.len(), next));
+        for animation in &self.animations {
+            ui.weak(format!("{}: every {} ms", animation.name, animation.interval.as_millis()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn the_fastest_animation_sets_the_rate() {
+        let (mut scheduler, now) = (AnimationScheduler::default(), Instant::now());
+        scheduler.animate("smooth", SMOOTH, now + SECOND * 60);
+        scheduler.animate("progress", PROGRESS, now + SECOND);
+        assert_eq!(scheduler.next_frame(now, Some(SECOND)), Some(SMOOTH));
+    }
+
+    #[test]
+    fn taking_an_animation_back_lowers_the_rate() {
+        let (mut scheduler, now) = (AnimationScheduler::default(), Instant::now());
+        scheduler.animate("smooth", SMOOTH, now + SECOND * 60);
+        scheduler.animate("progress", PROGRESS, now + SECOND);
+        scheduler.stop("smooth");
+        assert_eq!(scheduler.next_frame(now, Some(SECOND)), Some(PROGRESS));
+    }
+
+    #[test]
+    fn an_animation_run_out_no_longer_counts() {
+        let (mut scheduler, now) = (AnimationScheduler::default(), Instant::now());
+        scheduler.animate("progress", PROGRESS, now + SECOND);
+        assert_eq!(scheduler.next_frame(now + SECOND, Some(SECOND)), Some(SECOND));
+        assert_eq!(scheduler.next_frame(now + SECOND, None), None, "idle with the timer stopped too");
+    }
+
+    #[test]
+    fn asking_again_replaces_the_earlier_request() {
+        let (mut scheduler, now) = (AnimationScheduler::default(), Instant::now());
+        scheduler.animate("mascot", SMOOTH, now + SECOND);
+        scheduler.animate("mascot", PROGRESS, now + SECOND);
+        assert_eq!(scheduler.next_frame(now, None), Some(PROGRESS));
+    }
+
+    #[test]
+    fn the_frame_leaving_an_animation_at_rest_is_drawn() {
+        let (mut scheduler, now) = (AnimationScheduler::default(), Instant::now());
+        scheduler.animate("theme change", SECOND * 5, now + SECOND);
+        assert_eq!(scheduler.next_frame(now, None), Some(SECOND));
+    }
+}
@@ -0,0 +1,89 @@
+//! Fixed points in the day a focus session shouldn't run into, such as a daily stand-up or the
+//! wind-down time. Starting a session that would, offers a shorter one that ends in time.
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use time::{OffsetDateTime, Time};
+
+// Left between the end of a shortened session and the appointment
+const BUFFER: Duration = Duration::from_secs(60);
+// Anything shorter isn't worth starting
+const SHORTEST: Duration = Duration::from_secs(5 * 60);
+
+/// Something at the same time every day.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Appointment {
+    pub name: String,
+    /// `[hour, minute]` in local time.
+    pub at: [u8; 2],
+}
+
+/// A session of `length` started now that an appointment would cut into.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Conflict {
+    pub name: String,
+    /// Until the appointment.
+    pub starts_in: Duration,
+    /// What still fits, in whole minutes and leaving the buffer.
+    pub fits: Duration,
+}
+
+/// The first of `appointments`, or the wind-down time, within `length` plus the buffer from
+/// `now`; none when there is too little time left for a shortened session either.
+pub fn conflict(appointments: &[Appointment], wind_down_at: Option<[u8; 2]>, length: Duration, now: OffsetDateTime) -> Option<Conflict> {
+    let wind_down = wind_down_at.map(|at| Appointment { name: "Wind-down".to_owned(), at });
+    let (name, starts_in) = appointments
+        .iter()
+        .chain(&wind_down)
+        .filter_map(|appointment| Some((&appointment.name, until(appointment.at, now)?)))
+        .filter(|(_, starts_in)| *starts_in < length + BUFFER)
+        .min_by_key(|(_, starts_in)| *starts_in)?;
+    let fits = Duration::from_secs(starts_in.saturating_sub(BUFFER).as_secs() / 60 * 60);
+    (fits >= SHORTEST).then(|| Conflict { name: name.clone(), starts_in, fits })
+}
+
+// Until the next time it is `at` today, if it is still ahead
+fn until(at: [u8; 2], now: OffsetDateTime) -> Option<Duration> {
+    let time = Time::from_hms(at[0], at[1], 0).ok()?;
+    let next = now.replace_time(time);
+    Duration::try_from(next - now).ok().filter(|until| !until.is_zero())
+}
+
+pub enum Choice {
+    Shorten,
+    FullLength,
+    NotNow,
+}
+
+/// Offers the shortened session; closing the window or pressing Escape means not now.
+pub fn prompt(ctx: &egui::Context, conflict: &Conflict) -> Option<Choice> {
+    let mut open = true;
+    let mut choice = None;
+    let (starts_in, fits) = (conflict.starts_in.as_secs().div_ceil(60), conflict.fits.as_secs() / 60);
+    egui::Window::new("Short on time")
+        .open(&mut open)
+        .resizable(false)
+        .collapsible(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, |ui| {
+            crate::compact_text(ui.style_mut());
+            ui.label(format!("{} starts in {} minutes. Start a {}-minute session instead?", conflict.name, starts_in, fits));
+            ui.weak("Only this session is shortened; the preset stays as it is.");
+            ui.horizontal(|ui| {
+                if ui.button(format!("Start {} minutes", fits)).clicked() {
+                    choice = Some(Choice::Shorten);
+                }
+                if ui.button("Start the full session").clicked() {
+                    choice = Some(Choice::FullLength);
+                }
+                if ui.button("Not now").clicked() {
+                    choice = Some(Choice::NotNow);
+                }
+            });
+        });
+    if !open || ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+        return Some(Choice::NotNow);
+    }
+    choice
+}
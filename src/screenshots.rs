@@ -0,0 +1,193 @@
+//! Screenshots at the end of each focus session, for looking back on a day: of one window
+//! picked by its title, or of the whole screen. Off unless turned on. They are taken with the
+//! platform's own tool, stay in a folder per day in the data dir, and the oldest are deleted
+//! once they all take up more than the space allowed.
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+use std::process::Command;
+use time::OffsetDateTime;
+
+use crate::paths;
+
+const DIR_NAME: &str = "screenshots";
+const THUMBNAIL_SIZE: [u32; 2] = [160, 100];
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Screenshots {
+    /// Part of the title of the window to capture; the whole screen while empty.
+    pub window: String,
+    /// Megabytes all screenshots together may take up.
+    pub keep_mb: u32,
+}
+
+impl Default for Screenshots {
+    fn default() -> Self {
+        Self { window: String::new(), keep_mb: 500 }
+    }
+}
+
+pub fn dir() -> Option<PathBuf> {
+    paths::data_dir().map(|dir| dir.join(DIR_NAME))
+}
+
+/// Where the screenshot of a session that ended at `end` goes, relative to [`dir`], e.g.
+/// `2024-06-04/09-30-00.png`.
+pub fn name(end: OffsetDateTime) -> String {
+    format!("{}/{:02}-{:02}-{:02}.png", end.date(), end.hour(), end.minute(), end.second())
+}
+
+/// Takes the screenshot on a thread of its own, then deletes the oldest ones over the limit.
+pub fn capture(config: &Screenshots, name: String) {
+    let (window, limit) = (config.window.trim().to_owned(), u64::from(config.keep_mb) * 1_000_000);
+    let Some(dir) = dir() else {
+        return;
+    };
+    let spawned = std::thread::Builder::new().name("screenshot".to_owned()).spawn(move || {
+        let path = dir.join(&name);
+        if let Some(day) = path.parent() {
+            let _ = std::fs::create_dir_all(day);
+        }
+        match take(&window, &path) {
+            Ok(()) => log::debug!("Took screenshot {}", path.display()),
+            Err(err) => log::warn!("Cannot take a screenshot: {}", err),
+        }
+        trim(&dir, limit);
+    });
+    if let Err(err) = spawned {
+        log::warn!("Cannot start taking a screenshot: {}", err);
+    }
+}
+
+/// Checks that the tools screenshots are taken with are installed. Unlike other self-tests it
+/// doesn't try the real thing, as that would capture the screen without being asked to.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+pub fn self_test() -> Result<String, String> {
+    #[cfg(target_os = "linux")]
+    let tools: &[(&str, &str)] =
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() { &[("grim", "-h")] } else { &[("import", "-version"), ("xdotool", "version")] };
+    #[cfg(target_os = "macos")]
+    let tools: &[(&str, &str)] = &[("screencapture", "-h")];
+    for (program, arg) in tools {
+        Command::new(program).arg(arg).output().map_err(|err| tool_error(program, err))?;
+    }
+    let names: Vec<&str> = tools.iter().map(|(program, _)| *program).collect();
+    Ok(format!("{} installed", names.join(" and ")))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn self_test() -> Result<String, String> {
+    Err("not supported on this platform".to_owned())
+}
+
+/// A small version of the screenshot `name` to show; none once it has been deleted.
+pub fn thumbnail(ctx: &egui::Context, name: &str) -> Option<egui::TextureHandle> {
+    let image = image::open(dir()?.join(name)).ok()?;
+    let small = image.thumbnail(THUMBNAIL_SIZE[0], THUMBNAIL_SIZE[1]).to_rgba8();
+    let size = [small.width() as usize, small.height() as usize];
+    let pixels = egui::ColorImage::from_rgba_unmultiplied(size, small.as_raw());
+    Some(ctx.load_texture(name, pixels, egui::TextureOptions::default()))
+}
+
+/// Opens the screenshot `name` in the default image viewer.
+pub fn open(name: &str) {
+    let Some(path) = dir().map(|dir| dir.join(name)) else {
+        return;
+    };
+    if let Err(err) = opener::open(&path) {
+        log::warn!("Cannot open {}: {}", path.display(), err);
+    }
+}
+
+// Oldest first, which is name order with a folder per day and the time in the file name
+fn trim(dir: &Path, limit: u64) {
+    let mut days: Vec<PathBuf> = read_dir(dir);
+    days.sort();
+    let mut files: Vec<(PathBuf, u64)> = days
+        .iter()
+        .flat_map(|day| {
+            let mut files = read_dir(day);
+            files.sort();
+            files
+        })
+        .map(|file| {
+            let size = std::fs::metadata(&file).map(|metadata| metadata.len()).unwrap_or(0);
+            (file, size)
+        })
+        .collect();
+    let mut total: u64 = files.iter().map(|(_, size)| size).sum();
+    files.reverse();
+    while total > limit {
+        let Some((file, size)) = files.pop() else {
+            break;
+        };
+        match std::fs::remove_file(&file) {
+            Ok(()) => total -= size,
+            Err(err) => log::warn!("Cannot delete old screenshot {}: {}", file.display(), err),
+        }
+    }
+    for day in days {
+        // Fails while there is still something in it
+        let _ = std::fs::remove_dir(day);
+    }
+}
+
+fn read_dir(dir: &Path) -> Vec<PathBuf> {
+    std::fs::read_dir(dir).map(|entries| entries.flatten().map(|entry| entry.path()).collect()).unwrap_or_default()
+}
+
+#[cfg(target_os = "linux")]
+fn take(window: &str, path: &Path) -> Result<(), String> {
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        let mut grim = Command::new("grim");
+        if !window.is_empty() {
+            grim.arg("-g").arg(crate::compositor::window_geometry(window)?);
+        }
+        return run(grim.arg(path));
+    }
+    let target = if window.is_empty() { "root".to_owned() } else { x11_window(window)? };
+    run(Command::new("import").args(["-window", &target]).arg(path))
+}
+
+#[cfg(target_os = "linux")]
+fn x11_window(title: &str) -> Result<String, String> {
+    let output = Command::new("xdotool").args(["search", "--onlyvisible", "--name", title]).output().map_err(|err| tool_error("xdotool", err))?;
+    let id = String::from_utf8_lossy(&output.stdout).lines().next().map(str::to_owned);
+    id.ok_or_else(|| format!("no window titled \"{}\"", title))
+}
+
+#[cfg(target_os = "macos")]
+fn take(window: &str, path: &Path) -> Result<(), String> {
+    if !window.is_empty() {
+        return Err("picking a window isn't supported on macOS, clear the window title to capture the whole screen".to_owned());
+    }
+    // -x keeps it from playing the shutter sound
+    run(Command::new("screencapture").arg("-x").arg(path))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn take(_window: &str, _path: &Path) -> Result<(), String> {
+    Err("not supported on this platform".to_owned())
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn run(command: &mut Command) -> Result<(), String> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let output = command.output().map_err(|err| tool_error(&program, err))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!("{} failed: {}", program, String::from_utf8_lossy(&output.stderr).trim()))
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn tool_error(program: &str, err: std::io::Error) -> String {
+    match err.kind() {
+        std::io::ErrorKind::NotFound => format!("{} is not installed", program),
+        _ => format!("cannot run {}: {}", program, err),
+    }
+}
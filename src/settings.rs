@@ -0,0 +1,519 @@
+use serde::{Deserialize, Serialize};
+use pomodoro_timer::duration_text;
+use pomodoro_timer::pacing::WeeklyGoal;
+use pomodoro_timer::timer::{self, Durations};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::alerts::Alerts;
+use crate::backup;
+use crate::history::{Counting, DayZone};
+use crate::stats::WeekId;
+use crate::hooks::Hook;
+use crate::logging;
+use crate::paths;
+use crate::schedule::Appointment;
+use crate::screenshots::Screenshots;
+use crate::standup::Standup;
+use crate::theme::{Schedule, Theme};
+use crate::timer_presets::TimerPreset;
+use crate::webhooks::Webhook;
+
+pub const FILE_NAME: &str = "settings.toml";
+const MAX_KEEP_MONTHS: u32 = 1200;
+pub const MAX_MINUTES: u32 = (timer::MAX_LENGTH.as_secs() / 60) as u32;
+
+/// Everything the user can configure, persisted as TOML in the config dir.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Custom alarm sound; the bundled chime is used when unset.
+    pub alarm_file: Option<PathBuf>,
+    /// Alarm volume from 0.0 (silent) to 1.0.
+    pub volume: f32,
+    /// Silences every sound without losing the volumes.
+    pub muted: bool,
+    /// Brown noise volume during focus sessions; off when unset.
+    pub ambient_volume: Option<f32>,
+    /// Sound and urgency of each kind of alert.
+    pub alerts: Alerts,
+    /// Length of focus sessions when no preset is active.
+    #[serde(deserialize_with = "duration_text::minutes")]
+    pub work_minutes: u32,
+    /// Length of short breaks when no preset is active.
+    #[serde(deserialize_with = "duration_text::minutes")]
+    pub break_minutes: u32,
+    /// Length of the break after every fourth focus session; all breaks are short when unset.
+    #[serde(deserialize_with = "duration_text::optional_minutes")]
+    pub long_break_minutes: Option<u32>,
+    /// Start the next focus session or break as soon as one ends, rather than waiting for a click.
+    pub auto_start_next: bool,
+    /// Say so, once, on coming back to an interval that ended while away from the computer.
+    pub greet_return: bool,
+    /// Seconds to get ready between a break and a focus session that starts by itself; focus
+    /// follows at once when unset.
+    #[serde(deserialize_with = "duration_text::optional_seconds")]
+    pub get_ready_secs: Option<u32>,
+    /// Ask before closing the window while a focus session is running.
+    pub confirm_quit: bool,
+    /// Ask what a focus session is for before it starts, and whether that was done when it ends.
+    pub ask_intent: bool,
+    /// Sessions older than this are folded into daily totals; kept forever when unset.
+    pub keep_sessions_months: Option<u32>,
+    /// Daily backups of the history and settings to keep; none are taken when 0.
+    pub keep_backups: u32,
+    /// On battery at or below this percentage, repaint less often and turn off animations and
+    /// ambient sound; never when unset.
+    pub power_saving_below: Option<u8>,
+    /// Show a battery icon while saving power.
+    pub power_saving_icon: bool,
+    /// Pause focus sessions and silence alerts while the microphone is in use, e.g. in a call.
+    pub pause_during_calls: bool,
+    /// Ask before focus sessions started after this `[hour, minute]`; never when unset.
+    pub wind_down_at: Option<[u8; 2]>,
+    /// Daily appointments a focus session started shortly before is offered to end ahead of.
+    pub appointments: Vec<Appointment>,
+    /// Which day sessions recorded in another time zone count toward.
+    pub day_zone: DayZone,
+    /// Whether the statistics, goals and reports count the time paused during sessions.
+    pub focus_counting: Counting,
+    /// The week this one is compared with in the statistics, besides last week.
+    pub baseline_week: Option<WeekId>,
+    /// Pomodoros to aim for each day; no goal when unset.
+    pub daily_goal: Option<u32>,
+    /// Focus minutes to aim for each week, spread over the workdays; no goal when unset.
+    #[serde(deserialize_with = "duration_text::optional_minutes")]
+    pub weekly_goal: Option<u32>,
+    /// Days the weekly goal asks for focus on, Monday first.
+    pub workdays: [bool; 7],
+    /// Carry minutes a workday fell short of the weekly goal's pace into the next one, at
+    /// most this many; none are carried when unset.
+    #[serde(deserialize_with = "duration_text::optional_minutes")]
+    pub carry_over_minutes: Option<u32>,
+    /// What "Copy for standup" reports, and how.
+    pub standup: Standup,
+    /// Seconds at the start of each break during which it can't be skipped; off when unset.
+    #[serde(deserialize_with = "duration_text::optional_seconds")]
+    pub enforced_break_secs: Option<u32>,
+    /// Window title with placeholders such as `{remaining}`; the app name when unset.
+    pub title_template: Option<String>,
+    /// Tint the focused window's border during focus sessions (Hyprland and Sway only).
+    pub tint_borders: bool,
+    pub focus_border_color: [u8; 3],
+    /// The monitor the focus companion fills during focus sessions (Hyprland and Sway only);
+    /// off when unset.
+    pub companion_monitor: Option<String>,
+    pub theme: Theme,
+    /// When the automatic theme turns dark and light.
+    pub theme_schedule: Schedule,
+    /// The highest alarm volume at night by the theme's schedule, while the theme is
+    /// automatic; no limit when unset.
+    pub night_volume: Option<f32>,
+    /// Color of progress bars and highlights.
+    pub accent_color: [u8; 3],
+    /// Follow the desktop's accent color where it has one, instead of `accent_color`.
+    pub system_accent: bool,
+    /// Show an animated Ferris next to the timer.
+    pub show_mascot: bool,
+    /// Keep animations still.
+    pub reduce_motion: bool,
+    /// Show a quote on the break screen.
+    pub show_quotes: bool,
+    /// Offer the breathing game during breaks, in builds with the `games` feature.
+    pub break_game: bool,
+    /// Keep the breathing game's scores by day.
+    pub keep_game_scores: bool,
+    /// Show a tip on the technique as each session starts, during the first week of use.
+    pub beginner_hints: bool,
+    /// Suggest switching between sitting and standing after this many minutes of running work time.
+    #[serde(deserialize_with = "duration_text::optional_minutes")]
+    pub posture_minutes: Option<u32>,
+    /// Play a short sound with the posture reminder.
+    pub posture_sound: bool,
+    /// Record timer commands and events to a file in the data dir for bug reports.
+    pub debug_recording: bool,
+    /// Keep file paths, labels and notes in the recording instead of redacting them.
+    pub record_unredacted: bool,
+    /// Show the developer panel, with self-tests and benchmarks, in the Help menu.
+    pub developer_panel: bool,
+    /// How log lines are written to stderr, unless `--log-format` says otherwise.
+    pub log_format: logging::Format,
+    /// Keep `status.json` and `summary.json` in the data dir up to date for scripts and widgets.
+    pub status_files: bool,
+    /// Shell commands run on timer events.
+    pub hooks: Vec<Hook>,
+    /// URLs sent timer events as signed JSON.
+    pub webhooks: Vec<Webhook>,
+    /// Nothing is sent over the network, not even to check whether it is there.
+    pub work_offline: bool,
+    /// The HTTP API on localhost for browser extensions; off when unset.
+    pub local_api: Option<LocalApi>,
+    /// A screenshot at the end of each focus session; off when unset.
+    pub screenshots: Option<Screenshots>,
+    pub presets: Vec<TimerPreset>,
+    /// Name of the preset in use; the global settings and lengths when unset.
+    pub active_preset: Option<String>,
+}
+
+/// Where the local API listens and who may call it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LocalApi {
+    pub port: u16,
+    /// Required with every request.
+    pub token: String,
+    /// Browser origins allowed besides pages on localhost, e.g. `chrome-extension://<id>`.
+    #[serde(default)]
+    pub origins: Vec<String>,
+}
+
+impl LocalApi {
+    pub const DEFAULT_PORT: u16 = 47615;
+
+    /// On the default port, with a new token.
+    pub fn new() -> Self {
+        Self { port: Self::DEFAULT_PORT, token: new_token(), origins: Vec::new() }
+    }
+}
+
+pub fn new_token() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            alarm_file: None,
+            volume: 1.0,
+            muted: false,
+            ambient_volume: None,
+            alerts: Alerts::default(),
+            work_minutes: 25,
+            break_minutes: 5,
+            long_break_minutes: Some(15),
+            auto_start_next: true,
+            greet_return: true,
+            get_ready_secs: None,
+            confirm_quit: true,
+            ask_intent: false,
+            keep_sessions_months: None,
+            keep_backups: 7,
+            power_saving_below: Some(20),
+            power_saving_icon: true,
+            pause_during_calls: false,
+            wind_down_at: None,
+            appointments: Vec::new(),
+            day_zone: DayZone::Recorded,
+            focus_counting: Counting::Focused,
+            baseline_week: None,
+            daily_goal: None,
+            weekly_goal: None,
+            workdays: [true, true, true, true, true, false, false],
+            carry_over_minutes: None,
+            standup: Standup::default(),
+            enforced_break_secs: None,
+            title_template: None,
+            tint_borders: false,
+            focus_border_color: [220, 50, 47],
+            companion_monitor: None,
+            theme: Theme::Dark,
+            theme_schedule: Schedule::default(),
+            night_volume: None,
+            accent_color: [0, 92, 128],
+            system_accent: false,
+            show_mascot: false,
+            reduce_motion: false,
+            show_quotes: true,
+            break_game: false,
+            keep_game_scores: false,
+            beginner_hints: true,
+            posture_minutes: None,
+            posture_sound: false,
+            debug_recording: false,
+            record_unredacted: false,
+            developer_panel: false,
+            log_format: logging::Format::Text,
+            status_files: false,
+            hooks: Vec::new(),
+            webhooks: Vec::new(),
+            work_offline: false,
+            local_api: None,
+            screenshots: None,
+            presets: Vec::new(),
+            active_preset: None,
+        }
+    }
+}
+
+/// A group of settings shown together, which can be reset on its own.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Section {
+    AlarmAndWindow,
+    GoalsAndHistory,
+    Behaviour,
+    Hooks,
+    Sound,
+}
+
+impl Settings {
+    pub fn active_preset(&self) -> Option<&TimerPreset> {
+        let name = self.active_preset.as_ref()?;
+        self.presets.iter().find(|preset| preset.name == *name)
+    }
+
+    /// Interval lengths: the active preset's, or else the global ones.
+    pub fn durations(&self) -> Durations {
+        match self.active_preset() {
+            Some(preset) => Durations { work: preset.work(), short_break: preset.short_break() },
+            None => Durations {
+                work: Duration::from_secs(u64::from(self.work_minutes) * 60),
+                short_break: Duration::from_secs(u64::from(self.break_minutes) * 60),
+            },
+        }
+    }
+
+    pub fn weekly_goal(&self) -> Option<WeeklyGoal> {
+        let minutes = self.weekly_goal?;
+        Some(WeeklyGoal { minutes, workdays: self.workdays, carry_over_cap: self.carry_over_minutes })
+    }
+
+    /// Puts the settings of one section back to their defaults, leaving the rest alone.
+    pub fn restore_defaults(&mut self, section: Section) {
+        let defaults = Self::default();
+        match section {
+            Section::AlarmAndWindow => {
+                self.alarm_file = defaults.alarm_file;
+                self.title_template = defaults.title_template;
+                self.tint_borders = defaults.tint_borders;
+                self.focus_border_color = defaults.focus_border_color;
+                self.companion_monitor = defaults.companion_monitor;
+                self.theme = defaults.theme;
+                self.theme_schedule = defaults.theme_schedule;
+                self.night_volume = defaults.night_volume;
+                self.accent_color = defaults.accent_color;
+                self.system_accent = defaults.system_accent;
+            }
+            Section::GoalsAndHistory => {
+                self.daily_goal = defaults.daily_goal;
+                self.weekly_goal = defaults.weekly_goal;
+                self.workdays = defaults.workdays;
+                self.carry_over_minutes = defaults.carry_over_minutes;
+                self.standup = defaults.standup;
+                self.day_zone = defaults.day_zone;
+                self.focus_counting = defaults.focus_counting;
+                self.baseline_week = defaults.baseline_week;
+                self.keep_sessions_months = defaults.keep_sessions_months;
+                self.keep_backups = defaults.keep_backups;
+            }
+            Section::Behaviour => {
+                self.confirm_quit = defaults.confirm_quit;
+                self.work_minutes = defaults.work_minutes;
+                self.break_minutes = defaults.break_minutes;
+                self.long_break_minutes = defaults.long_break_minutes;
+                self.auto_start_next = defaults.auto_start_next;
+                self.greet_return = defaults.greet_return;
+                self.get_ready_secs = defaults.get_ready_secs;
+                self.ask_intent = defaults.ask_intent;
+                self.show_quotes = defaults.show_quotes;
+                self.break_game = defaults.break_game;
+                self.keep_game_scores = defaults.keep_game_scores;
+                self.beginner_hints = defaults.beginner_hints;
+                self.show_mascot = defaults.show_mascot;
+                self.reduce_motion = defaults.reduce_motion;
+                self.enforced_break_secs = defaults.enforced_break_secs;
+                self.posture_minutes = defaults.posture_minutes;
+                self.posture_sound = defaults.posture_sound;
+                self.wind_down_at = defaults.wind_down_at;
+                self.appointments = defaults.appointments.clone();
+                self.power_saving_below = defaults.power_saving_below;
+                self.power_saving_icon = defaults.power_saving_icon;
+                self.pause_during_calls = defaults.pause_during_calls;
+                self.debug_recording = defaults.debug_recording;
+                self.record_unredacted = defaults.record_unredacted;
+                self.developer_panel = defaults.developer_panel;
+                self.log_format = defaults.log_format;
+                self.status_files = defaults.status_files;
+            }
+            Section::Hooks => {
+                self.hooks = defaults.hooks;
+                self.webhooks = defaults.webhooks;
+                self.work_offline = defaults.work_offline;
+                self.local_api = defaults.local_api;
+                self.screenshots = defaults.screenshots;
+            }
+            Section::Sound => {
+                self.volume = defaults.volume;
+                self.muted = defaults.muted;
+                self.ambient_volume = defaults.ambient_volume;
+                self.alerts = defaults.alerts;
+            }
+        }
+    }
+
+    /// Falls back to defaults when the file is missing or malformed. The second value
+    /// explains anything that was ignored, for showing to the user.
+    pub fn load() -> (Self, Option<String>) {
+        let Some(path) = paths::config_dir().map(|dir| dir.join(FILE_NAME)) else {
+            return (Self::default(), None);
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(text) => match toml::from_str::<Self>(&text) {
+                Ok(settings) => settings.sanitized(),
+                Err(err) => {
+                    log::warn!("Ignoring malformed {}: {}", path.display(), err);
+                    // Defaults get saved on exit, so keep what the user wrote
+                    let backup = PathBuf::from(format!("{}.bad", path.display()));
+                    let kept = match std::fs::copy(&path, &backup) {
+                        Ok(_) => format!("; your version is kept as {}", backup.display()),
+                        Err(_) => String::new(),
+                    };
+                    let message = format!(
+                        "Settings file is malformed, using defaults{} ({})",
+                        kept,
+                        err.message()
+                    );
+                    (Self::default(), Some(message))
+                }
+            },
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => (Self::default(), None),
+            Err(err) => {
+                log::warn!("Cannot read {}: {}", path.display(), err);
+                (Self::default(), Some(format!("Cannot read {}: {}", path.display(), err)))
+            }
+        }
+    }
+
+    /// Replaces values that parse but make no sense, explaining what was changed.
+    pub fn sanitized(mut self) -> (Self, Option<String>) {
+        let mut fixed = Vec::new();
+        if !(0.0..=1.0).contains(&self.volume) {
+            fixed.push("volume");
+            self.volume = if self.volume.is_nan() { 1.0 } else { self.volume.clamp(0.0, 1.0) };
+        }
+        if let Some(ambient) = self.ambient_volume.filter(|volume| !(0.0..=1.0).contains(volume)) {
+            fixed.push("ambient_volume");
+            self.ambient_volume = Some(if ambient.is_nan() { 0.5 } else { ambient.clamp(0.0, 1.0) });
+        }
+        if let Some(night) = self.night_volume.filter(|volume| !(0.0..=1.0).contains(volume)) {
+            fixed.push("night_volume");
+            self.night_volume = Some(if night.is_nan() { 0.3 } else { night.clamp(0.0, 1.0) });
+        }
+        for preset in &mut self.presets {
+            let sound = &mut preset.sound;
+            for volume in [&mut sound.volume, &mut sound.ambient_volume].into_iter().flatten() {
+                if !(0.0..=1.0).contains(volume) {
+                    fixed.push("presets");
+                    *volume = if volume.is_nan() { 0.5 } else { volume.clamp(0.0, 1.0) };
+                }
+            }
+        }
+        fixed.dedup();
+        if let Some(months) = self.keep_sessions_months.filter(|months| !(1..=MAX_KEEP_MONTHS).contains(months)) {
+            fixed.push("keep_sessions_months");
+            self.keep_sessions_months = Some(months.clamp(1, MAX_KEEP_MONTHS));
+        }
+        let (minute, second) = (Duration::from_secs(60), Duration::from_secs(1));
+        let mut lengths = vec![
+            ("work_minutes", Some(&mut self.work_minutes), minute),
+            ("break_minutes", Some(&mut self.break_minutes), minute),
+            ("long_break_minutes", self.long_break_minutes.as_mut(), minute),
+            ("get_ready_secs", self.get_ready_secs.as_mut(), second),
+            ("enforced_break_secs", self.enforced_break_secs.as_mut(), second),
+        ];
+        for preset in &mut self.presets {
+            lengths.push(("presets", Some(&mut preset.work_minutes), minute));
+            lengths.push(("presets", Some(&mut preset.break_minutes), minute));
+        }
+        for (name, length, unit) in lengths {
+            if length.is_some_and(|length| clamp_length(length, unit)) && !fixed.contains(&name) {
+                fixed.push(name);
+            }
+        }
+        if fixed.is_empty() {
+            return (self, None);
+        }
+        log::warn!("Out of range settings adjusted: {}", fixed.join(", "));
+        (self, Some(format!("Adjusted out of range settings: {}", fixed.join(", "))))
+    }
+
+    /// The long break is shorter than the short one, which is allowed but likely a mistake.
+    pub fn long_break_too_short(&self) -> bool {
+        self.long_break_minutes.is_some_and(|minutes| Duration::from_secs(u64::from(minutes) * 60) < self.durations().short_break)
+    }
+
+    pub fn save(&self) {
+        let Some(dir) = paths::config_dir() else {
+            log::warn!("No home directory found, settings are not saved");
+            return;
+        };
+        if let Err(err) = self.write(&dir) {
+            log::warn!("Cannot save settings: {}", err);
+        }
+    }
+
+    // Written next to the old file and renamed over it, so a crash never leaves half a config
+    fn write(&self, dir: &Path) -> Result<(), String> {
+        backup::before_write();
+        let text = toml::to_string_pretty(self).map_err(|e| e.to_string())?;
+        let temp = dir.join(format!("{}.tmp", FILE_NAME));
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        std::fs::write(&temp, text).map_err(|e| e.to_string())?;
+        std::fs::rename(&temp, dir.join(FILE_NAME)).map_err(|e| e.to_string())
+    }
+}
+
+// Keeps a length counted in `unit`s between the timer's shortest and longest; true if it wasn't
+fn clamp_length(length: &mut u32, unit: Duration) -> bool {
+    let shortest = timer::MIN_LENGTH.as_secs().div_ceil(unit.as_secs()) as u32;
+    let longest = (timer::MAX_LENGTH.as_secs() / unit.as_secs()) as u32;
+    let clamped = (*length).clamp(shortest, longest);
+    std::mem::replace(length, clamped) != clamped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(text: &str) -> (Settings, Option<String>) {
+        toml::from_str::<Settings>(text).unwrap().sanitized()
+    }
+
+    #[test]
+    fn out_of_range_values_are_adjusted_and_named() {
+        let (settings, note) = read("volume = nan\nambient_volume = -1.0\nnight_volume = inf\nkeep_sessions_months = 0\nwork_minutes = 0\nbreak_minutes = 4294967295\nget_ready_secs = 1");
+        assert_eq!(settings.volume, 1.0);
+        assert_eq!(settings.ambient_volume, Some(0.0));
+        assert_eq!(settings.night_volume, Some(1.0));
+        assert_eq!(settings.keep_sessions_months, Some(1));
+        assert_eq!(settings.work_minutes, 1);
+        assert_eq!(settings.break_minutes, MAX_MINUTES);
+        assert_eq!(settings.get_ready_secs, Some(10));
+        assert_eq!(
+            note.as_deref(),
+            Some("Adjusted out of range settings: volume, ambient_volume, night_volume, keep_sessions_months, work_minutes, break_minutes, get_ready_secs")
+        );
+    }
+
+    #[test]
+    fn sensible_settings_are_left_alone() {
+        let (settings, note) = read("volume = 0.0\nwork_minutes = \"1h30m\"\nkeep_sessions_months = 1200");
+        assert_eq!(note, None);
+        assert_eq!((settings.volume, settings.work_minutes, settings.keep_sessions_months), (0.0, 90, Some(1200)));
+        assert_eq!(read("").0, Settings::default());
+    }
+
+    #[test]
+    fn malformed_files_are_refused_whole() {
+        for text in [
+            "volume = ",
+            "volume = \"loud\"",
+            "work_minutes = -5",
+            "work_minutes = 99999999999",
+            "work_minutes = \"25mm\"",
+            "[volume]",
+            "\u{0}",
+            &"[".repeat(10_000),
+        ] {
+            assert!(toml::from_str::<Settings>(text).is_err(), "{:?} was read", text.get(..20));
+        }
+    }
+}
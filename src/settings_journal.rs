@@ -0,0 +1,189 @@
+//! Every change to the settings, written down so the settings window can show when something
+//! changed and put back the settings of an earlier day.
+
+use pomodoro_timer::journal::{self, Entry};
+use serde_json::{Map, Value};
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use time::OffsetDateTime;
+
+use crate::paths;
+use crate::preset;
+use crate::settings::Settings;
+
+const FILE_NAME: &str = "settings_journal.jsonl";
+// A full journal is moved here, replacing the one before, so at most twice as many are kept
+const ROTATED_FILE_NAME: &str = "settings_journal.1.jsonl";
+const ENTRIES_PER_FILE: usize = 1000;
+
+/// Where a change was made.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Source {
+    /// The settings window, the menus or a shortcut.
+    App,
+    Undo,
+    Defaults,
+    Import,
+    Backup,
+    Revert,
+}
+
+impl Source {
+    fn name(self) -> &'static str {
+        match self {
+            Source::App => "app",
+            Source::Undo => "undo",
+            Source::Defaults => "restore defaults",
+            Source::Import => "import",
+            Source::Backup => "backup",
+            Source::Revert => "revert",
+        }
+    }
+}
+
+pub struct Journal {
+    /// Oldest first, from both files.
+    entries: Vec<Entry>,
+    /// How many of them are in the current file.
+    in_file: usize,
+    /// The settings as last written down.
+    last: Map<String, Value>,
+}
+
+impl Journal {
+    pub fn load(settings: &Settings) -> Self {
+        let mut entries = read(ROTATED_FILE_NAME);
+        let current = read(FILE_NAME);
+        let in_file = current.len();
+        entries.extend(current);
+        Self { entries, in_file, last: preset::to_map(settings) }
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Writes down what changed since the last time, if anything.
+    pub fn record(&mut self, settings: &Settings, source: Source, now: OffsetDateTime) {
+        let changes = self.changes(settings, source, now);
+        if changes.is_empty() {
+            return;
+        }
+        if let Err(err) = self.append(&changes) {
+            log::warn!("Cannot write to the settings journal: {}", err);
+        }
+        self.entries.extend(changes);
+    }
+
+    // What changed since the last time, which is then taken as written down
+    fn changes(&mut self, settings: &Settings, source: Source, now: OffsetDateTime) -> Vec<Entry> {
+        let new = preset::to_map(settings);
+        let changes = journal::changes(&self.last, &new, now, source.name());
+        self.last = new;
+        changes
+    }
+
+    /// The settings as they were at `at`, as far as the journal goes back.
+    pub fn as_of(&self, current: &Settings, at: OffsetDateTime) -> Result<Settings, String> {
+        let map = journal::as_of(&preset::to_map(current), &self.entries, at);
+        let settings: Settings = serde_json::from_value(Value::Object(map)).map_err(|e| e.to_string())?;
+        Ok(settings.sanitized().0)
+    }
+
+    fn append(&mut self, changes: &[Entry]) -> io::Result<()> {
+        let dir = paths::data_dir().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory"))?;
+        std::fs::create_dir_all(&dir)?;
+        if self.in_file >= ENTRIES_PER_FILE {
+            std::fs::rename(dir.join(FILE_NAME), dir.join(ROTATED_FILE_NAME))?;
+            self.entries.drain(..self.entries.len() - self.in_file);
+            self.in_file = 0;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(dir.join(FILE_NAME))?;
+        for change in changes {
+            writeln!(file, "{}", serde_json::to_string(change)?)?;
+            self.in_file += 1;
+        }
+        Ok(())
+    }
+}
+
+// Lines that can't be read, e.g. one cut off by a crash, are skipped
+fn read(name: &str) -> Vec<Entry> {
+    paths::data_dir()
+        .and_then(|dir| std::fs::read_to_string(dir.join(name)).ok())
+        .map(|text| text.lines().filter_map(|line| serde_json::from_str(line).ok()).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use time::macros::datetime;
+
+    // A journal begun with `settings`, kept in memory
+    fn begun_with(settings: &Settings) -> Journal {
+        Journal { entries: Vec::new(), in_file: 0, last: preset::to_map(settings) }
+    }
+
+    // Writes down the change as `record` does, without the file
+    fn note(journal: &mut Journal, settings: &Settings, source: Source, now: OffsetDateTime) {
+        let changes = journal.changes(settings, source, now);
+        journal.entries.extend(changes);
+    }
+
+    #[test]
+    fn reverting_gives_back_the_settings_of_each_day() {
+        let monday = Settings::default();
+        let tuesday = Settings { work_minutes: 20, daily_goal: Some(8), title_template: Some("{remaining}".to_owned()), ..monday.clone() };
+        let wednesday = Settings { volume: 0.5, long_break_minutes: Some(20), ..tuesday.clone() };
+        let thursday = Settings { work_minutes: 25, ..wednesday.clone() };
+
+        let mut journal = begun_with(&monday);
+        note(&mut journal, &tuesday, Source::App, datetime!(2024-06-04 9:00 UTC));
+        note(&mut journal, &wednesday, Source::Import, datetime!(2024-06-05 9:00 UTC));
+        note(&mut journal, &thursday, Source::Undo, datetime!(2024-06-06 9:00 UTC));
+        // Nothing changed, nothing written down
+        note(&mut journal, &thursday, Source::App, datetime!(2024-06-06 10:00 UTC));
+        assert_eq!(journal.entries().len(), 6);
+
+        let at = |when| journal.as_of(&thursday, when).unwrap();
+        assert_eq!(at(datetime!(2024-06-03 12:00 UTC)), monday);
+        assert_eq!(at(datetime!(2024-06-04 12:00 UTC)), tuesday);
+        assert_eq!(at(datetime!(2024-06-05 12:00 UTC)), wednesday);
+        assert_eq!(at(datetime!(2024-06-06 12:00 UTC)), thursday);
+        // A change made at that very moment is part of it
+        assert_eq!(at(datetime!(2024-06-05 9:00 UTC)), wednesday);
+
+        let undone = journal.entries().last().unwrap();
+        assert_eq!((undone.source.as_str(), undone.field.as_str()), ("undo", "work_minutes"));
+        assert_eq!((&undone.old, &undone.new), (&json!(20), &json!(25)));
+    }
+
+    #[test]
+    fn reverting_to_a_value_out_of_range_adjusts_it() {
+        let settings = Settings::default();
+        let mut journal = begun_with(&settings);
+        journal.entries.push(Entry {
+            at: datetime!(2024-06-04 9:00 UTC),
+            source: "app".to_owned(),
+            field: "volume".to_owned(),
+            old: json!(3.0),
+            new: json!(1.0),
+        });
+        let reverted = journal.as_of(&settings, datetime!(2024-06-03 9:00 UTC)).unwrap();
+        assert_eq!(reverted, settings);
+    }
+
+    #[test]
+    fn settings_since_removed_are_left_out_and_unreadable_values_refused() {
+        let settings = Settings::default();
+        let mut journal = begun_with(&settings);
+        let entry = |field: &str, old| Entry { at: datetime!(2024-06-04 9:00 UTC), source: "app".to_owned(), field: field.to_owned(), old, new: json!(null) };
+        journal.entries.push(entry("focus_music", json!("lofi")));
+        assert_eq!(journal.as_of(&settings, datetime!(2024-06-03 9:00 UTC)).unwrap(), settings);
+
+        journal.entries.push(entry("work_minutes", json!("soon")));
+        assert!(journal.as_of(&settings, datetime!(2024-06-03 9:00 UTC)).is_err());
+    }
+}
@@ -0,0 +1,130 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use time::OffsetDateTime;
+
+use crate::history::DayTotals;
+use crate::settings::Settings;
+use crate::{clock, paths};
+
+/// Bumped whenever a field changes meaning; snapshots from a newer version are refused.
+pub const VERSION: u32 = 1;
+
+/// The complete runtime state of the app, for debugging reports and reproducing them.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    #[serde(with = "time::serde::rfc3339")]
+    pub taken_at: OffsetDateTime,
+    pub timer: TimerState,
+    /// Label for the next focus session.
+    #[serde(default)]
+    pub task: String,
+    pub settings: Settings,
+    /// Restored as the totals of the day the snapshot is restored on.
+    pub today: DayTotals,
+    pub toasts: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TimerState {
+    pub work_period: bool,
+    pub running: bool,
+    pub ended: bool,
+    pub duration_secs: f64,
+    pub elapsed_secs: f64,
+}
+
+impl Snapshot {
+    /// Writes to a new timestamped file in the data dir and returns its path.
+    pub fn save(&self) -> Result<PathBuf, String> {
+        let dir = paths::data_dir().ok_or("no data directory")?.join("snapshots");
+        fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        let stamp = clock::now().unix_timestamp();
+        let path = dir.join(format!("snapshot-{}.json", stamp));
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(&path, json).map_err(|e| e.to_string())?;
+        Ok(path)
+    }
+
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        #[derive(Deserialize)]
+        struct Versioned {
+            version: u32,
+        }
+        // Checked on its own first, so a newer snapshot gets this message rather than a parse error
+        let versioned: Versioned = serde_json::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+        if versioned.version > VERSION {
+            return Err(format!(
+                "{} was taken by a newer FerrisFocus (snapshot version {}, this one reads up to {})",
+                path.display(),
+                versioned.version,
+                VERSION
+            ));
+        }
+        serde_json::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GOLDEN: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/fixtures/snapshot.json");
+
+    // Halfway through a 50-minute focus session, as kept in the golden file
+    fn halfway() -> Snapshot {
+        Snapshot {
+            version: VERSION,
+            taken_at: time::macros::datetime!(2026-03-14 09:17:30 +01:00),
+            timer: TimerState { work_period: true, running: true, ended: false, duration_secs: 1500.0, elapsed_secs: 750.5 },
+            task: "Quarterly report".to_owned(),
+            settings: Settings { volume: 0.4, work_minutes: 50, ..Settings::default() },
+            today: DayTotals { sessions: 3, focus_secs: 4500, paused_secs: 120, late_sessions: 0, easy_starts: 1 },
+            toasts: vec!["Your focus session ended while your computer was asleep".to_owned()],
+        }
+    }
+
+    fn json(snapshot: &Snapshot) -> String {
+        serde_json::to_string_pretty(snapshot).unwrap() + "\n"
+    }
+
+    // A file of its own in the temporary folder, removed once read
+    fn read_text(name: &str, text: &str) -> Result<Snapshot, String> {
+        let path = std::env::temp_dir().join(format!("ferrisfocus-{}-{}.json", name, std::process::id()));
+        fs::write(&path, text).unwrap();
+        let read = Snapshot::read(&path);
+        let _ = fs::remove_file(&path);
+        read
+    }
+
+    /// Any change to what a snapshot holds shows up in `assets/fixtures/snapshot.json` first.
+    #[test]
+    fn a_snapshot_is_written_as_the_golden_file() {
+        let golden = fs::read_to_string(GOLDEN).unwrap();
+        for (number, (line, wanted)) in json(&halfway()).lines().zip(golden.lines()).enumerate() {
+            assert_eq!(line, wanted, "line {} differs", number + 1);
+        }
+        assert_eq!(json(&halfway()).lines().count(), golden.lines().count());
+    }
+
+    #[test]
+    fn the_golden_file_reads_back_as_taken() {
+        let read = Snapshot::read(Path::new(GOLDEN)).unwrap();
+        assert_eq!(json(&read), json(&halfway()));
+        assert_eq!(read.settings, halfway().settings);
+        assert_eq!((read.task, read.today), (halfway().task, halfway().today));
+        assert_eq!((read.timer.elapsed_secs, read.timer.running), (750.5, true));
+    }
+
+    #[test]
+    fn snapshots_from_a_newer_version_are_refused() {
+        let newer = json(&halfway()).replacen("\"version\": 1", "\"version\": 2", 1);
+        let err = read_text("newer-snapshot", &newer).unwrap_err();
+        assert!(err.contains("taken by a newer FerrisFocus (snapshot version 2, this one reads up to 1)"), "{}", err);
+        // Even when nothing else in it would read
+        assert!(read_text("newer-snapshot", r#"{"version": 7, "timer": []}"#).unwrap_err().contains("snapshot version 7"));
+        assert!(read_text("broken-snapshot", r#"{"version": 1, "timer": []}"#).is_err());
+    }
+}
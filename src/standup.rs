@@ -0,0 +1,162 @@
+//! A short report of one day for pasting into a standup: focus time, the tasks worked on with
+//! the pomodoros each took, and the day's note. Written from a template like the window title,
+//! as plain text for chat or as Markdown.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use time::{Date, Duration};
+
+use crate::history::{DayNote, Session};
+use crate::stats::StatsIndex;
+use crate::template::Template;
+
+/// Placeholders the report template may use.
+pub const FIELDS: [&str; 5] = ["day", "focus", "sessions", "tasks", "note"];
+const DEFAULT_TEMPLATE: &str = "{day}: {focus|no focus} in {sessions|0} pomodoros\n{tasks}\n{note}";
+
+/// Which day the report is of.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Range {
+    #[default]
+    Yesterday,
+    Today,
+    /// The last of the workdays before today.
+    LastWorkday,
+}
+
+impl Range {
+    pub const ALL: [Range; 3] = [Range::Yesterday, Range::Today, Range::LastWorkday];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Range::Yesterday => "Yesterday",
+            Range::Today => "Today",
+            Range::LastWorkday => "Last workday",
+        }
+    }
+
+    /// The day reported on `today`, with `workdays` Monday first.
+    pub fn date(self, today: Date, workdays: &[bool; 7]) -> Date {
+        let yesterday = today.previous_day().unwrap_or(today);
+        match self {
+            Range::Today => today,
+            Range::Yesterday => yesterday,
+            Range::LastWorkday => (1..=7)
+                .map(|days| today - Duration::days(days))
+                .find(|day| workdays[usize::from(day.weekday().number_days_from_monday())])
+                .unwrap_or(yesterday),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Standup {
+    pub range: Range,
+    /// Markdown rather than plain text.
+    pub markdown: bool,
+    /// The report with placeholders such as `{focus}`.
+    pub template: String,
+}
+
+impl Default for Standup {
+    fn default() -> Self {
+        Self { range: Range::default(), markdown: false, template: DEFAULT_TEMPLATE.to_owned() }
+    }
+}
+
+impl Standup {
+    /// Checks the template, for showing what is wrong with it while it is edited.
+    pub fn parse(&self) -> Result<Template, String> {
+        Template::parse_with(&self.template, &FIELDS)
+    }
+
+    /// The report of `date` from the sessions that started on it and its note, with days and
+    /// focus time as `index` counts them. Tasks marked `done` say so. Only the day is filled in
+    /// when `private`, as in privacy mode.
+    pub fn report(&self, date: Date, sessions: &[Session], index: &StatsIndex, done: impl Fn(&str) -> bool, note: Option<&DayNote>, private: bool) -> String {
+        let template = self.parse().unwrap_or_else(|_| Template::parse_with(DEFAULT_TEMPLATE, &FIELDS).expect("the default template parses"));
+        let sessions = index.sessions_on(sessions, date);
+        let focus_secs: u64 = sessions.iter().map(|session| session.counted_secs(index.counting())).sum();
+        let pomodoros = sessions.iter().filter(|session| !session.easy_start).count();
+        let mut tasks: BTreeMap<&str, usize> = BTreeMap::new();
+        for session in sessions.iter().filter(|session| !session.easy_start) {
+            if let Some(label) = &session.label {
+                *tasks.entry(label).or_default() += 1;
+            }
+        }
+        let mut tasks: Vec<(&str, usize)> = tasks.into_iter().collect();
+        tasks.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        let text = template.render(|field| match field {
+            "day" => Some(format!("{}, {} {}", self.range.label(), date.weekday(), date)),
+            _ if private => None,
+            "focus" => (focus_secs > 0).then(|| focus(focus_secs)),
+            "sessions" => (pomodoros > 0).then(|| pomodoros.to_string()),
+            "tasks" => {
+                let lines: Vec<String> = tasks.iter().map(|(task, count)| self.task_line(task, *count, done(task))).collect();
+                Some(lines.join("\n"))
+            }
+            "note" => {
+                let note = note.map(|note| note.note.trim()).filter(|note| !note.is_empty());
+                note.map(|note| if self.markdown { format!("> {}", note) } else { format!("Note: {}", note) })
+            }
+            _ => None,
+        });
+        tidy(&text)
+    }
+
+    fn task_line(&self, task: &str, count: usize, done: bool) -> String {
+        let pomodoros = if count == 1 { "1 pomodoro".to_owned() } else { format!("{} pomodoros", count) };
+        let done = if done { ", done" } else { "" };
+        if self.markdown {
+            format!("- **{}** — {}{}", task, pomodoros, done)
+        } else {
+            format!("• {} — {}{}", task, pomodoros, done)
+        }
+    }
+}
+
+fn focus(secs: u64) -> String {
+    let minutes = secs / 60;
+    match minutes / 60 {
+        0 => format!("{} min", minutes),
+        hours => format!("{} h {} min", hours, minutes % 60),
+    }
+}
+
+// Placeholders left empty would leave blank lines and trailing spaces behind
+fn tidy(text: &str) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    for line in text.lines().map(str::trim_end) {
+        if line.is_empty() && lines.last().is_none_or(|last| last.is_empty()) {
+            continue;
+        }
+        lines.push(line);
+    }
+    lines.join("\n").trim_end().to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::{Compacted, Counting, DayZone};
+    use crate::stats::Fingerprint;
+    use time::macros::date;
+
+    #[test]
+    fn focus_time_is_reported_as_the_statistics_count_it() {
+        let session = |start: &str, paused_secs: u64| -> Session {
+            let line = format!(r#"{{"start":"{}","duration_secs":1500,"label":"Report","paused_secs":{}}}"#, start, paused_secs);
+            serde_json::from_str(&line).unwrap()
+        };
+        let sessions = [session("2024-03-01T09:00:00+01:00", 1200), session("2024-03-01T10:00:00+01:00", 0), session("2024-03-02T09:00:00+01:00", 0)];
+        let standup = Standup { template: "{focus} in {sessions}\n{tasks}".to_owned(), ..Standup::default() };
+        let report = |counting| {
+            let index = StatsIndex::build(&sessions, &Compacted::default(), Fingerprint::default(), DayZone::Recorded, counting);
+            standup.report(date!(2024 - 03 - 01), &sessions, &index, |_| false, None, false)
+        };
+        assert_eq!(report(Counting::Focused), "50 min in 2\n• Report — 2 pomodoros");
+        assert_eq!(report(Counting::WallClock), "1 h 10 min in 2\n• Report — 2 pomodoros");
+    }
+}
@@ -0,0 +1,401 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::fmt;
+use std::path::Path;
+use time::{Date, Weekday};
+
+use crate::history::{self, Compacted, Counting, DayNote, DayTotals, DayZone, Session};
+
+// Raised whenever the way totals are computed changes, so older caches get rebuilt
+const VERSION: u32 = 2;
+
+/// FNV-1a over the session file, updatable line by line as sessions are appended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fingerprint {
+    pub len: u64,
+    pub hash: u64,
+}
+
+impl Default for Fingerprint {
+    fn default() -> Self {
+        Self { len: 0, hash: 0xcbf2_9ce4_8422_2325 }
+    }
+}
+
+impl Fingerprint {
+    pub fn of(bytes: &[u8]) -> Self {
+        let mut fingerprint = Self::default();
+        fingerprint.extend(bytes);
+        fingerprint
+    }
+
+    pub fn extend(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.hash = (self.hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+        self.len += bytes.len() as u64;
+    }
+}
+
+/// Per-day totals kept up to date as sessions are recorded, so answering "how much today"
+/// costs a lookup instead of a pass over every session ever recorded.
+/// Cached next to the history with the fingerprint of the session file it was built from.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct StatsIndex {
+    #[serde(default)]
+    version: u32,
+    source: Fingerprint,
+    compacted_before: Option<Date>,
+    #[serde(default)]
+    zone: DayZone,
+    #[serde(default)]
+    counting: Counting,
+    days: BTreeMap<Date, DayTotals>,
+}
+
+impl StatsIndex {
+    pub fn build(sessions: &[Session], compacted: &Compacted, source: Fingerprint, zone: DayZone, counting: Counting) -> Self {
+        let mut days = compacted.days.clone();
+        if counting == Counting::WallClock {
+            for day in days.values_mut() {
+                day.focus_secs = day.focus_secs.saturating_add(day.paused_secs);
+            }
+        }
+        let mut index = Self { version: VERSION, source, compacted_before: compacted.compacted_before, zone, counting, days };
+        for session in sessions {
+            index.add(session);
+        }
+        index
+    }
+
+    /// The cached index if it still matches the history, otherwise a freshly built one.
+    pub fn load_or_build(
+        path: &Path,
+        sessions: &[Session],
+        compacted: &Compacted,
+        source: Fingerprint,
+        zone: DayZone,
+        counting: Counting,
+    ) -> Self {
+        let cached = fs::read_to_string(path).ok().and_then(|text| serde_json::from_str::<Self>(&text).ok());
+        match cached {
+            Some(index)
+                if index.version == VERSION
+                    && index.zone == zone
+                    && index.counting == counting
+                    && index.source == source
+                    && index.compacted_before == compacted.compacted_before =>
+            {
+                index
+            }
+            _ => {
+                log::info!("Rebuilding stats index from {} sessions", sessions.len());
+                let index = Self::build(sessions, compacted, source, zone, counting);
+                index.save(path);
+                index
+            }
+        }
+    }
+
+    pub fn save(&self, path: &Path) {
+        let result = serde_json::to_string(self)
+            .map_err(|e| e.to_string())
+            .and_then(|json| fs::write(path, json).map_err(|e| e.to_string()));
+        if let Err(err) = result {
+            log::warn!("Cannot save stats index: {}", err);
+        }
+    }
+
+    /// Counts a session that was just appended as `line` to the session file.
+    pub fn record(&mut self, session: &Session, line: &[u8]) {
+        self.add(session);
+        self.source.extend(line);
+    }
+
+    fn add(&mut self, session: &Session) {
+        history::add_to_days(&mut self.days, session, self.zone, self.counting);
+    }
+
+    pub fn source(&self) -> Fingerprint {
+        self.source
+    }
+
+    pub fn zone(&self) -> DayZone {
+        self.zone
+    }
+
+    pub fn counting(&self) -> Counting {
+        self.counting
+    }
+
+    pub fn day(&self, date: Date) -> DayTotals {
+        self.days.get(&date).copied().unwrap_or_default()
+    }
+
+    /// The sessions of `sessions` that started on `date`, the ones its totals count.
+    pub fn sessions_on<'a>(&self, sessions: &'a [Session], date: Date) -> Vec<&'a Session> {
+        sessions.iter().filter(|session| session.local_start(self.zone).date() == date).collect()
+    }
+
+    pub fn days(&self) -> &BTreeMap<Date, DayTotals> {
+        &self.days
+    }
+
+    /// The days of `week`, Monday first. Days before the first one recorded are missing, as
+    /// nothing is known about them; later days without sessions are zeros.
+    pub fn week(&self, week: WeekId) -> [Option<DayTotals>; 7] {
+        let first = self.days.keys().next().copied();
+        std::array::from_fn(|day| {
+            let date = week.monday() + time::Duration::days(day as i64);
+            first.is_some_and(|first| date >= first).then(|| self.day(date))
+        })
+    }
+}
+
+/// A Monday-to-Sunday week, written the ISO 8601 way like `2024-W23`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct WeekId {
+    monday: Date,
+}
+
+impl WeekId {
+    /// The week `date` is in.
+    pub fn of(date: Date) -> Self {
+        Self { monday: date - time::Duration::days(i64::from(date.weekday().number_days_from_monday())) }
+    }
+
+    pub fn monday(self) -> Date {
+        self.monday
+    }
+
+    pub fn previous(self) -> Self {
+        Self { monday: self.monday - time::Duration::weeks(1) }
+    }
+}
+
+impl fmt::Display for WeekId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (year, week, _) = self.monday.to_iso_week_date();
+        write!(f, "{}-W{:02}", year, week)
+    }
+}
+
+impl TryFrom<String> for WeekId {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, String> {
+        let invalid = || format!("{} is not a week like 2024-W23", text);
+        let (year, week) = text.split_once("-W").ok_or_else(invalid)?;
+        let (year, week) = (year.parse().map_err(|_| invalid())?, week.parse().map_err(|_| invalid())?);
+        let monday = Date::from_iso_week_date(year, week, Weekday::Monday).map_err(|_| invalid())?;
+        Ok(Self { monday })
+    }
+}
+
+impl From<WeekId> for String {
+    fn from(week: WeekId) -> Self {
+        week.to_string()
+    }
+}
+
+/// For each mood rating given, the number of days and their average focus time in seconds.
+pub fn focus_by_mood(index: &StatsIndex, notes: &BTreeMap<Date, DayNote>) -> BTreeMap<u8, (u32, u64)> {
+    let mut moods = BTreeMap::<u8, (u32, u64)>::new();
+    for note in notes.values() {
+        if let Some(mood) = note.mood {
+            let (days, secs) = moods.entry(mood).or_default();
+            *days += 1;
+            *secs += index.day(note.date).focus_secs;
+        }
+    }
+    for (days, secs) in moods.values_mut() {
+        *secs /= u64::from(*days);
+    }
+    moods
+}
+
+/// Every task label recorded, in alphabetical order.
+pub fn labels(sessions: &[Session]) -> Vec<String> {
+    let labels: BTreeSet<&String> = sessions.iter().filter_map(|session| session.label.as_ref()).collect();
+    labels.into_iter().cloned().collect()
+}
+
+/// Every tag recorded, in alphabetical order.
+pub fn tags(sessions: &[Session]) -> Vec<String> {
+    let tags: BTreeSet<&String> = sessions.iter().flat_map(|session| &session.tags).collect();
+    tags.into_iter().cloned().collect()
+}
+
+/// Whether reports group sessions by their label or by their tags.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Grouping {
+    #[default]
+    Label,
+    Tag,
+}
+
+impl Grouping {
+    /// Every label or tag recorded, in alphabetical order.
+    pub fn names(self, sessions: &[Session]) -> Vec<String> {
+        match self {
+            Grouping::Label => labels(sessions),
+            Grouping::Tag => tags(sessions),
+        }
+    }
+
+    // The positions in `names` a session counts toward; a session has many tags
+    fn rows(self, session: &Session, names: &[String]) -> Vec<usize> {
+        let position = |name: &String| names.iter().position(|n| n == name);
+        match self {
+            Grouping::Label => session.label.iter().filter_map(position).collect(),
+            Grouping::Tag => session.tags.iter().filter_map(position).collect(),
+        }
+    }
+}
+
+/// Counted seconds of each of `names` in each of `weeks` weeks starting on `first_day`, split
+/// at midnight like the daily totals. A name without sessions gets all zeros; by tag, a
+/// session counts toward each of its tags. Compacted sessions have lost their labels and
+/// tags, so they don't count.
+pub fn weekly_by(
+    sessions: &[Session],
+    grouping: Grouping,
+    names: &[String],
+    first_day: Date,
+    weeks: usize,
+    zone: DayZone,
+    counting: Counting,
+) -> Vec<Vec<u64>> {
+    let mut totals = vec![vec![0; weeks]; names.len()];
+    for session in sessions {
+        for row in grouping.rows(session, names) {
+            for (date, secs) in session.days(zone, counting) {
+                let week = (date - first_day).whole_days().div_euclid(7);
+                if let Some(total) = usize::try_from(week).ok().and_then(|week| totals[row].get_mut(week)) {
+                    *total += secs;
+                }
+            }
+        }
+    }
+    totals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+    use time::macros::{date, datetime};
+
+    const RECORDS: usize = 100_000;
+
+    // A session line every 50 minutes from early 2020 on, through every hour of the day, with
+    // a pause in every seventh; and the session file they make up
+    fn fixture() -> (Vec<Session>, String) {
+        let first = datetime!(2020-01-06 08:00 +01:00);
+        let lines: String = (0..RECORDS)
+            .map(|n| {
+                let start = (first + time::Duration::minutes(50 * n as i64)).format(&time::format_description::well_known::Rfc3339).unwrap();
+                let paused = if n % 7 == 0 { r#","pauses":1,"paused_secs":120"# } else { "" };
+                format!("{{\"start\":\"{}\",\"duration_secs\":1500{}}}\n", start, paused)
+            })
+            .collect();
+        let sessions = lines.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        (sessions, lines)
+    }
+
+    fn build(sessions: &[Session], lines: &str) -> StatsIndex {
+        StatsIndex::build(sessions, &Compacted::default(), Fingerprint::of(lines.as_bytes()), DayZone::Recorded, Counting::Focused)
+    }
+
+    #[test]
+    fn recording_sessions_one_by_one_keeps_what_a_rebuild_builds() {
+        let (sessions, lines) = fixture();
+        let mut index = build(&[], "");
+        for (session, line) in sessions.iter().zip(lines.split_inclusive('\n')) {
+            index.record(session, line.as_bytes());
+        }
+        assert_eq!(index, build(&sessions, &lines));
+        // 28.8 sessions a day, split across the midnights they run past
+        let day = index.day(date!(2021 - 06 - 15));
+        assert!((28..=29).contains(&day.sessions), "{:?}", day);
+        assert_eq!(index.days().values().map(|day| u64::from(day.sessions)).sum::<u64>(), RECORDS as u64);
+        assert_eq!(index.days().values().map(|day| day.focus_secs).sum::<u64>(), 1500 * RECORDS as u64);
+    }
+
+    #[test]
+    fn a_cached_index_is_used_only_while_it_matches_the_history() {
+        let (sessions, lines) = fixture();
+        let path = std::env::temp_dir().join(format!("ferrisfocus-stats-{}.json", std::process::id()));
+        let source = Fingerprint::of(lines.as_bytes());
+        let built = StatsIndex::load_or_build(&path, &sessions, &Compacted::default(), source, DayZone::Recorded, Counting::Focused);
+        // Loaded from the cache: the sessions given aren't looked at
+        let cached = StatsIndex::load_or_build(&path, &[], &Compacted::default(), source, DayZone::Recorded, Counting::Focused);
+        assert_eq!(cached, built);
+        let mut changed = source;
+        changed.extend(b"\n");
+        let rebuilt = StatsIndex::load_or_build(&path, &[], &Compacted::default(), changed, DayZone::Recorded, Counting::Focused);
+        assert!(rebuilt.days().is_empty());
+        let recounted = StatsIndex::load_or_build(&path, &sessions, &Compacted::default(), changed, DayZone::Recorded, Counting::WallClock);
+        assert_eq!(recounted.counting(), Counting::WallClock);
+        let _ = fs::remove_file(&path);
+    }
+
+    /// Answering for a day or a week costs a lookup, not a pass over the history.
+    #[test]
+    fn compacted_days_and_recent_sessions_count_either_way_alike() {
+        let mut paused: Session = serde_json::from_str(r#"{"start":"2024-03-01T09:00:00+01:00","duration_secs":1500}"#).unwrap();
+        paused.paused_secs = 600;
+        paused.label = Some("report".to_owned());
+        let mut compacted = Compacted::default();
+        history::add_to_days(&mut compacted.days, &paused, DayZone::Recorded, Counting::Focused);
+        let mut recent = paused.clone();
+        recent.start += time::Duration::days(1);
+
+        for (counting, secs) in [(Counting::Focused, 1500), (Counting::WallClock, 2100)] {
+            let index = StatsIndex::build(std::slice::from_ref(&recent), &compacted, Fingerprint::default(), DayZone::Recorded, counting);
+            assert_eq!(index.day(date!(2024 - 03 - 01)).focus_secs, secs, "compacted, {:?}", counting);
+            assert_eq!(index.day(date!(2024 - 03 - 02)).focus_secs, secs, "recent, {:?}", counting);
+            // The task comparison counts the same
+            let names = ["report".to_owned()];
+            let weeks = weekly_by(&[paused.clone(), recent.clone()], Grouping::Label, &names, date!(2024 - 02 - 26), 1, DayZone::Recorded, counting);
+            assert_eq!(weeks, [[2 * secs]], "{:?}", counting);
+        }
+    }
+
+    #[test]
+    fn an_index_counted_the_other_way_is_rebuilt() {
+        let (sessions, lines) = fixture();
+        let sessions = &sessions[..1000];
+        let lines: String = lines.lines().take(1000).map(|line| format!("{}\n", line)).collect();
+        let path = std::env::temp_dir().join(format!("ferrisfocus-counting-{}.json", std::process::id()));
+        let source = Fingerprint::of(lines.as_bytes());
+        let load = |counting| StatsIndex::load_or_build(&path, sessions, &Compacted::default(), source, DayZone::Recorded, counting);
+        let focused = load(Counting::Focused);
+        let wall_clock = load(Counting::WallClock);
+        let _ = fs::remove_file(&path);
+        assert_eq!(wall_clock.counting(), Counting::WallClock);
+        // Every seventh session was paused for two minutes
+        let paused = 120 * sessions.iter().step_by(7).count() as u64;
+        let total = |index: &StatsIndex| index.days().values().map(|day| day.focus_secs).sum::<u64>();
+        assert_eq!(total(&wall_clock), total(&focused) + paused);
+    }
+
+    #[test]
+    fn queries_take_a_fraction_of_a_rebuild() {
+        let (sessions, lines) = fixture();
+        let started = Instant::now();
+        let index = build(&sessions, &lines);
+        let rebuild = started.elapsed();
+        let started = Instant::now();
+        let mut week = WeekId::of(date!(2025 - 06 - 30));
+        for _ in 0..100 {
+            std::hint::black_box(index.week(week));
+            std::hint::black_box(index.day(week.monday()));
+            week = week.previous();
+        }
+        let queries = started.elapsed();
+        assert!(queries * 10 < rebuild.max(Duration::from_millis(1)), "100 weeks took {:?}, a rebuild {:?}", queries, rebuild);
+    }
+}
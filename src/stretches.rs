@@ -0,0 +1,174 @@
+//! Short stretch routines to follow during breaks, step by step. Routines are data: the bundled
+//! ones plus any in `stretches.toml` in the config dir, so adding one needs no code.
+
+use eframe::egui;
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+
+use crate::paths;
+
+const BUNDLED: &str = include_str!("../assets/stretches.toml");
+/// Read from the config dir and used alongside the bundled routines.
+pub const USER_FILE: &str = "stretches.toml";
+
+/// Which breaks a routine fits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Length {
+    Short,
+    Long,
+}
+
+/// A stick figure facing the user, in degrees; see the bundled file for what each means.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Pose {
+    pub lean: f32,
+    pub head: f32,
+    pub left_arm: f32,
+    pub right_arm: f32,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct Step {
+    pub name: String,
+    pub secs: u32,
+    #[serde(default)]
+    pub pose: Pose,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct Routine {
+    pub name: String,
+    pub length: Length,
+    #[serde(rename = "step")]
+    pub steps: Vec<Step>,
+}
+
+#[derive(Deserialize)]
+struct File {
+    #[serde(default, rename = "routine")]
+    routines: Vec<Routine>,
+}
+
+/// Every routine, and what was wrong with the user's file if it couldn't be used.
+pub fn load() -> (Vec<Routine>, Option<String>) {
+    let mut routines = parse(BUNDLED).unwrap_or_default();
+    let Some(path) = paths::config_dir().map(|dir| dir.join(USER_FILE)) else {
+        return (routines, None);
+    };
+    let problem = match std::fs::read_to_string(&path) {
+        Ok(text) => match parse(&text) {
+            Ok(user) => {
+                routines.extend(user);
+                None
+            }
+            Err(err) => Some(format!("Cannot use {}: {}", path.display(), err)),
+        },
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+        Err(err) => Some(format!("Cannot read {}: {}", path.display(), err)),
+    };
+    (routines, problem)
+}
+
+// Routines without steps, or with a step of no time, would be over before they began
+fn parse(text: &str) -> Result<Vec<Routine>, String> {
+    let file: File = toml::from_str(text).map_err(|e| e.to_string())?;
+    Ok(file.routines.into_iter().filter(|routine| !routine.steps.is_empty() && routine.steps.iter().all(|step| step.secs > 0)).collect())
+}
+
+/// A routine being followed.
+pub struct Player {
+    routine: Routine,
+    step: usize,
+    // Time spent in the current step before the last pause
+    banked: Duration,
+    running_since: Option<Instant>,
+}
+
+impl Player {
+    pub fn new(routine: Routine, now: Instant) -> Self {
+        Self { routine, step: 0, banked: Duration::ZERO, running_since: Some(now) }
+    }
+
+    fn elapsed(&self, now: Instant) -> Duration {
+        self.banked + self.running_since.map_or(Duration::ZERO, |since| now.saturating_duration_since(since))
+    }
+
+    fn current(&self) -> Option<&Step> {
+        self.routine.steps.get(self.step)
+    }
+
+    /// Moves on once the step's time is up. Returns whether a new step began, to chime for it.
+    pub fn tick(&mut self, now: Instant) -> bool {
+        let Some(step) = self.current() else {
+            return false;
+        };
+        if self.elapsed(now) < Duration::from_secs(step.secs.into()) {
+            return false;
+        }
+        self.next_step(now);
+        self.current().is_some()
+    }
+
+    fn next_step(&mut self, now: Instant) {
+        self.step += 1;
+        self.banked = Duration::ZERO;
+        self.running_since = self.running_since.map(|_| now);
+    }
+
+    pub fn finished(&self) -> bool {
+        self.current().is_none()
+    }
+}
+
+pub enum Control {
+    Stop,
+}
+
+/// The current step with its countdown and figure, and the controls.
+pub fn show(ui: &mut egui::Ui, player: &mut Player, now: Instant) -> Option<Control> {
+    let mut control = None;
+    let Some(step) = player.current().cloned() else {
+        return Some(Control::Stop);
+    };
+    let left = Duration::from_secs(step.secs.into()).saturating_sub(player.elapsed(now)).as_secs();
+    ui.weak(format!("{} · step {} of {}", player.routine.name, player.step + 1, player.routine.steps.len()));
+    ui.label(egui::RichText::new(&step.name).size(18.0));
+    figure(ui, step.pose);
+    ui.label(format!("{}:{:02}", left / 60, left % 60));
+    ui.horizontal(|ui| {
+        let paused = player.running_since.is_none();
+        if ui.button(if paused { "Resume" } else { "Pause" }).clicked() {
+            match player.running_since.take() {
+                Some(since) => player.banked += now.saturating_duration_since(since),
+                None => player.running_since = Some(now),
+            }
+        }
+        if ui.button("Next step").clicked() {
+            player.next_step(now);
+        }
+        if ui.button("Stop").clicked() {
+            control = Some(Control::Stop);
+        }
+    });
+    control
+}
+
+fn figure(ui: &mut egui::Ui, pose: Pose) {
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(120.0, 130.0), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let stroke = egui::Stroke::new(3.0, ui.visuals().strong_text_color());
+    let rotate = |v: egui::Vec2, degrees: f32| egui::Vec2::angled(v.angle() + degrees.to_radians());
+    let hip = egui::pos2(rect.center().x, rect.bottom() - 45.0);
+    for side in [-1.0, 1.0] {
+        painter.line_segment([hip, hip + egui::vec2(12.0 * side, 42.0)], stroke);
+    }
+    let up = rotate(egui::vec2(0.0, -1.0), pose.lean);
+    let shoulder = hip + up * 45.0;
+    painter.line_segment([hip, shoulder], stroke);
+    painter.circle_stroke(shoulder + rotate(up, pose.head) * 14.0, 10.0, stroke);
+    // Screen y points down, so turning "down" by a positive angle swings it to the left
+    painter.line_segment([shoulder, shoulder + rotate(-up, pose.left_arm) * 35.0], stroke);
+    painter.line_segment([shoulder, shoulder + rotate(-up, -pose.right_arm) * 35.0], stroke);
+}
@@ -0,0 +1,193 @@
+//! What the statistics window, the task suggestions and the plan's forecast read off every
+//! session, worked out once per [`History::revision`] rather than on every frame.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+use time::{Date, OffsetDateTime};
+
+use crate::history::{self, History, PauseReason, Session};
+use crate::intent::{self, Tally};
+use crate::{stats, tags};
+
+/// Pauses in the sessions recorded on some days.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Pauses {
+    pub sessions: usize,
+    /// Sessions paused at least once.
+    pub interrupted: usize,
+    pub pauses: u32,
+    pub paused_secs: u64,
+    pub by_hand_secs: u64,
+    pub during_calls_secs: u64,
+}
+
+impl Pauses {
+    fn of(session: &Session) -> Self {
+        Self {
+            sessions: 1,
+            interrupted: usize::from(session.pauses > 0),
+            pauses: session.pauses,
+            paused_secs: session.paused_secs,
+            by_hand_secs: session.paused_for(PauseReason::Manual),
+            during_calls_secs: session.paused_for(PauseReason::External),
+        }
+    }
+
+    fn add(&mut self, other: Pauses) {
+        self.sessions += other.sessions;
+        self.interrupted += other.interrupted;
+        self.pauses += other.pauses;
+        self.paused_secs += other.paused_secs;
+        self.by_hand_secs += other.by_hand_secs;
+        self.during_calls_secs += other.during_calls_secs;
+    }
+}
+
+pub struct Summary {
+    revision: u64,
+    /// Every tag used, sorted.
+    pub tags: Vec<String>,
+    /// Every label used, the most recently used first.
+    pub labels: Vec<String>,
+    pauses: BTreeMap<Date, Pauses>,
+    /// How the answered intents turned out, by day and by task.
+    pub intents: (BTreeMap<Date, Tally>, BTreeMap<String, Tally>),
+    /// When each session started and how long it ran, for the forecast.
+    pub past: Vec<(OffsetDateTime, Duration)>,
+    tagged: Option<Tagged>,
+}
+
+/// The sessions with all of some tags.
+pub struct Tagged {
+    tags: Vec<String>,
+    /// Like [`History::revision`], for what is worked out from these sessions in turn.
+    pub revision: u64,
+    pub sessions: Vec<Session>,
+    /// How the answered intents of these sessions turned out, by task.
+    pub intent_tasks: BTreeMap<String, Tally>,
+}
+
+/// The summary of `history` as it is now, worked out again only if it changed since `cache`.
+pub fn of<'a>(cache: &'a mut Option<Summary>, history: &History) -> &'a mut Summary {
+    if !matches!(cache, Some(summary) if summary.revision == history.revision) {
+        *cache = None;
+    }
+    cache.get_or_insert_with(|| Summary::new(history))
+}
+
+impl Summary {
+    fn new(history: &History) -> Self {
+        let mut pauses = BTreeMap::<Date, Pauses>::new();
+        for session in &history.sessions {
+            pauses.entry(session.date()).or_default().add(Pauses::of(session));
+        }
+        let mut seen = BTreeSet::new();
+        let labels = history.sessions.iter().rev().filter_map(|session| session.label.clone()).filter(|label| seen.insert(label.clone())).collect();
+        Self {
+            revision: history.revision,
+            tags: stats::tags(&history.sessions),
+            labels,
+            pauses,
+            intents: intent::tally(&history.sessions, &history.outcomes),
+            past: history.sessions.iter().map(|session| (session.start, Duration::from_secs(session.duration_secs))).collect(),
+            tagged: None,
+        }
+    }
+
+    /// Pauses in the sessions recorded in the 14 days up to `today`.
+    pub fn fortnight(&self, today: Date) -> Pauses {
+        let mut total = Pauses::default();
+        for day in self.pauses.range(today - time::Duration::days(13)..).map(|(_, day)| *day) {
+            total.add(day);
+        }
+        total
+    }
+
+    /// Picks out the sessions of `history` tagged with all of `wanted`, or none for no tags.
+    /// They are sorted out again only when asked for other tags.
+    pub fn tag(&mut self, history: &History, wanted: &[String]) {
+        if wanted.is_empty() {
+            self.tagged = None;
+        } else if !matches!(&self.tagged, Some(tagged) if tagged.tags == wanted) {
+            let sessions: Vec<Session> = history.sessions.iter().filter(|session| tags::has_all(&session.tags, wanted)).cloned().collect();
+            let intent_tasks = intent::tally(&sessions, &history.outcomes).1;
+            self.tagged = Some(Tagged { tags: wanted.to_vec(), revision: history::next_revision(), sessions, intent_tasks });
+        }
+    }
+
+    /// The sessions picked out by [`Summary::tag`], if any tags were asked for.
+    pub fn tagged(&self) -> Option<&Tagged> {
+        self.tagged.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::Pause;
+    use time::macros::{date, datetime};
+
+    fn session(start: OffsetDateTime, label: &str, tags: &[&str]) -> Session {
+        Session {
+            start,
+            duration_secs: 25 * 60,
+            while_away: false,
+            label: Some(label.to_owned()),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            late_night: false,
+            intent: None,
+            pauses: 0,
+            paused_secs: 0,
+            pause_segments: Vec::new(),
+            easy_start: false,
+            screenshot: None,
+        }
+    }
+
+    #[test]
+    fn it_is_worked_out_again_only_once_the_history_changes() {
+        let mut history = History { revision: 1, ..History::default() };
+        history.sessions.push(session(datetime!(2024-03-04 9:00 UTC), "Report", &["work"]));
+        let mut cache = None;
+        assert_eq!(of(&mut cache, &history).labels, ["Report"]);
+        // Pushed behind its back, so only a new revision shows it
+        history.sessions.push(session(datetime!(2024-03-04 10:00 UTC), "Email", &["chores"]));
+        assert_eq!(of(&mut cache, &history).labels, ["Report"]);
+        history.revision = 2;
+        let summary = of(&mut cache, &history);
+        assert_eq!((summary.labels.clone(), summary.tags.clone()), (vec!["Email".to_owned(), "Report".to_owned()], vec!["chores".to_owned(), "work".to_owned()]));
+        assert_eq!(summary.past.len(), 2);
+    }
+
+    #[test]
+    fn the_fortnight_counts_the_last_fourteen_days() {
+        let mut paused = session(datetime!(2024-03-14 9:00 UTC), "Report", &[]);
+        let pause = |reason| Pause { start: datetime!(2024-03-14 9:05 UTC), end: datetime!(2024-03-14 9:07 UTC), reason };
+        paused.pause_segments = vec![pause(PauseReason::Manual), pause(PauseReason::External)];
+        (paused.pauses, paused.paused_secs) = (2, 4 * 60);
+        let sessions = vec![session(datetime!(2024-03-01 9:00 UTC), "Report", &[]), session(datetime!(2024-03-02 9:00 UTC), "Report", &[]), paused];
+        let history = History { sessions, ..History::default() };
+        let fortnight = of(&mut None, &history).fortnight(date!(2024 - 03 - 15));
+        let expected = Pauses { sessions: 2, interrupted: 1, pauses: 2, paused_secs: 4 * 60, by_hand_secs: 2 * 60, during_calls_secs: 2 * 60 };
+        assert_eq!(fortnight, expected);
+    }
+
+    #[test]
+    fn tagged_sessions_are_sorted_out_again_for_other_tags() {
+        let sessions = vec![session(datetime!(2024-03-04 9:00 UTC), "Report", &["work"]), session(datetime!(2024-03-04 10:00 UTC), "Email", &["chores"])];
+        let history = History { sessions, ..History::default() };
+        let mut cache = None;
+        let summary = of(&mut cache, &history);
+        let revision = |summary: &Summary| summary.tagged().map(|tagged| tagged.revision);
+        summary.tag(&history, &["work".to_owned()]);
+        let first = revision(summary);
+        summary.tag(&history, &["work".to_owned()]);
+        assert_eq!(revision(summary), first);
+        summary.tag(&history, &["chores".to_owned()]);
+        assert_ne!(revision(summary), first);
+        let labels: Vec<_> = summary.tagged().unwrap().sessions.iter().map(|session| session.label.as_deref()).collect();
+        assert_eq!(labels, [Some("Email")]);
+        summary.tag(&history, &[]);
+        assert!(summary.tagged().is_none());
+    }
+}
@@ -0,0 +1,99 @@
+//! Sunrise and sunset from a latitude and longitude alone, with the sunrise equation as NOAA
+//! publishes it; good to a minute or two, which is plenty for switching a theme. Near the
+//! equator the day is about twelve hours long all year:
+//!
+//! ```
+//! use pomodoro_timer::sun::{daylight, Daylight};
+//! use time::macros::date;
+//!
+//! let Daylight::Rises { sunrise, sunset } = daylight(date!(2024-03-20), 0.0, 0.0) else {
+//!     panic!("the sun rises at the equator");
+//! };
+//! assert_eq!((sunrise.hour(), sunset.hour()), (6, 18));
+//! ```
+//!
+//! Far north it doesn't set in June and doesn't rise in December, while the south has it the
+//! other way around:
+//!
+//! ```
+//! use pomodoro_timer::sun::{daylight, Daylight};
+//! use time::macros::date;
+//!
+//! let (tromso, longyearbyen, mcmurdo) = ((69.65, 18.96), (78.22, 15.65), (-77.85, 166.67));
+//! assert_eq!(daylight(date!(2024-06-21), tromso.0, tromso.1), Daylight::AlwaysUp);
+//! assert_eq!(daylight(date!(2024-12-21), tromso.0, tromso.1), Daylight::AlwaysDown);
+//! assert_eq!(daylight(date!(2024-06-21), longyearbyen.0, longyearbyen.1), Daylight::AlwaysUp);
+//! assert_eq!(daylight(date!(2024-06-21), mcmurdo.0, mcmurdo.1), Daylight::AlwaysDown);
+//! assert_eq!(daylight(date!(2024-12-21), mcmurdo.0, mcmurdo.1), Daylight::AlwaysUp);
+//! assert_eq!(daylight(date!(2024-06-21), 90.0, 0.0), Daylight::AlwaysUp);
+//! assert_eq!(daylight(date!(2024-06-21), -90.0, 0.0), Daylight::AlwaysDown);
+//!
+//! // Tromsø in March has ordinary days, about twelve hours long
+//! let Daylight::Rises { sunrise, sunset } = daylight(date!(2024-03-20), tromso.0, tromso.1) else {
+//!     panic!("the sun rises in Tromsø in March");
+//! };
+//! assert!((sunset - sunrise - time::Duration::hours(12)).abs() < time::Duration::minutes(30));
+//! ```
+//!
+//! Elsewhere, times come out within a few minutes of the almanac (London, midsummer):
+//!
+//! ```
+//! use pomodoro_timer::sun::{daylight, Daylight};
+//! use time::macros::{date, datetime};
+//!
+//! let Daylight::Rises { sunrise, sunset } = daylight(date!(2024-06-21), 51.51, -0.13) else {
+//!     panic!("the sun rises in London");
+//! };
+//! assert!((sunrise - datetime!(2024-06-21 3:43 UTC)).abs() < time::Duration::minutes(3));
+//! assert!((sunset - datetime!(2024-06-21 20:21 UTC)).abs() < time::Duration::minutes(3));
+//! ```
+
+use time::{Date, OffsetDateTime};
+
+/// The sun on one day at one place.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Daylight {
+    /// Times in UTC.
+    Rises { sunrise: OffsetDateTime, sunset: OffsetDateTime },
+    /// Midnight sun.
+    AlwaysUp,
+    /// Polar night.
+    AlwaysDown,
+}
+
+// The Julian date of 2000-01-01 12:00 UTC, and of the Unix epoch
+const J2000: f64 = 2_451_545.0;
+const UNIX_EPOCH: f64 = 2_440_587.5;
+// The sun's upper edge at the horizon, with refraction
+const HORIZON_DEGREES: f64 = -0.833;
+const OBLIQUITY_DEGREES: f64 = 23.4397;
+
+/// Sunrise and sunset on `date` at `latitude` and `longitude` in degrees, north and east
+/// being positive. The day is the one of the solar noon nearest to noon UTC on `date`.
+pub fn daylight(date: Date, latitude: f64, longitude: f64) -> Daylight {
+    let days = f64::from(date.to_julian_day()) - J2000 + 0.0008;
+    let mean_noon = days - longitude / 360.0;
+    let anomaly = (357.5291 + 0.985_600_28 * mean_noon).rem_euclid(360.0).to_radians();
+    let center = 1.9148 * anomaly.sin() + 0.02 * (2.0 * anomaly).sin() + 0.0003 * (3.0 * anomaly).sin();
+    let ecliptic = (anomaly.to_degrees() + center + 180.0 + 102.9372).rem_euclid(360.0).to_radians();
+    let transit = J2000 + mean_noon + 0.0053 * anomaly.sin() - 0.0069 * (2.0 * ecliptic).sin();
+    let declination = (ecliptic.sin() * OBLIQUITY_DEGREES.to_radians().sin()).asin();
+    let latitude = latitude.clamp(-90.0, 90.0).to_radians();
+    let cos_hour_angle = (HORIZON_DEGREES.to_radians().sin() - latitude.sin() * declination.sin()) / (latitude.cos() * declination.cos());
+    if cos_hour_angle.is_nan() || cos_hour_angle > 1.0 {
+        // At a pole the sun is up if it is on the pole's side of the equator
+        return if cos_hour_angle.is_nan() && latitude.signum() == declination.signum() { Daylight::AlwaysUp } else { Daylight::AlwaysDown };
+    }
+    if cos_hour_angle < -1.0 {
+        return Daylight::AlwaysUp;
+    }
+    let half_day = cos_hour_angle.acos().to_degrees() / 360.0;
+    match (from_julian(transit - half_day), from_julian(transit + half_day)) {
+        (Some(sunrise), Some(sunset)) => Daylight::Rises { sunrise, sunset },
+        _ => Daylight::AlwaysDown,
+    }
+}
+
+fn from_julian(julian: f64) -> Option<OffsetDateTime> {
+    OffsetDateTime::from_unix_timestamp(((julian - UNIX_EPOCH) * 86_400.0).round() as i64).ok()
+}
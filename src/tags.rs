@@ -0,0 +1,66 @@
+//! Tags typed into the task label with a leading `#`, such as "Draft intro #writing #clientA".
+//! A session has one label but any number of tags; they are taken out of the text, lowercased
+//! and kept once each:
+//!
+//! ```
+//! use pomodoro_timer::tags;
+//!
+//! let tagged = tags::parse("Draft #Writing intro #clientA #writing");
+//! assert_eq!(tagged.label, "Draft intro");
+//! assert_eq!(tagged.tags.into_iter().collect::<Vec<_>>(), ["clienta", "writing"]);
+//! ```
+//!
+//! A `#` without a name after it is just text, as is one inside a word; repeated `#`s and
+//! punctuation at the end don't count towards the name, and names may be in any script:
+//!
+//! ```
+//! use pomodoro_timer::tags;
+//!
+//! let tagged = tags::parse("## Fix C# parser #");
+//! assert_eq!(tagged.label, "## Fix C# parser #");
+//! assert!(tagged.tags.is_empty());
+//!
+//! let tagged = tags::parse("##deep, #Café. #日本語 #🎉");
+//! assert_eq!(tagged.label, "#🎉");
+//! assert_eq!(tagged.tags.into_iter().collect::<Vec<_>>(), ["café", "deep", "日本語"]);
+//!
+//! assert_eq!(tags::parse("  #only  ").label, "");
+//! ```
+
+use std::collections::BTreeSet;
+
+/// A label with its tags taken out.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Tagged {
+    /// The rest of the text; as typed when there were no tags, otherwise its words separated by
+    /// single spaces. Empty when the text was only tags.
+    pub label: String,
+    pub tags: BTreeSet<String>,
+}
+
+pub fn parse(text: &str) -> Tagged {
+    let mut tagged = Tagged::default();
+    let mut words = Vec::new();
+    for word in text.split_whitespace() {
+        match tag(word) {
+            Some(tag) => {
+                tagged.tags.insert(tag);
+            }
+            None => words.push(word),
+        }
+    }
+    tagged.label = if tagged.tags.is_empty() { text.trim().to_owned() } else { words.join(" ") };
+    tagged
+}
+
+/// The tag a word names, lowercased, if it is one.
+pub fn tag(word: &str) -> Option<String> {
+    let name = word.strip_prefix('#')?.trim_start_matches('#').trim_end_matches(|c: char| !c.is_alphanumeric());
+    let valid = !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+    valid.then(|| name.to_lowercase())
+}
+
+/// Whether `tags` has every one of `wanted`; true when nothing is wanted.
+pub fn has_all(tags: &BTreeSet<String>, wanted: &[String]) -> bool {
+    wanted.iter().all(|tag| tags.contains(tag))
+}
@@ -0,0 +1,183 @@
+//! The task list as a document of its own, so it can move between machines without the
+//! session history. Tasks are matched by id, so importing the same list twice changes nothing:
+//!
+//! ```
+//! use pomodoro_timer::tasks::{Task, TaskList};
+//! use time::macros::datetime;
+//!
+//! let mut task = Task::new("Write report", 3, datetime!(2024-03-01 9:00 UTC));
+//! task.record_session("2024-03-01T09:00:00Z", datetime!(2024-03-01 9:25 UTC));
+//! let list = TaskList::new(vec![task]);
+//!
+//! let json = list.to_json();
+//! let imported = TaskList::from_json(&json).unwrap();
+//! assert_eq!(imported, list);
+//!
+//! let mut merged = list.clone();
+//! let merge = merged.merge(imported);
+//! assert_eq!((merge.added, merge.updated), (0, 0));
+//! assert_eq!(merged, list);
+//! ```
+//!
+//! Where both copies changed a task, the later edit wins, while pomodoros spent on either
+//! machine add up, counting a session both know of once:
+//!
+//! ```
+//! use pomodoro_timer::tasks::{Task, TaskList};
+//! use time::macros::datetime;
+//!
+//! let mut laptop = Task::new("Write report", 3, datetime!(2024-03-01 9:00 UTC));
+//! laptop.record_session("2024-03-01T09:00:00Z", datetime!(2024-03-01 9:25 UTC));
+//! let mut desktop = laptop.clone();
+//! laptop.record_session("2024-03-01T10:00:00Z", datetime!(2024-03-01 10:25 UTC));
+//! desktop.record_session("2024-03-01T14:00:00Z", datetime!(2024-03-01 14:25 UTC));
+//! desktop.name = "Write the report".to_owned();
+//! desktop.edited = datetime!(2024-03-01 15:00 UTC);
+//!
+//! let mut list = TaskList::new(vec![laptop]);
+//! let merge = list.merge(TaskList::new(vec![desktop]));
+//! assert_eq!(merge.updated, 1);
+//! let task = &list.tasks[0];
+//! assert_eq!(task.name, "Write the report");
+//! assert_eq!(task.consumed, 3);
+//! assert_eq!(task.sessions.len(), 3);
+//! ```
+
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+/// Written into exported documents; raised when a change would trip up older importers.
+pub const VERSION: u32 = 1;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Task {
+    #[serde(default = "Uuid::new_v4")]
+    pub id: Uuid,
+    /// Matched against the task label of finished sessions.
+    pub name: String,
+    /// Pomodoros expected to take.
+    pub estimate: u32,
+    #[serde(default)]
+    pub done: bool,
+    /// Put away with its project; out of the task list and the suggestions until unarchived.
+    #[serde(default)]
+    pub archived: bool,
+    /// Pomodoros spent so far.
+    #[serde(default)]
+    pub consumed: u32,
+    /// Start times of the sessions spent on it, in RFC 3339 as in the history; they identify
+    /// the sessions.
+    #[serde(default)]
+    pub sessions: Vec<String>,
+    /// When the name, estimate, done or archived mark last changed; decides merges.
+    #[serde(default = "unix_epoch", with = "time::serde::rfc3339")]
+    pub edited: OffsetDateTime,
+}
+
+fn unix_epoch() -> OffsetDateTime {
+    OffsetDateTime::UNIX_EPOCH
+}
+
+impl Task {
+    pub fn new(name: impl Into<String>, estimate: u32, now: OffsetDateTime) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: name.into(),
+            estimate,
+            done: false,
+            archived: false,
+            consumed: 0,
+            sessions: Vec::new(),
+            edited: now,
+        }
+    }
+
+    /// Counts a finished session towards the task, once however often it is recorded.
+    pub fn record_session(&mut self, session: &str, now: OffsetDateTime) {
+        if self.sessions.iter().any(|known| known == session) {
+            return;
+        }
+        self.sessions.push(session.to_owned());
+        self.consumed += 1;
+        self.edited = now;
+    }
+
+    /// Pomodoros still expected; none once it is marked done or archived.
+    pub fn remaining(&self) -> u32 {
+        if self.done || self.archived {
+            0
+        } else {
+            self.estimate.saturating_sub(self.consumed)
+        }
+    }
+
+    fn merge(&mut self, other: Task) {
+        let shared = self.sessions.iter().filter(|session| other.sessions.contains(session)).count() as u32;
+        let consumed = (self.consumed + other.consumed).saturating_sub(shared);
+        let mut sessions = std::mem::take(&mut self.sessions);
+        for session in &other.sessions {
+            if !sessions.contains(session) {
+                sessions.push(session.clone());
+            }
+        }
+        if other.edited > self.edited {
+            *self = other;
+        }
+        self.sessions = sessions;
+        self.consumed = consumed;
+    }
+}
+
+/// The exported document.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskList {
+    pub version: u32,
+    pub tasks: Vec<Task>,
+}
+
+/// What an import changed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Merge {
+    pub added: usize,
+    pub updated: usize,
+}
+
+impl TaskList {
+    pub fn new(tasks: Vec<Task>) -> Self {
+        Self { version: VERSION, tasks }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, String> {
+        let list: Self = serde_json::from_str(text).map_err(|e| e.to_string())?;
+        if list.version > VERSION {
+            return Err(format!("made by a newer version (format {}, this one reads up to {})", list.version, VERSION));
+        }
+        Ok(list)
+    }
+
+    /// Adds the tasks this list doesn't have and merges the ones it does, by id.
+    pub fn merge(&mut self, other: TaskList) -> Merge {
+        let mut merge = Merge::default();
+        for task in other.tasks {
+            match self.tasks.iter_mut().find(|known| known.id == task.id) {
+                Some(known) => {
+                    let before = known.clone();
+                    known.merge(task);
+                    if *known != before {
+                        merge.updated += 1;
+                    }
+                }
+                None => {
+                    self.tasks.push(task);
+                    merge.added += 1;
+                }
+            }
+        }
+        merge
+    }
+}
@@ -0,0 +1,80 @@
+/// Placeholders a template may use. Values can be missing, e.g. `{task}` when no task is set.
+pub const FIELDS: [&str; 7] = ["phase", "icon", "remaining", "paused", "task", "sessions_today", "goal"];
+
+#[derive(Clone, Debug, PartialEq)]
+enum Part {
+    Text(String),
+    Field { name: String, fallback: Option<String> },
+}
+
+/// A user-written string like `{remaining} {phase|Idle}`. `{{` and `}}` stand for literal braces,
+/// and `|` gives the text used when a value is missing (empty otherwise).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+impl Template {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        Self::parse_with(text, &FIELDS)
+    }
+
+    /// Like [`Template::parse`], for a text that has `fields` to fill in instead.
+    pub fn parse_with(text: &str, fields: &[&str]) -> Result<Self, String> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '}' => return Err("unmatched \"}\", write \"}}\" for a literal brace".to_owned()),
+                '{' => {
+                    let mut inner = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some('{') | None => return Err(format!("placeholder \"{{{}\" is not closed", inner)),
+                            Some(c) => inner.push(c),
+                        }
+                    }
+                    let (name, fallback) = match inner.split_once('|') {
+                        Some((name, fallback)) => (name.trim(), Some(fallback.to_owned())),
+                        None => (inner.trim(), None),
+                    };
+                    if !fields.contains(&name) {
+                        return Err(format!("unknown placeholder {{{}}}, available: {}", name, fields.join(", ")));
+                    }
+                    if !literal.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut literal)));
+                    }
+                    parts.push(Part::Field { name: name.to_owned(), fallback });
+                }
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Text(literal));
+        }
+        Ok(Self { parts })
+    }
+
+    /// Fills in placeholders with `value`, which returns `None` for missing values.
+    pub fn render(&self, value: impl Fn(&str) -> Option<String>) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => text.clone(),
+                Part::Field { name, fallback } => {
+                    value(name).filter(|v| !v.is_empty()).or_else(|| fallback.clone()).unwrap_or_default()
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,103 @@
+//! Light and dark looks, and switching between them by the time of day: at sunrise and sunset
+//! worked out for a place, or at fixed times.
+
+use eframe::egui;
+use pomodoro_timer::sun::{self, Daylight};
+use serde::{Deserialize, Serialize};
+use time::{OffsetDateTime, Time};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+    /// Light by day and dark by night, following a [`Schedule`].
+    Automatic,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::Dark, Theme::Light, Theme::Automatic];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Theme::Dark => "Dark",
+            Theme::Light => "Light",
+            Theme::Automatic => "Light by day, dark by night",
+        }
+    }
+}
+
+/// When night begins and ends for the automatic theme.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "by", rename_all = "snake_case")]
+pub enum Schedule {
+    /// Sunrise and sunset at a place, in degrees north and east.
+    Sun { latitude: f64, longitude: f64 },
+    /// The same local `[hour, minute]`s every day.
+    Times { light_at: [u8; 2], dark_at: [u8; 2] },
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Schedule::Times { light_at: [7, 0], dark_at: [19, 0] }
+    }
+}
+
+/// Whether it is night at `now`, and when that next changes. Where the sun doesn't rise or
+/// set today, it is looked at again at midnight.
+pub fn night(schedule: Schedule, now: OffsetDateTime) -> (bool, OffsetDateTime) {
+    let midnight = now.date().next_day().map_or(now, |day| day.midnight().assume_offset(now.offset()));
+    match schedule {
+        Schedule::Sun { latitude, longitude } => match sun::daylight(now.date(), latitude, longitude) {
+            Daylight::Rises { sunrise, .. } if now < sunrise => (true, sunrise),
+            Daylight::Rises { sunset, .. } if now < sunset => (false, sunset),
+            Daylight::Rises { .. } => match now.date().next_day().map(|day| sun::daylight(day, latitude, longitude)) {
+                Some(Daylight::Rises { sunrise, .. }) => (true, sunrise),
+                _ => (true, midnight),
+            },
+            Daylight::AlwaysUp => (false, midnight),
+            Daylight::AlwaysDown => (true, midnight),
+        },
+        Schedule::Times { light_at, dark_at } => {
+            let at = |[hour, minute]: [u8; 2]| Time::from_hms(hour, minute, 0).unwrap_or(Time::MIDNIGHT);
+            let (light, dark) = (at(light_at), at(dark_at));
+            let time = now.time();
+            let night = if light <= dark { time < light || time >= dark } else { time >= dark && time < light };
+            let next = if night { light } else { dark };
+            let change = now.replace_time(next);
+            (night, if change > now { change } else { change + time::Duration::days(1) })
+        }
+    }
+}
+
+/// The app's look, dark or light; the same shapes and strokes either way.
+pub fn visuals(dark: bool) -> egui::Visuals {
+    let (line, base) = if dark { (egui::Color32::WHITE, egui::Visuals::dark()) } else { (egui::Color32::BLACK, egui::Visuals::light()) };
+    // Grays are given for the dark look and mirrored for the light one
+    let widget = |gray: u8, stroke: f32, fg: f32| egui::style::WidgetVisuals {
+        bg_fill: egui::Color32::from_gray(if dark { gray } else { 255 - gray }),
+        bg_stroke: egui::Stroke::new(stroke, line),
+        fg_stroke: egui::Stroke::new(fg, line),
+        rounding: egui::Rounding::same(5.0),
+        weak_bg_fill: egui::Color32::TRANSPARENT,
+        expansion: 0.0,
+    };
+    egui::Visuals {
+        dark_mode: dark,
+        widgets: egui::style::Widgets {
+            inactive: widget(40, 1.0, 1.5),
+            active: widget(70, 2.0, 2.0),
+            hovered: widget(50, 1.5, 1.5),
+            noninteractive: widget(30, 1.0, 1.0),
+            open: widget(60, 1.0, 1.0),
+        },
+        ..base
+    }
+}
+
+/// Nobody is clicking, dragging or typing, so the look can change without a flicker under
+/// the pointer.
+pub fn idle(ctx: &egui::Context) -> bool {
+    ctx.dragged_id().is_none() && ctx.input(|i| !i.pointer.any_down() && i.keys_down.is_empty())
+}
@@ -0,0 +1,385 @@
+use std::time::{Duration, Instant};
+
+/// The shortest interval settings may ask for.
+pub const MIN_LENGTH: Duration = Duration::from_secs(10);
+/// The longest interval settings may ask for.
+pub const MAX_LENGTH: Duration = Duration::from_secs(12 * 60 * 60);
+
+/// How much of `whole` `part` is, from 0.0 to 1.0; all of it when `whole` is zero, so
+/// nothing divides by zero.
+///
+/// ```
+/// use pomodoro_timer::timer::fraction;
+/// use std::time::Duration;
+///
+/// assert_eq!(fraction(Duration::from_secs(15), Duration::from_secs(60)), 0.25);
+/// assert_eq!(fraction(Duration::from_secs(90), Duration::from_secs(60)), 1.0);
+/// assert_eq!(fraction(Duration::ZERO, Duration::ZERO), 1.0);
+/// ```
+pub fn fraction(part: Duration, whole: Duration) -> f32 {
+    if whole.is_zero() {
+        return 1.0;
+    }
+    (part.as_secs_f32() / whole.as_secs_f32()).min(1.0)
+}
+
+/// Which kind of interval is counting down.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    Work,
+    Break,
+    /// A short countdown between a break and a focus session that starts by itself; it counts
+    /// as neither.
+    GetReady,
+    /// A couple of minutes of focus to get going; when it ends, it either carries on as a focus
+    /// session or ends on its own.
+    EasyStart,
+}
+
+impl Phase {
+    pub fn next(self) -> Phase {
+        match self {
+            Phase::Work => Phase::Break,
+            Phase::Break | Phase::GetReady | Phase::EasyStart => Phase::Work,
+        }
+    }
+}
+
+/// How long each phase lasts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Durations {
+    pub work: Duration,
+    pub short_break: Duration,
+}
+
+impl Default for Durations {
+    fn default() -> Self {
+        Self {
+            work: Duration::new(25 * 60, 0),
+            short_break: Duration::new(5 * 60, 0),
+        }
+    }
+}
+
+impl Durations {
+    /// Getting ready and easy starts have no length of their own, see
+    /// [`PomodoroTimer::get_ready`] and [`PomodoroTimer::easy_start`].
+    pub fn of(&self, phase: Phase) -> Duration {
+        match phase {
+            Phase::Work => self.work,
+            Phase::Break => self.short_break,
+            Phase::GetReady | Phase::EasyStart => Duration::ZERO,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum State {
+    /// At the start of an interval, nothing elapsed yet.
+    Idle,
+    Running { since: Instant },
+    Paused { since: Instant },
+}
+
+/// Reported by [`PomodoroTimer::tick`] when an interval runs out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Transition {
+    PeriodFinished { finished: Phase, next: Phase },
+}
+
+/// The timer as a runtime state machine. Time is always passed in, never read,
+/// so the caller decides what clock drives it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PomodoroTimer {
+    durations: Durations,
+    phase: Phase,
+    state: State,
+    // Length of the current interval, which can differ from `durations`, e.g. once extended
+    length: Duration,
+    // Time spent running in the current interval before the last pause
+    banked: Duration,
+    // Pauses in the current interval, and their length up to the last resume
+    pauses: u32,
+    paused: Duration,
+}
+
+impl PomodoroTimer {
+    pub fn new(durations: Durations) -> Self {
+        Self {
+            durations,
+            phase: Phase::Work,
+            state: State::Idle,
+            length: durations.work,
+            banked: Duration::ZERO,
+            pauses: 0,
+            paused: Duration::ZERO,
+        }
+    }
+
+    /// A timer `elapsed` into an interval of `phase` lasting `length`, e.g. one saved earlier.
+    /// It runs from `now` if `running`, otherwise it is paused since `now`, or idle if nothing
+    /// has elapsed.
+    pub fn with_progress(
+        durations: Durations,
+        phase: Phase,
+        length: Duration,
+        elapsed: Duration,
+        running: bool,
+        now: Instant,
+    ) -> Self {
+        let state = match (running, elapsed.is_zero()) {
+            (true, _) => State::Running { since: now },
+            (false, false) => State::Paused { since: now },
+            (false, true) => State::Idle,
+        };
+        Self { durations, phase, state, length, banked: elapsed, pauses: 0, paused: Duration::ZERO }
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    pub fn durations(&self) -> Durations {
+        self.durations
+    }
+
+    pub fn is_running(&self) -> bool {
+        matches!(self.state, State::Running { .. })
+    }
+
+    /// Length of the current interval.
+    pub fn duration(&self) -> Duration {
+        self.length
+    }
+
+    /// Changes the length of the current interval only, e.g. to extend it.
+    pub fn set_duration(&mut self, length: Duration) {
+        self.length = length;
+    }
+
+    /// New lengths for the coming intervals. An idle interval takes its new length right away.
+    pub fn set_durations(&mut self, durations: Durations) {
+        self.durations = durations;
+        if self.state == State::Idle {
+            self.length = durations.of(self.phase);
+        }
+    }
+
+    /// Counts `time` as elapsed while running, such as time the computer spent asleep.
+    pub fn credit(&mut self, time: Duration) {
+        if self.is_running() {
+            self.banked += time;
+        }
+    }
+
+    /// Starts an idle timer or resumes a paused one; does nothing while running.
+    pub fn start(&mut self, now: Instant) {
+        if !self.is_running() {
+            self.state = State::Running { since: now };
+        }
+    }
+
+    /// Stops the countdown, keeping the time elapsed so far.
+    pub fn pause(&mut self, now: Instant) {
+        if let State::Running { since } = self.state {
+            self.banked += now.saturating_duration_since(since);
+            self.state = State::Paused { since: now };
+            self.pauses += 1;
+        }
+    }
+
+    /// Continues a paused timer from where it stopped.
+    ///
+    /// ```
+    /// use pomodoro_timer::timer::{Durations, PomodoroTimer};
+    /// use std::time::{Duration, Instant};
+    ///
+    /// let start = Instant::now();
+    /// let minutes = |m: u64| start + Duration::from_secs(m * 60);
+    /// let mut timer = PomodoroTimer::new(Durations::default());
+    /// timer.start(start);
+    /// timer.pause(minutes(10));
+    /// assert_eq!(timer.paused_for(minutes(25)), Some(Duration::from_secs(15 * 60)));
+    /// timer.resume(minutes(30));
+    /// assert_eq!(timer.remaining(minutes(35)), Duration::from_secs(10 * 60));
+    /// assert_eq!(timer.interruptions(minutes(35)), (1, Duration::from_secs(20 * 60)));
+    /// assert!(timer.tick(minutes(39)).is_none());
+    /// assert!(timer.tick(minutes(45)).is_some());
+    /// ```
+    pub fn resume(&mut self, now: Instant) {
+        if let State::Paused { since } = self.state {
+            self.paused += now.saturating_duration_since(since);
+            self.state = State::Running { since: now };
+        }
+    }
+
+    /// How long the current pause has lasted, if paused.
+    pub fn paused_for(&self, now: Instant) -> Option<Duration> {
+        match self.state {
+            State::Paused { since } => Some(now.saturating_duration_since(since)),
+            State::Idle | State::Running { .. } => None,
+        }
+    }
+
+    /// How often the current interval was paused, and for how long in all, the current pause
+    /// included. None of it counts as elapsed.
+    pub fn interruptions(&self, now: Instant) -> (u32, Duration) {
+        (self.pauses, self.paused + self.paused_for(now).unwrap_or_default())
+    }
+
+    /// Counts down `length` before the next focus session, running from `now`.
+    pub fn get_ready(&mut self, length: Duration, now: Instant) {
+        self.phase = Phase::GetReady;
+        self.state = State::Running { since: now };
+        self.length = length;
+        self.banked = Duration::ZERO;
+        self.pauses = 0;
+        self.paused = Duration::ZERO;
+    }
+
+    /// Begins focus with an easy start of `length`, running from `now`.
+    pub fn easy_start(&mut self, length: Duration, now: Instant) {
+        self.get_ready(length, now);
+        self.phase = Phase::EasyStart;
+    }
+
+    /// Starts an idle interval with `counted` already elapsed, such as the easy start before a
+    /// focus session that keeps going; does nothing unless idle.
+    ///
+    /// ```
+    /// use pomodoro_timer::timer::{Durations, Phase, PomodoroTimer, Transition};
+    /// use std::time::{Duration, Instant};
+    ///
+    /// let start = Instant::now();
+    /// let minutes = |m: u64| start + Duration::from_secs(m * 60);
+    /// let mut timer = PomodoroTimer::new(Durations::default());
+    /// timer.easy_start(Duration::from_secs(2 * 60), start);
+    /// let finished = timer.tick(minutes(2));
+    /// assert_eq!(finished, Some(Transition::PeriodFinished { finished: Phase::EasyStart, next: Phase::Work }));
+    /// timer.start_with(Duration::from_secs(2 * 60), minutes(3));
+    /// assert_eq!(timer.remaining(minutes(3)), Duration::from_secs(23 * 60));
+    /// ```
+    pub fn start_with(&mut self, counted: Duration, now: Instant) {
+        if self.state == State::Idle {
+            self.banked = counted;
+            self.state = State::Running { since: now };
+        }
+    }
+
+    /// Back to the start of a work interval.
+    pub fn reset(&mut self) {
+        self.phase = Phase::Work;
+        self.state = State::Idle;
+        self.length = self.durations.work;
+        self.banked = Duration::ZERO;
+        self.pauses = 0;
+        self.paused = Duration::ZERO;
+    }
+
+    /// Moves on to the next phase without finishing the current one; it starts idle.
+    pub fn skip(&mut self) {
+        self.phase = self.phase.next();
+        self.state = State::Idle;
+        self.length = self.durations.of(self.phase);
+        self.banked = Duration::ZERO;
+        self.pauses = 0;
+        self.paused = Duration::ZERO;
+    }
+
+    pub fn elapsed(&self, now: Instant) -> Duration {
+        match self.state {
+            State::Running { since } => self.banked + now.saturating_duration_since(since),
+            State::Idle | State::Paused { .. } => self.banked,
+        }
+    }
+
+    pub fn remaining(&self, now: Instant) -> Duration {
+        self.duration().saturating_sub(self.elapsed(now))
+    }
+
+    /// Share of the current interval that has elapsed, from 0.0 to 1.0.
+    pub fn progress(&self, now: Instant) -> f32 {
+        fraction(self.elapsed(now), self.duration())
+    }
+
+    /// Ends the interval if it has run out. The next phase is left idle: whether it starts
+    /// right away is up to the caller.
+    ///
+    /// Even one-second intervals, started again on each transition, end one after another
+    /// with none skipped, at the first tick after their second is up, whichever way the ticks
+    /// fall; zero-length ones end at once:
+    ///
+    /// ```
+    /// use pomodoro_timer::timer::{Durations, Phase, PomodoroTimer, Transition};
+    /// use std::time::{Duration, Instant};
+    ///
+    /// let second = Duration::from_secs(1);
+    /// for step_ms in [1, 100, 250, 333, 1000, 1500] {
+    ///     let (start, step) = (Instant::now(), Duration::from_millis(step_ms));
+    ///     let mut timer = PomodoroTimer::new(Durations { work: second, short_break: second });
+    ///     timer.start(start);
+    ///     let (mut started, mut finished) = (start, Vec::new());
+    ///     for ticks in 1..=20_000 / step_ms as u32 {
+    ///         let now = start + step * ticks;
+    ///         assert!((0.0..=1.0).contains(&timer.progress(now)));
+    ///         if let Some(Transition::PeriodFinished { finished: phase, next }) = timer.tick(now) {
+    ///             assert_eq!(next, phase.next());
+    ///             assert!(now - started >= second && now - started < second + step, "ticking every {} ms", step_ms);
+    ///             finished.push(phase);
+    ///             started = now;
+    ///             timer.start(now);
+    ///         }
+    ///     }
+    ///     assert!(finished.len() >= 20_000 / (1000 + step_ms as usize), "ticking every {} ms", step_ms);
+    ///     assert!(finished.iter().step_by(2).all(|phase| *phase == Phase::Work));
+    ///     assert!(finished.iter().skip(1).step_by(2).all(|phase| *phase == Phase::Break));
+    /// }
+    ///
+    /// let start = Instant::now();
+    /// let mut timer = PomodoroTimer::new(Durations { work: Duration::ZERO, short_break: Duration::ZERO });
+    /// timer.start(start);
+    /// assert_eq!(timer.progress(start), 1.0);
+    /// assert!(timer.tick(start).is_some());
+    /// ```
+    pub fn tick(&mut self, now: Instant) -> Option<Transition> {
+        if !self.is_running() || !self.remaining(now).is_zero() {
+            return None;
+        }
+        let finished = self.phase;
+        self.skip();
+        Some(Transition::PeriodFinished { finished, next: self.phase })
+    }
+}
+
+// Waking a little late is harmless, waking early would redraw the old digits once more
+const REPAINT_MARGIN: Duration = Duration::from_millis(2);
+
+/// How long until a countdown showing whole seconds of `remaining` changes its digits, so a
+/// repaint can land right after each change instead of drifting against it.
+///
+/// ```
+/// use pomodoro_timer::timer::until_next_second;
+/// use std::time::Duration;
+///
+/// for offset_ms in (0..1000).step_by(37) {
+///     let mut remaining = Duration::from_secs(10) - Duration::from_millis(offset_ms);
+///     let mut shown = remaining.as_secs();
+///     while shown > 0 {
+///         remaining = remaining.saturating_sub(until_next_second(remaining));
+///         assert_eq!(remaining.as_secs(), shown - 1, "skipped or repeated after {}", shown);
+///         shown -= 1;
+///     }
+/// }
+/// ```
+pub fn until_next_second(remaining: Duration) -> Duration {
+    Duration::from_nanos(u64::from(remaining.subsec_nanos())) + REPAINT_MARGIN
+}
+
+/// Like [`until_next_second`], for a count going up such as the time paused.
+pub fn until_second_passes(elapsed: Duration) -> Duration {
+    Duration::from_secs(1) - Duration::from_nanos(u64::from(elapsed.subsec_nanos())) + REPAINT_MARGIN
+}
@@ -0,0 +1,245 @@
+//! Named timer setups such as "Deep work" or "Admin sprints", each with its own durations and
+//! optionally its own sound. Whatever a preset doesn't override comes from the global settings.
+
+use eframe::egui;
+use pomodoro_timer::duration_text;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::settings::Settings;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimerPreset {
+    pub name: String,
+    #[serde(deserialize_with = "duration_text::minutes")]
+    pub work_minutes: u32,
+    #[serde(deserialize_with = "duration_text::minutes")]
+    pub break_minutes: u32,
+    pub sound: SoundOverrides,
+}
+
+impl Default for TimerPreset {
+    fn default() -> Self {
+        Self { name: "New preset".to_owned(), work_minutes: 25, break_minutes: 5, sound: SoundOverrides::default() }
+    }
+}
+
+impl TimerPreset {
+    pub fn work(&self) -> Duration {
+        Duration::from_secs(u64::from(self.work_minutes.max(1)) * 60)
+    }
+
+    pub fn short_break(&self) -> Duration {
+        Duration::from_secs(u64::from(self.break_minutes.max(1)) * 60)
+    }
+}
+
+/// Sound settings a preset can override; unset ones are inherited.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SoundOverrides {
+    pub alarm_file: Option<PathBuf>,
+    pub volume: Option<f32>,
+    /// Brown noise volume, where 0 turns it off even if it is on globally.
+    pub ambient_volume: Option<f32>,
+    pub posture_sound: Option<bool>,
+}
+
+/// The sound settings in effect.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sound {
+    pub alarm_file: Option<PathBuf>,
+    pub volume: f32,
+    pub ambient_volume: Option<f32>,
+    pub posture_sound: bool,
+}
+
+/// The active preset's overrides over the global settings. Everything that plays a sound
+/// goes through this, so a preset can't be half applied, and muting silences all of it.
+pub fn resolve(settings: &Settings) -> Sound {
+    let sound = overridden(settings);
+    if settings.muted {
+        return Sound { volume: 0.0, ambient_volume: None, posture_sound: false, ..sound };
+    }
+    sound
+}
+
+fn overridden(settings: &Settings) -> Sound {
+    let inherited = global(settings);
+    let Some(overrides) = settings.active_preset().map(|preset| &preset.sound) else {
+        return inherited;
+    };
+    Sound {
+        alarm_file: overrides.alarm_file.clone().or(inherited.alarm_file),
+        volume: overrides.volume.unwrap_or(inherited.volume),
+        ambient_volume: match overrides.ambient_volume {
+            Some(volume) => (volume > 0.0).then_some(volume),
+            None => inherited.ambient_volume,
+        },
+        posture_sound: overrides.posture_sound.unwrap_or(inherited.posture_sound),
+    }
+}
+
+/// The sound settings without any preset.
+pub fn global(settings: &Settings) -> Sound {
+    Sound {
+        alarm_file: settings.alarm_file.clone(),
+        volume: settings.volume,
+        ambient_volume: settings.ambient_volume,
+        posture_sound: settings.posture_sound,
+    }
+}
+
+/// What the user did in [`editor`].
+#[derive(Default)]
+pub struct Edit {
+    /// A change worth saving.
+    pub changed: bool,
+    pub removed: bool,
+}
+
+/// Edits one preset, showing each sound setting as inherited from `global` or overridden.
+pub fn editor(ui: &mut egui::Ui, preset: &mut TimerPreset, global: &Sound) -> Edit {
+    let mut edit = Edit::default();
+    ui.horizontal(|ui| {
+        edit.changed |= ui.add(egui::TextEdit::singleline(&mut preset.name).desired_width(120.0)).lost_focus();
+        let range = 1..=crate::settings::MAX_MINUTES;
+        edit.changed |= committed(&ui.add(egui::DragValue::new(&mut preset.work_minutes).range(range.clone()).suffix(" min focus")));
+        edit.changed |= committed(&ui.add(egui::DragValue::new(&mut preset.break_minutes).range(range).suffix(" min break")));
+        edit.removed = ui.button("Remove").clicked();
+    });
+    let sound = &mut preset.sound;
+    ui.indent(&preset.name, |ui| {
+        let volume = format!("{:.0} %", global.volume * 100.0);
+        edit.changed |= row(ui, "Alarm volume", &mut sound.volume, global.volume, &volume, |ui, volume| {
+            committed(&ui.add(egui::Slider::new(volume, 0.0..=1.0)))
+        });
+        let ambient = global.ambient_volume.map_or("off".to_owned(), |volume| format!("{:.0} %", volume * 100.0));
+        let ambient_default = global.ambient_volume.unwrap_or(0.0);
+        edit.changed |= row(ui, "Brown noise", &mut sound.ambient_volume, ambient_default, &ambient, |ui, volume| {
+            committed(&ui.add(egui::Slider::new(volume, 0.0..=1.0)))
+        });
+        let alarm = global.alarm_file.as_ref().map_or("bundled chime".to_owned(), |path| path.display().to_string());
+        let alarm_default = global.alarm_file.clone().unwrap_or_default();
+        edit.changed |= row(ui, "Alarm sound", &mut sound.alarm_file, alarm_default, &alarm, |ui, path| {
+            let mut text = path.display().to_string();
+            let response = ui.add(egui::TextEdit::singleline(&mut text).hint_text("Sound file"));
+            *path = PathBuf::from(text);
+            response.lost_focus()
+        });
+        let posture = if global.posture_sound { "on" } else { "off" };
+        edit.changed |= row(ui, "Posture sound", &mut sound.posture_sound, global.posture_sound, posture, |ui, on| {
+            ui.checkbox(on, "").changed()
+        });
+    });
+    edit
+}
+
+/// One overridable setting: its editor with a way back to the global value, or the inherited
+/// value with a way to override it. Returns whether something worth saving changed.
+fn row<T>(
+    ui: &mut egui::Ui,
+    label: &str,
+    value: &mut Option<T>,
+    inherited: T,
+    shown: &str,
+    edit: impl FnOnce(&mut egui::Ui, &mut T) -> bool,
+) -> bool {
+    ui.horizontal(|ui| {
+        ui.label(label);
+        match value {
+            Some(overridden) => {
+                let changed = edit(ui, overridden);
+                if ui.small_button("Reset to global").clicked() {
+                    *value = None;
+                    return true;
+                }
+                changed
+            }
+            None => {
+                ui.weak(format!("{} (global)", shown));
+                if ui.small_button("Override").clicked() {
+                    *value = Some(inherited);
+                    return true;
+                }
+                false
+            }
+        }
+    })
+    .inner
+}
+
+// Sliders and drag values change continuously; only the end of a change is saved
+fn committed(response: &egui::Response) -> bool {
+    response.drag_stopped() || response.lost_focus() || (response.changed() && !response.dragged())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_preset(sound: SoundOverrides) -> Settings {
+        let preset = TimerPreset { name: "Deep work".to_owned(), work_minutes: 50, break_minutes: 10, sound };
+        Settings {
+            alarm_file: Some(PathBuf::from("/sounds/gong.ogg")),
+            volume: 0.8,
+            ambient_volume: Some(0.4),
+            posture_sound: true,
+            presets: vec![preset],
+            active_preset: Some("Deep work".to_owned()),
+            ..Settings::default()
+        }
+    }
+
+    #[test]
+    fn a_preset_without_overrides_inherits_everything() {
+        let settings = with_preset(SoundOverrides::default());
+        assert_eq!(resolve(&settings), global(&settings));
+        assert_eq!(settings.durations().work, Duration::from_secs(50 * 60));
+    }
+
+    #[test]
+    fn overrides_replace_only_what_they_set() {
+        let settings = with_preset(SoundOverrides { volume: Some(0.2), posture_sound: Some(false), ..SoundOverrides::default() });
+        let sound = resolve(&settings);
+        assert_eq!((sound.volume, sound.posture_sound), (0.2, false));
+        assert_eq!(sound.alarm_file, Some(PathBuf::from("/sounds/gong.ogg")));
+        assert_eq!(sound.ambient_volume, Some(0.4));
+    }
+
+    #[test]
+    fn a_preset_can_turn_ambient_sound_off_or_on() {
+        assert_eq!(resolve(&with_preset(SoundOverrides { ambient_volume: Some(0.0), ..SoundOverrides::default() })).ambient_volume, None);
+        let mut settings = with_preset(SoundOverrides { ambient_volume: Some(0.6), ..SoundOverrides::default() });
+        settings.ambient_volume = None;
+        assert_eq!(resolve(&settings).ambient_volume, Some(0.6));
+    }
+
+    #[test]
+    fn only_the_active_preset_applies() {
+        let mut settings = with_preset(SoundOverrides { volume: Some(0.2), ..SoundOverrides::default() });
+        settings.active_preset = Some("Admin sprints".to_owned());
+        assert_eq!(resolve(&settings).volume, 0.8);
+        settings.active_preset = None;
+        assert_eq!(resolve(&settings).volume, 0.8);
+    }
+
+    #[test]
+    fn muting_silences_overrides_too() {
+        let mut settings = with_preset(SoundOverrides { volume: Some(1.0), ambient_volume: Some(0.5), posture_sound: Some(true), ..SoundOverrides::default() });
+        settings.muted = true;
+        let sound = resolve(&settings);
+        assert_eq!((sound.volume, sound.ambient_volume, sound.posture_sound), (0.0, None, false));
+        // Still the preset's alarm, for when the sound comes back
+        assert_eq!(sound.alarm_file, Some(PathBuf::from("/sounds/gong.ogg")));
+    }
+
+    #[test]
+    fn presets_read_from_settings_inherit_what_they_leave_out() {
+        let preset: TimerPreset = toml::from_str("name = \"Admin\"\nwork_minutes = \"15m\"\n[sound]\nvolume = 0.5").unwrap();
+        assert_eq!(preset.break_minutes, 5);
+        assert_eq!(preset.sound, SoundOverrides { volume: Some(0.5), ..SoundOverrides::default() });
+    }
+}
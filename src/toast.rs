@@ -0,0 +1,50 @@
+use eframe::egui;
+use std::time::{Duration, Instant};
+
+const LIFETIME: Duration = Duration::new(5, 0);
+
+/// Short-lived messages stacked in the bottom-right corner.
+#[derive(Default)]
+pub struct Toasts {
+    items: Vec<(String, Instant)>,
+}
+
+impl Toasts {
+    pub fn push(&mut self, text: impl Into<String>) {
+        self.items.push((text.into(), Instant::now()));
+    }
+
+    pub fn extend(&mut self, texts: impl IntoIterator<Item = String>) {
+        for text in texts {
+            self.push(text);
+        }
+    }
+
+    /// Messages still on screen, oldest first.
+    pub fn texts(&self) -> impl Iterator<Item = &str> {
+        self.items.iter().map(|(text, _)| text.as_str())
+    }
+
+    /// When the oldest message goes away.
+    pub fn expires(&self) -> Option<Instant> {
+        self.items.first().map(|(_, shown)| *shown + LIFETIME)
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        self.items.retain(|(_, shown)| shown.elapsed() < LIFETIME);
+        if self.items.is_empty() {
+            return;
+        }
+        egui::Area::new(egui::Id::new("toasts"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, [-10.0, -10.0])
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                crate::compact_text(ui.style_mut());
+                for (text, _) in &self.items {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.label(text);
+                    });
+                }
+            });
+    }
+}
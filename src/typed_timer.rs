@@ -0,0 +1,142 @@
+//! A typestate wrapper around [`PomodoroTimer`]: each state is its own type, so operations
+//! that make no sense in a state (resuming an idle timer, pausing a paused one) don't compile.
+//!
+//! A full cycle driven by hand:
+//!
+//! ```
+//! use pomodoro_timer::timer::{Durations, Phase};
+//! use pomodoro_timer::typed_timer::Timer;
+//! use std::time::{Duration, Instant};
+//!
+//! let t0 = Instant::now();
+//! let minutes = |m: u64| t0 + Duration::from_secs(m * 60);
+//!
+//! let idle = Timer::new(Durations::default());
+//! let running = idle.start(t0);
+//! let paused = running.pause(minutes(10));
+//! assert_eq!(paused.remaining(), Duration::from_secs(15 * 60));
+//!
+//! // Ten minutes of coffee don't count
+//! let running = paused.resume(minutes(20));
+//! assert_eq!(running.remaining(minutes(30)), Duration::from_secs(5 * 60));
+//!
+//! // Too early: the running timer is handed back unchanged
+//! let running = running.finish(minutes(30)).unwrap_err();
+//! let on_break = running.finish(minutes(35)).unwrap();
+//! assert_eq!(on_break.phase(), Phase::Break);
+//!
+//! let back_to_work = on_break.start(minutes(35)).finish(minutes(40)).unwrap();
+//! assert_eq!(back_to_work.phase(), Phase::Work);
+//! ```
+//!
+//! Resuming a timer that was never started is rejected at compile time:
+//!
+//! ```compile_fail
+//! use pomodoro_timer::timer::Durations;
+//! use pomodoro_timer::typed_timer::Timer;
+//!
+//! let idle = Timer::new(Durations::default());
+//! idle.resume(std::time::Instant::now());
+//! ```
+
+use std::marker::PhantomData;
+use std::time::{Duration, Instant};
+
+use crate::timer::{Durations, Phase, PomodoroTimer, State};
+
+/// At the start of an interval.
+#[derive(Debug)]
+pub struct Idle;
+/// Counting down.
+#[derive(Debug)]
+pub struct Running;
+/// Stopped partway through an interval.
+#[derive(Debug)]
+pub struct Paused;
+
+#[derive(Debug)]
+pub struct Timer<S> {
+    inner: PomodoroTimer,
+    _state: PhantomData<S>,
+}
+
+impl<S> Timer<S> {
+    fn wrap(inner: PomodoroTimer) -> Self {
+        Self { inner, _state: PhantomData }
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.inner.phase()
+    }
+
+    /// Back to the start of a work interval, whatever the state.
+    pub fn reset(mut self) -> Timer<Idle> {
+        self.inner.reset();
+        Timer::wrap(self.inner)
+    }
+}
+
+impl Timer<Idle> {
+    pub fn new(durations: Durations) -> Self {
+        Self::wrap(PomodoroTimer::new(durations))
+    }
+
+    pub fn start(mut self, now: Instant) -> Timer<Running> {
+        self.inner.start(now);
+        Timer::wrap(self.inner)
+    }
+}
+
+impl Timer<Running> {
+    pub fn pause(mut self, now: Instant) -> Timer<Paused> {
+        self.inner.pause(now);
+        Timer::wrap(self.inner)
+    }
+
+    pub fn remaining(&self, now: Instant) -> Duration {
+        self.inner.remaining(now)
+    }
+
+    /// Moves to the next phase if the interval has run out, otherwise hands the timer back.
+    pub fn finish(mut self, now: Instant) -> Result<Timer<Idle>, Timer<Running>> {
+        match self.inner.tick(now) {
+            Some(_) => Ok(Timer::wrap(self.inner)),
+            None => Err(self),
+        }
+    }
+}
+
+impl Timer<Paused> {
+    pub fn resume(mut self, now: Instant) -> Timer<Running> {
+        self.inner.resume(now);
+        Timer::wrap(self.inner)
+    }
+
+    pub fn remaining(&self) -> Duration {
+        // Paused time doesn't move, so any instant gives the same answer
+        self.inner.remaining(Instant::now())
+    }
+}
+
+/// A [`PomodoroTimer`] sorted into its typestate.
+pub enum AnyTimer {
+    Idle(Timer<Idle>),
+    Running(Timer<Running>),
+    Paused(Timer<Paused>),
+}
+
+impl From<PomodoroTimer> for AnyTimer {
+    fn from(timer: PomodoroTimer) -> Self {
+        match timer.state() {
+            State::Idle => AnyTimer::Idle(Timer::wrap(timer)),
+            State::Running { .. } => AnyTimer::Running(Timer::wrap(timer)),
+            State::Paused { .. } => AnyTimer::Paused(Timer::wrap(timer)),
+        }
+    }
+}
+
+impl<S> From<Timer<S>> for PomodoroTimer {
+    fn from(timer: Timer<S>) -> Self {
+        timer.inner
+    }
+}
@@ -0,0 +1,43 @@
+use std::collections::VecDeque;
+
+// Enough for a long fiddling session; older states are forgotten
+const CAPACITY: usize = 50;
+
+/// Undo and redo over whole values, in memory only. Every recorded change is one step.
+pub struct Undo<T> {
+    undo: VecDeque<T>,
+    redo: Vec<T>,
+    current: T,
+}
+
+impl<T: Clone + PartialEq> Undo<T> {
+    pub fn new(current: T) -> Self {
+        Self { undo: VecDeque::new(), redo: Vec::new(), current }
+    }
+
+    /// Makes `value` the current state, undoable unless nothing changed.
+    pub fn record(&mut self, value: &T) {
+        if *value == self.current {
+            return;
+        }
+        if self.undo.len() == CAPACITY {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(std::mem::replace(&mut self.current, value.clone()));
+        self.redo.clear();
+    }
+
+    /// The state before the last change, which becomes current.
+    pub fn undo(&mut self) -> Option<T> {
+        let previous = self.undo.pop_back()?;
+        self.redo.push(std::mem::replace(&mut self.current, previous.clone()));
+        Some(previous)
+    }
+
+    /// The state before the last undo, which becomes current.
+    pub fn redo(&mut self) -> Option<T> {
+        let next = self.redo.pop()?;
+        self.undo.push_back(std::mem::replace(&mut self.current, next.clone()));
+        Some(next)
+    }
+}
@@ -0,0 +1,86 @@
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use time::OffsetDateTime;
+
+use crate::paths;
+
+const FILE_NAME: &str = "last_context.json";
+// After a week away the old task is more likely finished than worth resuming
+const MAX_AGE: time::Duration = time::Duration::days(7);
+
+/// What the last focus session was started with, kept apart from the history so resuming it
+/// doesn't mean reading every session.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LastContext {
+    pub task: Option<String>,
+    /// Name of the preset in use; none for the default durations.
+    #[serde(default)]
+    pub preset: Option<String>,
+    pub work_secs: u64,
+    #[serde(with = "time::serde::rfc3339")]
+    pub started: OffsetDateTime,
+    /// The user closed the card; not offered again until a new session is started.
+    #[serde(default)]
+    pub dismissed: bool,
+}
+
+impl LastContext {
+    /// The context worth offering at `now`, if any.
+    pub fn load(now: OffsetDateTime) -> Option<Self> {
+        let text = std::fs::read_to_string(path()?).ok()?;
+        let context: Self = serde_json::from_str(&text).ok()?;
+        (!context.dismissed && now - context.started < MAX_AGE).then_some(context)
+    }
+
+    pub fn save(&self) {
+        let Some(path) = path() else {
+            return;
+        };
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let result = serde_json::to_string(self)
+            .map_err(|e| e.to_string())
+            .and_then(|json| std::fs::write(&path, json).map_err(|e| e.to_string()));
+        if let Err(err) = result {
+            log::warn!("Cannot save {}: {}", path.display(), err);
+        }
+    }
+}
+
+fn path() -> Option<PathBuf> {
+    paths::data_dir().map(|dir| dir.join(FILE_NAME))
+}
+
+pub enum Choice {
+    Resume,
+    Dismiss,
+}
+
+/// The "start where you left off" card.
+/// The task is masked when `private`.
+pub fn card(ui: &mut egui::Ui, context: &LastContext, private: bool) -> Option<Choice> {
+    let mut choice = None;
+    egui::Frame::group(ui.style()).show(ui, |ui| {
+        crate::compact_text(ui.style_mut());
+        ui.horizontal(|ui| {
+            let task = match &context.task {
+                Some(_) if private => "•••••",
+                Some(task) => task,
+                None => "No task",
+            };
+            match &context.preset {
+                Some(preset) => ui.label(format!("Last time: {} · {}", task, preset)),
+                None => ui.label(format!("Last time: {} · {} min", task, context.work_secs / 60)),
+            };
+            if ui.button("Start where you left off").clicked() {
+                choice = Some(Choice::Resume);
+            }
+            if ui.small_button("✖").on_hover_text("Don't offer this again").clicked() {
+                choice = Some(Choice::Dismiss);
+            }
+        });
+    });
+    choice
+}
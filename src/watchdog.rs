@@ -0,0 +1,53 @@
+use std::fmt;
+use std::time::Duration;
+
+// Late enough that someone watching the countdown would notice
+const WARN_AFTER: Duration = Duration::new(2, 0);
+// Upper bounds of the histogram buckets; the last bucket takes everything beyond
+const BUCKETS: [Duration; 5] = [
+    Duration::from_millis(100),
+    Duration::from_millis(500),
+    Duration::from_secs(1),
+    Duration::from_secs(2),
+    Duration::from_secs(5),
+];
+
+/// How late interval transitions run compared to when the interval was due to end.
+#[derive(Debug, Default)]
+pub struct Watchdog {
+    counts: [u32; BUCKETS.len() + 1],
+    worst: Duration,
+}
+
+impl Watchdog {
+    /// Notes a transition that ran `late` after its scheduled end.
+    pub fn record(&mut self, late: Duration) {
+        let bucket = BUCKETS.iter().position(|bound| late < *bound).unwrap_or(BUCKETS.len());
+        self.counts[bucket] = self.counts[bucket].saturating_add(1);
+        self.worst = self.worst.max(late);
+        if late > WARN_AFTER {
+            log::warn!("Interval transition ran {} ms late", late.as_millis());
+        } else {
+            log::debug!("Interval transition ran {} ms late", late.as_millis());
+        }
+    }
+}
+
+impl fmt::Display for Watchdog {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.counts.iter().all(|count| *count == 0) {
+            return write!(f, "no transitions yet");
+        }
+        let mut lower = Duration::ZERO;
+        let mut buckets = Vec::new();
+        for (index, count) in self.counts.iter().enumerate() {
+            let label = match BUCKETS.get(index) {
+                Some(upper) => format!("{}–{} ms", lower.as_millis(), upper.as_millis()),
+                None => format!("≥{} ms", lower.as_millis()),
+            };
+            buckets.push(format!("{}: {}", label, count));
+            lower = BUCKETS.get(index).copied().unwrap_or(lower);
+        }
+        write!(f, "{} (worst {} ms)", buckets.join(", "), self.worst.as_millis())
+    }
+}
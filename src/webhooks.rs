@@ -0,0 +1,310 @@
+//! Timer events POSTed as JSON to URLs the user set up, e.g. to log sessions in a team
+//! dashboard. Each request is signed with the URL's secret, failed ones are retried with
+//! growing pauses, and the recent attempts are kept to look at in the settings. While the
+//! network is unreachable, payloads are held and sent once it is back.
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use time::OffsetDateTime;
+
+use crate::connectivity::{self, Connectivity};
+use crate::events::Event;
+use crate::{clock, paths};
+
+/// Holds `sha256=` and the hex HMAC-SHA256 of the body, keyed with the URL's secret.
+pub const SIGNATURE_HEADER: &str = "X-FerrisFocus-Signature";
+/// Attempts kept in the delivery log.
+pub const LOG_LENGTH: usize = 50;
+const FILE_NAME: &str = "webhooks.json";
+const TIMEOUT: Duration = Duration::from_secs(10);
+// Enough of the response to recognise an error page
+const RESPONSE_CHARS: usize = 200;
+const FIRST_RETRY: Duration = Duration::from_secs(30);
+const MAX_RETRY: Duration = Duration::from_secs(60 * 60);
+// About a day of retrying with the pauses above
+const MAX_ATTEMPTS: u32 = 30;
+// How soon held payloads go out once the network is back
+const OFFLINE_POLL: Duration = Duration::from_secs(5);
+
+/// A webhook as stored in the settings.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Webhook {
+    pub url: String,
+    /// Signs each request; requests go unsigned while empty.
+    pub secret: String,
+}
+
+/// One attempt to deliver a payload.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Delivery {
+    #[serde(with = "time::serde::rfc3339")]
+    pub at: OffsetDateTime,
+    pub url: String,
+    pub payload: String,
+    /// Counting from 1; higher ones are retries.
+    pub attempt: u32,
+    /// None when no response arrived at all.
+    pub status: Option<u16>,
+    pub latency_ms: u64,
+    /// The start of the response, or why there was none.
+    pub response: String,
+}
+
+impl Delivery {
+    pub fn succeeded(&self) -> bool {
+        self.status.is_some_and(|status| (200..300).contains(&status))
+    }
+
+    // Timeouts, server errors and rate limits may pass; anything else the receiver rejected
+    fn worth_retrying(&self) -> bool {
+        match self.status {
+            None => true,
+            Some(status) => status == 429 || status >= 500,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Retry {
+    url: String,
+    payload: String,
+    /// Attempts made so far.
+    attempts: u32,
+    #[serde(with = "time::serde::rfc3339")]
+    due: OffsetDateTime,
+}
+
+/// What survives a restart: the delivery log and the retries still to make.
+#[derive(Default, Serialize, Deserialize)]
+struct State {
+    log: VecDeque<Delivery>,
+    retries: Vec<Retry>,
+}
+
+impl State {
+    fn load() -> Self {
+        path()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) {
+        let Some(path) = path() else {
+            return;
+        };
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let result = serde_json::to_string(self)
+            .map_err(|e| e.to_string())
+            .and_then(|json| std::fs::write(&path, json).map_err(|e| e.to_string()));
+        if let Err(err) = result {
+            log::warn!("Cannot save {}: {}", path.display(), err);
+        }
+    }
+}
+
+fn path() -> Option<PathBuf> {
+    paths::data_dir().map(|dir| dir.join(FILE_NAME))
+}
+
+enum Job {
+    Configure(Vec<Webhook>),
+    Send { url: String, payload: String },
+}
+
+/// Sends payloads from a thread of its own, so a slow receiver holds up neither the window
+/// nor other event subscribers. Cheap to clone; clones share the thread.
+#[derive(Clone)]
+pub struct Webhooks {
+    state: Arc<Mutex<State>>,
+    jobs: Sender<Job>,
+    connectivity: Connectivity,
+}
+
+impl Webhooks {
+    /// Picks up the retries left from the last run.
+    pub fn start(webhooks: &[Webhook], connectivity: Connectivity) -> Self {
+        let state = Arc::new(Mutex::new(State::load()));
+        let (jobs, receiver) = mpsc::channel();
+        let worker = Worker { state: Arc::clone(&state), webhooks: webhooks.to_vec(), connectivity: connectivity.clone() };
+        let spawned = std::thread::Builder::new().name("webhooks".to_owned()).spawn(move || worker.run(receiver));
+        if let Err(err) = spawned {
+            log::warn!("Cannot start sending webhooks: {}", err);
+        }
+        Self { state, jobs, connectivity }
+    }
+
+    /// Also tells the network check which hosts to probe, or to stay quiet.
+    pub fn configure(&self, webhooks: &[Webhook], work_offline: bool) {
+        let hosts = webhooks.iter().filter_map(|webhook| connectivity::host(&webhook.url)).collect();
+        self.connectivity.configure(hosts, work_offline);
+        let _ = self.jobs.send(Job::Configure(webhooks.to_vec()));
+    }
+
+    /// Sends the event to every webhook, if it is one they are told about.
+    pub fn handle(&self, webhooks: &[Webhook], event: Event) {
+        let Some(payload) = payload(&event) else {
+            return;
+        };
+        for webhook in webhooks.iter().filter(|webhook| !webhook.url.trim().is_empty()) {
+            let _ = self.jobs.send(Job::Send { url: webhook.url.clone(), payload: payload.clone() });
+        }
+    }
+
+    /// Sends a logged payload again now; a retry already waiting for it is replaced.
+    pub fn retry(&self, delivery: &Delivery) {
+        let _ = self.jobs.send(Job::Send { url: delivery.url.clone(), payload: delivery.payload.clone() });
+    }
+
+    /// The delivery log, newest first, and how many retries and held payloads are waiting.
+    pub fn log(&self) -> (Vec<Delivery>, usize) {
+        let state = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        (state.log.iter().rev().cloned().collect(), state.retries.len())
+    }
+}
+
+fn payload(event: &Event) -> Option<String> {
+    let (name, details) = match event {
+        Event::PeriodStarted { work: true, label } => ("focus_started", serde_json::json!({ "label": label })),
+        Event::PeriodStarted { work: false, .. } => ("break_started", serde_json::json!({})),
+        Event::PeriodFinished { work, while_away, .. } => {
+            (if *work { "focus_finished" } else { "break_finished" }, serde_json::json!({ "while_away": while_away }))
+        }
+        Event::GoalReached { goal } => ("goal_reached", serde_json::json!({ "goal": goal })),
+        _ => return None,
+    };
+    let mut payload = serde_json::json!({
+        "event": name,
+        "sent_at": clock::now().format(&time::format_description::well_known::Rfc3339).ok(),
+    });
+    if let (Some(payload), serde_json::Value::Object(details)) = (payload.as_object_mut(), details) {
+        payload.extend(details);
+    }
+    Some(payload.to_string())
+}
+
+/// `sha256=` and the hex HMAC-SHA256 of `body`, as sent in [`SIGNATURE_HEADER`].
+pub fn sign(secret: &str, body: &str) -> String {
+    // HMAC takes keys of any length, so this cannot fail
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC key of any length");
+    mac.update(body.as_bytes());
+    let hex: String = mac.finalize().into_bytes().iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("sha256={}", hex)
+}
+
+fn backoff(attempts: u32) -> Duration {
+    FIRST_RETRY.saturating_mul(2u32.saturating_pow(attempts.saturating_sub(1))).min(MAX_RETRY)
+}
+
+struct Worker {
+    state: Arc<Mutex<State>>,
+    webhooks: Vec<Webhook>,
+    connectivity: Connectivity,
+}
+
+impl Worker {
+    fn run(mut self, jobs: mpsc::Receiver<Job>) {
+        loop {
+            match jobs.recv_timeout(self.until_next_retry()) {
+                Ok(Job::Configure(webhooks)) => self.webhooks = webhooks,
+                Ok(Job::Send { url, payload }) => self.attempt(url, payload, 1),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+            if !self.connectivity.reachable() {
+                continue;
+            }
+            let now = clock::now();
+            let due: Vec<Retry> = {
+                let mut state = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                let (due, waiting) = std::mem::take(&mut state.retries).into_iter().partition(|retry| retry.due <= now);
+                state.retries = waiting;
+                due
+            };
+            for retry in due {
+                self.attempt(retry.url, retry.payload, retry.attempts + 1);
+            }
+        }
+    }
+
+    fn until_next_retry(&self) -> Duration {
+        if !self.connectivity.reachable() {
+            return OFFLINE_POLL;
+        }
+        let state = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let now = clock::now();
+        let next = state.retries.iter().map(|retry| retry.due).min();
+        next.map_or(MAX_RETRY, |due| (due - now).unsigned_abs().min(MAX_RETRY).max(Duration::from_millis(100)))
+    }
+
+    fn attempt(&mut self, url: String, payload: String, attempt: u32) {
+        // Removed from the settings since, so not wanted anymore
+        let Some(webhook) = self.webhooks.iter().find(|webhook| webhook.url == url) else {
+            log::debug!("Dropped webhook delivery to {}, which is no longer set up", url);
+            return;
+        };
+        if !self.connectivity.reachable() {
+            // Held without counting as an attempt, to go out once the network is back
+            let mut state = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            state.retries.retain(|retry| retry.url != url || retry.payload != payload);
+            state.retries.push(Retry { url, payload, attempts: attempt - 1, due: clock::now() });
+            state.save();
+            return;
+        }
+        let delivery = post(webhook, payload, attempt);
+        if delivery.status.is_none() {
+            self.connectivity.failed();
+        }
+        match delivery.status {
+            Some(status) if delivery.succeeded() => log::debug!(url:%, status, attempt; "Webhook answered"),
+            Some(status) => log::warn!(url:%, status, attempt, response:% = delivery.response; "Webhook answered with an error"),
+            None => log::warn!(url:%, attempt, error:% = delivery.response; "Cannot reach webhook"),
+        }
+        let mut state = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        state.retries.retain(|retry| retry.url != delivery.url || retry.payload != delivery.payload);
+        if !delivery.succeeded() && delivery.worth_retrying() && attempt < MAX_ATTEMPTS {
+            state.retries.push(Retry {
+                url: delivery.url.clone(),
+                payload: delivery.payload.clone(),
+                attempts: attempt,
+                due: clock::now() + backoff(attempt),
+            });
+        }
+        state.log.push_back(delivery);
+        while state.log.len() > LOG_LENGTH {
+            state.log.pop_front();
+        }
+        state.save();
+    }
+}
+
+fn post(webhook: &Webhook, payload: String, attempt: u32) -> Delivery {
+    let at = clock::now();
+    let started = Instant::now();
+    let mut request = ureq::post(webhook.url.trim()).timeout(TIMEOUT).set("Content-Type", "application/json");
+    if !webhook.secret.is_empty() {
+        request = request.set(SIGNATURE_HEADER, &sign(&webhook.secret, &payload));
+    }
+    let (status, response) = match request.send_string(&payload) {
+        Ok(response) => (Some(response.status()), response.into_string().unwrap_or_default()),
+        Err(ureq::Error::Status(status, response)) => (Some(status), response.into_string().unwrap_or_default()),
+        Err(err) => (None, err.to_string()),
+    };
+    Delivery {
+        at,
+        url: webhook.url.clone(),
+        payload,
+        attempt,
+        status,
+        latency_ms: started.elapsed().as_millis() as u64,
+        response: response.chars().take(RESPONSE_CHARS).collect(),
+    }
+}
@@ -0,0 +1,137 @@
+//! This week so far against the same days of last week and of a baseline week picked from the
+//! chart of recent weeks, since the week before alone is a noisy yardstick.
+
+use eframe::egui;
+use time::Date;
+
+use crate::history::DayTotals;
+use crate::stats::{StatsIndex, WeekId};
+
+const WEEKS: usize = 8;
+const BAR: egui::Color32 = egui::Color32::from_rgb(38, 139, 210);
+const BASELINE: egui::Color32 = egui::Color32::from_rgb(181, 137, 0);
+
+/// Sums over some days of a week.
+#[derive(Clone, Copy, Default)]
+struct Totals {
+    sessions: f64,
+    focus_secs: f64,
+    focus_days: f64,
+}
+
+impl Totals {
+    /// The known days up to `through` (Monday being 0), scaled up to stand for all of them
+    /// when some are missing; with how many were known. None when none were.
+    fn of(days: &[Option<DayTotals>; 7], through: usize) -> Option<(Self, usize)> {
+        let known: Vec<&DayTotals> = days[..=through].iter().flatten().collect();
+        if known.is_empty() {
+            return None;
+        }
+        let scale = (through + 1) as f64 / known.len() as f64;
+        let totals = Self {
+            sessions: known.iter().map(|day| f64::from(day.sessions)).sum::<f64>() * scale,
+            focus_secs: known.iter().map(|day| day.focus_secs as f64).sum::<f64>() * scale,
+            focus_days: known.iter().filter(|day| day.focus_secs > 0).count() as f64 * scale,
+        };
+        Some((totals, known.len()))
+    }
+}
+
+/// The chart, where right-clicking a week makes it the baseline, and the table of this week
+/// against the others. Returns whether the baseline was picked or cleared.
+pub fn show(ui: &mut egui::Ui, index: &StatsIndex, today: Date, baseline: &mut Option<WeekId>) -> bool {
+    let this_week = WeekId::of(today);
+    let mut changed = chart(ui, index, this_week, baseline);
+
+    let through = usize::from(today.weekday().number_days_from_monday());
+    let Some((now, _)) = Totals::of(&index.week(this_week), through) else {
+        ui.weak("Nothing recorded this week yet.");
+        return changed;
+    };
+    let mut rows = vec![("Last week".to_owned(), this_week.previous())];
+    rows.extend(baseline.map(|week| (format!("Baseline {}", week), week)));
+    egui::Grid::new("weekly_comparison").striped(true).show(ui, |ui| {
+        ui.label(if through == 6 { "This week" } else { "This week so far" });
+        ui.label(format!("{:.0} sessions", now.sessions));
+        ui.label(format!("{:.0} min", now.focus_secs / 60.0));
+        ui.label(format!("{:.0} days with focus", now.focus_days));
+        ui.end_row();
+        for (name, week) in rows {
+            ui.label(name);
+            match Totals::of(&index.week(week), through) {
+                None => {
+                    ui.weak("nothing recorded");
+                }
+                Some((then, known)) => {
+                    ui.label(delta(now.sessions, then.sessions, "sessions"));
+                    ui.label(delta(now.focus_secs / 60.0, then.focus_secs / 60.0, "min"));
+                    ui.label(delta(now.focus_days, then.focus_days, "days"));
+                    if known <= through {
+                        ui.weak(format!("from {} of {} days", known, through + 1))
+                            .on_hover_text("The history starts within this week, so the days recorded stand for all of them");
+                    }
+                }
+            }
+            if Some(week) == *baseline && ui.small_button("Clear").clicked() {
+                *baseline = None;
+                changed = true;
+            }
+            ui.end_row();
+        }
+    });
+    changed
+}
+
+// Focus time per week, with the baseline in its own color
+fn chart(ui: &mut egui::Ui, index: &StatsIndex, this_week: WeekId, baseline: &mut Option<WeekId>) -> bool {
+    let weeks: Vec<WeekId> = std::iter::successors(Some(this_week), |week| Some(week.previous())).take(WEEKS).collect();
+    let totals: Vec<u64> = weeks.iter().map(|week| index.week(*week).iter().flatten().map(|day| day.focus_secs).sum()).collect();
+    let highest = totals.iter().copied().max().unwrap_or(0).max(1);
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(ui.available_width().max(240.0), 100.0), egui::Sense::hover());
+    let chart = egui::Rect::from_min_max(rect.min, rect.max - egui::vec2(0.0, 14.0));
+    let slot = chart.width() / WEEKS as f32;
+    let text = ui.visuals().weak_text_color();
+    let mut changed = false;
+    // Oldest on the left
+    for (slot_index, (week, secs)) in weeks.iter().zip(&totals).rev().enumerate() {
+        let left = chart.left() + slot * slot_index as f32;
+        let area = egui::Rect::from_min_max(egui::pos2(left, chart.top()), egui::pos2(left + slot, rect.bottom()));
+        let response = ui.interact(area, ui.id().with(("week", week.monday())), egui::Sense::click());
+        let height = (*secs as f32 / highest as f32 * chart.height()).max(1.0);
+        let bar = egui::Rect::from_min_max(egui::pos2(left + slot * 0.15, chart.bottom() - height), egui::pos2(left + slot * 0.85, chart.bottom()));
+        let color = if Some(*week) == *baseline { BASELINE } else { BAR };
+        ui.painter().rect_filled(bar, 0.0, if response.hovered() { color.gamma_multiply(0.7) } else { color });
+        let monday = week.monday();
+        ui.painter().text(
+            egui::pos2(left + slot * 0.5, rect.bottom()),
+            egui::Align2::CENTER_BOTTOM,
+            format!("{}.{}.", monday.day(), u8::from(monday.month())),
+            egui::FontId::proportional(10.0),
+            text,
+        );
+        let response = response.on_hover_text(format!("{}: {} min\nRight-click to compare with this week", week, secs / 60));
+        response.context_menu(|ui| {
+            crate::compact_text(ui.style_mut());
+            if Some(*week) == *baseline {
+                if ui.button("Clear baseline").clicked() {
+                    *baseline = None;
+                    changed = true;
+                    ui.close_menu();
+                }
+            } else if ui.button("Use as baseline").clicked() {
+                *baseline = Some(*week);
+                changed = true;
+                ui.close_menu();
+            }
+        });
+    }
+    changed
+}
+
+// Signed percentage, or the plain difference where the other week had none
+fn delta(now: f64, then: f64, unit: &str) -> String {
+    if then == 0.0 {
+        return format!("{:+.0} {}", now, unit);
+    }
+    format!("{:+.0} %", (now - then) / then * 100.0)
+}
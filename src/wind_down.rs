@@ -0,0 +1,58 @@
+use eframe::egui;
+use time::{Date, OffsetDateTime, Time};
+
+// Evenings run until this hour, so starting at 1 am still counts as late
+const MORNING_HOUR: u8 = 5;
+
+/// Whether `at` is between the wind-down time (`[hour, minute]`) and the next morning.
+pub fn is_late(at: OffsetDateTime, from: [u8; 2]) -> bool {
+    let Ok(from) = Time::from_hms(from[0], from[1], 0) else {
+        return false;
+    };
+    at.time() >= from || at.hour() < MORNING_HOUR
+}
+
+/// The day whose evening `at` belongs to, so one prompt covers both sides of midnight.
+pub fn evening(at: OffsetDateTime) -> Date {
+    if at.hour() < MORNING_HOUR {
+        at.date().previous_day().unwrap_or(at.date())
+    } else {
+        at.date()
+    }
+}
+
+pub enum Choice {
+    Proceed,
+    HalfLength,
+    NotNow,
+}
+
+/// Asks before a late focus session; closing the window or pressing Escape means not now.
+pub fn prompt(ctx: &egui::Context) -> Option<Choice> {
+    let mut open = true;
+    let mut choice = None;
+    egui::Window::new("It's late")
+        .open(&mut open)
+        .resizable(false)
+        .collapsible(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, |ui| {
+            crate::compact_text(ui.style_mut());
+            ui.label("Sure you want to start a focus session? Consider a shorter one.");
+            ui.horizontal(|ui| {
+                if ui.button("Start anyway").clicked() {
+                    choice = Some(Choice::Proceed);
+                }
+                if ui.button("Start a half-length session").clicked() {
+                    choice = Some(Choice::HalfLength);
+                }
+                if ui.button("Not now").clicked() {
+                    choice = Some(Choice::NotNow);
+                }
+            });
+        });
+    if !open || ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+        return Some(Choice::NotNow);
+    }
+    choice
+}
@@ -0,0 +1,108 @@
+//! The local API's event stream, served on a free port and read as a browser would.
+
+use pomodoro_timer::local_api::{Config, Focus, Server};
+use std::io::{BufRead, BufReader, Lines, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+use time::macros::datetime;
+
+const TOKEN: &str = "secret";
+
+fn start() -> Server {
+    Server::start(0, Config { token: TOKEN.to_owned(), origins: Vec::new() }).unwrap()
+}
+
+fn focus(phase: &str) -> Focus {
+    let focusing = phase == "work";
+    Focus { phase: phase.to_owned(), focusing, until: focusing.then_some(datetime!(2024-03-01 9:25 UTC)) }
+}
+
+struct Events(Lines<BufReader<TcpStream>>);
+
+impl Events {
+    fn connect(server: &Server) -> Self {
+        let mut stream = TcpStream::connect(server.addr()).unwrap();
+        // A stream that stops delivering fails the test rather than hanging it
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        stream.write_all(format!("GET /events?token={} HTTP/1.1\r\n\r\n", TOKEN).as_bytes()).unwrap();
+        let mut lines = BufReader::new(stream).lines();
+        assert_eq!(lines.next().unwrap().unwrap(), "HTTP/1.1 200 OK");
+        Self(lines)
+    }
+
+    // The phase of the next event, checking it is framed as one
+    fn next_phase(&mut self) -> String {
+        let mut named = false;
+        for line in self.0.by_ref() {
+            let line = line.unwrap();
+            if line == "event: phase" {
+                named = true;
+            } else if let Some(data) = line.strip_prefix("data: ") {
+                assert!(named, "data without an event name: {}", data);
+                let focus: serde_json::Value = serde_json::from_str(data).unwrap();
+                return focus["phase"].as_str().unwrap().to_owned();
+            }
+        }
+        panic!("the stream ended");
+    }
+}
+
+#[test]
+fn events_arrive_in_the_order_the_phases_changed() {
+    let server = start();
+    let mut events = Events::connect(&server);
+    assert_eq!(events.next_phase(), "idle");
+    // A work interval paused, resumed, ended and the break skipped
+    let transition = ["work", "paused", "work", "break", "idle"];
+    for phase in transition {
+        assert!(server.update(focus(phase)));
+    }
+    for phase in transition {
+        assert_eq!(events.next_phase(), phase);
+    }
+}
+
+#[test]
+fn unchanged_state_sends_nothing() {
+    let server = start();
+    let mut events = Events::connect(&server);
+    assert_eq!(events.next_phase(), "idle");
+    assert!(server.update(focus("work")));
+    // Every tick updates the state; only a new phase is an event
+    assert!(!server.update(focus("work")));
+    assert!(server.update(focus("break")));
+    assert_eq!(events.next_phase(), "work");
+    assert_eq!(events.next_phase(), "break");
+}
+
+#[test]
+fn every_stream_sees_the_same_order() {
+    let server = start();
+    let mut first = Events::connect(&server);
+    assert_eq!(first.next_phase(), "idle");
+    server.update(focus("work"));
+    // Connecting mid-interval starts from the phase at the time
+    let mut second = Events::connect(&server);
+    assert_eq!(second.next_phase(), "work");
+    server.update(focus("paused"));
+    server.update(focus("work"));
+    for phase in ["work", "paused", "work"] {
+        assert_eq!(first.next_phase(), phase);
+    }
+    for phase in ["paused", "work"] {
+        assert_eq!(second.next_phase(), phase);
+    }
+}
+
+#[test]
+fn a_closed_stream_holds_up_no_other() {
+    let server = start();
+    let gone = Events::connect(&server);
+    let mut staying = Events::connect(&server);
+    assert_eq!(staying.next_phase(), "idle");
+    drop(gone);
+    for phase in ["work", "break", "idle", "work"] {
+        server.update(focus(phase));
+        assert_eq!(staying.next_phase(), phase);
+    }
+}